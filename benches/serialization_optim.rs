@@ -1,7 +1,7 @@
 //! Benchmark for serialization optimizations (JSON vs MessagePack)
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use magneto_serge::cassette::Headers;
 use magneto_serge::cassette::{Cassette, HttpRequest, HttpResponse, InteractionKind};
-use std::collections::HashMap;
 
 /// Helper to create test cassette with N interactions
 fn create_test_cassette(name: &str, interactions: usize) -> Cassette {
@@ -12,7 +12,7 @@ fn create_test_cassette(name: &str, interactions: usize) -> Cassette {
             method: "GET".to_string(),
             url: format!("https://api.example.com/resource/{}", i),
             headers: {
-                let mut h = HashMap::new();
+                let mut h = Headers::new();
                 h.insert("Content-Type".to_string(), "application/json".to_string());
                 h.insert("Authorization".to_string(), format!("Bearer token-{}", i));
                 h
@@ -23,7 +23,7 @@ fn create_test_cassette(name: &str, interactions: usize) -> Cassette {
         let response = HttpResponse {
             status: 200,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = Headers::new();
                 h.insert("Content-Type".to_string(), "application/json".to_string());
                 h
             },
@@ -6,10 +6,10 @@
 //!
 //! Run with: cargo run --example advanced_matching
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::{
     BodyMatchMode, HttpRequest, HttpResponse, MatchingStrategy, Player, Recorder, UrlMatchMode,
 };
-use std::collections::HashMap;
 use tempfile::tempdir;
 
 fn main() {
@@ -46,13 +46,13 @@ fn example_regex_url_matching() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/users/123".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"id\":123,\"name\":\"Alice\"}".to_vec()),
     };
 
@@ -74,7 +74,7 @@ fn example_regex_url_matching() {
     let test_request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/users/456".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -97,13 +97,13 @@ fn example_ignore_query_params() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/search?q=rust&page=1&timestamp=12345".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"results\":[{\"title\":\"Learning Rust\"}]}".to_vec()),
     };
 
@@ -125,7 +125,7 @@ fn example_ignore_query_params() {
     let test_request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/search?q=rust&page=5&timestamp=99999".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -154,13 +154,13 @@ fn example_json_path_matching() {
     let request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(serde_json::to_vec(&body1).unwrap()),
     };
 
     let response = HttpResponse {
         status: 201,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"status\":\"created\"}".to_vec()),
     };
 
@@ -192,7 +192,7 @@ fn example_json_path_matching() {
     let test_request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(serde_json::to_vec(&body2).unwrap()),
     };
 
@@ -211,13 +211,13 @@ fn example_lenient_matching() {
     let request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/events?token=abc&session=xyz".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"event data here".to_vec()),
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"status\":\"ok\"}".to_vec()),
     };
 
@@ -237,7 +237,7 @@ fn example_lenient_matching() {
     let test_request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/events?token=123&session=456".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"different event data".to_vec()),
     };
 
@@ -253,7 +253,7 @@ fn example_header_matching() {
 
     let mut recorder = Recorder::new("header-example".to_string());
 
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Authorization".to_string(), "Bearer secret123".to_string());
     headers.insert("User-Agent".to_string(), "MyApp/1.0".to_string());
     headers.insert("X-Request-ID".to_string(), "req-001".to_string());
@@ -267,7 +267,7 @@ fn example_header_matching() {
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"data\":\"secret stuff\"}".to_vec()),
     };
 
@@ -284,7 +284,7 @@ fn example_header_matching() {
         .with_matching_strategy(strategy);
 
     // Match with same Authorization but different User-Agent and X-Request-ID
-    let mut test_headers = HashMap::new();
+    let mut test_headers = Headers::new();
     test_headers.insert("Authorization".to_string(), "Bearer secret123".to_string());
     test_headers.insert("User-Agent".to_string(), "DifferentApp/2.0".to_string());
     test_headers.insert("X-Request-ID".to_string(), "req-999".to_string());
@@ -311,13 +311,13 @@ fn example_body_size_matching() {
     let request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/upload".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(vec![0u8; 1024]), // 1KB of zeros
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"status\":\"uploaded\"}".to_vec()),
     };
 
@@ -337,7 +337,7 @@ fn example_body_size_matching() {
     let test_request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/upload".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(vec![255u8; 1024]), // 1KB of 0xFF
     };
 
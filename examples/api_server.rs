@@ -10,6 +10,7 @@
 //!   curl http://localhost:8889/cassettes
 
 use magneto_serge::api::handlers::start_server;
+use magneto_serge::api::ApiConfig;
 use std::path::PathBuf;
 
 #[tokio::main]
@@ -40,7 +41,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  GET  /cassettes/stats             - Global statistics");
     println!("\n⚡ Press Ctrl+C to stop the server\n");
 
-    start_server(host, port, cassette_dir).await?;
+    let config = ApiConfig {
+        host: host.to_string(),
+        port,
+        cassette_dir: cassette_dir.display().to_string(),
+        ..ApiConfig::default()
+    };
+    start_server(&config).await?;
 
     Ok(())
 }
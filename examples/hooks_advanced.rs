@@ -3,6 +3,7 @@
 //! This example demonstrates how to create custom hooks for
 //! advanced use cases like timestamp normalization and metrics.
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::cassette::{HttpRequest, HttpResponse, Interaction, InteractionKind};
 use magneto_serge::error::Result;
 use magneto_serge::hooks::{RecordHook, ReplayHook};
@@ -233,7 +234,7 @@ fn main() -> Result<()> {
     let request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/posts".to_string(),
-        headers: HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+        headers: Headers::from([("content-type".to_string(), "application/json".to_string())]),
         body: Some(
             format!(
                 r#"{{"title":"Hello","body":"World","timestamp":"{}"}}"#,
@@ -245,7 +246,7 @@ fn main() -> Result<()> {
 
     let response = HttpResponse {
         status: 201,
-        headers: HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+        headers: Headers::from([("content-type".to_string(), "application/json".to_string())]),
         body: Some(
             format!(
                 r#"{{"id":"123","title":"Hello","created_at":"{}","updated_at":"{}"}}"#,
@@ -264,13 +265,13 @@ fn main() -> Result<()> {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: format!("https://api.example.com/posts/{}", i),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+            headers: Headers::from([("content-type".to_string(), "application/json".to_string())]),
             body: Some(
                 format!(
                     r#"{{"id":"{}","title":"Post {}","created_at":"{}"}}"#,
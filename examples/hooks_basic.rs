@@ -3,10 +3,10 @@
 //! This example demonstrates how to use built-in hooks to filter
 //! sensitive data from recorded cassettes.
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::cassette::{HttpRequest, HttpResponse};
 use magneto_serge::hooks::builtins::{BodyPatternReplacer, LoggingHook, SensitiveHeaderFilter};
 use magneto_serge::recorder::Recorder;
-use std::collections::HashMap;
 use std::path::PathBuf;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -51,7 +51,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let login_request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/auth/login".to_string(),
-        headers: HashMap::from([
+        headers: Headers::from([
             ("content-type".to_string(), "application/json".to_string()),
             (
                 "x-api-key".to_string(),
@@ -63,7 +63,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let login_response = HttpResponse {
         status: 200,
-        headers: HashMap::from([
+        headers: Headers::from([
             ("content-type".to_string(), "application/json".to_string()),
             (
                 "set-cookie".to_string(),
@@ -82,7 +82,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let api_request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/users/me".to_string(),
-        headers: HashMap::from([
+        headers: Headers::from([
             (
                 "authorization".to_string(),
                 "Bearer jwt-eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9".to_string(),
@@ -97,7 +97,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let api_response = HttpResponse {
         status: 200,
-        headers: HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+        headers: Headers::from([("content-type".to_string(), "application/json".to_string())]),
         body: Some(br#"{"id":"123","username":"alice","email":"alice@example.com"}"#.to_vec()),
     };
 
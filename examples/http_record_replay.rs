@@ -5,12 +5,12 @@
 //! 2. Sauvegarde dans une cassette
 //! 3. Replay depuis la cassette
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::{
     cassette::{HttpRequest, HttpResponse},
     player::Player,
     recorder::Recorder,
 };
-use std::collections::HashMap;
 use std::path::Path;
 
 #[tokio::main]
@@ -37,7 +37,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         method: "GET".to_string(),
         url: "https://api.github.com/users/octocat".to_string(),
         headers: {
-            let mut h = HashMap::new();
+            let mut h = Headers::new();
             h.insert("User-Agent".to_string(), "magneto-serge/0.1.0".to_string());
             h.insert("Accept".to_string(), "application/json".to_string());
             h
@@ -49,7 +49,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let response = HttpResponse {
         status: 200,
         headers: {
-            let mut h = HashMap::new();
+            let mut h = Headers::new();
             h.insert("Content-Type".to_string(), "application/json".to_string());
             h.insert("X-GitHub-Media-Type".to_string(), "github.v3".to_string());
             h
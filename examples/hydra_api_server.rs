@@ -5,6 +5,9 @@
 //! Usage:
 //!   cargo run --example hydra_api_server --features hydra
 //!
+//! Host, port and cassette directory come from magneto.toml and the
+//! `MAGNETO_*` environment variables (e.g. `MAGNETO_API_PORT=9000`).
+//!
 //! Then test with:
 //!   curl http://localhost:8889/api
 //!   curl http://localhost:8889/api/cassettes
@@ -15,25 +18,14 @@
 //!   curl -H "Accept: application/ld+json" http://localhost:8889/api
 
 use magneto_serge::api::{ApiConfig, ApiServer};
+use magneto_serge::Config;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(tracing::Level::INFO.into()),
-        )
-        .init();
+    let magneto = Config::load(None)?;
+    magneto.logging.init()?;
 
-    let config = ApiConfig {
-        host: "127.0.0.1".to_string(),
-        port: 8889,
-        proxy_port: 8888,
-        cassette_dir: "./cassettes".to_string(),
-        auth_enabled: false,
-        api_key: None,
-    };
+    let config = ApiConfig::from_config(&magneto);
 
     println!("🚀 Starting Magnéto-Serge Hydra API Server");
     println!("📂 Cassette directory: {}", config.cassette_dir);
@@ -58,7 +50,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  http :8889/api/cassettes page==1 limit==5");
     println!("\n⚡ Press Ctrl+C to stop the server\n");
 
-    let server = ApiServer::new(config);
+    let server = ApiServer::new(config).with_proxy_config(magneto);
 
     #[cfg(feature = "hydra")]
     {
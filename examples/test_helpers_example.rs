@@ -5,10 +5,10 @@
 //! This example shows various assertion helpers for testing cassettes.
 
 use chrono::Utc;
+use magneto_serge::cassette::Headers;
 use magneto_serge::cassette::{Cassette, HttpRequest, HttpResponse, Interaction, InteractionKind};
 use magneto_serge::cookies::Cookie;
 use magneto_serge::test_helpers::*;
-use std::collections::HashMap;
 
 fn create_example_cassette() -> Cassette {
     let mut cassette = Cassette::new("example".to_string());
@@ -48,12 +48,12 @@ fn create_example_cassette() -> Cassette {
             request: HttpRequest {
                 method: "GET".to_string(),
                 url: "https://api.example.com/users".to_string(),
-                headers: HashMap::from([("Accept".to_string(), "application/json".to_string())]),
+                headers: Headers::from([("Accept".to_string(), "application/json".to_string())]),
                 body: None,
             },
             response: HttpResponse {
                 status: 200,
-                headers: HashMap::from([(
+                headers: Headers::from([(
                     "Content-Type".to_string(),
                     "application/json".to_string(),
                 )]),
@@ -62,6 +62,8 @@ fn create_example_cassette() -> Cassette {
         },
         recorded_at: Utc::now(),
         response_time_ms: Some(50),
+        http_version: None,
+        content_encoding: None,
     });
 
     // POST request
@@ -70,7 +72,7 @@ fn create_example_cassette() -> Cassette {
             request: HttpRequest {
                 method: "POST".to_string(),
                 url: "https://api.example.com/users".to_string(),
-                headers: HashMap::from([(
+                headers: Headers::from([(
                     "Content-Type".to_string(),
                     "application/json".to_string(),
                 )]),
@@ -78,7 +80,7 @@ fn create_example_cassette() -> Cassette {
             },
             response: HttpResponse {
                 status: 201,
-                headers: HashMap::from([(
+                headers: Headers::from([(
                     "Content-Type".to_string(),
                     "application/json".to_string(),
                 )]),
@@ -87,6 +89,8 @@ fn create_example_cassette() -> Cassette {
         },
         recorded_at: Utc::now(),
         response_time_ms: Some(75),
+        http_version: None,
+        content_encoding: None,
     });
 
     // GET request
@@ -95,12 +99,12 @@ fn create_example_cassette() -> Cassette {
             request: HttpRequest {
                 method: "GET".to_string(),
                 url: "https://api.example.com/users/1".to_string(),
-                headers: HashMap::new(),
+                headers: Headers::new(),
                 body: None,
             },
             response: HttpResponse {
                 status: 200,
-                headers: HashMap::from([(
+                headers: Headers::from([(
                     "Content-Type".to_string(),
                     "application/json".to_string(),
                 )]),
@@ -109,6 +113,8 @@ fn create_example_cassette() -> Cassette {
         },
         recorded_at: Utc::now(),
         response_time_ms: Some(45),
+        http_version: None,
+        content_encoding: None,
     });
 
     cassette
@@ -121,7 +127,7 @@ fn main() {
 
     println!("📋 Basic Assertions:");
     println!("  ✓ Cassette version: {}", cassette.version);
-    assert_cassette_version(&cassette, "1.0");
+    assert_cassette_version(&cassette, "2.0");
 
     println!("  ✓ Interaction count: {}", cassette.interactions.len());
     assert_interaction_count(&cassette, 3);
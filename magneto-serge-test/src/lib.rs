@@ -113,7 +113,7 @@ pub fn magneto_test(args: TokenStream, input: TokenStream) -> TokenStream {
 
     // Parse args as a list of Meta items
     let args_parser = syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated;
-    let args = match args_parser.parse(args.clone().into()) {
+    let args = match args_parser.parse(args.clone()) {
         Ok(args) => args,
         Err(_) => syn::punctuated::Punctuated::new(),
     };
@@ -2,7 +2,7 @@
 //!
 //! Provides HTTP endpoints to list, inspect, validate, and delete cassettes.
 
-use crate::cassette::Cassette;
+use crate::cassette::{pending, storage, Cassette, CassetteFormat, CASSETTE_VERSION};
 use crate::error::{MatgtoError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -53,6 +53,14 @@ pub struct CassetteStats {
     /// WebSocket interactions
     pub websocket_count: usize,
 
+    /// Server-Sent Events streams
+    #[serde(default)]
+    pub sse_count: usize,
+
+    /// gRPC calls
+    #[serde(default)]
+    pub grpc_count: usize,
+
     /// HTTP errors (timeouts, DNS failures, etc.)
     pub http_error_count: usize,
 
@@ -116,12 +124,15 @@ impl CassetteManager {
             let entry = entry?;
             let path = entry.path();
 
-            // Only process .json and .msgpack files
-            if let Some(ext) = path.extension() {
-                if ext == "json" || ext == "msgpack" {
-                    if let Ok(metadata) = self.get_cassette_metadata(&path) {
-                        cassettes.push(metadata);
-                    }
+            // Only process files in a known cassette format, skipping pending sidecars
+            let is_cassette = path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .and_then(storage::parse_cassette_file_name)
+                .is_some_and(|(name, _)| !pending::is_pending_name(name));
+            if is_cassette {
+                if let Ok(metadata) = self.get_cassette_metadata(&path) {
+                    cassettes.push(metadata);
                 }
             }
         }
@@ -138,20 +149,16 @@ impl CassetteManager {
         let size_bytes = file_metadata.len();
 
         // Load cassette to get internal metadata
-        let file = std::fs::File::open(path)?;
-        let cassette: Cassette = serde_json::from_reader(file)?;
-
-        let name = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+        let cassette = storage::load_cassette(path)?;
 
-        let format = path
-            .extension()
+        let (name, format) = match path
+            .file_name()
             .and_then(|s| s.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+            .and_then(storage::parse_cassette_file_name)
+        {
+            Some((name, format)) => (name.to_string(), format.extension().to_string()),
+            None => ("unknown".to_string(), "unknown".to_string()),
+        };
 
         let duration = Utc::now() - cassette.recorded_at;
         let age_days = duration.num_days();
@@ -177,6 +184,8 @@ impl CassetteManager {
             total_interactions: cassette.interactions.len(),
             http_count: 0,
             websocket_count: 0,
+            sse_count: 0,
+            grpc_count: 0,
             http_error_count: 0,
             status_codes: HashMap::new(),
             http_methods: HashMap::new(),
@@ -215,6 +224,13 @@ impl CassetteManager {
                 crate::cassette::InteractionKind::WebSocket { .. } => {
                     stats.websocket_count += 1;
                 }
+                crate::cassette::InteractionKind::ServerSentEvents { response, .. } => {
+                    stats.sse_count += 1;
+                    *stats.status_codes.entry(response.status).or_insert(0) += 1;
+                }
+                crate::cassette::InteractionKind::Grpc { .. } => {
+                    stats.grpc_count += 1;
+                }
             }
 
             // Response times
@@ -253,17 +269,18 @@ impl CassetteManager {
         };
 
         // Check version
-        if cassette.version != "1.0" && cassette.version != "2.0" {
+        if !matches!(cassette.version.as_str(), "1.0" | "1.1" | "2.0") {
             result.warnings.push(format!(
-                "Unknown cassette version: {} (expected 1.0 or 2.0)",
+                "Unknown cassette version: {} (expected 1.0, 1.1 or 2.0)",
                 cassette.version
             ));
         }
 
-        if cassette.version == "1.0" {
-            result.warnings.push(
-                "Cassette is v1.0, consider migrating to v2.0 for cookie support".to_string(),
-            );
+        if cassette.version != CASSETTE_VERSION {
+            result.warnings.push(format!(
+                "Cassette is v{}, run `magneto migrate` to store readable bodies (v{})",
+                cassette.version, CASSETTE_VERSION
+            ));
         }
 
         // Check age
@@ -322,6 +339,22 @@ impl CassetteManager {
                         result.valid = false;
                     }
                 }
+                crate::cassette::InteractionKind::ServerSentEvents { request, .. } => {
+                    if request.url.is_empty() {
+                        result
+                            .errors
+                            .push(format!("Interaction {}: empty event stream URL", i));
+                        result.valid = false;
+                    }
+                }
+                crate::cassette::InteractionKind::Grpc { request, .. } => {
+                    if request.url.is_empty() {
+                        result
+                            .errors
+                            .push(format!("Interaction {}: empty gRPC URL", i));
+                        result.valid = false;
+                    }
+                }
             }
         }
 
@@ -346,29 +379,37 @@ impl CassetteManager {
     /// Load a cassette by name
     pub fn load_cassette(&self, name: &str) -> Result<Cassette> {
         let path = self.cassette_path(name)?;
-        let file = std::fs::File::open(&path).map_err(MatgtoError::Io)?;
-        let cassette: Cassette =
-            serde_json::from_reader(file).map_err(MatgtoError::Serialization)?;
-        Ok(cassette)
+        storage::load_cassette(&path)
     }
 
-    /// Get cassette file path
-    fn cassette_path(&self, name: &str) -> Result<PathBuf> {
-        // Try .json first
-        let json_path = self.cassette_dir.join(format!("{}.json", name));
-        if json_path.exists() {
-            return Ok(json_path);
-        }
+    /// Rewrite a cassette in the current format version
+    ///
+    /// The file keeps its format (JSON, MessagePack, ...). With `backup`, the
+    /// original is first copied to `<file>.bak`. Returns the version the
+    /// cassette was migrated from.
+    pub fn migrate_cassette(&self, name: &str, backup: bool) -> Result<String> {
+        let path = self.cassette_path(name)?;
+        let mut cassette = storage::load_cassette(&path)?;
 
-        // Try .msgpack
-        let msgpack_path = self.cassette_dir.join(format!("{}.msgpack", name));
-        if msgpack_path.exists() {
-            return Ok(msgpack_path);
+        if backup {
+            let mut backup_path = path.clone().into_os_string();
+            backup_path.push(".bak");
+            fs::copy(&path, backup_path)?;
         }
 
-        Err(MatgtoError::CassetteNotFound {
-            name: name.to_string(),
-        })
+        let from = std::mem::replace(&mut cassette.version, CASSETTE_VERSION.to_string());
+        storage::save_cassette(&cassette, &path, storage::detect_format(&path))?;
+
+        Ok(from)
+    }
+
+    /// Get cassette file path
+    fn cassette_path(&self, name: &str) -> Result<PathBuf> {
+        storage::find_cassette(&self.cassette_dir, name, CassetteFormat::default()).ok_or_else(
+            || MatgtoError::CassetteNotFound {
+                name: name.to_string(),
+            },
+        )
     }
 
     /// Get global statistics
@@ -466,6 +507,45 @@ fn format_bytes(bytes: u64) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_migrate_cassette() {
+        let dir = tempfile::tempdir().unwrap();
+        let v1 = r#"{
+            "version": "1.0",
+            "name": "api",
+            "recorded_at": "2024-01-01T00:00:00Z",
+            "interactions": [{
+                "type": "Http",
+                "request": {"method": "GET", "url": "/users", "headers": {}, "body": null},
+                "response": {
+                    "status": 200,
+                    "headers": {"content-type": "application/json"},
+                    "body": [91, 49, 93]
+                },
+                "recorded_at": "2024-01-01T00:00:00Z"
+            }]
+        }"#;
+        fs::write(dir.path().join("api.json"), v1).unwrap();
+
+        let manager = CassetteManager::new(dir.path());
+        assert_eq!(manager.migrate_cassette("api", true).unwrap(), "1.0");
+
+        let migrated: serde_json::Value =
+            serde_json::from_slice(&fs::read(dir.path().join("api.json")).unwrap()).unwrap();
+        assert_eq!(migrated["version"], CASSETTE_VERSION);
+        let response = &migrated["interactions"][0]["response"];
+        assert_eq!(response["body"], serde_json::json!({"json": [1]}));
+        assert_eq!(
+            response["headers"],
+            serde_json::json!([["content-type", "application/json"]])
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("api.json.bak")).unwrap(),
+            v1
+        );
+        assert!(manager.migrate_cassette("missing", false).is_err());
+    }
+
     #[test]
     fn test_format_bytes() {
         assert_eq!(format_bytes(500), "500 bytes");
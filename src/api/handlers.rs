@@ -6,10 +6,12 @@
 use super::cassettes::{
     CassetteManager, CassetteMetadata, CassetteStats, GlobalStats, ValidationResult,
 };
+use super::ApiConfig;
 use crate::error::Result;
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    extract::{Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::{delete, get, post},
     Json, Router,
@@ -360,36 +362,64 @@ pub fn build_combined_router(
         .merge(build_hydra_router(hydra_state))
 }
 
-/// Start the API server with Hydra support
-#[cfg(feature = "hydra")]
-pub async fn start_server_with_hydra(
-    host: &str,
-    port: u16,
-    cassette_dir: impl Into<std::path::PathBuf>,
-) -> Result<()> {
-    let base_url = format!("http://{}:{}", host, port);
-    let app = build_combined_router(cassette_dir, base_url);
+/// Require `Authorization: Bearer <api_key>` on every route when
+/// `auth_enabled` is set
+///
+/// Without an API key, no request is authorized.
+pub fn with_auth(router: Router, config: &ApiConfig) -> Router {
+    if !config.auth_enabled {
+        return router;
+    }
 
-    let addr = format!("{}:{}", host, port);
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let expected = config
+        .api_key
+        .as_ref()
+        .map(|api_key| Arc::new(format!("Bearer {}", api_key)));
+    router.layer(middleware::from_fn_with_state(expected, require_api_key))
+}
 
-    tracing::info!("API server listening on {}", addr);
+async fn require_api_key(
+    State(expected): State<Option<Arc<String>>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let authorization = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+
+    match expected {
+        Some(expected) if authorization == Some(expected.as_str()) => next.run(req).await,
+        _ => ApiError {
+            error: "unauthorized".to_string(),
+            message: "Invalid or missing API key".to_string(),
+            status: 401,
+        }
+        .into_response(),
+    }
+}
 
-    axum::serve(listener, app).await?;
+/// Start the API server with Hydra support
+#[cfg(feature = "hydra")]
+pub async fn start_server_with_hydra(config: &ApiConfig) -> Result<()> {
+    let base_url = format!("http://{}:{}", config.host, config.port);
+    let app = build_combined_router(&config.cassette_dir, base_url);
 
-    Ok(())
+    serve(config, app).await
 }
 
 /// Start the API server (legacy REST API only)
-pub async fn start_server(
-    host: &str,
-    port: u16,
-    cassette_dir: impl Into<std::path::PathBuf>,
-) -> Result<()> {
-    let state = ApiState::new(cassette_dir);
+pub async fn start_server(config: &ApiConfig) -> Result<()> {
+    let state = ApiState::new(&config.cassette_dir);
     let app = build_router(state);
 
-    let addr = format!("{}:{}", host, port);
+    serve(config, app).await
+}
+
+async fn serve(config: &ApiConfig, app: Router) -> Result<()> {
+    let app = with_auth(app, config);
+
+    let addr = format!("{}:{}", config.host, config.port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
 
     tracing::info!("API server listening on {}", addr);
@@ -419,4 +449,31 @@ mod tests {
         let response = health().await;
         assert_eq!(response.0.status, "healthy");
     }
+
+    #[tokio::test]
+    async fn test_api_key_is_required_when_auth_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ApiConfig {
+            auth_enabled: true,
+            api_key: Some("secret".to_string()),
+            ..ApiConfig::default()
+        };
+        let app = with_auth(build_router(ApiState::new(dir.path())), &config);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = reqwest::Client::new();
+        let status = |authorization: Option<&str>| {
+            let mut req = client.get(&url);
+            if let Some(authorization) = authorization {
+                req = req.header("Authorization", authorization);
+            }
+            async move { req.send().await.unwrap().status().as_u16() }
+        };
+
+        assert_eq!(status(None).await, 401);
+        assert_eq!(status(Some("Bearer wrong")).await, 401);
+        assert_eq!(status(Some("Bearer secret")).await, 200);
+    }
 }
@@ -20,6 +20,7 @@ pub use hydra_handlers::{
 pub use openapi::{generate_openapi_spec, OpenApiSpec};
 pub use server::ApiServer;
 
+use crate::config::Config;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -58,6 +59,20 @@ impl Default for ApiConfig {
     }
 }
 
+impl ApiConfig {
+    /// API settings from the `[api]` and `[magneto]` sections of magneto.toml
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            host: config.api.host.clone(),
+            port: config.api.port,
+            proxy_port: config.magneto.proxy_port,
+            cassette_dir: config.magneto.cassette_dir.display().to_string(),
+            auth_enabled: config.api.auth_enabled,
+            api_key: config.api.api_key.clone(),
+        }
+    }
+}
+
 /// Proxy status response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyStatus {
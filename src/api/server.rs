@@ -6,6 +6,7 @@ use super::{
     ApiConfig, ApiResponse, CassetteInfo, HydraLink, HydraOperation, ProxyStats, ProxyStatus,
     StartProxyRequest, StopProxyRequest,
 };
+use crate::config::Config;
 use crate::proxy::MagnetoProxy;
 use crate::Result;
 use hyper::service::{make_service_fn, service_fn};
@@ -22,6 +23,9 @@ pub struct ApiServer {
     /// Configuration
     config: ApiConfig,
 
+    /// Settings of the proxies started through the API
+    proxy_config: Config,
+
     /// Proxy instance
     proxy: Arc<RwLock<Option<Arc<MagnetoProxy>>>>,
 
@@ -37,6 +41,7 @@ impl ApiServer {
     pub fn new(config: ApiConfig) -> Self {
         Self {
             config,
+            proxy_config: Config::default(),
             proxy: Arc::new(RwLock::new(None)),
             start_time: std::time::Instant::now(),
             stats: Arc::new(RwLock::new(ProxyStats {
@@ -51,6 +56,14 @@ impl ApiServer {
         }
     }
 
+    /// Build the proxies started through the API from `config`
+    ///
+    /// The cassette directory and default port still come from [`ApiConfig`].
+    pub fn with_proxy_config(mut self, config: Config) -> Self {
+        self.proxy_config = config;
+        self
+    }
+
     /// Start the API server
     pub async fn start(self) -> Result<()> {
         let addr: SocketAddr = format!("{}:{}", self.config.host, self.config.port)
@@ -266,12 +279,14 @@ impl ApiServer {
         }
 
         // Create proxy
-        let cassette_dir = PathBuf::from(&self.config.cassette_dir);
-        let proxy = MagnetoProxy::new_internal(&cassette_dir).map_err(|e| {
-            crate::MatgtoError::ProxyStartFailed {
-                reason: format!("Failed to create proxy: {}", e),
-            }
-        })?;
+        let mut proxy_config = self.proxy_config.clone();
+        proxy_config.magneto.cassette_dir = PathBuf::from(&self.config.cassette_dir);
+        let proxy =
+            proxy_config
+                .build_proxy()
+                .map_err(|e| crate::MatgtoError::ProxyStartFailed {
+                    reason: format!("Failed to create proxy: {}", e),
+                })?;
 
         let proxy = Arc::new(proxy);
         let port = start_req.port.unwrap_or(self.config.proxy_port);
@@ -464,17 +479,9 @@ impl ApiServer {
                 if let Ok(metadata) = entry.metadata() {
                     if metadata.is_file() {
                         if let Some(name) = entry.file_name().to_str() {
-                            if name.ends_with(".json") || name.ends_with(".msgpack") {
-                                let format = if name.ends_with(".json.gz") {
-                                    "json.gz"
-                                } else if name.ends_with(".msgpack.gz") {
-                                    "msgpack.gz"
-                                } else if name.ends_with(".json") {
-                                    "json"
-                                } else {
-                                    "msgpack"
-                                };
-
+                            if let Some((_, format)) =
+                                crate::cassette::storage::parse_cassette_file_name(name)
+                            {
                                 cassettes.push(CassetteInfo {
                                     name: name.to_string(),
                                     size_bytes: metadata.len(),
@@ -483,7 +490,7 @@ impl ApiServer {
                                         metadata.created().unwrap_or(std::time::SystemTime::now()),
                                     )
                                     .to_rfc3339(),
-                                    format: format.to_string(),
+                                    format: format.extension().to_string(),
                                 });
                             }
                         }
@@ -11,6 +11,7 @@
 
 #![allow(clippy::too_many_arguments)]
 //! - `export`   - Export cassettes to different formats
+//! - `pending`  - Review interactions hybrid mode left pending
 //! - `serve`    - Start REST API server
 //! - `migrate`  - Migrate cassettes between versions
 //! - `replay`   - Replay mode (use cassettes without recording)
@@ -23,7 +24,13 @@ use colored::*;
 use magneto_serge::api::handlers::start_server;
 #[cfg(feature = "hydra")]
 use magneto_serge::api::handlers::start_server_with_hydra;
-use magneto_serge::{api::cassettes::CassetteManager, error::Result};
+use magneto_serge::api::ApiConfig;
+use magneto_serge::cassette::{pending, storage, InteractionKind, CASSETTE_VERSION};
+use magneto_serge::config::{Config, LogLevel};
+use magneto_serge::proxy::upstream::parse_no_proxy;
+use magneto_serge::{
+    api::cassettes::CassetteManager, error::MatgtoError, error::Result, MagnetoProxy,
+};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -31,9 +38,14 @@ use std::path::PathBuf;
 #[command(about = "Magnéto-Serge - HTTP/WebSocket testing tool", long_about = None)]
 #[command(version, author)]
 struct Cli {
-    /// Cassette directory path
-    #[arg(short, long, default_value = "./cassettes")]
-    cassette_dir: PathBuf,
+    /// Configuration file (default: ./magneto.toml, then
+    /// $XDG_CONFIG_HOME/magneto/magneto.toml)
+    #[arg(long, global = true, env = "MAGNETO_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Cassette directory path [default: magneto.cassette_dir]
+    #[arg(short, long)]
+    cassette_dir: Option<PathBuf>,
 
     /// Output format: text, json, table
     #[arg(short = 'f', long, default_value = "table")]
@@ -145,15 +157,33 @@ enum Commands {
         format: String,
     },
 
+    /// Review interactions hybrid mode left pending (list, accept or reject)
+    Pending {
+        /// Cassette name (without extension)
+        name: String,
+
+        /// Append the pending interactions to the cassette
+        #[arg(short, long, conflicts_with = "reject")]
+        accept: bool,
+
+        /// Discard the pending interactions
+        #[arg(short, long)]
+        reject: bool,
+
+        /// Only review the pending interaction at this index (repeatable)
+        #[arg(short, long = "index")]
+        indices: Vec<usize>,
+    },
+
     /// Start REST API server
     Serve {
-        /// Server host
-        #[arg(short = 'H', long, default_value = "127.0.0.1")]
-        host: String,
+        /// Server host [default: api.host]
+        #[arg(short = 'H', long)]
+        host: Option<String>,
 
-        /// Server port
-        #[arg(short, long, default_value = "8889")]
-        port: u16,
+        /// Server port [default: api.port]
+        #[arg(short, long)]
+        port: Option<u16>,
     },
 
     /// Migrate cassettes between versions
@@ -176,9 +206,12 @@ enum Commands {
 
     /// Replay mode (use cassettes without recording)
     Replay {
-        /// Proxy port
-        #[arg(short, long, default_value = "8888")]
-        port: u16,
+        /// Cassette name (without extension)
+        name: String,
+
+        /// Proxy port [default: magneto.proxy_port]
+        #[arg(short, long)]
+        port: Option<u16>,
 
         /// Strict mode (error on missing interactions)
         #[arg(short, long)]
@@ -190,17 +223,33 @@ enum Commands {
         /// Cassette name (without extension)
         name: String,
 
-        /// Proxy port
-        #[arg(short, long, default_value = "8888")]
-        port: u16,
+        /// Proxy port [default: magneto.proxy_port]
+        #[arg(short, long)]
+        port: Option<u16>,
 
-        /// Enable filtering
+        /// Enable filtering (preset from [recording.filters])
         #[arg(short, long)]
         filter: bool,
 
         /// Overwrite existing cassette
         #[arg(short, long)]
         overwrite: bool,
+
+        /// Upstream proxy to reach real servers through (http:// or socks5://)
+        #[arg(long, env = "HTTPS_PROXY")]
+        upstream_proxy: Option<String>,
+
+        /// Hosts reached without the upstream proxy (comma separated)
+        #[arg(long, env = "NO_PROXY")]
+        no_proxy: Option<String>,
+
+        /// Extra CA certificates (PEM) to trust upstream (repeatable)
+        #[arg(long = "ca-bundle")]
+        ca_bundles: Vec<PathBuf>,
+
+        /// Accept any upstream certificate (throwaway environments only)
+        #[arg(long)]
+        insecure_skip_verify: bool,
     },
 
     /// Initialize magneto.toml configuration
@@ -222,14 +271,18 @@ async fn main() {
 async fn run() -> Result<()> {
     let cli = Cli::parse();
 
-    // Initialize logging if verbose
+    let mut config = Config::load(cli.config.as_deref())?;
+    if let Some(cassette_dir) = cli.cassette_dir {
+        config.magneto.cassette_dir = cassette_dir;
+    }
     if cli.verbose {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::DEBUG)
-            .init();
+        config.logging.level = LogLevel::Debug;
+        config.logging.filter = None;
     }
+    config.logging.init()?;
 
-    let manager = CassetteManager::new(&cli.cassette_dir);
+    let cassette_dir = config.magneto.cassette_dir.clone();
+    let manager = CassetteManager::new(&cassette_dir);
 
     match cli.command {
         Commands::List {
@@ -277,8 +330,23 @@ async fn run() -> Result<()> {
             cmd_export(&manager, &name, &output, &format)?;
         }
 
+        Commands::Pending {
+            name,
+            accept,
+            reject,
+            indices,
+        } => {
+            cmd_pending(&config, &name, accept, reject, &indices)?;
+        }
+
         Commands::Serve { host, port } => {
-            cmd_serve(&host, port, &cli.cassette_dir).await?;
+            if let Some(host) = host {
+                config.api.host = host;
+            }
+            if let Some(port) = port {
+                config.api.port = port;
+            }
+            cmd_serve(&ApiConfig::from_config(&config)).await?;
         }
 
         Commands::Migrate {
@@ -290,8 +358,12 @@ async fn run() -> Result<()> {
             cmd_migrate(&manager, &from, &to, &name, backup)?;
         }
 
-        Commands::Replay { port, strict } => {
-            cmd_replay(port, strict, &cli.cassette_dir)?;
+        Commands::Replay { name, port, strict } => {
+            if let Some(port) = port {
+                config.magneto.proxy_port = port;
+            }
+            config.magneto.strict |= strict;
+            cmd_replay(&config, &name)?;
         }
 
         Commands::Record {
@@ -299,8 +371,23 @@ async fn run() -> Result<()> {
             port,
             filter,
             overwrite,
+            upstream_proxy,
+            no_proxy,
+            ca_bundles,
+            insecure_skip_verify,
         } => {
-            cmd_record(&name, port, filter, overwrite, &cli.cassette_dir)?;
+            if let Some(port) = port {
+                config.magneto.proxy_port = port;
+            }
+            config.recording.filters.enabled |= filter;
+            if let Some(url) = upstream_proxy {
+                config.upstream_proxy.url = Some(url);
+                config.upstream_proxy.no_proxy =
+                    parse_no_proxy(no_proxy.as_deref().unwrap_or_default());
+            }
+            config.upstream_tls.ca_bundles.extend(ca_bundles);
+            config.upstream_tls.insecure_skip_verify |= insecure_skip_verify;
+            cmd_record(&config, &name, overwrite)?;
         }
 
         Commands::Init { force } => {
@@ -674,6 +761,11 @@ fn cmd_stats(manager: &CassetteManager, name: &str, format: &OutputFormat) -> Re
                     "WebSocket Messages: {}",
                     stats.websocket_count.to_string().bright_blue()
                 );
+                println!(
+                    "Event Streams: {}",
+                    stats.sse_count.to_string().bright_blue()
+                );
+                println!("gRPC Calls: {}", stats.grpc_count.to_string().bright_blue());
                 println!(
                     "HTTP Errors: {}",
                     stats.http_error_count.to_string().bright_red()
@@ -774,47 +866,148 @@ fn cmd_export(manager: &CassetteManager, name: &str, output: &PathBuf, format: &
     Ok(())
 }
 
+/// List, accept or reject pending interactions
+fn cmd_pending(
+    config: &Config,
+    name: &str,
+    accept: bool,
+    reject: bool,
+    indices: &[usize],
+) -> Result<()> {
+    let cassette_dir = config.magneto.cassette_dir.as_path();
+    let format = config.recording.cassette_format()?;
+    let selection = (!indices.is_empty()).then_some(indices);
+
+    if accept || reject {
+        let review = if accept {
+            pending::accept_pending(cassette_dir, name, format, selection)?
+        } else {
+            pending::reject_pending(cassette_dir, name, format, selection)?
+        };
+
+        println!(
+            "{} {} {} pending interaction(s) {} '{}' ({} still pending)",
+            "✅".green(),
+            if accept { "Accepted" } else { "Rejected" },
+            review.reviewed,
+            if accept { "into" } else { "from" },
+            name.bright_white(),
+            review.remaining
+        );
+        return Ok(());
+    }
+
+    let interactions = pending::load_pending(cassette_dir, name, format)?;
+    if interactions.is_empty() {
+        println!("{} No pending interactions for '{}'", "✅".green(), name);
+        return Ok(());
+    }
+
+    println!(
+        "\n{} {} pending interaction(s) for '{}'\n",
+        "📝".bright_cyan(),
+        interactions.len(),
+        name.bright_white()
+    );
+    for (idx, interaction) in interactions.iter().enumerate() {
+        let summary = match &interaction.kind {
+            InteractionKind::Http { request, response } => {
+                format!("{} {} → {}", request.method, request.url, response.status)
+            }
+            InteractionKind::HttpError { request, error } => {
+                format!("{} {} → {}", request.method, request.url, error)
+            }
+            InteractionKind::ServerSentEvents {
+                request, events, ..
+            } => format!(
+                "{} {} → {} events",
+                request.method,
+                request.url,
+                events.len()
+            ),
+            InteractionKind::WebSocket { url, messages, .. } => {
+                format!("WS {} → {} messages", url, messages.len())
+            }
+            InteractionKind::Grpc { request, .. } => {
+                format!("gRPC {}/{}", request.service, request.method)
+            }
+        };
+        println!(
+            "  {:>3}  {}  {}",
+            idx.to_string().bright_blue(),
+            summary,
+            interaction
+                .recorded_at
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+                .bright_black()
+        );
+    }
+    println!(
+        "\nAccept with `magneto pending {} --accept`, reject with `--reject` (`--index N` to pick)\n",
+        name
+    );
+
+    Ok(())
+}
+
 /// Start API server
-async fn cmd_serve(host: &str, port: u16, cassette_dir: &PathBuf) -> Result<()> {
+async fn cmd_serve(api: &ApiConfig) -> Result<()> {
+    let (host, port) = (&api.host, api.port);
     println!(
         "\n{}",
         "🚀 Starting Magnéto-Serge API Server..."
             .bright_cyan()
             .bold()
     );
-    println!("📂 Cassette directory: {:?}", cassette_dir);
+    println!("📂 Cassette directory: {:?}", api.cassette_dir);
     println!("🌐 Listening on: {}:{}", host, port);
+    if api.auth_enabled {
+        println!("🔐 Authentication: Bearer API key");
+    }
 
     #[cfg(feature = "hydra")]
     {
         println!("📖 REST API: http://{}:{}/cassettes", host, port);
         println!("📖 Hydra API: http://{}:{}/api/cassettes\n", host, port);
         println!("{} Press Ctrl+C to stop\n", "ℹ️ ".blue());
-
-        // Use Hydra-enabled server for full hypermedia support
-        start_server_with_hydra(host, port, cassette_dir).await?;
     }
 
     #[cfg(not(feature = "hydra"))]
     {
         println!("📖 API documentation: http://{}:{}/health\n", host, port);
         println!("{} Press Ctrl+C to stop\n", "ℹ️ ".blue());
-
-        // Use REST API only
-        start_server(host, port, cassette_dir).await?;
     }
 
-    Ok(())
+    serve_api(api).await
+}
+
+/// Serve the REST API (with the Hydra API when built in)
+async fn serve_api(api: &ApiConfig) -> Result<()> {
+    // Use Hydra-enabled server for full hypermedia support
+    #[cfg(feature = "hydra")]
+    return start_server_with_hydra(api).await;
+
+    // Use REST API only
+    #[cfg(not(feature = "hydra"))]
+    return start_server(api).await;
 }
 
 /// Migrate cassettes
 fn cmd_migrate(
-    _manager: &CassetteManager,
+    manager: &CassetteManager,
     from: &str,
     to: &str,
-    _name: &str,
-    _backup: bool,
+    name: &str,
+    backup: bool,
 ) -> Result<()> {
+    if !matches!(from, "1.0" | "1.1") || to != CASSETTE_VERSION {
+        return Err(MatgtoError::Config(format!(
+            "Unsupported migration: v{} → v{} (supported: v1.0 or v1.1 → v{})",
+            from, to, CASSETTE_VERSION
+        )));
+    }
+
     println!(
         "\n{} Migrating cassettes from v{} to v{}\n",
         "🔄".bright_cyan(),
@@ -822,33 +1015,59 @@ fn cmd_migrate(
         to
     );
 
-    if from == "1.0" && to == "2.0" {
-        // TODO: Implement v1.0 → v2.0 migration
-        println!(
-            "{} v1.0 → v2.0 migration not yet implemented",
-            "⚠️ ".yellow()
-        );
-        println!("   This will:");
-        println!("   - Add cookies field");
-        println!("   - Add filtered metadata field");
-        println!("   - Update version number");
+    let names = if name == "all" {
+        manager
+            .list_cassettes()?
+            .into_iter()
+            .map(|metadata| metadata.name)
+            .collect()
     } else {
-        println!("{} Unsupported migration: {} → {}", "❌".red(), from, to);
+        vec![name.to_string()]
+    };
+
+    let mut migrated = 0;
+    for name in &names {
+        let version = manager.load_cassette(name)?.version;
+        if version != from {
+            println!(
+                "  {} {} (v{}, skipped)",
+                "⏭️ ".bright_black(),
+                name,
+                version
+            );
+            continue;
+        }
+
+        manager.migrate_cassette(name, backup)?;
+        migrated += 1;
+        println!("  {} {}", "✅".green(), name.bright_white());
+    }
+
+    println!(
+        "\n{} Migrated {} of {} cassette(s) to v{}",
+        "✨".bright_green(),
+        migrated,
+        names.len(),
+        to
+    );
+    if backup && migrated > 0 {
+        println!("   Originals kept as <file>.bak");
     }
 
     Ok(())
 }
 
 /// Replay mode
-fn cmd_replay(port: u16, strict: bool, cassette_dir: &PathBuf) -> Result<()> {
+fn cmd_replay(config: &Config, name: &str) -> Result<()> {
+    let strict = config.magneto.strict;
     println!(
         "\n{}",
         "▶️  Starting Magnéto-Serge in REPLAY mode"
             .bright_green()
             .bold()
     );
-    println!("📂 Cassette directory: {:?}", cassette_dir);
-    println!("🌐 Proxy port: {}", port);
+    println!("📼 Cassette name: {}", name.bright_white());
+    println!("📂 Cassette directory: {:?}", config.magneto.cassette_dir);
     println!(
         "📏 Strict mode: {}",
         if strict {
@@ -857,27 +1076,31 @@ fn cmd_replay(port: u16, strict: bool, cassette_dir: &PathBuf) -> Result<()> {
             "disabled".bright_green()
         }
     );
-    println!(
-        "\n{} Configure your app to use proxy: http://localhost:{}\n",
-        "ℹ️ ".blue(),
-        port
-    );
-    println!(
-        "{} Replay mode not yet fully implemented (TODO: integrate with proxy)\n",
-        "⚠️ ".yellow()
-    );
 
-    Ok(())
+    run_proxy(config, |proxy| {
+        if strict {
+            proxy.replay_strict_internal(name.to_string())
+        } else {
+            proxy.replay_internal(name.to_string())
+        }
+    })
 }
 
 /// Record mode
-fn cmd_record(
-    name: &str,
-    port: u16,
-    filter: bool,
-    overwrite: bool,
-    cassette_dir: &PathBuf,
-) -> Result<()> {
+fn cmd_record(config: &Config, name: &str, overwrite: bool) -> Result<()> {
+    let cassette_dir = &config.magneto.cassette_dir;
+    let format = config.recording.cassette_format()?;
+    if let Some(path) = storage::find_cassette(cassette_dir, name, format) {
+        if !overwrite {
+            return Err(MatgtoError::RecordingFailed {
+                reason: format!(
+                    "{} already exists (use --overwrite to replace it)",
+                    path.display()
+                ),
+            });
+        }
+    }
+
     println!(
         "\n{}",
         "⏺️  Starting Magnéto-Serge in RECORD mode"
@@ -886,10 +1109,9 @@ fn cmd_record(
     );
     println!("📼 Cassette name: {}", name.bright_white());
     println!("📂 Cassette directory: {:?}", cassette_dir);
-    println!("🌐 Proxy port: {}", port);
     println!(
         "🔍 Filtering: {}",
-        if filter {
+        if config.recording.filters.enabled {
             "enabled".bright_green()
         } else {
             "disabled".bright_yellow()
@@ -903,17 +1125,58 @@ fn cmd_record(
             "no".bright_green()
         }
     );
-    println!(
-        "\n{} Configure your app to use proxy: http://localhost:{}\n",
-        "ℹ️ ".blue(),
-        port
-    );
-    println!(
-        "{} Record mode not yet fully implemented (TODO: integrate with proxy)\n",
-        "⚠️ ".yellow()
-    );
+    if let Some(upstream) = config.upstream_proxy.resolve()? {
+        println!("🔗 Upstream proxy: {}", upstream);
+        if !upstream.no_proxy().is_empty() {
+            println!("   Direct: {}", upstream.no_proxy().join(", "));
+        }
+    }
+    for path in &config.upstream_tls.ca_bundles {
+        println!("🔐 Trusted CA bundle: {}", path.display());
+    }
+    if config.upstream_tls.insecure_skip_verify {
+        println!("{} Upstream certificates are NOT verified", "⚠️ ".yellow());
+    }
 
-    Ok(())
+    run_proxy(config, |proxy| {
+        proxy.start_recording_internal(name.to_string())
+    })
+}
+
+/// Build the configured proxy, start it with `begin` and serve until Ctrl+C
+///
+/// The REST API is served alongside when `[api] enabled` is set. Recordings
+/// are saved on the way out.
+fn run_proxy(config: &Config, begin: impl FnOnce(&MagnetoProxy) -> Result<()>) -> Result<()> {
+    // The proxy owns a runtime, which must not be dropped from async code
+    tokio::task::block_in_place(|| {
+        let proxy = config.build_proxy()?;
+        begin(&proxy)?;
+
+        println!(
+            "\n{} Configure your app to use proxy: http://localhost:{}",
+            "ℹ️ ".blue(),
+            proxy.port()
+        );
+        if config.api.enabled {
+            let api = ApiConfig::from_config(config);
+            println!(
+                "{} REST API: http://{}:{}",
+                "ℹ️ ".blue(),
+                api.host,
+                api.port
+            );
+            tokio::spawn(async move {
+                if let Err(e) = serve_api(&api).await {
+                    eprintln!("{} REST API stopped: {}", "Error:".red().bold(), e);
+                }
+            });
+        }
+        println!("{} Press Ctrl+C to stop\n", "ℹ️ ".blue());
+
+        tokio::runtime::Handle::current().block_on(proxy.start())?;
+        proxy.shutdown_internal()
+    })
 }
 
 /// Initialize configuration
@@ -939,7 +1202,7 @@ cassette_dir = "./cassettes"
 # Default proxy port
 proxy_port = 8888
 
-# Default mode: auto, record, replay, passthrough
+# Default mode: auto, record, replay, strict, hybrid, once, passthrough
 mode = "auto"
 
 # Strict mode for replay (error if interaction not found)
@@ -952,35 +1215,163 @@ ignore_headers = ["User-Agent", "Date", "X-Request-Id", "Accept-Encoding"]
 # Query parameters to ignore
 ignore_query_params = ["timestamp", "_t", "cache_bust"]
 
-[recording]
-# Headers to filter from cassettes (sensitive data)
-filter_headers = ["Authorization", "X-API-Key", "Cookie", "Set-Cookie"]
+# URL matching: exact, regex (with url_pattern), ignore_query, path_only
+url_mode = "exact"
+# url_pattern = "^https://api\\.example\\.com/users/\\d+$"
+
+# Body matching: hash, ignore, json_path (with body_pattern), regex, size_only
+body_mode = "hash"
+# body_pattern = "user.id"
+
+# Headers that must be equal for a request to match
+# match_headers = ["Accept"]
 
+[recording]
 # Compress cassettes with gzip
 compress = false
 
 # Format: json or msgpack
 format = "json"
 
-[filters]
-# Enable smart filtering to reduce cassette size
-enabled = true
+# Hybrid mode: write new interactions to <name>.pending.json instead of the
+# cassette; review them with `magneto pending <name> --accept/--reject`
+pending_review = false
+
+[upstream_proxy]
+# Reach real servers through a corporate proxy (http:// or socks5://)
+# url = "http://proxy.example.com:3128"
+# username = "ci"
+# password = "secret"
+# no_proxy = ["localhost", "127.0.0.1", ".internal.example.com"]
+
+# Use HTTPS_PROXY / HTTP_PROXY / NO_PROXY when url is not set
+from_env = false
+
+[upstream_tls]
+# Extra CA certificates (PEM) trusted on top of the system roots
+# ca_bundles = ["certs/internal-ca.pem"]
+
+# Accept any upstream certificate - throwaway environments only
+insecure_skip_verify = false
+
+# Client certificate (mTLS) presented to matching hosts
+# [[upstream_tls.client_certs]]
+# hosts = ["staging.example.com", "*.staging.example.com"]
+# cert = "certs/client.pem"
+# key = "certs/client-key.pem"
+
+[reverse_proxy]
+# Serve http://localhost:PORT/ as a reverse proxy instead of a MITM proxy:
+# apps change their base URL, no proxy setting or CA needed.
+# Routes match by path prefix (stripped) or Host header; the most specific wins.
+# [[reverse_proxy.routes]]
+# prefix = "/"
+# upstream = "https://api.example.com"
+#
+# [[reverse_proxy.routes]]
+# host = "payments.localhost"
+# upstream = "https://api.stripe.com/v1"
+
+[grpc]
+# gRPC calls are recorded message by message (service, method, trailers).
+# Descriptor sets (protoc --include_imports --descriptor_set_out=api.desc)
+# store messages decoded to JSON and match them on content.
+# Requires the `grpc` feature.
+# descriptor_sets = ["protos/api.desc"]
+
+[ignore]
+# Hosts forwarded live in every mode, never recorded nor replayed
+# (your own test server, telemetry, auth providers...).
+# Rules: exact host (optionally host:port), *.domain, CIDR, URL prefix.
+localhost = false
+# hosts = ["auth.example.com", "*.sentry.io", "10.0.0.0/8"]
+
+[sensitive_data]
+# Values replaced with placeholders (<API_KEY>) before anything is recorded.
+# Live requests are redacted the same way when matching, and placeholders in
+# replayed responses get the live values back.
+# headers = ["authorization", "x-api-key"]
+# query_params = ["api_key", "access_token"]
+# json_paths = ["password", "auth.token", "users.*.ssn"]
+# Environment variables whose values are replaced wherever they appear
+# env = ["STRIPE_SECRET_KEY"]
+#
+# [[sensitive_data.patterns]]
+# regex = "sk_live_[A-Za-z0-9]+"
+# placeholder = "<STRIPE_KEY>"
 
-# Preset: web_assets, api_only, minimal, or custom
+[re_record]
+# Re-fetch interactions older than this many days from upstream in auto,
+# hybrid and once modes, replacing them in the cassette (fresh ones replay).
+# interval_days = 30
+
+# Per-cassette intervals in days, overriding interval_days
+# [re_record.cassettes]
+# auth = 7
+
+[recording.filters]
+# Leave static assets and noise out of recordings (`magneto record --filter`)
+enabled = false
+
+# Preset: web_assets, images, fonts, comprehensive, none
 preset = "web_assets"
 
-# Custom extensions to exclude (if preset = "custom")
-exclude_extensions = [".js", ".css", ".png", ".jpg", ".woff2", ".svg"]
+# Extra extensions to exclude, on top of the preset
+exclude_extensions = [".map"]
 
 # Status codes to exclude
 exclude_status_codes = [404, 500, 502, 503]
 
+# Largest body to record, in KB
+# max_body_size_kb = 1024
+
+[cookies]
+# Store Set-Cookie values in the cassette and replay them through the cookie jar
+enabled = true
+
+# Sensitive cookie names (regex), dropped from recorded headers
+filter_patterns = [".*_token$", "secret_.*", "api_key.*"]
+
+# Keep sensitive cookies with a SHA-256 hashed value instead of dropping them
+hash_sensitive = false
+
+[replay]
+# Latency simulation: none, recorded, fixed, scaled
+latency_mode = "none"
+fixed_latency_ms = 100
+latency_scale_percent = 100
+
+# Once a repeated request's responses are used up: repeat_last, cycle, error
+# when_exhausted = "repeat_last"
+
+[websocket]
+# Record and replay WebSocket sessions (forwarded live when false)
+enabled = true
+
 [api]
-# REST API server configuration
+# REST API server configuration (`magneto serve`)
+# Also serve it alongside `magneto record` and `magneto replay`
 enabled = false
 host = "127.0.0.1"
 port = 8889
 auth_enabled = false
+# api_key = "your-secret-key-here"
+
+[logging]
+# Log level: trace, debug, info, warn, error (`--verbose` forces debug)
+level = "info"
+
+# Log format: text, json
+format = "text"
+
+# Log file (stderr when not set)
+# file = "./magneto.log"
+
+# Filter in RUST_LOG syntax, overriding level
+# filter = "magneto_serge=debug,hyper=warn"
+
+# Every key can be overridden with a MAGNETO_* environment variable, e.g.
+# MAGNETO_PROXY_PORT=9000 or MAGNETO_MATCHING_IGNORE_HEADERS=date,user-agent
 "#;
     std::fs::write(config_path, default_config)?;
 
@@ -992,8 +1383,9 @@ auth_enabled = false
     println!("  - Cassette directory");
     println!("  - Proxy port");
     println!("  - Filtering rules");
+    println!("  - Request matching");
     println!("  - Cookie preservation");
-    println!("  - API server settings\n");
+    println!("  - API server and logging settings\n");
 
     Ok(())
 }
@@ -1,29 +1,34 @@
 //! Magnéto-Serge REST API Server
 //!
-//! Standalone API server for cassette management.
+//! Standalone API server for cassette management. Settings come from
+//! magneto.toml (or the file named by `MAGNETO_CONFIG`) and the `MAGNETO_*`
+//! environment variables.
 
 use magneto_serge::api::handlers::start_server;
+use magneto_serge::api::ApiConfig;
+use magneto_serge::Config;
 use std::env;
+use std::path::PathBuf;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
+    let config_path = env::var_os("MAGNETO_CONFIG").map(PathBuf::from);
+    let config = Config::load(config_path.as_deref())?;
+    config.logging.init()?;
 
-    let host = env::var("MAGNETO_API_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-    let port = env::var("MAGNETO_API_PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(8889);
-    let cassette_dir =
-        env::var("MAGNETO_CASSETTE_DIR").unwrap_or_else(|_| "./cassettes".to_string());
+    let api = ApiConfig::from_config(&config);
+    let (host, port) = (&api.host, api.port);
 
     println!("🚀 Magnéto-Serge API Server v0.2.0");
     println!("   Listening on http://{}:{}", host, port);
-    println!("   Cassette directory: {}", cassette_dir);
+    println!("   Cassette directory: {}", api.cassette_dir);
     println!("   Health check: http://{}:{}/health", host, port);
+    if api.auth_enabled {
+        println!("   Authentication: Bearer API key");
+    }
     println!();
 
-    start_server(&host, port, cassette_dir).await?;
+    start_server(&api).await?;
 
     Ok(())
 }
@@ -624,6 +624,8 @@ fn cmd_stats(manager: &CassetteManager, name: &str, format: &OutputFormat) -> Re
                 println!("Total Interactions: {}", stats.total_interactions.to_string().bright_white());
                 println!("HTTP Requests: {}", stats.http_count.to_string().bright_green());
                 println!("WebSocket Messages: {}", stats.websocket_count.to_string().bright_blue());
+                println!("Event Streams: {}", stats.sse_count.to_string().bright_blue());
+                println!("gRPC Calls: {}", stats.grpc_count.to_string().bright_blue());
                 println!("HTTP Errors: {}", stats.http_error_count.to_string().bright_red());
 
                 println!("\nHTTP Methods:");
@@ -0,0 +1,186 @@
+//! Serialization of HTTP request and response bodies
+//!
+//! Cassette v2 stores a body as a single-key object tagged with its
+//! representation, so recorded exchanges can be read and reviewed:
+//!
+//! ```json
+//! "body": {"json": {"users": ["alice", "bob"]}}
+//! "body": {"text": "<html>...</html>"}
+//! "body": {"base64": "iVBORw0KGgo="}
+//! ```
+//!
+//! A body is embedded as JSON only when re-serializing the value gives back
+//! the exact recorded bytes, so replay is byte-for-byte identical; other
+//! UTF-8 bodies are stored as text and everything else as base64.
+//!
+//! Cassette v1 stored bodies as arrays of byte values; they are still
+//! accepted on load.
+//!
+//! Used through `#[serde(with = "body")]` on `Option<Vec<u8>>` fields.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserializer, Serializer};
+use std::fmt;
+
+const JSON: &str = "json";
+const TEXT: &str = "text";
+const BASE64_TAG: &str = "base64";
+
+pub fn serialize<S: Serializer>(body: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+    let Some(body) = body else {
+        return serializer.serialize_none();
+    };
+
+    let mut map = serializer.serialize_map(Some(1))?;
+    if let Some(value) = as_json(body) {
+        map.serialize_entry(JSON, &value)?;
+    } else if let Ok(text) = std::str::from_utf8(body) {
+        map.serialize_entry(TEXT, text)?;
+    } else {
+        map.serialize_entry(BASE64_TAG, &BASE64.encode(body))?;
+    }
+    map.end()
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<u8>>, D::Error> {
+    deserializer.deserialize_option(OptionVisitor)
+}
+
+/// The body as a JSON value, if serializing that value yields the same bytes
+fn as_json(body: &[u8]) -> Option<serde_json::Value> {
+    let first = body.iter().find(|b| !b.is_ascii_whitespace())?;
+    if !matches!(first, b'{' | b'[') {
+        return None;
+    }
+
+    let value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let bytes = serde_json::to_vec(&value).ok()?;
+    (bytes == body).then_some(value)
+}
+
+struct OptionVisitor;
+
+impl<'de> Visitor<'de> for OptionVisitor {
+    type Value = Option<Vec<u8>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an optional body")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(BodyVisitor).map(Some)
+    }
+}
+
+/// Accepts the v2 tagged object, the v1 array of bytes and raw bytes
+struct BodyVisitor;
+
+impl<'de> Visitor<'de> for BodyVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a {\"json\"|\"text\"|\"base64\": ...} object or an array of bytes")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Vec<u8>, A::Error> {
+        let tag: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let body = match tag.as_str() {
+            JSON => serde_json::to_vec(&map.next_value::<serde_json::Value>()?)
+                .map_err(de::Error::custom)?,
+            TEXT => map.next_value::<String>()?.into_bytes(),
+            BASE64_TAG => BASE64
+                .decode(map.next_value::<String>()?.as_bytes())
+                .map_err(de::Error::custom)?,
+            other => return Err(de::Error::unknown_field(other, &[JSON, TEXT, BASE64_TAG])),
+        };
+
+        if map.next_key::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::custom("a body has exactly one representation"));
+        }
+        Ok(body)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut body = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            body.push(byte);
+        }
+        Ok(body)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Message {
+        #[serde(default, with = "super")]
+        body: Option<Vec<u8>>,
+    }
+
+    fn roundtrip(body: &[u8]) -> String {
+        let message = Message {
+            body: Some(body.to_vec()),
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        let back: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, message);
+        json
+    }
+
+    #[test]
+    fn test_tagged_representations() {
+        assert_eq!(
+            roundtrip(br#"{"users":["alice","bob"],"total":2}"#),
+            r#"{"body":{"json":{"users":["alice","bob"],"total":2}}}"#
+        );
+        // Not byte-identical once re-serialized: kept as text
+        assert_eq!(
+            roundtrip(b"{ \"a\": 1.50 }"),
+            r#"{"body":{"text":"{ \"a\": 1.50 }"}}"#
+        );
+        assert_eq!(roundtrip(b"hello"), r#"{"body":{"text":"hello"}}"#);
+        assert_eq!(
+            roundtrip(&[0x89, 0x50, 0x4e, 0x47]),
+            r#"{"body":{"base64":"iVBORw=="}}"#
+        );
+
+        let none = serde_json::to_string(&Message { body: None }).unwrap();
+        assert_eq!(none, r#"{"body":null}"#);
+        assert_eq!(serde_json::from_str::<Message>(&none).unwrap().body, None);
+    }
+
+    #[test]
+    fn test_deserializes_v1_byte_array() {
+        let message: Message = serde_json::from_str(r#"{"body":[104,105]}"#).unwrap();
+        assert_eq!(message.body.as_deref(), Some(b"hi".as_slice()));
+
+        let message: Message = serde_json::from_str("{}").unwrap();
+        assert_eq!(message.body, None);
+
+        assert!(serde_json::from_str::<Message>(r#"{"body":{"hex":"00"}}"#).is_err());
+    }
+}
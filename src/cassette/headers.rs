@@ -0,0 +1,342 @@
+//! Ordered, multi-valued HTTP headers
+//!
+//! Headers keep every occurrence in wire order, so repeated `Set-Cookie`,
+//! `Link`, `Vary` or `Via` headers survive a record/replay round trip, and
+//! values that are not valid UTF-8 are kept as raw bytes.
+//!
+//! ## Serialization
+//!
+//! Cassette v2 stores headers as a list of `[name, value]` pairs. Values
+//! that are not UTF-8 are written as `{"base64": "..."}`:
+//!
+//! ```json
+//! "headers": [
+//!   ["set-cookie", "session=abc"],
+//!   ["set-cookie", "theme=dark"],
+//!   ["x-raw", {"base64": "/w=="}]
+//! ]
+//! ```
+//!
+//! Cassette v1 stored a `{name: value}` map; it is still accepted on load.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A single header line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// Header name, as received
+    pub name: String,
+
+    /// Raw header value
+    pub value: Vec<u8>,
+}
+
+impl Header {
+    /// Value as text (None if it is not valid UTF-8)
+    pub fn value_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.value).ok()
+    }
+}
+
+/// Ordered, multi-valued HTTP headers
+///
+/// Name lookups are case-insensitive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers {
+    entries: Vec<Header>,
+}
+
+impl Headers {
+    /// Create empty headers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of header lines (duplicates counted separately)
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no headers
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// First value of a header as text
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case(name))
+            .find_map(Header::value_str)
+    }
+
+    /// Every text value of a header, in order
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |h| h.name.eq_ignore_ascii_case(name))
+            .filter_map(Header::value_str)
+    }
+
+    /// First raw value of a header
+    pub fn get_raw(&self, name: &str) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_slice())
+    }
+
+    /// Whether a header is present
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|h| h.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Set a header, replacing every existing value
+    ///
+    /// The header keeps the position of its first occurrence.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.insert_raw(name, value.into().into_bytes());
+    }
+
+    /// Set a header to a raw value, replacing every existing value
+    pub fn insert_raw(&mut self, name: impl Into<String>, value: Vec<u8>) {
+        let name = name.into();
+        match self
+            .entries
+            .iter()
+            .position(|h| h.name.eq_ignore_ascii_case(&name))
+        {
+            Some(first) => {
+                // Entries before `first` never match, so its index survives the retain
+                let mut index = 0;
+                self.entries.retain(|h| {
+                    let keep = index == first || !h.name.eq_ignore_ascii_case(&name);
+                    index += 1;
+                    keep
+                });
+                self.entries[first] = Header { name, value };
+            }
+            None => self.entries.push(Header { name, value }),
+        }
+    }
+
+    /// Add a header line, keeping existing values
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.append_raw(name, value.into().into_bytes());
+    }
+
+    /// Add a header line with a raw value, keeping existing values
+    pub fn append_raw(&mut self, name: impl Into<String>, value: Vec<u8>) {
+        self.entries.push(Header {
+            name: name.into(),
+            value,
+        });
+    }
+
+    /// Remove every value of a header, returning the first one as text
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let removed = self
+            .get_raw(name)
+            .map(|v| String::from_utf8_lossy(v).into_owned());
+        self.entries.retain(|h| !h.name.eq_ignore_ascii_case(name));
+        removed
+    }
+
+    /// Keep only the header lines for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&Header) -> bool) {
+        self.entries.retain(|h| keep(h));
+    }
+
+    /// Header lines with text values, in order
+    ///
+    /// Values that are not valid UTF-8 are skipped; use `entries` to see them.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .filter_map(|h| h.value_str().map(|v| (h.name.as_str(), v)))
+    }
+
+    /// Every header line, including raw values
+    pub fn entries(&self) -> &[Header] {
+        &self.entries
+    }
+
+    /// Distinct header names, in order of first appearance
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        let mut seen: Vec<&str> = Vec::new();
+        self.entries.iter().filter_map(move |h| {
+            if seen.iter().any(|s| s.eq_ignore_ascii_case(&h.name)) {
+                None
+            } else {
+                seen.push(&h.name);
+                Some(h.name.as_str())
+            }
+        })
+    }
+}
+
+impl<'a> IntoIterator for &'a Headers {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Box<dyn Iterator<Item = (&'a str, &'a str)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut headers = Headers::new();
+        for (name, value) in iter {
+            headers.append(name, value);
+        }
+        headers
+    }
+}
+
+impl<K: Into<String>, V: Into<String>, const N: usize> From<[(K, V); N]> for Headers {
+    fn from(pairs: [(K, V); N]) -> Self {
+        pairs.into_iter().collect()
+    }
+}
+
+impl From<HashMap<String, String>> for Headers {
+    fn from(map: HashMap<String, String>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+/// Serialized form of one header value
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum WireValue {
+    Text(String),
+    Binary { base64: String },
+}
+
+impl Serialize for Headers {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.entries.len()))?;
+        for header in &self.entries {
+            let value = match header.value_str() {
+                Some(text) => WireValue::Text(text.to_string()),
+                None => WireValue::Binary {
+                    base64: BASE64.encode(&header.value),
+                },
+            };
+            seq.serialize_element(&(&header.name, value))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(HeadersVisitor)
+    }
+}
+
+/// Accepts both the v2 list of pairs and the v1 map
+struct HeadersVisitor;
+
+impl<'de> Visitor<'de> for HeadersVisitor {
+    type Value = Headers;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of [name, value] pairs or a map of header values")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Headers, A::Error> {
+        let mut headers = Headers::new();
+        while let Some((name, value)) = seq.next_element::<(String, WireValue)>()? {
+            let value = match value {
+                WireValue::Text(text) => text.into_bytes(),
+                WireValue::Binary { base64 } => BASE64
+                    .decode(base64.as_bytes())
+                    .map_err(de::Error::custom)?,
+            };
+            headers.append_raw(name, value);
+        }
+        Ok(headers)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Headers, A::Error> {
+        let mut headers = Headers::new();
+        while let Some((name, value)) = map.next_entry::<String, String>()? {
+            headers.append(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_duplicates_in_order() {
+        let mut headers = Headers::new();
+        headers.append("set-cookie", "a=1");
+        headers.append("content-type", "text/html");
+        headers.append("Set-Cookie", "b=2");
+
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers.get("SET-COOKIE"), Some("a=1"));
+        assert_eq!(
+            headers.get_all("set-cookie").collect::<Vec<_>>(),
+            vec!["a=1", "b=2"]
+        );
+        assert_eq!(
+            headers.keys().collect::<Vec<_>>(),
+            vec!["set-cookie", "content-type"]
+        );
+    }
+
+    #[test]
+    fn test_insert_replaces_every_value() {
+        let mut headers: Headers = [("via", "1.1 a"), ("accept", "*/*"), ("via", "1.1 b")].into();
+
+        headers.insert("Via", "1.1 c");
+
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            vec![("Via", "1.1 c"), ("accept", "*/*")]
+        );
+        assert_eq!(headers.remove("via"), Some("1.1 c".to_string()));
+        assert!(!headers.contains_key("via"));
+    }
+
+    #[test]
+    fn test_serde_roundtrip_with_raw_bytes() {
+        let mut headers = Headers::new();
+        headers.append("link", "</a>; rel=next");
+        headers.append("link", "</b>; rel=prev");
+        headers.append_raw("x-raw", vec![0xff, 0x00]);
+
+        let json = serde_json::to_string(&headers).unwrap();
+        assert_eq!(
+            json,
+            r#"[["link","</a>; rel=next"],["link","</b>; rel=prev"],["x-raw",{"base64":"/wA="}]]"#
+        );
+
+        let back: Headers = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, headers);
+        assert_eq!(back.get_raw("x-raw"), Some(&[0xff, 0x00][..]));
+        assert_eq!(back.get("x-raw"), None);
+    }
+
+    #[test]
+    fn test_deserializes_v1_map() {
+        let headers: Headers =
+            serde_json::from_str(r#"{"content-type": "application/json"}"#).unwrap();
+
+        assert_eq!(headers.get("Content-Type"), Some("application/json"));
+    }
+}
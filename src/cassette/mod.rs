@@ -1,14 +1,31 @@
 // ! Cassette format definitions and types
 
+mod body;
+pub mod headers;
+pub mod pending;
 pub mod storage;
 
 use crate::cookies::Cookie;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+
+pub use headers::{Header, Headers};
 
 // Re-export storage types
-pub use storage::{detect_format, AsyncCassetteStorage, BufferedCassetteWriter, CassetteFormat};
+pub use storage::{
+    detect_format, find_cassette, load_cassette, save_cassette, AsyncCassetteStorage,
+    BufferedCassetteWriter, CassetteFormat,
+};
+
+/// Cassette format version written by this crate
+///
+/// - `2.0`: HTTP bodies are written as `{"json": ...}`, `{"text": ...}` or
+///   `{"base64": ...}` objects
+/// - `1.1`: headers are written as ordered `[name, value]` pairs (see [`Headers`])
+/// - `1.0`: headers were a `{name: value}` map and bodies arrays of bytes
+///
+/// Older cassettes are still read; `magneto migrate` rewrites them.
+pub const CASSETTE_VERSION: &str = "2.0";
 
 /// A cassette containing recorded HTTP/WebSocket interactions
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,7 +47,7 @@ pub struct Cassette {
     pub interactions: Vec<Interaction>,
 }
 
-/// A single recorded interaction (HTTP or WebSocket)
+/// A single recorded interaction (HTTP, SSE, WebSocket or gRPC)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Interaction {
     /// Type of interaction
@@ -44,6 +61,43 @@ pub struct Interaction {
     /// None if not recorded or unknown
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_time_ms: Option<u64>,
+
+    /// HTTP version negotiated with the upstream server
+    /// None if not recorded (cassettes from before HTTP/2 support, WebSocket)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_version: Option<HttpVersion>,
+
+    /// `Content-Encoding` the response was served with upstream
+    ///
+    /// The recorded body is decoded (see `content_encoding`); None when it is
+    /// stored as received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+}
+
+/// HTTP protocol version of a recorded exchange
+///
+/// Informational: replay always answers over the protocol the client negotiated.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HttpVersion {
+    #[serde(rename = "HTTP/1.0")]
+    Http10,
+
+    #[serde(rename = "HTTP/1.1")]
+    Http11,
+
+    #[serde(rename = "HTTP/2")]
+    Http2,
+}
+
+impl std::fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpVersion::Http10 => write!(f, "HTTP/1.0"),
+            HttpVersion::Http11 => write!(f, "HTTP/1.1"),
+            HttpVersion::Http2 => write!(f, "HTTP/2"),
+        }
+    }
 }
 
 /// Network error types
@@ -97,6 +151,22 @@ pub enum InteractionKind {
         messages: Vec<WebSocketMessage>,
         close_frame: Option<CloseFrame>,
     },
+
+    /// Server-Sent Events stream (`text/event-stream`) with its events
+    ///
+    /// The response holds the status and headers; its body is always None.
+    ServerSentEvents {
+        request: HttpRequest,
+        response: HttpResponse,
+        events: Vec<SseEvent>,
+    },
+
+    /// gRPC call (unary or streaming) with its messages in both directions
+    Grpc {
+        request: GrpcRequest,
+        response: GrpcResponse,
+        messages: Vec<GrpcMessage>,
+    },
 }
 
 /// HTTP request data
@@ -108,10 +178,13 @@ pub struct HttpRequest {
     /// Request URL
     pub url: String,
 
-    /// Request headers
-    pub headers: HashMap<String, String>,
+    /// Request headers (ordered, duplicates kept)
+    pub headers: Headers,
 
     /// Request body (None if empty)
+    ///
+    /// Stored as tagged JSON, text or base64 (see `body`).
+    #[serde(default, with = "body")]
     pub body: Option<Vec<u8>>,
 }
 
@@ -121,10 +194,13 @@ pub struct HttpResponse {
     /// HTTP status code
     pub status: u16,
 
-    /// Response headers
-    pub headers: HashMap<String, String>,
+    /// Response headers (ordered, duplicates kept)
+    pub headers: Headers,
 
     /// Response body (None if empty)
+    ///
+    /// Stored as tagged JSON, text or base64 (see `body`).
+    #[serde(default, with = "body")]
     pub body: Option<Vec<u8>>,
 }
 
@@ -169,6 +245,83 @@ pub enum MessagePayload {
     Pong { data: Vec<u8> },
 }
 
+/// Server-Sent Event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Timestamp in milliseconds (relative to the response start)
+    pub timestamp_ms: u64,
+
+    /// Event ID (`id` field)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// Event type (`event` field)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+
+    /// Event data (`data` lines joined with `\n`)
+    #[serde(default)]
+    pub data: String,
+
+    /// Reconnection time in milliseconds (`retry` field)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<u64>,
+}
+
+/// gRPC call data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcRequest {
+    /// Request URL (`https://host/package.Service/Method`)
+    pub url: String,
+
+    /// Fully qualified service name (`package.Service`)
+    pub service: String,
+
+    /// Method name
+    pub method: String,
+
+    /// Request metadata (headers)
+    pub metadata: Headers,
+}
+
+/// gRPC call outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcResponse {
+    /// gRPC status code (`grpc-status`, 0 = OK)
+    pub status: u32,
+
+    /// gRPC status message (`grpc-message`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Response metadata (headers)
+    pub metadata: Headers,
+
+    /// Trailing metadata, `grpc-status` included (empty for trailers-only responses)
+    pub trailers: Headers,
+}
+
+/// gRPC message (one length-prefixed frame)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcMessage {
+    /// Message direction
+    pub direction: Direction,
+
+    /// Timestamp in milliseconds (relative to the start of the call)
+    pub timestamp_ms: u64,
+
+    /// Whether the payload is compressed with the call's `grpc-encoding`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compressed: bool,
+
+    /// Protobuf payload
+    pub data: Vec<u8>,
+
+    /// Payload decoded with the configured descriptor sets (protobuf JSON mapping)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<serde_json::Value>,
+}
+
 /// WebSocket close frame
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CloseFrame {
@@ -183,7 +336,7 @@ impl Cassette {
     /// Create a new empty cassette
     pub fn new(name: String) -> Self {
         Self {
-            version: "1.0".to_string(),
+            version: CASSETTE_VERSION.to_string(),
             name,
             recorded_at: Utc::now(),
             cookies: None,
@@ -197,6 +350,8 @@ impl Cassette {
             kind,
             recorded_at: Utc::now(),
             response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
         });
     }
 
@@ -206,6 +361,8 @@ impl Cassette {
             kind,
             recorded_at: Utc::now(),
             response_time_ms: Some(response_time_ms),
+            http_version: None,
+            content_encoding: None,
         });
     }
 
@@ -215,8 +372,39 @@ impl Cassette {
             kind: InteractionKind::HttpError { request, error },
             recorded_at: Utc::now(),
             response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
         });
     }
+
+    /// Cookies of the cassette, timed for a cassette recorded at `recorded_at`
+    ///
+    /// Replay ages cookies from their cassette's `recorded_at`; moving them
+    /// by the same amount keeps their lifetime when they are copied to a
+    /// cassette recorded at another time.
+    pub fn cookies_rebased(&self, recorded_at: DateTime<Utc>) -> Vec<Cookie> {
+        let shift = recorded_at - self.recorded_at;
+        self.cookies
+            .iter()
+            .flatten()
+            .map(|cookie| cookie.shifted(shift))
+            .collect()
+    }
+
+    /// Add cookies, each replacing the one with the same name, domain and path
+    pub fn merge_cookies(&mut self, cookies: Vec<Cookie>) {
+        if cookies.is_empty() {
+            return;
+        }
+
+        let stored = self.cookies.get_or_insert_with(Vec::new);
+        for cookie in cookies {
+            stored.retain(|c| {
+                c.name != cookie.name || c.domain != cookie.domain || c.path != cookie.path
+            });
+            stored.push(cookie);
+        }
+    }
 }
 
 impl NetworkError {
@@ -271,3 +459,27 @@ impl NetworkError {
         }
     }
 }
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DnsResolutionFailed { message } => {
+                write!(f, "DNS resolution failed: {}", message)
+            }
+            Self::ConnectionRefused { message } => write!(f, "Connection refused: {}", message),
+            Self::Timeout {
+                message,
+                timeout_ms,
+            } => {
+                write!(f, "Timeout after {}ms: {}", timeout_ms, message)
+            }
+            Self::TlsError { message } => write!(f, "TLS error: {}", message),
+            Self::ConnectionReset { message } => write!(f, "Connection reset: {}", message),
+            Self::TooManyRedirects {
+                message,
+                redirect_count,
+            } => write!(f, "Too many redirects ({}): {}", redirect_count, message),
+            Self::Other { message } => write!(f, "{}", message),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Pending interactions awaiting review
+//!
+//! With pending review on, hybrid mode leaves the cassette untouched and
+//! writes new interactions to a sidecar cassette next to it
+//! (`name.pending.json` for `name.json`). Each capture then becomes an
+//! explicit change: `accept_pending` appends interactions to the cassette,
+//! `reject_pending` discards them. The sidecar is removed once empty.
+
+use super::storage::{self, CassetteFormat};
+use super::{Cassette, Interaction};
+use crate::error::{MatgtoError, Result};
+use std::path::{Path, PathBuf};
+
+/// Suffix added to the cassette name for its pending sidecar
+pub const PENDING_SUFFIX: &str = ".pending";
+
+/// Name of the sidecar cassette holding the pending interactions of `name`
+pub fn pending_name(name: &str) -> String {
+    format!("{}{}", name, PENDING_SUFFIX)
+}
+
+/// Whether a cassette name is a pending sidecar
+pub fn is_pending_name(name: &str) -> bool {
+    name.ends_with(PENDING_SUFFIX)
+}
+
+/// Path of the pending sidecar of cassette `name` for the given format
+pub fn pending_path(cassette_dir: &Path, name: &str, format: CassetteFormat) -> PathBuf {
+    storage::cassette_path(cassette_dir, &pending_name(name), format)
+}
+
+/// Find the pending sidecar of cassette `name`, whichever format it was saved in
+pub fn find_pending(cassette_dir: &Path, name: &str, preferred: CassetteFormat) -> Option<PathBuf> {
+    storage::find_cassette(cassette_dir, &pending_name(name), preferred)
+}
+
+/// Load the pending interactions of cassette `name` (empty if there are none)
+pub fn load_pending(
+    cassette_dir: &Path,
+    name: &str,
+    preferred: CassetteFormat,
+) -> Result<Vec<Interaction>> {
+    match find_pending(cassette_dir, name, preferred) {
+        Some(path) => Ok(storage::load_cassette(&path)?.interactions),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Outcome of reviewing pending interactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingReview {
+    /// Interactions accepted into, or rejected from, the cassette
+    pub reviewed: usize,
+
+    /// Interactions still pending
+    pub remaining: usize,
+}
+
+/// Append pending interactions to the cassette
+///
+/// `indices` selects interactions by their position in the pending file
+/// (None = all of them). The cassette is created in `format` if it does not
+/// exist yet, otherwise it keeps its own format.
+pub fn accept_pending(
+    cassette_dir: &Path,
+    name: &str,
+    format: CassetteFormat,
+    indices: Option<&[usize]>,
+) -> Result<PendingReview> {
+    review(cassette_dir, name, format, indices, |selected, pending| {
+        let (mut cassette, path, cassette_format) =
+            match storage::find_cassette(cassette_dir, name, format) {
+                Some(path) => (
+                    storage::load_cassette(&path)?,
+                    path.clone(),
+                    storage::detect_format(&path),
+                ),
+                None => (
+                    Cassette::new(name.to_string()),
+                    storage::cassette_path(cassette_dir, name, format),
+                    format,
+                ),
+            };
+
+        cassette.interactions.extend(selected);
+        cassette.merge_cookies(pending.cookies_rebased(cassette.recorded_at));
+        storage::save_cassette(&cassette, &path, cassette_format)
+    })
+}
+
+/// Discard pending interactions
+///
+/// `indices` selects interactions by their position in the pending file
+/// (None = all of them).
+pub fn reject_pending(
+    cassette_dir: &Path,
+    name: &str,
+    format: CassetteFormat,
+    indices: Option<&[usize]>,
+) -> Result<PendingReview> {
+    review(cassette_dir, name, format, indices, |_, _| Ok(()))
+}
+
+/// Split the selected interactions off the pending file, hand them and the
+/// pending cassette to `apply`, then save what remains (or remove the file
+/// when nothing does)
+fn review<F>(
+    cassette_dir: &Path,
+    name: &str,
+    format: CassetteFormat,
+    indices: Option<&[usize]>,
+    apply: F,
+) -> Result<PendingReview>
+where
+    F: FnOnce(Vec<Interaction>, &Cassette) -> Result<()>,
+{
+    let path =
+        find_pending(cassette_dir, name, format).ok_or_else(|| MatgtoError::CassetteNotFound {
+            name: pending_name(name),
+        })?;
+    let mut pending = storage::load_cassette(&path)?;
+
+    if let Some(indices) = indices {
+        if let Some(idx) = indices
+            .iter()
+            .find(|idx| **idx >= pending.interactions.len())
+        {
+            return Err(MatgtoError::Config(format!(
+                "No pending interaction #{} in '{}' ({} pending)",
+                idx,
+                name,
+                pending.interactions.len()
+            )));
+        }
+    }
+
+    let (selected, remaining): (Vec<_>, Vec<_>) = pending
+        .interactions
+        .drain(..)
+        .enumerate()
+        .partition(|(idx, _)| indices.map_or(true, |indices| indices.contains(idx)));
+    let selected: Vec<Interaction> = selected.into_iter().map(|(_, i)| i).collect();
+    pending.interactions = remaining.into_iter().map(|(_, i)| i).collect();
+
+    let review = PendingReview {
+        reviewed: selected.len(),
+        remaining: pending.interactions.len(),
+    };
+    apply(selected, &pending)?;
+
+    if pending.interactions.is_empty() {
+        std::fs::remove_file(&path)?;
+    } else {
+        storage::save_cassette(&pending, &path, storage::detect_format(&path))?;
+    }
+
+    Ok(review)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::{Headers, HttpRequest, HttpResponse, InteractionKind};
+    use tempfile::tempdir;
+
+    fn cassette(name: &str, urls: &[&str]) -> Cassette {
+        let mut cassette = Cassette::new(name.to_string());
+        for url in urls {
+            cassette.add_interaction(InteractionKind::Http {
+                request: HttpRequest {
+                    method: "GET".to_string(),
+                    url: url.to_string(),
+                    headers: Headers::new(),
+                    body: None,
+                },
+                response: HttpResponse {
+                    status: 200,
+                    headers: Headers::new(),
+                    body: None,
+                },
+            });
+        }
+        cassette
+    }
+
+    fn urls(interactions: &[Interaction]) -> Vec<String> {
+        interactions
+            .iter()
+            .filter_map(|interaction| match &interaction.kind {
+                InteractionKind::Http { request, .. } => Some(request.url.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_accept_and_reject_pending() {
+        let dir = tempdir().unwrap();
+        let format = CassetteFormat::Json;
+        let main = storage::cassette_path(dir.path(), "api", format);
+        storage::save_cassette(&cassette("api", &["/a"]), &main, format).unwrap();
+        storage::save_cassette(
+            &cassette("api", &["/b", "/c", "/d"]),
+            &pending_path(dir.path(), "api", format),
+            format,
+        )
+        .unwrap();
+
+        let review = accept_pending(dir.path(), "api", format, Some(&[1])).unwrap();
+        assert_eq!(
+            review,
+            PendingReview {
+                reviewed: 1,
+                remaining: 2
+            }
+        );
+        let cassette = storage::load_cassette(&main).unwrap();
+        assert_eq!(urls(&cassette.interactions), vec!["/a", "/c"]);
+        assert_eq!(
+            urls(&load_pending(dir.path(), "api", format).unwrap()),
+            vec!["/b", "/d"]
+        );
+
+        assert!(reject_pending(dir.path(), "api", format, Some(&[5])).is_err());
+        let review = reject_pending(dir.path(), "api", format, None).unwrap();
+        assert_eq!(review.reviewed, 2);
+        assert!(find_pending(dir.path(), "api", format).is_none());
+        assert!(accept_pending(dir.path(), "api", format, None).is_err());
+    }
+
+    #[test]
+    fn test_accept_creates_cassette() {
+        let dir = tempdir().unwrap();
+        let format = CassetteFormat::Json;
+        storage::save_cassette(
+            &cassette("new", &["/x"]),
+            &pending_path(dir.path(), "new", format),
+            format,
+        )
+        .unwrap();
+
+        accept_pending(dir.path(), "new", format, None).unwrap();
+        let path = storage::find_cassette(dir.path(), "new", format).unwrap();
+        let cassette = storage::load_cassette(&path).unwrap();
+        assert_eq!(cassette.name, "new");
+        assert_eq!(urls(&cassette.interactions), vec!["/x"]);
+        assert!(is_pending_name(&pending_name("new")));
+    }
+
+    #[test]
+    fn test_accept_merges_pending_cookies() {
+        let dir = tempdir().unwrap();
+        let format = CassetteFormat::Json;
+        let main = cassette("api", &["/a"]);
+        storage::save_cassette(&main, &dir.path().join("api.json"), format).unwrap();
+
+        let mut pending = cassette("api", &["/b"]);
+        pending.recorded_at = main.recorded_at + chrono::Duration::hours(1);
+        let mut cookie = crate::cookies::Cookie::parse("session=abc").unwrap();
+        cookie.created_at = pending.recorded_at;
+        pending.merge_cookies(vec![cookie]);
+        storage::save_cassette(&pending, &pending_path(dir.path(), "api", format), format).unwrap();
+
+        accept_pending(dir.path(), "api", format, None).unwrap();
+        let cassette = storage::load_cassette(&dir.path().join("api.json")).unwrap();
+        let cookies = cassette.cookies.unwrap();
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].value, "abc");
+        // Same age relative to the main cassette's recording
+        assert_eq!(cookies[0].created_at, cassette.recorded_at);
+    }
+}
@@ -14,9 +14,10 @@ use tokio::sync::mpsc;
 use tokio::sync::Mutex;
 
 /// Format for cassette serialization
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CassetteFormat {
     /// JSON format (human-readable, larger files)
+    #[default]
     Json,
 
     /// MessagePack format (binary, smaller files, faster)
@@ -32,6 +33,67 @@ pub enum CassetteFormat {
     MessagePackGzip,
 }
 
+impl CassetteFormat {
+    /// Every format enabled in this build, JSON first
+    pub fn all() -> Vec<CassetteFormat> {
+        vec![
+            CassetteFormat::Json,
+            #[cfg(feature = "msgpack")]
+            CassetteFormat::MessagePack,
+            #[cfg(feature = "compression")]
+            CassetteFormat::JsonGzip,
+            #[cfg(all(feature = "msgpack", feature = "compression"))]
+            CassetteFormat::MessagePackGzip,
+        ]
+    }
+
+    /// File extension used for this format (without the leading dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CassetteFormat::Json => "json",
+            #[cfg(feature = "msgpack")]
+            CassetteFormat::MessagePack => "msgpack",
+            #[cfg(feature = "compression")]
+            CassetteFormat::JsonGzip => "json.gz",
+            #[cfg(all(feature = "msgpack", feature = "compression"))]
+            CassetteFormat::MessagePackGzip => "msgpack.gz",
+        }
+    }
+
+    /// Serialize a cassette in this format
+    ///
+    /// MessagePack is written with field names: optional fields are skipped
+    /// when empty, which the compact array encoding cannot represent.
+    pub fn encode(&self, cassette: &Cassette) -> Result<Vec<u8>> {
+        let data = match self {
+            CassetteFormat::Json => serde_json::to_vec_pretty(cassette)?,
+            #[cfg(feature = "msgpack")]
+            CassetteFormat::MessagePack => rmp_serde::to_vec_named(cassette)?,
+            #[cfg(feature = "compression")]
+            CassetteFormat::JsonGzip => compress_data(&serde_json::to_vec_pretty(cassette)?)?,
+            #[cfg(all(feature = "msgpack", feature = "compression"))]
+            CassetteFormat::MessagePackGzip => compress_data(&rmp_serde::to_vec_named(cassette)?)?,
+        };
+
+        Ok(data)
+    }
+
+    /// Deserialize a cassette stored in this format
+    pub fn decode(&self, data: &[u8]) -> Result<Cassette> {
+        let cassette = match self {
+            CassetteFormat::Json => serde_json::from_slice(data)?,
+            #[cfg(feature = "msgpack")]
+            CassetteFormat::MessagePack => rmp_serde::from_slice(data)?,
+            #[cfg(feature = "compression")]
+            CassetteFormat::JsonGzip => serde_json::from_slice(&decompress_data(data)?)?,
+            #[cfg(all(feature = "msgpack", feature = "compression"))]
+            CassetteFormat::MessagePackGzip => rmp_serde::from_slice(&decompress_data(data)?)?,
+        };
+
+        Ok(cassette)
+    }
+}
+
 /// Message sent to background writer
 enum WriterMessage {
     /// Save a cassette to disk
@@ -131,21 +193,7 @@ impl AsyncCassetteStorage {
         }
 
         // Serialize cassette
-        let data = match format {
-            CassetteFormat::Json => serde_json::to_vec_pretty(cassette)?,
-            #[cfg(feature = "msgpack")]
-            CassetteFormat::MessagePack => rmp_serde::to_vec(cassette)?,
-            #[cfg(feature = "compression")]
-            CassetteFormat::JsonGzip => {
-                let json_data = serde_json::to_vec_pretty(cassette)?;
-                Self::compress_data(&json_data)?
-            }
-            #[cfg(all(feature = "msgpack", feature = "compression"))]
-            CassetteFormat::MessagePackGzip => {
-                let msgpack_data = rmp_serde::to_vec(cassette)?;
-                Self::compress_data(&msgpack_data)?
-            }
-        };
+        let data = format.encode(cassette)?;
 
         // Write atomically (write to temp file, then rename)
         let temp_path = path.with_extension("tmp");
@@ -158,51 +206,7 @@ impl AsyncCassetteStorage {
     /// Load cassette asynchronously
     pub async fn load_async(path: &Path, format: CassetteFormat) -> Result<Cassette> {
         let data = fs::read(path).await?;
-
-        let cassette = match format {
-            CassetteFormat::Json => serde_json::from_slice(&data)?,
-            #[cfg(feature = "msgpack")]
-            CassetteFormat::MessagePack => rmp_serde::from_slice(&data)?,
-            #[cfg(feature = "compression")]
-            CassetteFormat::JsonGzip => {
-                let decompressed = Self::decompress_data(&data)?;
-                serde_json::from_slice(&decompressed)?
-            }
-            #[cfg(all(feature = "msgpack", feature = "compression"))]
-            CassetteFormat::MessagePackGzip => {
-                let decompressed = Self::decompress_data(&data)?;
-                rmp_serde::from_slice(&decompressed)?
-            }
-        };
-
-        Ok(cassette)
-    }
-
-    /// Compress data using gzip
-    #[cfg(feature = "compression")]
-    fn compress_data(data: &[u8]) -> Result<Vec<u8>> {
-        use flate2::write::GzEncoder;
-        use flate2::Compression;
-        use std::io::Write;
-
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-        encoder.write_all(data)?;
-        let compressed = encoder.finish()?;
-
-        Ok(compressed)
-    }
-
-    /// Decompress gzip data
-    #[cfg(feature = "compression")]
-    fn decompress_data(data: &[u8]) -> Result<Vec<u8>> {
-        use flate2::read::GzDecoder;
-        use std::io::Read;
-
-        let mut decoder = GzDecoder::new(data);
-        let mut decompressed = Vec::new();
-        decoder.read_to_end(&mut decompressed)?;
-
-        Ok(decompressed)
+        format.decode(&data)
     }
 
     /// Shutdown background writer gracefully
@@ -315,9 +319,98 @@ pub fn detect_format(path: &Path) -> CassetteFormat {
     }
 }
 
+/// Path of the cassette `name` in `cassette_dir` for the given format
+pub fn cassette_path(cassette_dir: &Path, name: &str, format: CassetteFormat) -> PathBuf {
+    cassette_dir.join(format!("{}.{}", name, format.extension()))
+}
+
+/// Find the file holding cassette `name`, whichever format it was saved in
+///
+/// `preferred` is tried first, so a cassette re-saved in a new format wins over
+/// a stale copy in the old one. Other formats are tried in `CassetteFormat::all` order.
+pub fn find_cassette(
+    cassette_dir: &Path,
+    name: &str,
+    preferred: CassetteFormat,
+) -> Option<PathBuf> {
+    std::iter::once(preferred)
+        .chain(CassetteFormat::all())
+        .map(|format| cassette_path(cassette_dir, name, format))
+        .find(|path| path.exists())
+}
+
+/// Split a cassette file name into the cassette name and its format
+///
+/// Returns `None` for files that are not cassettes (unknown extension).
+pub fn parse_cassette_file_name(file_name: &str) -> Option<(&str, CassetteFormat)> {
+    // Longest extensions first so "x.json.gz" is not read as "x.json" + ".gz"
+    let mut formats = CassetteFormat::all();
+    formats.sort_by_key(|format| std::cmp::Reverse(format.extension().len()));
+
+    formats.into_iter().find_map(|format| {
+        file_name
+            .strip_suffix(format.extension())
+            .and_then(|stem| stem.strip_suffix('.'))
+            .filter(|stem| !stem.is_empty())
+            .map(|stem| (stem, format))
+    })
+}
+
+/// Load a cassette file, detecting its format from the extension
+pub fn load_cassette(path: &Path) -> Result<Cassette> {
+    let data = std::fs::read(path)?;
+    detect_format(path).decode(&data)
+}
+
+/// Save a cassette file in the given format
+///
+/// The file is written to a temporary path and renamed, so readers never see
+/// a partially written cassette.
+pub fn save_cassette(cassette: &Cassette, path: &Path, format: CassetteFormat) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let data = format.encode(cassette)?;
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    std::fs::write(&temp_path, &data)?;
+    std::fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+/// Compress data using gzip
+#[cfg(feature = "compression")]
+fn compress_data(data: &[u8]) -> Result<Vec<u8>> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let compressed = encoder.finish()?;
+
+    Ok(compressed)
+}
+
+/// Decompress gzip data
+#[cfg(feature = "compression")]
+fn decompress_data(data: &[u8]) -> Result<Vec<u8>> {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    let mut decoder = GzDecoder::new(data);
+    let mut decompressed = Vec::new();
+    decoder.read_to_end(&mut decompressed)?;
+
+    Ok(decompressed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::Headers;
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -444,7 +537,6 @@ mod tests {
     #[tokio::test]
     async fn test_json_gzip_compression() {
         use crate::cassette::{HttpRequest, HttpResponse, InteractionKind};
-        use std::collections::HashMap;
 
         let storage = AsyncCassetteStorage::new();
         let mut cassette = Cassette::new("test-json-gzip".to_string());
@@ -454,13 +546,13 @@ mod tests {
             let request = HttpRequest {
                 method: "GET".to_string(),
                 url: format!("https://api.example.com/test/{}", i),
-                headers: HashMap::new(),
+                headers: Headers::new(),
                 body: Some(b"test request body".to_vec()),
             };
 
             let response = HttpResponse {
                 status: 200,
-                headers: HashMap::new(),
+                headers: Headers::new(),
                 body: Some(b"test response body with some data to compress".to_vec()),
             };
 
@@ -513,7 +605,6 @@ mod tests {
     #[ignore] // TODO: Fix MessagePack backward compatibility for new 'cookies' field
     async fn test_messagepack_gzip_compression() {
         use crate::cassette::{HttpRequest, HttpResponse, InteractionKind};
-        use std::collections::HashMap;
 
         let storage = AsyncCassetteStorage::new();
         let mut cassette = Cassette::new("test-msgpack-gzip".to_string());
@@ -523,13 +614,13 @@ mod tests {
             let request = HttpRequest {
                 method: "POST".to_string(),
                 url: format!("https://api.example.com/data/{}", i),
-                headers: HashMap::new(),
+                headers: Headers::new(),
                 body: Some(b"msgpack test request body".to_vec()),
             };
 
             let response = HttpResponse {
                 status: 201,
-                headers: HashMap::new(),
+                headers: Headers::new(),
                 body: Some(b"msgpack test response body with data".to_vec()),
             };
 
@@ -588,7 +679,6 @@ mod tests {
     #[tokio::test]
     async fn test_compression_with_large_data() {
         use crate::cassette::{HttpRequest, HttpResponse, InteractionKind};
-        use std::collections::HashMap;
 
         let storage = AsyncCassetteStorage::new();
         let mut cassette = Cassette::new("test-large".to_string());
@@ -598,13 +688,13 @@ mod tests {
             let request = HttpRequest {
                 method: "GET".to_string(),
                 url: format!("https://api.example.com/data/{}", i),
-                headers: HashMap::new(),
+                headers: Headers::new(),
                 body: Some(vec![b'x'; 1000]), // 1KB of data
             };
 
             let response = HttpResponse {
                 status: 200,
-                headers: HashMap::new(),
+                headers: Headers::new(),
                 body: Some(vec![b'y'; 5000]), // 5KB of data
             };
 
@@ -646,4 +736,102 @@ mod tests {
             "Expected at least 50% compression"
         );
     }
+
+    #[test]
+    fn test_extension_roundtrips_through_detect_format() {
+        for format in CassetteFormat::all() {
+            let path = cassette_path(Path::new("/tmp"), "api", format);
+            assert_eq!(detect_format(&path), format);
+            assert_eq!(
+                parse_cassette_file_name(path.file_name().unwrap().to_str().unwrap()),
+                Some(("api", format))
+            );
+        }
+
+        assert_eq!(parse_cassette_file_name("notes.txt"), None);
+        assert_eq!(parse_cassette_file_name(".json"), None);
+    }
+
+    #[test]
+    fn test_sync_save_and_load_every_format() {
+        let dir = tempdir().unwrap();
+        let cassette = Cassette::new("every-format".to_string());
+
+        for format in CassetteFormat::all() {
+            let path = cassette_path(dir.path(), "every-format", format);
+            save_cassette(&cassette, &path, format).unwrap();
+
+            let loaded = load_cassette(&path).unwrap();
+            assert_eq!(loaded.name, "every-format");
+        }
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_find_cassette_prefers_requested_format() {
+        let dir = tempdir().unwrap();
+        let cassette = Cassette::new("both".to_string());
+        for format in [CassetteFormat::Json, CassetteFormat::MessagePack] {
+            save_cassette(
+                &cassette,
+                &cassette_path(dir.path(), "both", format),
+                format,
+            )
+            .unwrap();
+        }
+
+        let found = find_cassette(dir.path(), "both", CassetteFormat::MessagePack).unwrap();
+        assert_eq!(detect_format(&found), CassetteFormat::MessagePack);
+
+        let found = find_cassette(dir.path(), "both", CassetteFormat::Json).unwrap();
+        assert_eq!(detect_format(&found), CassetteFormat::Json);
+
+        assert!(find_cassette(dir.path(), "missing", CassetteFormat::Json).is_none());
+    }
+
+    #[test]
+    fn test_load_v1_cassette_with_header_map() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("legacy.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "version": "1.0",
+                "name": "legacy",
+                "recorded_at": "2025-01-01T00:00:00Z",
+                "interactions": [{
+                    "type": "Http",
+                    "recorded_at": "2025-01-01T00:00:00Z",
+                    "request": {
+                        "method": "GET",
+                        "url": "https://api.example.com/me",
+                        "headers": {"accept": "application/json"},
+                        "body": null
+                    },
+                    "response": {
+                        "status": 200,
+                        "headers": {"set-cookie": "session=abc"},
+                        "body": null
+                    }
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let cassette = load_cassette(&path).unwrap();
+
+        match &cassette.interactions[0].kind {
+            crate::cassette::InteractionKind::Http { request, response } => {
+                assert_eq!(request.headers.get("Accept"), Some("application/json"));
+                assert_eq!(response.headers.get("set-cookie"), Some("session=abc"));
+            }
+            _ => panic!("Expected HTTP interaction"),
+        }
+
+        // Saving writes headers as ordered pairs
+        save_cassette(&cassette, &path, CassetteFormat::Json).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains(r#""accept","#));
+        assert!(!saved.contains(r#""accept":"#));
+    }
 }
@@ -0,0 +1,807 @@
+//! Typed configuration loaded from magneto.toml
+//!
+//! [`Config`] mirrors the file written by `magneto init`, one field per
+//! section. Every key has a default, so a missing file or section gives the
+//! same proxy as `MagnetoProxy::new_internal`. `Config::load` reads the
+//! first file found among:
+//!
+//! 1. the path given explicitly (`magneto --config`), which must exist
+//! 2. `./magneto.toml`
+//! 3. `$XDG_CONFIG_HOME/magneto/magneto.toml` (`~/.config/magneto/magneto.toml`
+//!    when the variable is unset)
+//!
+//! then applies the `MAGNETO_*` environment variables listed in
+//! [`ENV_OVERRIDES`] on top, e.g. `MAGNETO_PROXY_PORT=9000` or
+//! `MAGNETO_MATCHING_IGNORE_HEADERS=date,user-agent` (lists are comma
+//! separated).
+//!
+//! Unknown keys, wrong types and invalid values are errors naming what is
+//! wrong: the file line, the environment variable, or the `[section]` and
+//! key.
+//!
+//! ```no_run
+//! use magneto_serge::config::Config;
+//!
+//! let config = Config::load(None)?;
+//! let proxy = config.build_proxy()?;
+//! proxy.start_recording_internal("api".to_string())?;
+//! # Ok::<(), magneto_serge::MatgtoError>(())
+//! ```
+
+use crate::cassette::CassetteFormat;
+use crate::cookies::CookieConfig;
+use crate::error::{MatgtoError, Result};
+use crate::filters::FilterConfig;
+use crate::grpc::GrpcConfig;
+use crate::matching::MatchingConfig;
+use crate::player::ReplayConfig;
+use crate::proxy::{
+    IgnoreConfig, MagnetoProxy, ProxyMode, ReverseProxyConfig, UpstreamProxyConfig,
+};
+use crate::rerecord::ReRecordConfig;
+use crate::sensitive::SensitiveDataConfig;
+use crate::tls::UpstreamTlsConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::EnvFilter;
+
+/// Name of the configuration file
+pub const CONFIG_FILE: &str = "magneto.toml";
+
+/// Environment variables overriding a single key, and the key they set
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("MAGNETO_CASSETTE_DIR", "magneto.cassette_dir"),
+    ("MAGNETO_PROXY_PORT", "magneto.proxy_port"),
+    ("MAGNETO_MODE", "magneto.mode"),
+    ("MAGNETO_STRICT", "magneto.strict"),
+    ("MAGNETO_RECORDING_FORMAT", "recording.format"),
+    ("MAGNETO_RECORDING_COMPRESS", "recording.compress"),
+    (
+        "MAGNETO_RECORDING_PENDING_REVIEW",
+        "recording.pending_review",
+    ),
+    (
+        "MAGNETO_RECORDING_FILTERS_ENABLED",
+        "recording.filters.enabled",
+    ),
+    (
+        "MAGNETO_RECORDING_FILTERS_PRESET",
+        "recording.filters.preset",
+    ),
+    ("MAGNETO_MATCHING_URL_MODE", "matching.url_mode"),
+    ("MAGNETO_MATCHING_URL_PATTERN", "matching.url_pattern"),
+    ("MAGNETO_MATCHING_BODY_MODE", "matching.body_mode"),
+    ("MAGNETO_MATCHING_BODY_PATTERN", "matching.body_pattern"),
+    ("MAGNETO_MATCHING_MATCH_HEADERS", "matching.match_headers"),
+    ("MAGNETO_MATCHING_IGNORE_HEADERS", "matching.ignore_headers"),
+    (
+        "MAGNETO_MATCHING_IGNORE_QUERY_PARAMS",
+        "matching.ignore_query_params",
+    ),
+    ("MAGNETO_COOKIES_ENABLED", "cookies.enabled"),
+    ("MAGNETO_COOKIES_FILTER_PATTERNS", "cookies.filter_patterns"),
+    ("MAGNETO_COOKIES_HASH_SENSITIVE", "cookies.hash_sensitive"),
+    ("MAGNETO_REPLAY_LATENCY_MODE", "replay.latency_mode"),
+    ("MAGNETO_REPLAY_FIXED_LATENCY_MS", "replay.fixed_latency_ms"),
+    (
+        "MAGNETO_REPLAY_LATENCY_SCALE_PERCENT",
+        "replay.latency_scale_percent",
+    ),
+    ("MAGNETO_REPLAY_WHEN_EXHAUSTED", "replay.when_exhausted"),
+    ("MAGNETO_RE_RECORD_INTERVAL_DAYS", "re_record.interval_days"),
+    ("MAGNETO_SENSITIVE_DATA_HEADERS", "sensitive_data.headers"),
+    (
+        "MAGNETO_SENSITIVE_DATA_QUERY_PARAMS",
+        "sensitive_data.query_params",
+    ),
+    ("MAGNETO_SENSITIVE_DATA_ENV", "sensitive_data.env"),
+    ("MAGNETO_IGNORE_LOCALHOST", "ignore.localhost"),
+    ("MAGNETO_IGNORE_HOSTS", "ignore.hosts"),
+    ("MAGNETO_UPSTREAM_PROXY_URL", "upstream_proxy.url"),
+    ("MAGNETO_UPSTREAM_PROXY_NO_PROXY", "upstream_proxy.no_proxy"),
+    (
+        "MAGNETO_UPSTREAM_TLS_INSECURE_SKIP_VERIFY",
+        "upstream_tls.insecure_skip_verify",
+    ),
+    ("MAGNETO_WEBSOCKET_ENABLED", "websocket.enabled"),
+    ("MAGNETO_API_ENABLED", "api.enabled"),
+    ("MAGNETO_API_HOST", "api.host"),
+    ("MAGNETO_API_PORT", "api.port"),
+    ("MAGNETO_API_AUTH_ENABLED", "api.auth_enabled"),
+    ("MAGNETO_API_KEY", "api.api_key"),
+    ("MAGNETO_LOGGING_LEVEL", "logging.level"),
+    ("MAGNETO_LOGGING_FORMAT", "logging.format"),
+    ("MAGNETO_LOGGING_FILE", "logging.file"),
+    ("MAGNETO_LOGGING_FILTER", "logging.filter"),
+];
+
+/// Settings of every entry point, as written in magneto.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `[magneto]`: cassette directory, port and mode
+    pub magneto: MagnetoConfig,
+
+    /// `[recording]`: cassette format, pending review and filters
+    pub recording: RecordingConfig,
+
+    /// `[matching]`: how live requests are matched to recorded ones
+    pub matching: MatchingConfig,
+
+    /// `[cookies]`: which cookies are recorded and replayed
+    pub cookies: CookieConfig,
+
+    /// `[replay]`: latency and exhausted sequences
+    pub replay: ReplayConfig,
+
+    /// `[re_record]`: when interactions are fetched again
+    pub re_record: ReRecordConfig,
+
+    /// `[sensitive_data]`: values replaced with placeholders
+    pub sensitive_data: SensitiveDataConfig,
+
+    /// `[ignore]`: hosts forwarded live
+    pub ignore: IgnoreConfig,
+
+    /// `[upstream_proxy]`: proxy upstream traffic is chained through
+    pub upstream_proxy: UpstreamProxyConfig,
+
+    /// `[upstream_tls]`: trust roots and client certificates
+    pub upstream_tls: UpstreamTlsConfig,
+
+    /// `[reverse_proxy]`: routes of the reverse-proxy listener
+    pub reverse_proxy: ReverseProxyConfig,
+
+    /// `[grpc]`: descriptor sets
+    pub grpc: GrpcConfig,
+
+    /// `[websocket]`: WebSocket settings
+    pub websocket: WebSocketConfig,
+
+    /// `[api]`: REST API server
+    pub api: ApiServerConfig,
+
+    /// `[logging]`: log level, format and destination
+    pub logging: LoggingConfig,
+}
+
+/// General settings (`[magneto]`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MagnetoConfig {
+    /// Directory where cassettes are stored
+    pub cassette_dir: PathBuf,
+
+    /// Proxy listening port (0 = let the OS pick a free port)
+    pub proxy_port: u16,
+
+    /// Default mode
+    pub mode: ProxyMode,
+
+    /// Error on missing cassettes and interactions (`replay` becomes `strict`)
+    pub strict: bool,
+}
+
+impl Default for MagnetoConfig {
+    fn default() -> Self {
+        Self {
+            cassette_dir: PathBuf::from("./cassettes"),
+            proxy_port: 8888,
+            mode: ProxyMode::Auto,
+            strict: false,
+        }
+    }
+}
+
+impl MagnetoConfig {
+    /// The configured mode, with `strict` applied
+    pub fn mode(&self) -> ProxyMode {
+        match self.mode {
+            ProxyMode::Replay if self.strict => ProxyMode::ReplayStrict,
+            mode => mode,
+        }
+    }
+}
+
+/// Cassette serialization, as written in magneto.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingFormat {
+    /// JSON
+    #[default]
+    Json,
+
+    /// MessagePack
+    #[serde(alias = "messagepack")]
+    Msgpack,
+}
+
+/// Recording settings (`[recording]`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecordingConfig {
+    /// Serialization of new cassettes
+    pub format: RecordingFormat,
+
+    /// Gzip new cassettes
+    pub compress: bool,
+
+    /// Hybrid mode writes new interactions to a pending file for review
+    pub pending_review: bool,
+
+    /// Interactions left out of recordings
+    pub filters: FilterConfig,
+}
+
+impl RecordingConfig {
+    /// The cassette format `format` and `compress` select
+    ///
+    /// Fails when the format needs a feature this build lacks.
+    pub fn cassette_format(&self) -> Result<CassetteFormat> {
+        let format = match (self.format, self.compress) {
+            (RecordingFormat::Json, false) => Some(CassetteFormat::Json),
+            #[cfg(feature = "msgpack")]
+            (RecordingFormat::Msgpack, false) => Some(CassetteFormat::MessagePack),
+            #[cfg(feature = "compression")]
+            (RecordingFormat::Json, true) => Some(CassetteFormat::JsonGzip),
+            #[cfg(all(feature = "msgpack", feature = "compression"))]
+            (RecordingFormat::Msgpack, true) => Some(CassetteFormat::MessagePackGzip),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        };
+
+        format.ok_or_else(|| {
+            MatgtoError::Config(format!(
+                "format = \"{}\"{} is not supported by this build (enable the `{}` feature)",
+                match self.format {
+                    RecordingFormat::Json => "json",
+                    RecordingFormat::Msgpack => "msgpack",
+                },
+                if self.compress { " with compress" } else { "" },
+                match (self.format, self.compress) {
+                    (RecordingFormat::Msgpack, false) => "msgpack",
+                    (RecordingFormat::Json, _) => "compression",
+                    (RecordingFormat::Msgpack, true) => "msgpack` and `compression",
+                }
+            ))
+        })
+    }
+}
+
+/// WebSocket settings (`[websocket]`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebSocketConfig {
+    /// Record and replay WebSocket connections (forwarded live otherwise)
+    pub enabled: bool,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// REST API server settings (`[api]`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiServerConfig {
+    /// Whether the REST API is served
+    pub enabled: bool,
+
+    /// Host the API listens on
+    pub host: String,
+
+    /// Port the API listens on
+    pub port: u16,
+
+    /// Require the API key on every request
+    pub auth_enabled: bool,
+
+    /// API key (required with `auth_enabled`)
+    pub api_key: Option<String>,
+}
+
+impl Default for ApiServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 8889,
+            auth_enabled: false,
+            api_key: None,
+        }
+    }
+}
+
+/// Log verbosity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Level name, as understood by `tracing` filters
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+/// Log line format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+
+    /// One JSON object per line
+    Json,
+}
+
+/// Logging settings (`[logging]`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Verbosity, unless `filter` is set
+    pub level: LogLevel,
+
+    /// Line format
+    pub format: LogFormat,
+
+    /// File logs are appended to (None = stderr)
+    pub file: Option<PathBuf>,
+
+    /// Filter directives in `RUST_LOG` syntax, overriding `level`
+    pub filter: Option<String>,
+}
+
+impl LoggingConfig {
+    /// The `tracing` filter: `filter` if set, `level` otherwise
+    pub fn env_filter(&self) -> Result<EnvFilter> {
+        match &self.filter {
+            Some(filter) => EnvFilter::try_new(filter)
+                .map_err(|e| MatgtoError::Config(format!("Invalid filter '{}': {}", filter, e))),
+            None => Ok(EnvFilter::new(self.level.as_str())),
+        }
+    }
+
+    /// Install the global `tracing` subscriber
+    ///
+    /// Fails if the log file cannot be opened or a subscriber is already set.
+    pub fn init(&self) -> Result<()> {
+        let filter = self.env_filter().map_err(at("logging"))?;
+        let writer = match &self.file {
+            Some(path) => {
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| {
+                        MatgtoError::Config(format!(
+                            "[logging] Cannot open {}: {}",
+                            path.display(),
+                            e
+                        ))
+                    })?;
+                BoxMakeWriter::new(std::sync::Mutex::new(file))
+            }
+            None => BoxMakeWriter::new(std::io::stderr),
+        };
+
+        let builder = tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_ansi(self.file.is_none())
+            .with_writer(writer);
+        let result = match self.format {
+            LogFormat::Text => builder.try_init(),
+            LogFormat::Json => builder.json().try_init(),
+        };
+        result.map_err(|e| MatgtoError::Config(format!("[logging] {}", e)))
+    }
+}
+
+impl Config {
+    /// Load the configuration file, with environment overrides
+    ///
+    /// `path` is the file given explicitly (it must exist); otherwise the
+    /// default locations are searched (see [`Config::locate`]) and the
+    /// defaults are used when there is no file.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) if !path.is_file() => {
+                return Err(MatgtoError::Config(format!(
+                    "Config file {} not found",
+                    path.display()
+                )));
+            }
+            Some(path) => Some(path.to_path_buf()),
+            None => Self::locate(),
+        };
+
+        let table = match &path {
+            Some(path) => {
+                tracing::debug!("Loading configuration from {}", path.display());
+                let text = std::fs::read_to_string(path)?;
+                parse_table(&text).map_err(|e| {
+                    MatgtoError::Config(format!("{}: {}", path.display(), message(e)))
+                })?
+            }
+            None => toml::Table::new(),
+        };
+
+        Self::from_table(table, std::env::vars())
+    }
+
+    /// Path of the configuration file used when none is given explicitly
+    ///
+    /// `./magneto.toml`, then `$XDG_CONFIG_HOME/magneto/magneto.toml`
+    /// (`~/.config/magneto/magneto.toml` when the variable is unset); None
+    /// when neither exists.
+    pub fn locate() -> Option<PathBuf> {
+        let local = PathBuf::from(CONFIG_FILE);
+        if local.is_file() {
+            return Some(local);
+        }
+
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| dirs::home_dir().map(|home| home.join(".config")))?;
+        let global = config_home.join("magneto").join(CONFIG_FILE);
+        global.is_file().then_some(global)
+    }
+
+    /// Parse and validate a configuration file's contents, without
+    /// environment overrides
+    pub fn from_toml(text: &str) -> Result<Self> {
+        Self::from_table(parse_table(text)?, std::iter::empty())
+    }
+
+    /// Apply environment overrides to a parsed file, then validate it
+    fn from_table<I>(mut table: toml::Table, vars: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let vars: HashMap<String, String> = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with("MAGNETO_"))
+            .collect();
+        for (var, key) in ENV_OVERRIDES {
+            if let Some(raw) = vars.get(*var) {
+                apply_override(&mut table, var, key, raw)?;
+            }
+        }
+
+        let config: Config = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| MatgtoError::Config(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the values that can be checked without reading other files
+    ///
+    /// Errors name the offending section.
+    pub fn validate(&self) -> Result<()> {
+        self.recording.cassette_format().map_err(at("recording"))?;
+        self.matching.resolve().map_err(at("matching"))?;
+        self.cookies.resolve().map_err(at("cookies"))?;
+        self.sensitive_data
+            .resolve()
+            .map_err(at("sensitive_data"))?;
+        self.ignore.resolve().map_err(at("ignore"))?;
+        self.upstream_proxy
+            .resolve()
+            .map_err(at("upstream_proxy"))?;
+        self.reverse_proxy.resolve().map_err(at("reverse_proxy"))?;
+        self.logging.env_filter().map_err(at("logging"))?;
+
+        if self.api.auth_enabled && self.api.api_key.is_none() {
+            return Err(MatgtoError::Config(
+                "[api] api_key is required with auth_enabled = true".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Build a proxy with every setting applied
+    ///
+    /// Reads the certificate and descriptor files the configuration refers to.
+    pub fn build_proxy(&self) -> Result<MagnetoProxy> {
+        let format = self.recording.cassette_format().map_err(at("recording"))?;
+        let strategy = self.matching.resolve().map_err(at("matching"))?;
+        let cookie_policy = self.cookies.resolve().map_err(at("cookies"))?;
+        let sensitive_data = self
+            .sensitive_data
+            .resolve()
+            .map_err(at("sensitive_data"))?;
+        let ignore_rules = self.ignore.resolve().map_err(at("ignore"))?;
+        let upstream_proxy = self
+            .upstream_proxy
+            .resolve()
+            .map_err(at("upstream_proxy"))?;
+        let reverse_proxy = self.reverse_proxy.resolve().map_err(at("reverse_proxy"))?;
+        let grpc_descriptors = self.grpc.load().map_err(at("grpc"))?;
+        let upstream_tls = if self.upstream_tls == UpstreamTlsConfig::default() {
+            None
+        } else {
+            Some(self.upstream_tls.load().map_err(at("upstream_tls"))?)
+        };
+
+        let proxy = MagnetoProxy::new_internal(&self.magneto.cassette_dir)?
+            .with_port(self.magneto.proxy_port)
+            .with_mode(self.magneto.mode())
+            .with_format(format)
+            .with_pending_review(self.recording.pending_review)
+            .with_matching_strategy(strategy)
+            .with_latency(self.replay.latency())
+            .with_re_record_policy(self.re_record.resolve())
+            .with_sensitive_data(sensitive_data)
+            .with_cookie_policy(cookie_policy)
+            .with_websocket_enabled(self.websocket.enabled);
+
+        if let Some(policy) = self.replay.when_exhausted {
+            proxy.set_exhausted_policy(policy);
+        }
+        proxy.set_recording_filters(self.recording.filters.resolve());
+        proxy.set_ignore_rules(ignore_rules);
+        proxy.set_upstream_proxy(upstream_proxy);
+        proxy.set_upstream_tls(upstream_tls);
+        proxy.set_reverse_proxy(reverse_proxy);
+        proxy.set_grpc_descriptors(grpc_descriptors);
+
+        Ok(proxy)
+    }
+}
+
+/// Parse a file, reporting type errors at their line
+fn parse_table(text: &str) -> Result<toml::Table> {
+    toml::from_str::<Config>(text).map_err(|e| MatgtoError::Config(e.to_string()))?;
+    toml::from_str(text).map_err(|e: toml::de::Error| MatgtoError::Config(e.to_string()))
+}
+
+/// Set `key` from an environment variable
+///
+/// The value is read as an integer, a boolean, a string or a comma-separated
+/// list, whichever the key accepts first.
+fn apply_override(table: &mut toml::Table, var: &str, key: &str, raw: &str) -> Result<()> {
+    let mut candidates = Vec::new();
+    if let Ok(integer) = raw.trim().parse::<i64>() {
+        candidates.push(toml::Value::Integer(integer));
+    }
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => candidates.push(toml::Value::Boolean(true)),
+        "false" | "0" | "no" | "off" => candidates.push(toml::Value::Boolean(false)),
+        _ => {}
+    }
+    candidates.push(toml::Value::String(raw.to_string()));
+    candidates.push(toml::Value::Array(
+        raw.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| toml::Value::String(item.to_string()))
+            .collect(),
+    ));
+
+    let mut first_error = None;
+    for value in candidates {
+        let mut candidate = table.clone();
+        set_key(&mut candidate, key, value);
+        match toml::Value::Table(candidate.clone()).try_into::<Config>() {
+            Ok(_) => {
+                *table = candidate;
+                return Ok(());
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    Err(MatgtoError::Config(format!(
+        "{} (sets {}): {}",
+        var,
+        key,
+        first_error
+            .map(|e| e.message().to_string())
+            .unwrap_or_default()
+    )))
+}
+
+/// Set a dotted key, creating the tables on its way
+fn set_key(table: &mut toml::Table, key: &str, value: toml::Value) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or(key);
+
+    let mut table = table;
+    for part in parts {
+        let entry = table
+            .entry(part.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        table = entry.as_table_mut().expect("just made a table");
+    }
+    table.insert(last.to_string(), value);
+}
+
+/// Prefix an error with the section it comes from
+fn at(section: &str) -> impl FnOnce(MatgtoError) -> MatgtoError + '_ {
+    move |e| MatgtoError::Config(format!("[{}] {}", section, message(e)))
+}
+
+/// An error's message, without the "Configuration error" prefix
+fn message(e: MatgtoError) -> String {
+    match e {
+        MatgtoError::Config(message) => message,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::{ExhaustedPolicy, LatencyMode};
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_repository_config_file_loads() {
+        let config = Config::from_toml(include_str!("../magneto.toml")).unwrap();
+        assert_eq!(config.magneto.proxy_port, 8888);
+        assert_eq!(config.magneto.mode(), ProxyMode::Auto);
+        assert_eq!(config.replay.latency(), LatencyMode::None);
+        assert_eq!(
+            config.replay.when_exhausted,
+            Some(ExhaustedPolicy::RepeatLast)
+        );
+        assert!(config.matching.ignore_headers.contains(&"date".to_string()));
+        assert!(config.cookies.resolve().unwrap().is_sensitive("csrf_token"));
+        assert!(config.recording.filters.resolve().is_none());
+
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let table =
+            parse_table("[magneto]\nproxy_port = 8888\n[api]\nhost = \"0.0.0.0\"\n").unwrap();
+        let config = Config::from_table(
+            table,
+            env(&[
+                ("MAGNETO_PROXY_PORT", "9000"),
+                ("MAGNETO_MODE", "replay"),
+                ("MAGNETO_STRICT", "1"),
+                ("MAGNETO_API_KEY", "1234"),
+                ("MAGNETO_MATCHING_IGNORE_HEADERS", "date, user-agent"),
+                ("MAGNETO_COOKIES_FILTER_PATTERNS", "session"),
+                ("MAGNETO_REPLAY_LATENCY_MODE", "fixed"),
+                ("MAGNETO_UNKNOWN", "ignored"),
+                ("PATH", "/usr/bin"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.magneto.proxy_port, 9000);
+        assert_eq!(config.magneto.mode(), ProxyMode::ReplayStrict);
+        assert_eq!(config.api.host, "0.0.0.0");
+        assert_eq!(config.api.api_key.as_deref(), Some("1234"));
+        assert_eq!(config.matching.ignore_headers, vec!["date", "user-agent"]);
+        assert_eq!(config.cookies.filter_patterns, vec!["session"]);
+        assert_eq!(config.replay.latency(), LatencyMode::Fixed(100));
+    }
+
+    #[test]
+    fn test_errors_name_the_offending_key() {
+        let error = |result: Result<Config>| result.unwrap_err().to_string();
+
+        let typo = error(Config::from_toml("[magneto]\nproxy_prot = 1\n"));
+        assert!(
+            typo.contains("line 2") && typo.contains("proxy_prot"),
+            "{}",
+            typo
+        );
+
+        let mode = error(Config::from_toml("[magneto]\nmode = \"rewind\"\n"));
+        assert!(
+            mode.contains("mode = \"rewind\"") && mode.contains("unknown variant"),
+            "{}",
+            mode
+        );
+
+        let port = error(Config::from_table(
+            toml::Table::new(),
+            env(&[("MAGNETO_PROXY_PORT", "70000")]),
+        ));
+        assert!(
+            port.contains("MAGNETO_PROXY_PORT (sets magneto.proxy_port)"),
+            "{}",
+            port
+        );
+
+        let pattern = error(Config::from_toml("[matching]\nurl_mode = \"regex\"\n"));
+        assert!(
+            pattern.contains("[matching] url_pattern is required"),
+            "{}",
+            pattern
+        );
+
+        let cookies = error(Config::from_toml("[cookies]\nfilter_patterns = [\"(\"]\n"));
+        assert!(
+            cookies.contains("[cookies] Invalid cookie filter"),
+            "{}",
+            cookies
+        );
+
+        let api = error(Config::from_toml("[api]\nauth_enabled = true\n"));
+        assert!(api.contains("[api] api_key"), "{}", api);
+
+        assert!(
+            error(Config::load(Some(Path::new("/nonexistent/magneto.toml")))).contains("not found")
+        );
+    }
+
+    #[test]
+    fn test_build_proxy() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::from_toml(&format!(
+            r#"
+            [magneto]
+            cassette_dir = "{}"
+            proxy_port = 0
+            mode = "hybrid"
+
+            [recording]
+            pending_review = true
+
+            [recording.filters]
+            enabled = true
+
+            [replay]
+            when_exhausted = "cycle"
+
+            [cookies]
+            enabled = false
+
+            [ignore]
+            localhost = true
+
+            [websocket]
+            enabled = false
+            "#,
+            dir.path().join("cassettes").display()
+        ))
+        .unwrap();
+
+        let proxy = config.build_proxy().unwrap();
+        assert_eq!(proxy.mode(), ProxyMode::Hybrid);
+        assert!(proxy.pending_review());
+        assert!(proxy.recording_filters().is_some());
+        assert_eq!(proxy.exhausted_policy(), Some(ExhaustedPolicy::Cycle));
+        assert!(!proxy.cookie_policy().is_enabled());
+        assert!(proxy.ignore_rules().is_some());
+        assert!(!proxy.websocket_enabled());
+        assert_eq!(proxy.format(), CassetteFormat::Json);
+    }
+}
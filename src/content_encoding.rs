@@ -0,0 +1,302 @@
+//! Transparent `Content-Encoding` handling for recorded bodies
+//!
+//! Responses served with `Content-Encoding: gzip`, `br` or `deflate` are
+//! decoded before they are recorded, so cassettes hold readable bodies that
+//! matchers, templates and hooks can work with. The interaction remembers
+//! the original encoding (`Interaction::content_encoding`) and the recorded
+//! headers describe the decoded body.
+//!
+//! On replay the body is encoded again when the client's `Accept-Encoding`
+//! allows it; otherwise it is served as identity, with `Content-Encoding`
+//! left out and `Content-Length` matching the decoded body.
+//!
+//! Decoding needs the `compression` feature; without it bodies are recorded
+//! as received.
+
+use crate::cassette::HttpResponse;
+#[cfg(feature = "compression")]
+use crate::error::{MatgtoError, Result};
+
+/// A content coding the proxy can decode and re-encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentCoding {
+    Gzip,
+    Deflate,
+    Brotli,
+}
+
+impl ContentCoding {
+    /// Parse a `Content-Encoding` token (`x-gzip` is an alias of `gzip`)
+    pub fn parse(token: &str) -> Option<Self> {
+        match token.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(ContentCoding::Gzip),
+            "deflate" => Some(ContentCoding::Deflate),
+            "br" => Some(ContentCoding::Brotli),
+            _ => None,
+        }
+    }
+
+    /// Token used in `Content-Encoding` and `Accept-Encoding`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentCoding::Gzip => "gzip",
+            ContentCoding::Deflate => "deflate",
+            ContentCoding::Brotli => "br",
+        }
+    }
+
+    /// Decode a body encoded with this coding
+    #[cfg(feature = "compression")]
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use std::io::Read;
+
+        let mut decoded = Vec::new();
+        let result = match self {
+            ContentCoding::Gzip => {
+                flate2::read::MultiGzDecoder::new(data).read_to_end(&mut decoded)
+            }
+            // `deflate` is zlib-wrapped per the RFC, but some servers send raw deflate
+            ContentCoding::Deflate => flate2::read::ZlibDecoder::new(data)
+                .read_to_end(&mut decoded)
+                .or_else(|_| {
+                    decoded.clear();
+                    flate2::read::DeflateDecoder::new(data).read_to_end(&mut decoded)
+                }),
+            ContentCoding::Brotli => {
+                brotli::Decompressor::new(data, 4096).read_to_end(&mut decoded)
+            }
+        };
+
+        result.map_err(|e| self.error("decode", e))?;
+        Ok(decoded)
+    }
+
+    /// Encode a body with this coding
+    #[cfg(feature = "compression")]
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use std::io::Write;
+
+        let result = match self {
+            ContentCoding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).and_then(|_| encoder.finish())
+            }
+            ContentCoding::Deflate => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).and_then(|_| encoder.finish())
+            }
+            ContentCoding::Brotli => {
+                let mut encoded = Vec::new();
+                let mut encoder = brotli::CompressorWriter::new(&mut encoded, 4096, 5, 22);
+                let result = encoder.write_all(data).and_then(|_| encoder.flush());
+                drop(encoder);
+                result.map(|_| encoded)
+            }
+        };
+
+        result.map_err(|e| self.error("encode", e))
+    }
+
+    #[cfg(feature = "compression")]
+    fn error(&self, action: &str, e: std::io::Error) -> MatgtoError {
+        MatgtoError::Http(format!(
+            "Failed to {} {} body: {}",
+            action,
+            self.as_str(),
+            e
+        ))
+    }
+}
+
+/// Decode a response body before it is recorded
+///
+/// Returns the original `Content-Encoding` when the body was decoded; the
+/// header is then removed and `Content-Length` (if any) updated. Bodies with
+/// an unknown or stacked encoding, or that fail to decode, are left as
+/// received and None is returned.
+pub fn decode_response(response: &mut HttpResponse) -> Option<String> {
+    let encoding = response.headers.get("Content-Encoding")?.trim().to_string();
+    let coding = ContentCoding::parse(&encoding)?;
+    let body = response.body.as_deref()?;
+
+    #[cfg(feature = "compression")]
+    {
+        let decoded = match coding.decode(body) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                tracing::warn!("Recording body as received: {}", e);
+                return None;
+            }
+        };
+
+        response.headers.remove("Content-Encoding");
+        if response.headers.contains_key("Content-Length") {
+            response
+                .headers
+                .insert("Content-Length", decoded.len().to_string());
+        }
+        response.body = Some(decoded);
+        Some(encoding)
+    }
+
+    #[cfg(not(feature = "compression"))]
+    {
+        let _ = (coding, body);
+        None
+    }
+}
+
+/// Whether an `Accept-Encoding` header value allows `coding`
+///
+/// Codings listed with `q=0` are refused; `*` stands for any other coding.
+/// Without the header only identity is assumed to be safe.
+pub fn accepts(accept_encoding: Option<&str>, coding: ContentCoding) -> bool {
+    let Some(accept_encoding) = accept_encoding else {
+        return false;
+    };
+
+    let mut wildcard = false;
+    for entry in accept_encoding.split(',') {
+        let mut parts = entry.split(';');
+        let token = parts.next().unwrap_or_default().trim();
+        let refused = parts.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+
+        if ContentCoding::parse(token) == Some(coding) {
+            return !refused;
+        }
+        if token == "*" {
+            wildcard = !refused;
+        }
+    }
+
+    wildcard
+}
+
+/// Prepare a recorded response for a client
+///
+/// A body recorded decoded from `encoding` is encoded again when the client
+/// accepts it (`Content-Encoding` restored, `Content-Length` updated);
+/// otherwise it is served as identity.
+pub fn encode_response(response: &mut HttpResponse, encoding: &str, accept_encoding: Option<&str>) {
+    let Some(coding) = ContentCoding::parse(encoding) else {
+        return;
+    };
+    if !accepts(accept_encoding, coding) {
+        return;
+    }
+
+    #[cfg(feature = "compression")]
+    {
+        let Some(body) = response.body.as_deref() else {
+            return;
+        };
+        let encoded = match coding.encode(body) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                tracing::warn!("Replaying body as identity: {}", e);
+                return;
+            }
+        };
+
+        response.headers.insert("Content-Encoding", encoding);
+        if response.headers.contains_key("Content-Length") {
+            response
+                .headers
+                .insert("Content-Length", encoded.len().to_string());
+        }
+        response.body = Some(encoded);
+    }
+
+    #[cfg(not(feature = "compression"))]
+    let _ = response;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::Headers;
+
+    fn response(encoding: &str, body: Vec<u8>) -> HttpResponse {
+        let mut headers = Headers::new();
+        headers.insert("Content-Type", "application/json");
+        headers.insert("Content-Encoding", encoding);
+        headers.insert("Content-Length", body.len().to_string());
+        HttpResponse {
+            status: 200,
+            headers,
+            body: Some(body),
+        }
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(accepts(Some("gzip, deflate, br"), ContentCoding::Brotli));
+        assert!(accepts(Some("br;q=1.0, gzip;q=0.8"), ContentCoding::Gzip));
+        assert!(!accepts(Some("gzip;q=0, *"), ContentCoding::Gzip));
+        assert!(accepts(Some("*"), ContentCoding::Deflate));
+        assert!(!accepts(Some("identity"), ContentCoding::Gzip));
+        assert!(!accepts(None, ContentCoding::Gzip));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decode_and_encode_round_trip() {
+        let json = br#"{"users":["alice","bob"]}"#.to_vec();
+
+        for coding in [
+            ContentCoding::Gzip,
+            ContentCoding::Deflate,
+            ContentCoding::Brotli,
+        ] {
+            let mut recorded = response(coding.as_str(), coding.encode(&json).unwrap());
+            assert_eq!(
+                decode_response(&mut recorded).as_deref(),
+                Some(coding.as_str())
+            );
+            assert_eq!(recorded.body.as_deref(), Some(json.as_slice()));
+            assert!(!recorded.headers.contains_key("content-encoding"));
+            assert_eq!(
+                recorded.headers.get("content-length"),
+                Some(json.len().to_string().as_str())
+            );
+
+            // Identity for a client that does not accept the coding
+            let mut identity = recorded.clone();
+            encode_response(&mut identity, coding.as_str(), Some("identity"));
+            assert_eq!(identity.body.as_deref(), Some(json.as_slice()));
+
+            // Encoded again for one that does
+            let mut encoded = recorded.clone();
+            encode_response(&mut encoded, coding.as_str(), Some("gzip, deflate, br"));
+            assert_eq!(
+                encoded.headers.get("content-encoding"),
+                Some(coding.as_str())
+            );
+            let body = encoded.body.unwrap();
+            assert_eq!(
+                encoded.headers.get("content-length"),
+                Some(body.len().to_string().as_str())
+            );
+            assert_eq!(coding.decode(&body).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn test_unknown_or_corrupt_bodies_are_kept() {
+        let mut zstd = response("zstd", vec![1, 2, 3]);
+        assert_eq!(decode_response(&mut zstd), None);
+        assert_eq!(zstd.headers.get("content-encoding"), Some("zstd"));
+
+        let mut corrupt = response("gzip", b"not gzip".to_vec());
+        assert_eq!(decode_response(&mut corrupt), None);
+        assert_eq!(corrupt.body.as_deref(), Some(b"not gzip".as_slice()));
+    }
+}
@@ -17,12 +17,38 @@
 //! let header = jar.get_header_value("https://example.com/api").unwrap();
 //! // Returns: "session=abc123"
 //! ```
+//!
+//! # Recording and replay
+//!
+//! The recorder parses `Set-Cookie` headers into the cassette's `cookies`,
+//! and the player loads them into its jar: during replay, cookies set by
+//! replayed responses are kept, and requests sent without a `Cookie` header
+//! are matched as if they carried the jar's cookies.
+//!
+//! Cookies whose name matches a [`CookiePolicy`] filter are sensitive: they
+//! are dropped from the cassette (`Set-Cookie` and `Cookie` headers
+//! included), or stored with their value hashed (`sha256:<hex>`) so that
+//! requests still match without the secret being recorded.
+//!
+//! ```toml
+//! [cookies]
+//! enabled = true
+//! filter_patterns = [".*_token$", "secret_.*"]
+//! hash_sensitive = false
+//! ```
 
+use crate::cassette::{Headers, HttpRequest, HttpResponse};
+use crate::error::MatgtoError;
 use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fmt;
 
+/// Prefix of cookie values stored as a SHA-256 hash
+pub const HASH_PREFIX: &str = "sha256:";
+
 /// A single HTTP cookie with all RFC 6265 attributes
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Cookie {
@@ -165,6 +191,15 @@ impl Cookie {
         Ok(cookie)
     }
 
+    /// The cookie with its creation and expiry times moved by `by`
+    pub fn shifted(&self, by: Duration) -> Cookie {
+        Cookie {
+            expires: self.expires.map(|expires| expires + by),
+            created_at: self.created_at + by,
+            ..self.clone()
+        }
+    }
+
     /// Check if cookie is expired
     pub fn is_expired(&self) -> bool {
         let now = Utc::now();
@@ -358,6 +393,227 @@ impl CookieJar {
     }
 }
 
+/// Cookie settings, as written in magneto.toml
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookieConfig {
+    /// Record cookies in cassettes and replay them through the player's jar
+    pub enabled: bool,
+
+    /// Regexes matching the names of sensitive cookies
+    pub filter_patterns: Vec<String>,
+
+    /// Store sensitive cookies with a hashed value instead of dropping them
+    pub hash_sensitive: bool,
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            filter_patterns: Vec::new(),
+            hash_sensitive: false,
+        }
+    }
+}
+
+impl CookieConfig {
+    /// Build the configured policy
+    ///
+    /// Fails on an invalid regex.
+    pub fn resolve(&self) -> crate::error::Result<CookiePolicy> {
+        let mut policy = CookiePolicy::new().with_hash_sensitive(self.hash_sensitive);
+        if !self.enabled {
+            policy = policy.disabled();
+        }
+        for pattern in &self.filter_patterns {
+            policy = policy.with_filter(pattern)?;
+        }
+        Ok(policy)
+    }
+}
+
+/// How cookies are recorded and replayed
+///
+/// Sensitive cookies are filtered even when preservation is disabled.
+#[derive(Debug, Clone)]
+pub struct CookiePolicy {
+    enabled: bool,
+    filters: Vec<Regex>,
+    hash_sensitive: bool,
+}
+
+impl Default for CookiePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            filters: Vec::new(),
+            hash_sensitive: false,
+        }
+    }
+}
+
+impl CookiePolicy {
+    /// Preserve every cookie as is
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Neither store cookies in the cassette nor replay them through the jar
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+
+    /// Whether cookies are stored in the cassette and replayed through the jar
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Treat cookies whose name matches `pattern` (anchored) as sensitive
+    pub fn with_filter(mut self, pattern: &str) -> crate::error::Result<Self> {
+        let regex = Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| {
+            MatgtoError::Config(format!("Invalid cookie filter '{}': {}", pattern, e))
+        })?;
+        self.filters.push(regex);
+        Ok(self)
+    }
+
+    /// Store sensitive cookies with a hashed value instead of dropping them
+    pub fn with_hash_sensitive(mut self, hash: bool) -> Self {
+        self.hash_sensitive = hash;
+        self
+    }
+
+    /// Whether a cookie name matches a filter
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        self.filters.iter().any(|filter| filter.is_match(name))
+    }
+
+    /// Value a cookie is stored with (None = dropped)
+    pub fn protect(&self, name: &str, value: &str) -> Option<String> {
+        if !self.is_sensitive(name) {
+            Some(value.to_string())
+        } else if self.hash_sensitive {
+            Some(hash_value(value))
+        } else {
+            None
+        }
+    }
+
+    /// Filter the cookies of an exchange about to be recorded
+    ///
+    /// Rewrites the request's `Cookie` header and the response's
+    /// `Set-Cookie` headers, and returns the cookies the response sets
+    /// (empty when preservation is disabled). Cookies without a `Domain`
+    /// are scoped to the request's host.
+    pub fn apply(&self, request: &mut HttpRequest, response: &mut HttpResponse) -> Vec<Cookie> {
+        self.apply_to_request(&mut request.headers);
+
+        let host = url::Url::parse(&request.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string));
+        let cookies = self.apply_to_response(&mut response.headers);
+        if !self.enabled {
+            return Vec::new();
+        }
+
+        cookies
+            .into_iter()
+            .map(|mut cookie| {
+                if cookie.domain.is_none() {
+                    cookie.domain = host.clone();
+                }
+                cookie
+            })
+            .collect()
+    }
+
+    /// Drop or hash sensitive cookies in a `Cookie` request header
+    pub fn apply_to_request(&self, headers: &mut Headers) {
+        if self.filters.is_empty() || !headers.contains_key("Cookie") {
+            return;
+        }
+
+        let mut filtered = Headers::new();
+        for header in headers.entries() {
+            let value = match header.value_str() {
+                Some(value) if header.name.eq_ignore_ascii_case("cookie") => value,
+                _ => {
+                    filtered.append_raw(header.name.as_str(), header.value.clone());
+                    continue;
+                }
+            };
+
+            let pairs: Vec<String> = value
+                .split(';')
+                .map(str::trim)
+                .filter(|pair| !pair.is_empty())
+                .filter_map(|pair| match pair.split_once('=') {
+                    Some((name, value)) => self
+                        .protect(name.trim(), value.trim())
+                        .map(|value| format!("{}={}", name.trim(), value)),
+                    None => Some(pair.to_string()),
+                })
+                .collect();
+            if !pairs.is_empty() {
+                filtered.append(header.name.as_str(), pairs.join("; "));
+            }
+        }
+        *headers = filtered;
+    }
+
+    /// Drop or hash sensitive cookies in `Set-Cookie` response headers
+    ///
+    /// Returns the cookies left, with the values they are stored with.
+    pub fn apply_to_response(&self, headers: &mut Headers) -> Vec<Cookie> {
+        let mut cookies = Vec::new();
+        if !headers.contains_key("Set-Cookie") {
+            return cookies;
+        }
+
+        let mut filtered = Headers::new();
+        for header in headers.entries() {
+            let parsed = match header.value_str() {
+                Some(value) if header.name.eq_ignore_ascii_case("set-cookie") => {
+                    Cookie::parse(value).ok().map(|cookie| (value, cookie))
+                }
+                _ => None,
+            };
+            let Some((line, mut cookie)) = parsed else {
+                filtered.append_raw(header.name.as_str(), header.value.clone());
+                continue;
+            };
+
+            let Some(value) = self.protect(&cookie.name, &cookie.value) else {
+                continue;
+            };
+            if value == cookie.value {
+                filtered.append(header.name.as_str(), line);
+            } else {
+                let attributes = line.find(';').map_or("", |start| &line[start..]);
+                filtered.append(
+                    header.name.as_str(),
+                    format!("{}={}{}", cookie.name, value, attributes),
+                );
+                cookie.value = value;
+            }
+            cookies.push(cookie);
+        }
+        *headers = filtered;
+
+        cookies
+    }
+}
+
+/// Hash a cookie value (values already hashed are kept)
+pub fn hash_value(value: &str) -> String {
+    if value.starts_with(HASH_PREFIX) {
+        return value.to_string();
+    }
+    format!("{}{:x}", HASH_PREFIX, Sha256::digest(value.as_bytes()))
+}
+
 /// Parse HTTP date format (RFC 7231 Section 7.1.1.1)
 fn parse_http_date(date_str: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
     // Try multiple formats
@@ -523,4 +779,80 @@ mod tests {
 
         assert_eq!(jar.len(), 1);
     }
+
+    fn exchange(cookie: &str, set_cookies: &[&str]) -> (HttpRequest, HttpResponse) {
+        let request = HttpRequest {
+            method: "GET".to_string(),
+            url: "https://api.example.com/me".to_string(),
+            headers: [("Cookie", cookie)].into(),
+            body: None,
+        };
+        let response = HttpResponse {
+            status: 200,
+            headers: set_cookies.iter().map(|c| ("Set-Cookie", *c)).collect(),
+            body: None,
+        };
+        (request, response)
+    }
+
+    #[test]
+    fn test_policy_drops_sensitive_cookies() {
+        let policy = CookiePolicy::new().with_filter(".*_token").unwrap();
+        let (mut request, mut response) = exchange(
+            "session=abc; csrf_token=t1",
+            &["session=def; Path=/", "refresh_token=t2; HttpOnly"],
+        );
+
+        let cookies = policy.apply(&mut request, &mut response);
+
+        assert_eq!(request.headers.get("cookie"), Some("session=abc"));
+        assert_eq!(
+            response.headers.get_all("set-cookie").collect::<Vec<_>>(),
+            vec!["session=def; Path=/"]
+        );
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].value, "def");
+        assert_eq!(cookies[0].domain.as_deref(), Some("api.example.com"));
+
+        // Nothing is kept for the cassette when preservation is disabled
+        let (mut request, mut response) = exchange("csrf_token=t1", &["session=def"]);
+        let disabled = policy.disabled();
+        assert!(disabled.apply(&mut request, &mut response).is_empty());
+        assert!(!request.headers.contains_key("cookie"));
+    }
+
+    #[test]
+    fn test_policy_hashes_sensitive_cookies() {
+        let config: CookieConfig = toml::from_str(
+            r#"
+            filter_patterns = ["secret_.*"]
+            hash_sensitive = true
+            "#,
+        )
+        .unwrap();
+        let policy = config.resolve().unwrap();
+        let (mut request, mut response) =
+            exchange("secret_id=42", &["secret_id=42; Secure", "theme=dark"]);
+
+        let cookies = policy.apply(&mut request, &mut response);
+
+        let hashed = hash_value("42");
+        assert!(hashed.starts_with(HASH_PREFIX));
+        assert_eq!(hash_value(&hashed), hashed);
+        assert_eq!(
+            request.headers.get("cookie"),
+            Some(format!("secret_id={}", hashed).as_str())
+        );
+        assert_eq!(
+            response.headers.get("set-cookie"),
+            Some(format!("secret_id={}; Secure", hashed).as_str())
+        );
+        assert_eq!(cookies.len(), 2);
+        assert!(CookieConfig {
+            filter_patterns: vec!["(".to_string()],
+            ..Default::default()
+        }
+        .resolve()
+        .is_err());
+    }
 }
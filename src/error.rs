@@ -1,5 +1,6 @@
 //! Error types for magneto-serge
 
+use crate::cassette::NetworkError;
 use thiserror::Error;
 
 /// Result type alias for magneto-serge operations
@@ -20,6 +21,14 @@ pub enum MatgtoError {
     #[error("No matching interaction for {method} {url}")]
     NoMatchingInteraction { method: String, url: String },
 
+    /// Every recorded response for a repeated request was already replayed
+    #[error("All {recorded} recorded responses for {method} {url} were already replayed")]
+    ReplayExhausted {
+        method: String,
+        url: String,
+        recorded: usize,
+    },
+
     /// No interaction found (generic)
     #[error("No interaction found")]
     NoInteractionFound,
@@ -44,6 +53,10 @@ pub enum MatgtoError {
     #[error("WebSocket error: {reason}")]
     WebSocketError { reason: String },
 
+    /// Upstream network failure (DNS, refused, timeout, TLS, reset)
+    #[error("Network error: {0}")]
+    Network(NetworkError),
+
     /// Serialization error (JSON)
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
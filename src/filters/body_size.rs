@@ -51,7 +51,7 @@ impl RequestFilter for BodySizeFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::cassette::Headers;
 
     #[test]
     fn test_body_size_filter() {
@@ -60,19 +60,19 @@ mod tests {
         let req = HttpRequest {
             method: "GET".to_string(),
             url: "http://example.com/test".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let res_small = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(vec![0u8; 512]), // 512 bytes
         };
 
         let res_large = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(vec![0u8; 2048]), // 2KB
         };
 
@@ -85,7 +85,7 @@ impl RequestFilter for ContentTypeFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::cassette::Headers;
 
     #[test]
     fn test_content_type_filter() {
@@ -95,13 +95,13 @@ mod tests {
         let req = HttpRequest {
             method: "GET".to_string(),
             url: "http://example.com/test".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let mut res_image = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
         res_image
@@ -110,7 +110,7 @@ mod tests {
 
         let mut res_json = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
         res_json
@@ -74,7 +74,7 @@ impl RequestFilter for ExtensionFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::cassette::Headers;
 
     #[test]
     fn test_extension_filter() {
@@ -84,20 +84,20 @@ mod tests {
         let req_js = HttpRequest {
             method: "GET".to_string(),
             url: "http://example.com/app.js".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let req_html = HttpRequest {
             method: "GET".to_string(),
             url: "http://example.com/index.html".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let res = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
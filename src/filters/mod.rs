@@ -31,6 +31,7 @@ pub mod url_pattern;
 use crate::cassette::{HttpRequest, HttpResponse};
 // use crate::error::Result;  // Not used yet
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 pub use body_size::BodySizeFilter;
 pub use content_type::ContentTypeFilter;
@@ -53,9 +54,9 @@ pub trait RequestFilter: Send + Sync + std::fmt::Debug {
 }
 
 /// Chain of filters with AND/OR logic
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FilterChain {
-    filters: Vec<Box<dyn RequestFilter>>,
+    filters: Vec<Arc<dyn RequestFilter>>,
     logic: FilterLogic,
 }
 
@@ -88,7 +89,7 @@ impl FilterChain {
 
     /// Add a filter to the chain
     pub fn add_filter<F: RequestFilter + 'static>(&mut self, filter: F) {
-        self.filters.push(Box::new(filter));
+        self.filters.push(Arc::new(filter));
     }
 
     /// Check if request/response should be recorded
@@ -218,6 +219,111 @@ impl FilterPresets {
     }
 }
 
+/// Preset a filter configuration starts from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterPreset {
+    /// See [`FilterPresets::web_assets`]
+    #[default]
+    WebAssets,
+
+    /// See [`FilterPresets::images`]
+    Images,
+
+    /// See [`FilterPresets::fonts`]
+    Fonts,
+
+    /// See [`FilterPresets::comprehensive`]
+    Comprehensive,
+
+    /// Only the custom exclusions
+    None,
+}
+
+/// Recording filters, as written in magneto.toml
+///
+/// The custom exclusions are added to the preset's filters.
+///
+/// ```toml
+/// [recording.filters]
+/// enabled = true
+/// preset = "web_assets"
+/// exclude_url_patterns = ["/static/*"]
+/// exclude_status_codes = [404]
+/// max_body_size_kb = 1024
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterConfig {
+    /// Apply the filters when recording
+    pub enabled: bool,
+
+    /// Preset the filters start from
+    pub preset: FilterPreset,
+
+    /// URL extensions not recorded (`.js`)
+    pub exclude_extensions: Vec<String>,
+
+    /// Response Content-Types not recorded (`image/*`)
+    pub exclude_content_types: Vec<String>,
+
+    /// URL path globs not recorded (`/static/*`)
+    pub exclude_url_patterns: Vec<String>,
+
+    /// Response status codes not recorded
+    pub exclude_status_codes: Vec<u16>,
+
+    /// Responses with a larger body are not recorded, in KB
+    pub max_body_size_kb: Option<usize>,
+}
+
+impl FilterConfig {
+    /// Build the configured filters (None when filtering is disabled)
+    pub fn resolve(&self) -> Option<FilterChain> {
+        if !self.enabled {
+            return None;
+        }
+
+        let mut chain = match self.preset {
+            FilterPreset::WebAssets => FilterPresets::web_assets(),
+            FilterPreset::Images => FilterPresets::images(),
+            FilterPreset::Fonts => FilterPresets::fonts(),
+            FilterPreset::Comprehensive => FilterPresets::comprehensive(),
+            FilterPreset::None => FilterChain::new(),
+        };
+
+        if !self.exclude_extensions.is_empty() {
+            let mut filter = ExtensionFilter::new();
+            filter.add_extensions(&as_strs(&self.exclude_extensions));
+            chain.add_filter(filter);
+        }
+        if !self.exclude_content_types.is_empty() {
+            let mut filter = ContentTypeFilter::new();
+            filter.add_patterns(&as_strs(&self.exclude_content_types));
+            chain.add_filter(filter);
+        }
+        if !self.exclude_url_patterns.is_empty() {
+            let mut filter = UrlPatternFilter::new();
+            filter.add_patterns(&as_strs(&self.exclude_url_patterns));
+            chain.add_filter(filter);
+        }
+        if !self.exclude_status_codes.is_empty() {
+            let mut filter = StatusCodeFilter::new();
+            filter.add_codes(&self.exclude_status_codes);
+            chain.add_filter(filter);
+        }
+        if let Some(kb) = self.max_body_size_kb {
+            chain.add_filter(BodySizeFilter::new_kb(kb));
+        }
+
+        Some(chain)
+    }
+}
+
+fn as_strs(values: &[String]) -> Vec<&str> {
+    values.iter().map(String::as_str).collect()
+}
+
 /// Filter statistics for reporting
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FilterStats {
@@ -86,7 +86,7 @@ impl RequestFilter for StatusCodeFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::cassette::Headers;
 
     #[test]
     fn test_status_code_filter() {
@@ -96,19 +96,19 @@ mod tests {
         let req = HttpRequest {
             method: "GET".to_string(),
             url: "http://example.com/test".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let res_200 = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let res_404 = HttpResponse {
             status: 404,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
@@ -123,19 +123,19 @@ mod tests {
         let req = HttpRequest {
             method: "GET".to_string(),
             url: "http://example.com/test".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let res_200 = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let res_403 = HttpResponse {
             status: 403,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
@@ -88,7 +88,7 @@ impl RequestFilter for UrlPatternFilter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::cassette::Headers;
 
     #[test]
     fn test_url_pattern_filter() {
@@ -98,20 +98,20 @@ mod tests {
         let req_static = HttpRequest {
             method: "GET".to_string(),
             url: "http://example.com/static/app.js".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let req_api = HttpRequest {
             method: "GET".to_string(),
             url: "http://example.com/api/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let res = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
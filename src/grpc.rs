@@ -0,0 +1,566 @@
+//! gRPC support
+//!
+//! gRPC calls are HTTP/2 POST requests to `/package.Service/Method` whose
+//! bodies are sequences of length-prefixed messages (a compression flag, a
+//! 4-byte big-endian length, then the protobuf payload). The outcome travels
+//! in the trailers (`grpc-status`, `grpc-message`), or in the headers of a
+//! trailers-only response.
+//!
+//! Calls are recorded as `InteractionKind::Grpc`, keeping every message of
+//! both directions in the order it crossed the proxy, so unary, client,
+//! server and bidi streaming calls replay the same way. With descriptor sets
+//! (`protoc --include_imports --descriptor_set_out=api.desc ...`) and the
+//! `grpc` feature, payloads are also stored decoded to JSON: cassettes become
+//! reviewable and replay matches messages on their content rather than on
+//! byte-exact encodings.
+//!
+//! ```toml
+//! [grpc]
+//! descriptor_sets = ["protos/api.desc"]
+//! ```
+
+use crate::cassette::{Direction, GrpcMessage, Headers};
+use crate::error::{MatgtoError, Result};
+
+use hyper::body::Bytes;
+use hyper::header::CONTENT_TYPE;
+use hyper::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Length of the prefix before each message (compression flag + length)
+const PREFIX_LEN: usize = 5;
+
+/// `grpc-status` sent when replay finds no recorded call (NOT_FOUND)
+pub const STATUS_NOT_FOUND: u32 = 5;
+
+/// Whether a request or response carries gRPC (`application/grpc[+proto|+json]`)
+///
+/// gRPC-Web is left out: its trailers travel inside the body.
+pub fn is_grpc(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            let value = value.to_ascii_lowercase();
+            value == "application/grpc"
+                || value.starts_with("application/grpc+")
+                || value.starts_with("application/grpc;")
+        })
+        .unwrap_or(false)
+}
+
+/// Service and method of a gRPC path (`/package.Service/Method`)
+pub fn parse_path(path: &str) -> Option<(String, String)> {
+    let (service, method) = path.strip_prefix('/')?.split_once('/')?;
+    if service.is_empty() || method.is_empty() || method.contains('/') {
+        return None;
+    }
+    Some((service.to_string(), method.to_string()))
+}
+
+/// Status code and message of a call
+///
+/// Read from the trailers, or from the headers of a trailers-only response.
+/// A call that ended without any status is reported as UNKNOWN (2).
+pub fn call_status(metadata: &Headers, trailers: &Headers) -> (u32, Option<String>) {
+    let source = if trailers.get("grpc-status").is_some() {
+        trailers
+    } else {
+        metadata
+    };
+    let status = source
+        .get("grpc-status")
+        .and_then(|status| status.trim().parse().ok())
+        .unwrap_or(2);
+    let message = source.get("grpc-message").map(percent_decode);
+    (status, message)
+}
+
+/// Decode a `grpc-message` value (percent-encoded UTF-8)
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(byte) = std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Encode a message as a length-prefixed frame
+pub fn encode_frame(compressed: bool, data: &[u8]) -> Bytes {
+    let mut frame = Vec::with_capacity(PREFIX_LEN + data.len());
+    frame.push(compressed as u8);
+    frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    frame.extend_from_slice(data);
+    Bytes::from(frame)
+}
+
+/// Incremental decoder of length-prefixed frames
+///
+/// Body chunks do not line up with messages: a chunk may hold several
+/// messages or a part of one. Incomplete data is kept until the next chunk.
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    /// Create an empty decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a body chunk, returning the messages it completes as `(compressed, payload)`
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<(bool, Vec<u8>)> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut frames = Vec::new();
+        let mut offset = 0;
+        while self.buffer.len() - offset >= PREFIX_LEN {
+            let header = &self.buffer[offset..offset + PREFIX_LEN];
+            let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+            if self.buffer.len() - offset - PREFIX_LEN < len {
+                break;
+            }
+            let start = offset + PREFIX_LEN;
+            frames.push((header[0] & 1 == 1, self.buffer[start..start + len].to_vec()));
+            offset = start + len;
+        }
+        self.buffer.drain(..offset);
+
+        frames
+    }
+
+    /// Bytes of an incomplete message waiting for more data
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// Whether a live message matches a recorded one
+///
+/// Decoded messages are compared on their JSON form, so equivalent encodings
+/// (field order, default values, packed repeated fields) match; otherwise the
+/// payloads must be identical.
+pub fn messages_match(recorded: &GrpcMessage, live: &GrpcMessage) -> bool {
+    match (&recorded.decoded, &live.decoded) {
+        (Some(recorded), Some(live)) => recorded == live,
+        _ => recorded.compressed == live.compressed && recorded.data == live.data,
+    }
+}
+
+/// Number of request messages a recorded call received before its first reply
+///
+/// Replay must have seen that many live messages before it commits to the
+/// call and starts answering (all of them when the call never replied).
+pub fn sent_before_reply(recorded: &[GrpcMessage]) -> usize {
+    recorded
+        .iter()
+        .take_while(|message| message.direction == Direction::Sent)
+        .count()
+}
+
+/// Whether a recorded call is still a candidate for the live request messages
+///
+/// Every live message must match the recorded one at the same position. Once
+/// the client finished sending (`ended`), the counts must be equal too.
+pub fn call_matches(recorded: &[GrpcMessage], live: &[GrpcMessage], ended: bool) -> bool {
+    let sent: Vec<&GrpcMessage> = recorded
+        .iter()
+        .filter(|message| message.direction == Direction::Sent)
+        .collect();
+
+    live.len() <= sent.len()
+        && (!ended || live.len() == sent.len())
+        && sent
+            .iter()
+            .zip(live)
+            .all(|(recorded, live)| messages_match(recorded, live))
+}
+
+/// gRPC settings, as written in magneto.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrpcConfig {
+    /// Binary `FileDescriptorSet` files used to decode messages
+    pub descriptor_sets: Vec<PathBuf>,
+}
+
+impl GrpcConfig {
+    /// Load the configured descriptor sets (None when none is configured)
+    pub fn load(&self) -> Result<Option<GrpcDescriptors>> {
+        if self.descriptor_sets.is_empty() {
+            return Ok(None);
+        }
+        GrpcDescriptors::from_files(&self.descriptor_sets).map(Some)
+    }
+}
+
+/// Protobuf descriptors used to decode gRPC messages
+///
+/// Decoding needs the `grpc` feature; without it descriptor sets cannot be
+/// loaded and messages are only stored as bytes.
+#[derive(Debug, Clone)]
+pub struct GrpcDescriptors {
+    #[cfg(feature = "grpc")]
+    pool: prost_reflect::DescriptorPool,
+}
+
+impl GrpcDescriptors {
+    /// Load binary `FileDescriptorSet` files
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        let sets = paths
+            .iter()
+            .map(|path| {
+                std::fs::read(path.as_ref()).map_err(|e| {
+                    MatgtoError::Config(format!(
+                        "Cannot read descriptor set {}: {}",
+                        path.as_ref().display(),
+                        e
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_bytes(&sets)
+    }
+
+    /// Load encoded `FileDescriptorSet`s
+    #[cfg(feature = "grpc")]
+    pub fn from_bytes<B: AsRef<[u8]>>(sets: &[B]) -> Result<Self> {
+        let mut pool = prost_reflect::DescriptorPool::new();
+        for set in sets {
+            pool.decode_file_descriptor_set(set.as_ref())
+                .map_err(|e| MatgtoError::Config(format!("Invalid descriptor set: {}", e)))?;
+        }
+        Ok(Self { pool })
+    }
+
+    /// Load encoded `FileDescriptorSet`s
+    #[cfg(not(feature = "grpc"))]
+    pub fn from_bytes<B: AsRef<[u8]>>(_sets: &[B]) -> Result<Self> {
+        Err(MatgtoError::Config(
+            "Decoding gRPC messages requires the `grpc` feature".to_string(),
+        ))
+    }
+
+    /// Decode a message of `service`/`method` to its JSON mapping
+    ///
+    /// Sent messages are decoded as the method's input type, received ones as
+    /// its output type. Returns None for unknown methods and invalid payloads.
+    #[cfg(feature = "grpc")]
+    pub fn decode(
+        &self,
+        service: &str,
+        method: &str,
+        direction: Direction,
+        data: &[u8],
+    ) -> Option<serde_json::Value> {
+        let method = self
+            .pool
+            .get_service_by_name(service)?
+            .methods()
+            .find(|m| m.name() == method)?;
+        let descriptor = match direction {
+            Direction::Sent => method.input(),
+            Direction::Received => method.output(),
+        };
+        let message = prost_reflect::DynamicMessage::decode(descriptor, data).ok()?;
+        serde_json::to_value(&message).ok()
+    }
+
+    /// Decode a message of `service`/`method` to its JSON mapping
+    #[cfg(not(feature = "grpc"))]
+    pub fn decode(
+        &self,
+        _service: &str,
+        _method: &str,
+        _direction: Direction,
+        _data: &[u8],
+    ) -> Option<serde_json::Value> {
+        None
+    }
+}
+
+/// Messages of a call being proxied, in the order they were seen
+///
+/// Both directions push the body chunks they stream; complete messages are
+/// stamped with the time elapsed since the call started and decoded when
+/// descriptors are available.
+#[derive(Debug)]
+pub struct CallCapture {
+    service: String,
+    method: String,
+    descriptors: Option<Arc<GrpcDescriptors>>,
+    started: Instant,
+    sent: FrameDecoder,
+    received: FrameDecoder,
+    messages: Vec<GrpcMessage>,
+}
+
+impl CallCapture {
+    /// Start capturing a call
+    pub fn new(
+        service: &str,
+        method: &str,
+        descriptors: Option<Arc<GrpcDescriptors>>,
+        started: Instant,
+    ) -> Self {
+        Self {
+            service: service.to_string(),
+            method: method.to_string(),
+            descriptors,
+            started,
+            sent: FrameDecoder::new(),
+            received: FrameDecoder::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Add a body chunk flowing in `direction`, returning the messages it completes
+    pub fn push(&mut self, direction: Direction, chunk: &[u8]) -> Vec<GrpcMessage> {
+        let decoder = match direction {
+            Direction::Sent => &mut self.sent,
+            Direction::Received => &mut self.received,
+        };
+        let frames = decoder.push(chunk);
+        let timestamp_ms = self.started.elapsed().as_millis() as u64;
+
+        let messages: Vec<GrpcMessage> = frames
+            .into_iter()
+            .map(|(compressed, data)| {
+                let decoded = match &self.descriptors {
+                    Some(descriptors) if !compressed => {
+                        descriptors.decode(&self.service, &self.method, direction, &data)
+                    }
+                    _ => None,
+                };
+                GrpcMessage {
+                    direction,
+                    timestamp_ms,
+                    compressed,
+                    data,
+                    decoded,
+                }
+            })
+            .collect();
+
+        self.messages.extend(messages.iter().cloned());
+        messages
+    }
+
+    /// Messages captured so far
+    pub fn messages(&self) -> &[GrpcMessage] {
+        &self.messages
+    }
+
+    /// Take the captured messages
+    pub fn into_messages(self) -> Vec<GrpcMessage> {
+        self.messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn test_is_grpc() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_grpc(&headers));
+
+        for (content_type, expected) in [
+            ("application/grpc", true),
+            ("application/grpc+proto", true),
+            ("application/grpc-web", false),
+            ("application/json", false),
+        ] {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            assert_eq!(is_grpc(&headers), expected, "{}", content_type);
+        }
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("/helloworld.Greeter/SayHello"),
+            Some(("helloworld.Greeter".to_string(), "SayHello".to_string()))
+        );
+        assert_eq!(parse_path("/users/1/posts"), None);
+        assert_eq!(parse_path("/health"), None);
+    }
+
+    #[test]
+    fn test_frames_split_across_chunks() {
+        let mut stream = encode_frame(false, b"first").to_vec();
+        stream.extend_from_slice(&encode_frame(true, b""));
+        stream.extend_from_slice(&encode_frame(false, b"third"));
+
+        let mut decoder = FrameDecoder::new();
+        assert_eq!(decoder.push(&stream[..3]), vec![]);
+        assert_eq!(
+            decoder.push(&stream[3..16]),
+            vec![(false, b"first".to_vec()), (true, Vec::new())]
+        );
+        assert_eq!(decoder.pending(), 1);
+        assert_eq!(
+            decoder.push(&stream[16..]),
+            vec![(false, b"third".to_vec())]
+        );
+        assert_eq!(decoder.pending(), 0);
+    }
+
+    #[test]
+    fn test_call_status() {
+        let mut trailers = Headers::new();
+        trailers.append("grpc-status", "3");
+        trailers.append("grpc-message", "bad%20name%3A%20%C3%A9");
+        assert_eq!(
+            call_status(&Headers::new(), &trailers),
+            (3, Some("bad name: é".to_string()))
+        );
+
+        // Trailers-only response
+        let mut metadata = Headers::new();
+        metadata.append("grpc-status", "0");
+        assert_eq!(call_status(&metadata, &Headers::new()), (0, None));
+
+        assert_eq!(call_status(&Headers::new(), &Headers::new()), (2, None));
+    }
+
+    #[test]
+    fn test_messages_match_on_decoded_form() {
+        let message = |data: &[u8], decoded: Option<serde_json::Value>| GrpcMessage {
+            direction: Direction::Sent,
+            timestamp_ms: 0,
+            compressed: false,
+            data: data.to_vec(),
+            decoded,
+        };
+
+        // Same content, different encodings
+        let recorded = message(&[8, 1, 18, 1, 97], Some(serde_json::json!({"id": 1})));
+        let live = message(&[18, 1, 97, 8, 1], Some(serde_json::json!({"id": 1})));
+        assert!(messages_match(&recorded, &live));
+
+        // Without descriptors the bytes must be equal
+        assert!(!messages_match(
+            &message(&[8, 1], None),
+            &message(&[8, 2], None)
+        ));
+        assert!(messages_match(
+            &message(&[8, 1], None),
+            &message(&[8, 1], None)
+        ));
+    }
+
+    #[test]
+    fn test_call_matches_request_messages() {
+        let message = |direction, data: &[u8]| GrpcMessage {
+            direction,
+            timestamp_ms: 0,
+            compressed: false,
+            data: data.to_vec(),
+            decoded: None,
+        };
+        // Client stream of two messages, one reply, then a third message
+        let recorded = vec![
+            message(Direction::Sent, b"a"),
+            message(Direction::Sent, b"b"),
+            message(Direction::Received, b"ok"),
+            message(Direction::Sent, b"c"),
+        ];
+        assert_eq!(sent_before_reply(&recorded), 2);
+
+        let live = [message(Direction::Sent, b"a")];
+        assert!(call_matches(&recorded, &live, false));
+        assert!(!call_matches(&recorded, &live, true));
+
+        let live = [
+            message(Direction::Sent, b"a"),
+            message(Direction::Sent, b"x"),
+        ];
+        assert!(!call_matches(&recorded, &live, false));
+    }
+
+    #[cfg(feature = "grpc")]
+    #[test]
+    fn test_decode_with_descriptor_set() {
+        use prost::Message;
+        use prost_types::field_descriptor_proto::{Label, Type};
+        use prost_types::{
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+            MethodDescriptorProto, ServiceDescriptorProto,
+        };
+
+        let field = |name: &str, number, kind: Type| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            json_name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(kind as i32),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("echo.proto".to_string()),
+            package: Some("echo".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Msg".to_string()),
+                field: vec![field("id", 1, Type::Int32), field("text", 2, Type::String)],
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Echo".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("Chat".to_string()),
+                    input_type: Some(".echo.Msg".to_string()),
+                    output_type: Some(".echo.Msg".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let set = FileDescriptorSet { file: vec![file] }.encode_to_vec();
+        let descriptors = Arc::new(GrpcDescriptors::from_bytes(&[set]).unwrap());
+
+        // Same message with its fields in both orders
+        let mut capture = CallCapture::new("echo.Echo", "Chat", Some(descriptors), Instant::now());
+        capture.push(Direction::Sent, &encode_frame(false, &[8, 1, 18, 1, b'a']));
+        capture.push(
+            Direction::Received,
+            &encode_frame(false, &[18, 1, b'a', 8, 1]),
+        );
+
+        let messages = capture.into_messages();
+        let expected = serde_json::json!({"id": 1, "text": "a"});
+        assert_eq!(messages[0].decoded, Some(expected.clone()));
+        assert_eq!(messages[1].decoded, Some(expected));
+        assert!(messages_match(&messages[0], &messages[1]));
+
+        assert!(GrpcDescriptors::from_bytes(&[b"not a descriptor set".to_vec()]).is_err());
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    #[test]
+    fn test_descriptors_require_feature() {
+        assert!(GrpcDescriptors::from_bytes(&[Vec::new()]).is_err());
+        assert!(GrpcConfig::default().load().unwrap().is_none());
+    }
+}
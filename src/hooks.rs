@@ -106,7 +106,7 @@ pub trait ReplayHook: Send + Sync + Debug {
 }
 
 /// Collection of record hooks
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct RecordHooks {
     hooks: Vec<Arc<dyn RecordHook>>,
 }
@@ -158,7 +158,7 @@ impl Debug for RecordHooks {
 }
 
 /// Collection of replay hooks
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ReplayHooks {
     hooks: Vec<Arc<dyn ReplayHook>>,
 }
@@ -366,7 +366,10 @@ pub mod builtins {
             if let InteractionKind::Http { request, .. } = &interaction.kind {
                 eprintln!("📝 Recording: {} {}", request.method, request.url);
                 if self.verbose {
-                    eprintln!("   Headers: {:?}", request.headers.keys());
+                    eprintln!(
+                        "   Headers: {:?}",
+                        request.headers.keys().collect::<Vec<_>>()
+                    );
                 }
             }
             Ok(())
@@ -392,7 +395,10 @@ pub mod builtins {
                     request.method, request.url, response.status
                 );
                 if self.verbose {
-                    eprintln!("   Headers: {:?}", request.headers.keys());
+                    eprintln!(
+                        "   Headers: {:?}",
+                        request.headers.keys().collect::<Vec<_>>()
+                    );
                 }
             }
             Ok(())
@@ -414,8 +420,8 @@ pub mod builtins {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::Headers;
     use crate::cassette::{HttpRequest, HttpResponse, Interaction, InteractionKind};
-    use std::collections::HashMap;
 
     #[test]
     fn test_sensitive_header_filter() {
@@ -427,7 +433,7 @@ mod tests {
                 request: HttpRequest {
                     method: "GET".to_string(),
                     url: "https://api.example.com".to_string(),
-                    headers: HashMap::from([
+                    headers: Headers::from([
                         ("authorization".to_string(), "Bearer secret123".to_string()),
                         ("x-custom-secret".to_string(), "my-secret".to_string()),
                         ("content-type".to_string(), "application/json".to_string()),
@@ -436,7 +442,7 @@ mod tests {
                 },
                 response: HttpResponse {
                     status: 200,
-                    headers: HashMap::from([
+                    headers: Headers::from([
                         ("set-cookie".to_string(), "session=abc123".to_string()),
                         ("content-type".to_string(), "application/json".to_string()),
                     ]),
@@ -445,31 +451,24 @@ mod tests {
             },
             recorded_at: chrono::Utc::now(),
             response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
         };
 
         filter.before_record(&mut interaction).unwrap();
 
         if let InteractionKind::Http { request, response } = &interaction.kind {
-            assert_eq!(
-                request.headers.get("authorization"),
-                Some(&"[FILTERED]".to_string())
-            );
-            assert_eq!(
-                request.headers.get("x-custom-secret"),
-                Some(&"[FILTERED]".to_string())
-            );
+            assert_eq!(request.headers.get("authorization"), Some("[FILTERED]"));
+            assert_eq!(request.headers.get("x-custom-secret"), Some("[FILTERED]"));
             assert_eq!(
                 request.headers.get("content-type"),
-                Some(&"application/json".to_string())
+                Some("application/json")
             );
 
-            assert_eq!(
-                response.headers.get("set-cookie"),
-                Some(&"[FILTERED]".to_string())
-            );
+            assert_eq!(response.headers.get("set-cookie"), Some("[FILTERED]"));
             assert_eq!(
                 response.headers.get("content-type"),
-                Some(&"application/json".to_string())
+                Some("application/json")
             );
         } else {
             panic!("Expected HTTP interaction");
@@ -491,17 +490,19 @@ mod tests {
                 request: HttpRequest {
                     method: "POST".to_string(),
                     url: "https://api.example.com/login".to_string(),
-                    headers: HashMap::new(),
+                    headers: Headers::new(),
                     body: Some(br#"{"username":"alice","password":"secret123"}"#.to_vec()),
                 },
                 response: HttpResponse {
                     status: 200,
-                    headers: HashMap::new(),
+                    headers: Headers::new(),
                     body: Some(br#"{"token":"jwt-token-xyz","user":"alice"}"#.to_vec()),
                 },
             },
             recorded_at: chrono::Utc::now(),
             response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
         };
 
         replacer.before_record(&mut interaction).unwrap();
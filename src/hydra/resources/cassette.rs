@@ -209,20 +209,20 @@ pub struct CassetteInput {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::Headers;
     use crate::cassette::{HttpRequest, HttpResponse, Interaction, InteractionKind};
-    use std::collections::HashMap;
 
     fn create_test_cassette() -> Cassette {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/test".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"test\":true}".to_vec()),
         };
 
@@ -230,6 +230,8 @@ mod tests {
             kind: InteractionKind::Http { request, response },
             recorded_at: chrono::Utc::now(),
             response_time_ms: Some(100),
+            http_version: None,
+            content_encoding: None,
         };
 
         Cassette {
@@ -1,12 +1,13 @@
 //! Interaction Hydra Resource
 //!
-//! Hypermedia representation of HTTP/WebSocket interactions.
+//! Hypermedia representation of HTTP/SSE/WebSocket/gRPC interactions.
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-
-use crate::cassette::{HttpRequest, HttpResponse, Interaction, InteractionKind, WebSocketMessage};
+use crate::cassette::{
+    GrpcMessage, Headers, HttpRequest, HttpResponse, Interaction, InteractionKind, SseEvent,
+    WebSocketMessage,
+};
 use crate::hydra::{HydraLink, HydraOperation};
+use serde::{Deserialize, Serialize};
 
 /// Interaction Resource
 ///
@@ -24,6 +25,17 @@ pub enum InteractionResource {
         #[serde(rename = "_links")]
         links: InteractionLinks,
     },
+    ServerSentEvents {
+        #[serde(rename = "@id")]
+        id: String,
+
+        request: HttpRequestResource,
+        response: HttpResponseResource,
+        events: Vec<SseEventResource>,
+
+        #[serde(rename = "_links")]
+        links: InteractionLinks,
+    },
     WebSocket {
         #[serde(rename = "@id")]
         id: String,
@@ -31,6 +43,23 @@ pub enum InteractionResource {
         url: String,
         messages: Vec<WebSocketMessageResource>,
 
+        #[serde(rename = "_links")]
+        links: InteractionLinks,
+    },
+    Grpc {
+        #[serde(rename = "@id")]
+        id: String,
+
+        url: String,
+        service: String,
+        method: String,
+        status: u32,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+
+        messages: Vec<GrpcMessageResource>,
+
         #[serde(rename = "_links")]
         links: InteractionLinks,
     },
@@ -60,7 +89,7 @@ impl InteractionResource {
                 // For HTTP errors, create a synthetic 500 response with error details
                 let error_response = HttpResponse {
                     status: 500,
-                    headers: std::collections::HashMap::new(),
+                    headers: Headers::new(),
                     body: Some(
                         serde_json::json!({
                             "error": format!("{:?}", error)
@@ -90,6 +119,34 @@ impl InteractionResource {
                     .collect(),
                 links: InteractionLinks::new(&interaction_url, cassette_name, index),
             },
+            InteractionKind::ServerSentEvents {
+                request,
+                response,
+                events,
+            } => Self::ServerSentEvents {
+                id: interaction_url.clone(),
+                request: HttpRequestResource::from_request(request),
+                response: HttpResponseResource::from_response(response),
+                events: events.iter().map(SseEventResource::from_event).collect(),
+                links: InteractionLinks::new(&interaction_url, cassette_name, index),
+            },
+            InteractionKind::Grpc {
+                request,
+                response,
+                messages,
+            } => Self::Grpc {
+                id: interaction_url.clone(),
+                url: request.url.clone(),
+                service: request.service.clone(),
+                method: request.method.clone(),
+                status: response.status,
+                message: response.message.clone(),
+                messages: messages
+                    .iter()
+                    .map(GrpcMessageResource::from_message)
+                    .collect(),
+                links: InteractionLinks::new(&interaction_url, cassette_name, index),
+            },
         }
     }
 
@@ -108,7 +165,7 @@ impl InteractionResource {
 pub struct HttpRequestResource {
     pub method: String,
     pub url: String,
-    pub headers: HashMap<String, String>,
+    pub headers: Headers,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<String>,
@@ -132,7 +189,7 @@ impl HttpRequestResource {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpResponseResource {
     pub status: u16,
-    pub headers: HashMap<String, String>,
+    pub headers: Headers,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<String>,
@@ -203,6 +260,63 @@ impl WebSocketMessageResource {
     }
 }
 
+/// Server-Sent Event Resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SseEventResource {
+    #[serde(rename = "timestampMs")]
+    pub timestamp_ms: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+
+    pub data: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<u64>,
+}
+
+impl SseEventResource {
+    fn from_event(event: &SseEvent) -> Self {
+        Self {
+            timestamp_ms: event.timestamp_ms,
+            id: event.id.clone(),
+            event: event.event.clone(),
+            data: event.data.clone(),
+            retry: event.retry,
+        }
+    }
+}
+
+/// gRPC Message Resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcMessageResource {
+    pub direction: String,
+
+    #[serde(rename = "timestampMs")]
+    pub timestamp_ms: u64,
+
+    /// Decoded message (protobuf JSON mapping), or the payload size when undecoded
+    pub data: serde_json::Value,
+}
+
+impl GrpcMessageResource {
+    fn from_message(message: &GrpcMessage) -> Self {
+        let data = match &message.decoded {
+            Some(decoded) => decoded.clone(),
+            None => serde_json::Value::String(format!("[{} bytes]", message.data.len())),
+        };
+
+        Self {
+            direction: format!("{:?}", message.direction),
+            timestamp_ms: message.timestamp_ms,
+            data,
+        }
+    }
+}
+
 /// Interaction Links
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InteractionLinks {
@@ -250,13 +364,13 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/test".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"test\":true}".to_vec()),
         };
 
@@ -264,6 +378,8 @@ mod tests {
             kind: InteractionKind::Http { request, response },
             recorded_at: chrono::Utc::now(),
             response_time_ms: Some(100),
+            http_version: None,
+            content_encoding: None,
         };
 
         let resource =
@@ -285,7 +401,7 @@ mod tests {
     fn test_template_detection() {
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"token\":\"{{ env \\\"API_KEY\\\" }}\"}".to_vec()),
         };
 
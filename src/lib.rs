@@ -10,14 +10,20 @@
 
 // Core modules (always available)
 pub mod cassette;
+pub mod config;
+pub mod content_encoding;
 pub mod cookies;
 pub mod error;
 pub mod filters;
+pub mod grpc;
 pub mod hooks;
 pub mod matching;
 pub mod player;
 pub mod proxy;
 pub mod recorder;
+pub mod rerecord;
+pub mod sensitive;
+pub mod sse;
 pub mod templates;
 pub mod test_helpers;
 pub mod tls;
@@ -32,21 +38,26 @@ pub mod api;
 pub mod hydra;
 
 // Core exports (always available)
+pub use config::Config;
 pub use error::{MatgtoError, Result};
 pub use filters::{FilterPresets, RecordingFilters};
 pub use hooks::{RecordHook, RecordHooks, ReplayHook, ReplayHooks};
 pub use matching::{
     BodyMatchMode, CustomMatcher, MatchingStrategy, RequestSignature, UrlMatchMode,
 };
-pub use player::{LatencyMode, Player};
+pub use player::{ExhaustedPolicy, LatencyMode, Player};
 pub use proxy::{MagnetoProxy, ProxyMode};
 pub use recorder::Recorder;
+pub use rerecord::{ReRecordPolicy, ReRecordSummary};
+pub use sensitive::SensitiveData;
 pub use templates::TemplateEngine;
 pub use tls::CertificateAuthority;
 pub use websocket::{WebSocketInterceptor, WebSocketPlayer, WebSocketRecorder};
 
 // Re-export cassette types
-pub use cassette::{Cassette, HttpRequest, HttpResponse, Interaction, WebSocketMessage};
+pub use cassette::{
+    Cassette, GrpcMessage, HttpRequest, HttpResponse, Interaction, SseEvent, WebSocketMessage,
+};
 
 // Re-export cookie types
 pub use cookies::{Cookie, CookieJar, SameSite};
@@ -77,6 +88,20 @@ pub fn create_proxy(cassette_dir: String) -> Option<std::sync::Arc<MagnetoProxy>
         .map(std::sync::Arc::new)
 }
 
+/// Create a MagnetoProxy from magneto.toml (returns None on error)
+///
+/// `config_path` is the file to load; without it the default locations are
+/// searched, see [`Config::load`]. `MAGNETO_*` environment overrides apply.
+pub fn create_proxy_from_config(
+    config_path: Option<String>,
+) -> Option<std::sync::Arc<MagnetoProxy>> {
+    Config::load(config_path.as_deref().map(std::path::Path::new))
+        .and_then(|config| config.build_proxy())
+        .map_err(|e| tracing::error!("Failed to create proxy from config: {}", e))
+        .ok()
+        .map(std::sync::Arc::new)
+}
+
 /// Get library version
 pub fn version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
@@ -92,4 +117,25 @@ mod tests {
         // Basic smoke test - verify version is set
         assert!(!crate::version().is_empty());
     }
+
+    #[test]
+    fn test_create_proxy_from_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("magneto.toml");
+        std::fs::write(
+            &path,
+            format!(
+                "[magneto]\ncassette_dir = \"{}\"\nproxy_port = 9999\nmode = \"replay\"\n",
+                dir.path().join("cassettes").display()
+            ),
+        )
+        .unwrap();
+
+        let proxy = crate::create_proxy_from_config(Some(path.display().to_string())).unwrap();
+        assert_eq!(proxy.port(), 9999);
+        assert_eq!(proxy.mode(), crate::ProxyMode::Replay);
+
+        let missing = dir.path().join("missing.toml").display().to_string();
+        assert!(crate::create_proxy_from_config(Some(missing)).is_none());
+    }
 }
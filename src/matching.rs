@@ -4,8 +4,8 @@
 //! including regex URL matching, partial body matching, header-specific matching,
 //! and custom matchers.
 
-use crate::cassette::HttpRequest;
-use crate::error::Result;
+use crate::cassette::{Headers, HttpRequest};
+use crate::error::{MatgtoError, Result};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -92,7 +92,7 @@ pub struct RequestSignature {
     pub method: String,
     pub url: String,
     pub body: Option<Vec<u8>>,
-    pub headers: HashMap<String, String>,
+    pub headers: Headers,
 }
 
 impl RequestSignature {
@@ -220,14 +220,14 @@ impl RequestSignature {
     /// Match headers according to the strategy
     fn matches_headers(
         &self,
-        recorded_headers: &HashMap<String, String>,
+        recorded_headers: &Headers,
         strategy: &MatchingStrategy,
     ) -> Result<bool> {
-        // Check required headers
+        // Check required headers (every value, in order)
         for header in &strategy.match_headers {
-            let self_value = self.headers.get(header);
-            let recorded_value = recorded_headers.get(header);
-            if self_value != recorded_value {
+            let self_values = self.headers.get_all(header);
+            let recorded_values = recorded_headers.get_all(header);
+            if !self_values.eq(recorded_values) {
                 return Ok(false);
             }
         }
@@ -338,6 +338,143 @@ impl MatchingStrategy {
     }
 }
 
+/// URL matching mode, as written in magneto.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlMatchKind {
+    /// See [`UrlMatchMode::Exact`]
+    #[default]
+    Exact,
+
+    /// See [`UrlMatchMode::Regex`], pattern in `url_pattern`
+    Regex,
+
+    /// See [`UrlMatchMode::IgnoreQuery`]
+    IgnoreQuery,
+
+    /// See [`UrlMatchMode::PathOnly`]
+    PathOnly,
+}
+
+/// Body matching mode, as written in magneto.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyMatchKind {
+    /// See [`BodyMatchMode::Hash`]
+    #[default]
+    Hash,
+
+    /// See [`BodyMatchMode::Ignore`]
+    Ignore,
+
+    /// See [`BodyMatchMode::JsonPath`], path in `body_pattern`
+    JsonPath,
+
+    /// See [`BodyMatchMode::Regex`], pattern in `body_pattern`
+    Regex,
+
+    /// See [`BodyMatchMode::SizeOnly`]
+    SizeOnly,
+}
+
+/// Matching settings, as written in magneto.toml
+///
+/// With `url_mode = "exact"`, `ignore_query_params` switches to
+/// [`UrlMatchMode::IgnoreQueryParams`].
+///
+/// ```toml
+/// [matching]
+/// url_mode = "regex"
+/// url_pattern = "^https://api\\.example\\.com/users/\\d+$"
+/// body_mode = "json_path"
+/// body_pattern = "user.id"
+/// match_headers = ["authorization"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatchingConfig {
+    /// URL matching mode
+    pub url_mode: UrlMatchKind,
+
+    /// Regex for `url_mode = "regex"`
+    pub url_pattern: Option<String>,
+
+    /// Body matching mode
+    pub body_mode: BodyMatchKind,
+
+    /// JSON path or regex for `body_mode = "json_path"` or `"regex"`
+    pub body_pattern: Option<String>,
+
+    /// Headers whose values must match
+    pub match_headers: Vec<String>,
+
+    /// Headers never taken into account
+    pub ignore_headers: Vec<String>,
+
+    /// Query parameters left out when comparing URLs
+    pub ignore_query_params: Vec<String>,
+}
+
+impl MatchingConfig {
+    /// Build the configured strategy
+    ///
+    /// Fails when a pattern the mode needs is missing or not a valid regex.
+    pub fn resolve(&self) -> Result<MatchingStrategy> {
+        let required = |pattern: &Option<String>, key: &str, mode: &str| {
+            pattern
+                .clone()
+                .ok_or_else(|| MatgtoError::Config(format!("{} is required with {}", key, mode)))
+        };
+        let regex = |pattern: String, key: &str| {
+            Regex::new(&pattern)
+                .map(|_| pattern)
+                .map_err(|e| MatgtoError::Config(format!("Invalid {}: {}", key, e)))
+        };
+
+        let url_mode = match self.url_mode {
+            UrlMatchKind::Exact if self.ignore_query_params.is_empty() => UrlMatchMode::Exact,
+            UrlMatchKind::Exact => UrlMatchMode::IgnoreQueryParams {
+                params: self.ignore_query_params.clone(),
+            },
+            UrlMatchKind::Regex => UrlMatchMode::Regex {
+                pattern: regex(
+                    required(&self.url_pattern, "url_pattern", "url_mode = \"regex\"")?,
+                    "url_pattern",
+                )?,
+            },
+            UrlMatchKind::IgnoreQuery => UrlMatchMode::IgnoreQuery,
+            UrlMatchKind::PathOnly => UrlMatchMode::PathOnly,
+        };
+
+        let body_mode = match self.body_mode {
+            BodyMatchKind::Hash => BodyMatchMode::Hash,
+            BodyMatchKind::Ignore => BodyMatchMode::Ignore,
+            BodyMatchKind::JsonPath => BodyMatchMode::JsonPath {
+                path: required(
+                    &self.body_pattern,
+                    "body_pattern",
+                    "body_mode = \"json_path\"",
+                )?,
+            },
+            BodyMatchKind::Regex => BodyMatchMode::Regex {
+                pattern: regex(
+                    required(&self.body_pattern, "body_pattern", "body_mode = \"regex\"")?,
+                    "body_pattern",
+                )?,
+            },
+            BodyMatchKind::SizeOnly => BodyMatchMode::SizeOnly,
+        };
+
+        let mut strategy = MatchingStrategy::new()
+            .with_url_mode(url_mode)
+            .with_body_mode(body_mode);
+        strategy.match_headers = self.match_headers.iter().cloned().collect();
+        strategy.ignore_headers = self.ignore_headers.iter().cloned().collect();
+        strategy.ignore_query_params = self.ignore_query_params.iter().cloned().collect();
+        Ok(strategy)
+    }
+}
+
 /// Helper: Match URLs while ignoring specific query parameters
 fn urls_match_ignoring_params(url1: &str, url2: &str, ignore_params: &[String]) -> Result<bool> {
     use url::Url;
@@ -426,7 +563,7 @@ mod tests {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
             body: None,
-            headers: HashMap::new(),
+            headers: Headers::new(),
         };
 
         let strategy = MatchingStrategy::default();
@@ -445,7 +582,7 @@ mod tests {
             method: "GET".to_string(),
             url: "https://api.example.com/users/123".to_string(),
             body: None,
-            headers: HashMap::new(),
+            headers: Headers::new(),
         };
 
         let mode = UrlMatchMode::Regex {
@@ -469,7 +606,7 @@ mod tests {
             method: "GET".to_string(),
             url: "https://api.example.com/users?page=1".to_string(),
             body: None,
-            headers: HashMap::new(),
+            headers: Headers::new(),
         };
 
         let mode = UrlMatchMode::IgnoreQuery;
@@ -488,7 +625,7 @@ mod tests {
             method: "GET".to_string(),
             url: "https://api.example.com/users?page=1&sort=name".to_string(),
             body: None,
-            headers: HashMap::new(),
+            headers: Headers::new(),
         };
 
         let mode = UrlMatchMode::IgnoreQueryParams {
@@ -512,7 +649,7 @@ mod tests {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
             body: None,
-            headers: HashMap::new(),
+            headers: Headers::new(),
         };
 
         let mode = UrlMatchMode::PathOnly;
@@ -534,7 +671,7 @@ mod tests {
             method: "POST".to_string(),
             url: "https://api.example.com/users".to_string(),
             body: Some(b"body1".to_vec()),
-            headers: HashMap::new(),
+            headers: Headers::new(),
         };
 
         let mode = BodyMatchMode::Ignore;
@@ -549,7 +686,7 @@ mod tests {
             method: "POST".to_string(),
             url: "https://api.example.com/users".to_string(),
             body: Some(b"12345".to_vec()),
-            headers: HashMap::new(),
+            headers: Headers::new(),
         };
 
         let mode = BodyMatchMode::SizeOnly;
@@ -579,7 +716,7 @@ mod tests {
             method: "POST".to_string(),
             url: "https://api.example.com/users".to_string(),
             body: Some(serde_json::to_vec(&body1).unwrap()),
-            headers: HashMap::new(),
+            headers: Headers::new(),
         };
 
         let mode = BodyMatchMode::JsonPath {
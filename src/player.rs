@@ -1,14 +1,17 @@
 //! Playing back recorded cassettes
 
-use crate::cassette::{Cassette, Interaction, InteractionKind};
-use crate::cookies::CookieJar;
+use crate::cassette::{storage, Cassette, CassetteFormat, Interaction, InteractionKind};
+use crate::cookies::{Cookie, CookieJar, CookiePolicy};
 use crate::error::{MatgtoError, Result};
 use crate::hooks::ReplayHooks;
 use crate::matching::{MatchingStrategy, RequestSignature as MatchingSignature};
+use crate::sensitive::SensitiveData;
 use crate::templates::TemplateEngine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
 use std::path::Path;
+use std::time::Duration;
 
 /// Signature used to match requests
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -53,14 +56,116 @@ pub enum LatencyMode {
     Scaled(u64), // Store as integer percentage (100 = 1.0x, 200 = 2.0x)
 }
 
+impl LatencyMode {
+    /// Delay before a streamed message recorded `elapsed_ms` after the previous one
+    ///
+    /// Used for messages within a WebSocket session, an event stream or a
+    /// gRPC call: `Recorded` and `Scaled` reproduce the recorded spacing,
+    /// `Fixed` waits the same time before every message.
+    pub fn message_delay(&self, elapsed_ms: u64) -> Option<u64> {
+        match self {
+            LatencyMode::None => None,
+            LatencyMode::Recorded => Some(elapsed_ms),
+            LatencyMode::Fixed(ms) => Some(*ms),
+            LatencyMode::Scaled(percentage) => Some((elapsed_ms * percentage) / 100),
+        }
+    }
+}
+
+/// What to replay once every recorded response for a request was served
+///
+/// A request recorded several times (e.g. polling `pending → running → done`)
+/// gets its recorded responses in order; this decides what comes after the last.
+///
+/// Written in snake_case in magneto.toml (`repeat_last`, `cycle`, `error`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExhaustedPolicy {
+    /// Keep replaying the last recorded response
+    #[default]
+    RepeatLast,
+
+    /// Start again from the first recorded response
+    Cycle,
+
+    /// Fail the request (default in strict mode)
+    Error,
+}
+
+/// Latency simulation mode, as written in magneto.toml
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyKind {
+    /// See [`LatencyMode::None`]
+    #[default]
+    None,
+
+    /// See [`LatencyMode::Recorded`]
+    Recorded,
+
+    /// See [`LatencyMode::Fixed`], delay in `fixed_latency_ms`
+    Fixed,
+
+    /// See [`LatencyMode::Scaled`], factor in `latency_scale_percent`
+    Scaled,
+}
+
+/// Replay settings, as written in magneto.toml
+///
+/// ```toml
+/// [replay]
+/// latency_mode = "scaled"
+/// latency_scale_percent = 50
+/// when_exhausted = "cycle"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplayConfig {
+    /// Latency simulation mode
+    pub latency_mode: LatencyKind,
+
+    /// Delay before every response with `latency_mode = "fixed"`, in ms
+    pub fixed_latency_ms: u64,
+
+    /// Recorded times scaling with `latency_mode = "scaled"` (100 = real time)
+    pub latency_scale_percent: u64,
+
+    /// What repeated requests get once every recorded response was served
+    /// (None = player default)
+    pub when_exhausted: Option<ExhaustedPolicy>,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            latency_mode: LatencyKind::None,
+            fixed_latency_ms: 100,
+            latency_scale_percent: 100,
+            when_exhausted: None,
+        }
+    }
+}
+
+impl ReplayConfig {
+    /// The configured latency simulation
+    pub fn latency(&self) -> LatencyMode {
+        match self.latency_mode {
+            LatencyKind::None => LatencyMode::None,
+            LatencyKind::Recorded => LatencyMode::Recorded,
+            LatencyKind::Fixed => LatencyMode::Fixed(self.fixed_latency_ms),
+            LatencyKind::Scaled => LatencyMode::Scaled(self.latency_scale_percent),
+        }
+    }
+}
+
 /// Plays back recorded interactions from cassettes
 #[derive(Debug)]
 pub struct Player {
     /// Loaded cassette
     cassette: Option<Cassette>,
 
-    /// Index of interactions for fast lookup
-    interactions_index: HashMap<RequestSignature, usize>,
+    /// Index of interactions for fast lookup, in recording order per request
+    interactions_index: HashMap<RequestSignature, Vec<usize>>,
 
     /// Count how many times each interaction has been replayed
     replay_count: HashMap<usize, usize>,
@@ -68,6 +173,9 @@ pub struct Player {
     /// Strict mode: fail fast on missing interactions
     strict_mode: bool,
 
+    /// What repeated requests get once their recorded responses are used up
+    exhausted_policy: ExhaustedPolicy,
+
     /// Latency simulation mode
     latency_mode: LatencyMode,
 
@@ -82,6 +190,18 @@ pub struct Player {
 
     /// Template engine for dynamic response rendering
     template_engine: TemplateEngine,
+
+    /// Format the cassette was loaded from
+    format: CassetteFormat,
+
+    /// Interactions recorded before this are stale and due for re-recording
+    re_record_cutoff: Option<DateTime<Utc>>,
+
+    /// Placeholders the cassette was recorded with
+    sensitive_data: SensitiveData,
+
+    /// Cookies the cassette was recorded with, and whether the jar is used
+    cookie_policy: CookiePolicy,
 }
 
 impl Player {
@@ -92,11 +212,16 @@ impl Player {
             interactions_index: HashMap::new(),
             replay_count: HashMap::new(),
             strict_mode: false,
+            exhausted_policy: ExhaustedPolicy::RepeatLast,
             latency_mode: LatencyMode::None,
             matching_strategy: MatchingStrategy::default(),
             cookie_jar: CookieJar::new(),
             hooks: ReplayHooks::new(),
             template_engine: TemplateEngine::new(),
+            format: CassetteFormat::default(),
+            re_record_cutoff: None,
+            sensitive_data: SensitiveData::new(),
+            cookie_policy: CookiePolicy::new(),
         }
     }
 
@@ -107,11 +232,16 @@ impl Player {
             interactions_index: HashMap::new(),
             replay_count: HashMap::new(),
             strict_mode: true,
+            exhausted_policy: ExhaustedPolicy::Error,
             latency_mode: LatencyMode::None,
             matching_strategy: MatchingStrategy::strict(),
             cookie_jar: CookieJar::new(),
             hooks: ReplayHooks::new(),
             template_engine: TemplateEngine::new(),
+            format: CassetteFormat::default(),
+            re_record_cutoff: None,
+            sensitive_data: SensitiveData::new(),
+            cookie_policy: CookiePolicy::new(),
         }
     }
 
@@ -126,6 +256,17 @@ impl Player {
         self.latency_mode
     }
 
+    /// Set what repeated requests get once their recorded responses are used up
+    pub fn with_exhausted_policy(mut self, policy: ExhaustedPolicy) -> Self {
+        self.exhausted_policy = policy;
+        self
+    }
+
+    /// Get the exhausted sequence policy
+    pub fn exhausted_policy(&self) -> ExhaustedPolicy {
+        self.exhausted_policy
+    }
+
     /// Set matching strategy
     pub fn with_matching_strategy(mut self, strategy: MatchingStrategy) -> Self {
         self.matching_strategy = strategy;
@@ -137,6 +278,127 @@ impl Player {
         &self.matching_strategy
     }
 
+    /// Treat interactions older than `interval` as stale
+    pub fn with_re_record_interval(mut self, interval: Duration) -> Self {
+        self.re_record_cutoff = chrono::Duration::from_std(interval)
+            .ok()
+            .and_then(|interval| Utc::now().checked_sub_signed(interval));
+        self
+    }
+
+    /// Whether an interaction is older than the re-record interval
+    ///
+    /// Every interaction is stale when the cassette itself is older.
+    pub fn is_stale(&self, idx: usize) -> bool {
+        let (Some(cutoff), Some(cassette)) = (self.re_record_cutoff, self.cassette.as_ref()) else {
+            return false;
+        };
+
+        cassette.recorded_at < cutoff
+            || cassette
+                .interactions
+                .get(idx)
+                .is_some_and(|interaction| interaction.recorded_at < cutoff)
+    }
+
+    /// Number of interactions older than the re-record interval
+    pub fn stale_count(&self) -> usize {
+        let total = self.cassette.as_ref().map_or(0, |c| c.interactions.len());
+        (0..total).filter(|idx| self.is_stale(*idx)).count()
+    }
+
+    /// Match live requests and restore replayed values through sensitive data placeholders
+    ///
+    /// Live requests are redacted before matching, and placeholders in
+    /// replayed responses are substituted with the live request's values
+    /// (see `SensitiveData::restore`).
+    pub fn with_sensitive_data(mut self, sensitive_data: SensitiveData) -> Self {
+        self.sensitive_data = sensitive_data;
+        self
+    }
+
+    /// Get the sensitive data filter
+    pub fn sensitive_data(&self) -> &SensitiveData {
+        &self.sensitive_data
+    }
+
+    /// Set how cookies were recorded and whether the cookie jar is used
+    ///
+    /// Live `Cookie` headers are filtered like recorded ones before matching.
+    pub fn with_cookie_policy(mut self, policy: CookiePolicy) -> Self {
+        self.cookie_policy = policy;
+        self
+    }
+
+    /// Get the cookie policy
+    pub fn cookie_policy(&self) -> &CookiePolicy {
+        &self.cookie_policy
+    }
+
+    /// A live request as it would have been recorded
+    ///
+    /// With `with_jar`, requests sent without cookies get the jar's. Then
+    /// sensitive cookies and values are filtered and replaced with placeholders.
+    fn as_recorded<'a>(
+        &self,
+        request: &'a crate::cassette::HttpRequest,
+        with_jar: bool,
+    ) -> std::borrow::Cow<'a, crate::cassette::HttpRequest> {
+        let mut request = std::borrow::Cow::Borrowed(request);
+
+        if with_jar && self.cookie_policy.is_enabled() && !request.headers.contains_key("Cookie") {
+            if let Some(cookies) = self.cookie_jar.get_header_value(&request.url) {
+                request.to_mut().headers.append("Cookie", cookies);
+            }
+        }
+        if request.headers.contains_key("Cookie") {
+            let mut headers = request.headers.clone();
+            self.cookie_policy.apply_to_request(&mut headers);
+            if headers != request.headers {
+                request.to_mut().headers = headers;
+            }
+        }
+        if !self.sensitive_data.is_empty() {
+            self.sensitive_data.redact_request(request.to_mut());
+        }
+
+        request
+    }
+
+    /// Keep the cookies a replayed response sets in the jar
+    fn remember_cookies(&mut self, idx: usize) {
+        if !self.cookie_policy.is_enabled() {
+            return;
+        }
+        let Some(interaction) = self.cassette.as_ref().and_then(|c| c.interactions.get(idx)) else {
+            return;
+        };
+        let (InteractionKind::Http { request, response }
+        | InteractionKind::ServerSentEvents {
+            request, response, ..
+        }) = &interaction.kind
+        else {
+            return;
+        };
+
+        let host = url::Url::parse(&request.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string));
+        for value in response.headers.get_all("Set-Cookie") {
+            if let Ok(mut cookie) = Cookie::parse(value) {
+                if cookie.domain.is_none() {
+                    cookie.domain = host.clone();
+                }
+                self.cookie_jar.store(cookie);
+            }
+        }
+    }
+
+    /// Get the format the cassette was loaded from
+    pub fn format(&self) -> CassetteFormat {
+        self.format
+    }
+
     /// Calculate delay for an interaction based on latency mode
     pub fn calculate_delay(&self, interaction: &crate::cassette::Interaction) -> Option<u64> {
         match self.latency_mode {
@@ -149,34 +411,47 @@ impl Player {
         }
     }
 
-    /// Load a cassette from disk
+    /// Load a cassette from disk (any supported format)
     pub fn load(cassette_dir: &Path, name: &str) -> Result<Self> {
-        Self::load_with_mode(cassette_dir, name, false)
+        Self::load_with_format(cassette_dir, name, CassetteFormat::default(), false)
     }
 
     /// Load a cassette from disk in strict mode
     pub fn load_strict(cassette_dir: &Path, name: &str) -> Result<Self> {
-        Self::load_with_mode(cassette_dir, name, true)
+        Self::load_with_format(cassette_dir, name, CassetteFormat::default(), true)
     }
 
-    /// Load a cassette from disk with specified mode
-    fn load_with_mode(cassette_dir: &Path, name: &str, strict: bool) -> Result<Self> {
-        let path = cassette_dir.join(format!("{}.json", name));
-
-        if !path.exists() {
-            return Err(MatgtoError::CassetteNotFound {
+    /// Load a cassette from disk, preferring `format` when several files exist
+    ///
+    /// The actual format is detected from the file found, see `format()`.
+    pub fn load_with_format(
+        cassette_dir: &Path,
+        name: &str,
+        format: CassetteFormat,
+        strict: bool,
+    ) -> Result<Self> {
+        let path = storage::find_cassette(cassette_dir, name, format).ok_or_else(|| {
+            MatgtoError::CassetteNotFound {
                 name: name.to_string(),
-            });
-        }
+            }
+        })?;
 
-        let file = File::open(path)?;
-        let cassette: Cassette = serde_json::from_reader(file)?;
+        let format = storage::detect_format(&path);
+        let cassette = storage::load_cassette(&path)?;
 
+        Ok(Self::from_cassette(cassette, format, strict))
+    }
+
+    /// Create a player for a cassette already in memory
+    pub fn from_cassette(cassette: Cassette, format: CassetteFormat, strict: bool) -> Self {
         // Build index for fast lookup
-        let mut interactions_index = HashMap::new();
+        let mut interactions_index: HashMap<RequestSignature, Vec<usize>> = HashMap::new();
 
         for (idx, interaction) in cassette.interactions.iter().enumerate() {
-            if let InteractionKind::Http { request, .. } = &interaction.kind {
+            if let InteractionKind::Http { request, .. }
+            | InteractionKind::HttpError { request, .. }
+            | InteractionKind::ServerSentEvents { request, .. } = &interaction.kind
+            {
                 let signature = RequestSignature {
                     method: request.method.clone(),
                     url: request.url.clone(),
@@ -188,28 +463,30 @@ impl Player {
                         hasher.finish()
                     }),
                 };
-                interactions_index.insert(signature, idx);
+                interactions_index.entry(signature).or_default().push(idx);
             }
         }
 
-        // Initialize cookie jar from cassette (Phase 1.1)
+        // Initialize cookie jar from cassette (Phase 1.1), with cookies aged
+        // from the recording so they expire as if it happened just now
         let mut cookie_jar = CookieJar::new();
         if let Some(cookies) = &cassette.cookies {
+            let age = Utc::now() - cassette.recorded_at;
             for cookie in cookies {
-                cookie_jar.store(cookie.clone());
+                cookie_jar.store(cookie.shifted(age));
             }
         }
 
         if strict {
             tracing::info!(
                 "🔒 Loaded cassette '{}' in STRICT mode with {} interactions",
-                name,
+                cassette.name,
                 cassette.interactions.len()
             );
         } else {
             tracing::info!(
                 "Loaded cassette '{}' with {} interactions",
-                name,
+                cassette.name,
                 cassette.interactions.len()
             );
         }
@@ -220,17 +497,32 @@ impl Player {
             MatchingStrategy::default()
         };
 
-        Ok(Self {
+        Self {
             cassette: Some(cassette),
             interactions_index,
             replay_count: HashMap::new(),
             strict_mode: strict,
+            exhausted_policy: if strict {
+                ExhaustedPolicy::Error
+            } else {
+                ExhaustedPolicy::RepeatLast
+            },
             latency_mode: LatencyMode::None,
             matching_strategy,
             cookie_jar,
             hooks: ReplayHooks::new(),
             template_engine: TemplateEngine::new(),
-        })
+            format,
+            re_record_cutoff: None,
+            sensitive_data: SensitiveData::new(),
+            cookie_policy: CookiePolicy::new(),
+        }
+    }
+
+    /// Replace the replay hooks
+    pub fn with_hooks(mut self, hooks: ReplayHooks) -> Self {
+        self.hooks = hooks;
+        self
     }
 
     /// Add a replay hook
@@ -254,28 +546,36 @@ impl Player {
     }
 
     /// Find a matching interaction by request signature (legacy, exact matching)
+    ///
+    /// A request recorded several times gets its recorded interactions in
+    /// order, see `ExhaustedPolicy`.
     pub fn find_interaction(&mut self, signature: &RequestSignature) -> Result<usize> {
-        let idx = self.interactions_index.get(signature).ok_or_else(|| {
-            if self.strict_mode {
-                tracing::error!(
-                    "🔒 STRICT MODE: No matching interaction found for {} {}",
-                    signature.method,
-                    signature.url
-                );
-                tracing::error!(
-                    "💡 Available interactions in cassette: {}",
-                    self.interactions_index.len()
-                );
-            }
+        let candidates = match self.interactions_index.get(signature) {
+            Some(candidates) => candidates.clone(),
+            None => {
+                if self.strict_mode {
+                    tracing::error!(
+                        "🔒 STRICT MODE: No matching interaction found for {} {}",
+                        signature.method,
+                        signature.url
+                    );
+                    tracing::error!(
+                        "💡 Available interactions in cassette: {}",
+                        self.interactions_index
+                            .values()
+                            .map(Vec::len)
+                            .sum::<usize>()
+                    );
+                }
 
-            MatgtoError::NoMatchingInteraction {
-                method: signature.method.clone(),
-                url: signature.url.clone(),
+                return Err(MatgtoError::NoMatchingInteraction {
+                    method: signature.method.clone(),
+                    url: signature.url.clone(),
+                });
             }
-        })?;
+        };
 
-        // Increment replay counter
-        *self.replay_count.entry(*idx).or_insert(0) += 1;
+        let idx = self.next_in_sequence(&candidates, &signature.method, &signature.url)?;
 
         if self.strict_mode {
             tracing::debug!(
@@ -286,17 +586,64 @@ impl Player {
             );
         }
 
-        Ok(*idx)
+        Ok(idx)
+    }
+
+    /// Pick the next interaction among those matching a request and count it as replayed
+    ///
+    /// `candidates` are in recording order: the first one not replayed yet is
+    /// picked, then the `ExhaustedPolicy` applies.
+    fn next_in_sequence(&mut self, candidates: &[usize], method: &str, url: &str) -> Result<usize> {
+        let count = |idx: &usize| self.replay_count.get(idx).copied().unwrap_or(0);
+
+        let next = match candidates.iter().copied().find(|idx| count(idx) == 0) {
+            Some(idx) => Some(idx),
+            None => match self.exhausted_policy {
+                ExhaustedPolicy::RepeatLast => candidates.last().copied(),
+                // The least replayed one, i.e. the next of the current round
+                ExhaustedPolicy::Cycle => candidates.iter().copied().min_by_key(count),
+                ExhaustedPolicy::Error => None,
+            },
+        };
+
+        let idx = next.ok_or_else(|| {
+            tracing::error!(
+                "All {} recorded responses for {} {} were already replayed",
+                candidates.len(),
+                method,
+                url
+            );
+
+            MatgtoError::ReplayExhausted {
+                method: method.to_string(),
+                url: url.to_string(),
+                recorded: candidates.len(),
+            }
+        })?;
+
+        // Increment replay counter
+        *self.replay_count.entry(idx).or_insert(0) += 1;
+
+        Ok(idx)
     }
 
     /// Find a matching interaction using advanced matching strategy
+    ///
+    /// Every interaction matching the request is a candidate; they are served
+    /// in recording order, see `ExhaustedPolicy`.
     pub fn find_interaction_advanced(
         &mut self,
         request: &crate::cassette::HttpRequest,
     ) -> Result<usize> {
-        let signature = MatchingSignature::from_request(request);
+        // Recorded requests hold placeholders instead of sensitive values
+        let signature = MatchingSignature::from_request(&self.as_recorded(request, false));
+
+        // A request sent without cookies gets the jar's, but only to match
+        // recorded requests that carried cookies (not the login setting them)
+        let jar_signature = (self.cookie_policy.is_enabled()
+            && !request.headers.contains_key("Cookie"))
+        .then(|| MatchingSignature::from_request(&self.as_recorded(request, true)));
 
-        // Find first matching interaction
         let cassette =
             self.cassette
                 .as_ref()
@@ -305,30 +652,52 @@ impl Player {
                     url: request.url.clone(),
                 })?;
 
+        let mut candidates = Vec::new();
         for (idx, interaction) in cassette.interactions.iter().enumerate() {
-            if let InteractionKind::Http {
-                request: recorded_request,
-                ..
-            } = &interaction.kind
-            {
-                if signature.matches(recorded_request, &self.matching_strategy)? {
-                    // Increment replay counter
-                    *self.replay_count.entry(idx).or_insert(0) += 1;
-
-                    if self.strict_mode {
-                        tracing::debug!(
-                            "🔒 STRICT MODE (advanced): Found interaction #{} for {} {}",
-                            idx,
-                            request.method,
-                            request.url
-                        );
-                    }
-
-                    return Ok(idx);
+            // Recorded network failures and event streams are replayed like responses
+            let recorded_request = match &interaction.kind {
+                InteractionKind::Http { request, .. }
+                | InteractionKind::HttpError { request, .. }
+                | InteractionKind::ServerSentEvents { request, .. } => request,
+                InteractionKind::WebSocket { .. } | InteractionKind::Grpc { .. } => continue,
+            };
+
+            // A matcher error (e.g. non-JSON body with JsonPath mode) only rules out
+            // this interaction, the remaining ones are still candidates
+            let signature = match &jar_signature {
+                Some(jar_signature) if recorded_request.headers.contains_key("Cookie") => {
+                    jar_signature
                 }
+                _ => &signature,
+            };
+            let is_match = signature
+                .matches(recorded_request, &self.matching_strategy)
+                .unwrap_or_else(|e| {
+                    tracing::debug!("Matcher error on interaction #{}: {}", idx, e);
+                    false
+                });
+
+            if is_match {
+                candidates.push(idx);
             }
         }
 
+        if !candidates.is_empty() {
+            let idx = self.next_in_sequence(&candidates, &request.method, &request.url)?;
+            self.remember_cookies(idx);
+
+            if self.strict_mode {
+                tracing::debug!(
+                    "🔒 STRICT MODE (advanced): Found interaction #{} for {} {}",
+                    idx,
+                    request.method,
+                    request.url
+                );
+            }
+
+            return Ok(idx);
+        }
+
         // No match found
         if self.strict_mode {
             tracing::error!(
@@ -377,6 +746,28 @@ impl Player {
         Ok(interaction_clone)
     }
 
+    /// Prepare an interaction for replay against a live request
+    ///
+    /// Clones the interaction and runs `before_replay` hooks, fills sensitive
+    /// data placeholders with the live request's values, then renders
+    /// Handlebars templates in the HTTP response body with the live request
+    /// (when the `templates` feature is enabled).
+    pub fn prepare_replay(
+        &self,
+        idx: usize,
+        request: &crate::cassette::HttpRequest,
+    ) -> Result<Interaction> {
+        let mut interaction = self.get_interaction_with_hooks(idx)?;
+        self.sensitive_data.restore(&mut interaction, request);
+
+        #[cfg(feature = "templates")]
+        if let InteractionKind::Http { response, .. } = &mut interaction.kind {
+            self.render_templates_in_response(request, response)?;
+        }
+
+        Ok(interaction)
+    }
+
     /// Mark an interaction as replayed and call after_replay hooks
     pub fn mark_replayed(&self, interaction: &Interaction) -> Result<()> {
         // Call after_replay hooks
@@ -384,6 +775,44 @@ impl Player {
         Ok(())
     }
 
+    /// Recorded gRPC calls of `service`/`method`, in cassette order, with hooks applied
+    ///
+    /// Returns the interaction indices along with the prepared interactions;
+    /// the caller picks one by comparing request messages, then reports it
+    /// with `mark_grpc_replayed`.
+    pub fn grpc_calls(&self, service: &str, method: &str) -> Vec<(usize, Interaction)> {
+        let cassette = match &self.cassette {
+            Some(cassette) => cassette,
+            None => return Vec::new(),
+        };
+
+        cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| {
+                matches!(
+                    &interaction.kind,
+                    InteractionKind::Grpc { request, .. }
+                        if request.service == service && request.method == method
+                )
+            })
+            .filter_map(|(idx, _)| match self.get_interaction_with_hooks(idx) {
+                Ok(interaction) => Some((idx, interaction)),
+                Err(e) => {
+                    tracing::error!("Failed to prepare interaction #{}: {}", idx, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Count a replayed gRPC call and call after_replay hooks
+    pub fn mark_grpc_replayed(&mut self, idx: usize, interaction: &Interaction) -> Result<()> {
+        *self.replay_count.entry(idx).or_insert(0) += 1;
+        self.mark_replayed(interaction)
+    }
+
     /// Get total number of replays across all interactions
     pub fn replay_count(&self) -> usize {
         self.replay_count.values().sum()
@@ -438,10 +867,10 @@ impl Default for Player {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::Headers;
     use crate::cassette::{HttpRequest, HttpResponse};
     use crate::error::MatgtoError;
     use crate::recorder::Recorder;
-    use std::collections::HashMap;
     use tempfile::tempdir;
 
     #[test]
@@ -458,13 +887,13 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"users\":[]}".to_vec()),
         };
 
@@ -495,13 +924,13 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"users\":[]}".to_vec()),
         };
 
@@ -530,13 +959,13 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"users\":[]}".to_vec()),
         };
 
@@ -561,13 +990,13 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"users\":[]}".to_vec()),
         };
 
@@ -607,13 +1036,13 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"users\":[]}".to_vec()),
         };
 
@@ -628,4 +1057,166 @@ mod tests {
         assert!(player.has_cassette());
         assert!(!player.is_strict());
     }
+
+    /// Save a cassette where the same poll was answered pending, running, done
+    fn save_polling_cassette(dir: &Path) -> HttpRequest {
+        let mut recorder = Recorder::new("test-polling".to_string());
+
+        let request = HttpRequest {
+            method: "GET".to_string(),
+            url: "https://api.example.com/jobs/1".to_string(),
+            headers: Headers::new(),
+            body: None,
+        };
+
+        for state in ["pending", "running", "done"] {
+            let response = HttpResponse {
+                status: 200,
+                headers: Headers::new(),
+                body: Some(state.as_bytes().to_vec()),
+            };
+            recorder.record_http(request.clone(), response);
+        }
+
+        recorder.save(dir).unwrap();
+        request
+    }
+
+    #[test]
+    fn test_cookie_jar_answers_requests_without_cookies() {
+        let dir = tempdir().unwrap();
+        let policy = CookiePolicy::new().with_filter("csrf_token").unwrap();
+        let mut recorder =
+            Recorder::new("test-cookies".to_string()).with_cookie_policy(policy.clone());
+        let request = |method: &str, path: &str, cookie: Option<&str>| HttpRequest {
+            method: method.to_string(),
+            url: format!("https://app.example.com{}", path),
+            headers: cookie.map(|c| ("Cookie", c)).into_iter().collect(),
+            body: None,
+        };
+        let response = |set_cookies: &[&str]| HttpResponse {
+            status: 200,
+            headers: set_cookies.iter().map(|c| ("Set-Cookie", *c)).collect(),
+            body: None,
+        };
+
+        recorder.record_http(
+            request("POST", "/login", None),
+            response(&["session=abc; Path=/", "csrf_token=x1"]),
+        );
+        recorder.record_http(
+            request("GET", "/me", Some("session=abc; csrf_token=x1")),
+            response(&[]),
+        );
+        recorder.save(dir.path()).unwrap();
+
+        let mut strategy = MatchingStrategy::default();
+        strategy.match_headers.insert("cookie".to_string());
+        let mut player = Player::load(dir.path(), "test-cookies")
+            .unwrap()
+            .with_matching_strategy(strategy)
+            .with_cookie_policy(policy);
+        let cookies = player.cassette().unwrap().cookies.clone().unwrap();
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name, "session");
+        assert_eq!(player.cookie_jar().len(), 1);
+
+        // The login was recorded without cookies, the jar's are not added to it
+        assert_eq!(
+            player
+                .find_interaction_advanced(&request("POST", "/login", None))
+                .unwrap(),
+            0
+        );
+        // No cookies sent: the jar's are used
+        assert_eq!(
+            player
+                .find_interaction_advanced(&request("GET", "/me", None))
+                .unwrap(),
+            1
+        );
+        // Filtered cookies do not take part in matching
+        assert_eq!(
+            player
+                .find_interaction_advanced(&request(
+                    "GET",
+                    "/me",
+                    Some("session=abc; csrf_token=x2")
+                ))
+                .unwrap(),
+            1
+        );
+        assert!(player
+            .find_interaction_advanced(&request("GET", "/me", Some("session=other")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_repeated_requests_replay_in_order() {
+        let dir = tempdir().unwrap();
+        let request = save_polling_cassette(dir.path());
+
+        // Repeat last (default)
+        let mut player = Player::load(dir.path(), "test-polling").unwrap();
+        assert_eq!(player.exhausted_policy(), ExhaustedPolicy::RepeatLast);
+        let served: Vec<usize> = (0..5)
+            .map(|_| player.find_interaction_advanced(&request).unwrap())
+            .collect();
+        assert_eq!(served, vec![0, 1, 2, 2, 2]);
+
+        // Cycle, through the legacy exact lookup
+        let mut player = Player::load(dir.path(), "test-polling")
+            .unwrap()
+            .with_exhausted_policy(ExhaustedPolicy::Cycle);
+        let signature = RequestSignature::from(request.clone());
+        let served: Vec<usize> = (0..5)
+            .map(|_| player.find_interaction(&signature).unwrap())
+            .collect();
+        assert_eq!(served, vec![0, 1, 2, 0, 1]);
+        assert_eq!(player.replay_count(), 5);
+
+        // Error (default in strict mode)
+        let mut player = Player::load_strict(dir.path(), "test-polling").unwrap();
+        assert_eq!(player.exhausted_policy(), ExhaustedPolicy::Error);
+        for expected in 0..3 {
+            assert_eq!(
+                player.find_interaction_advanced(&request).unwrap(),
+                expected
+            );
+        }
+        match player.find_interaction_advanced(&request) {
+            Err(MatgtoError::ReplayExhausted { recorded, .. }) => assert_eq!(recorded, 3),
+            other => panic!("expected ReplayExhausted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_stale_interactions() {
+        let dir = tempdir().unwrap();
+        save_polling_cassette(dir.path());
+
+        // Nothing is stale without an interval
+        let player = Player::load(dir.path(), "test-polling").unwrap();
+        assert_eq!(player.stale_count(), 0);
+
+        // Backdate the second interaction
+        let mut recorder = Recorder::new("test-polling".to_string());
+        *recorder.cassette_mut() = player.cassette().unwrap().clone();
+        recorder.cassette_mut().interactions[1].recorded_at =
+            Utc::now() - chrono::Duration::days(10);
+        recorder.save(dir.path()).unwrap();
+
+        let player = Player::load(dir.path(), "test-polling")
+            .unwrap()
+            .with_re_record_interval(Duration::from_secs(7 * 24 * 60 * 60));
+        assert!(!player.is_stale(0));
+        assert!(player.is_stale(1));
+        assert_eq!(player.stale_count(), 1);
+
+        // A longer interval keeps everything fresh
+        let player = Player::load(dir.path(), "test-polling")
+            .unwrap()
+            .with_re_record_interval(Duration::from_secs(30 * 24 * 60 * 60));
+        assert_eq!(player.stale_count(), 0);
+    }
 }
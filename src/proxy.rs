@@ -1,27 +1,48 @@
 //! Core proxy implementation
 
 // Module declarations (must come first)
+pub mod body;
 pub mod client;
 pub mod http_handler;
+pub mod ignore;
+pub mod reverse;
 pub mod server;
+pub mod upstream;
+pub mod websocket_handler;
 
+use crate::cassette::{find_cassette, pending, storage, Cassette, CassetteFormat};
+use crate::cookies::CookiePolicy;
 use crate::error::{MatgtoError, Result};
-use crate::player::Player;
-use crate::recorder::Recorder;
-use crate::tls::CertificateAuthority;
+use crate::filters::RecordingFilters;
+use crate::grpc::GrpcDescriptors;
+use crate::hooks::{RecordHook, RecordHooks, ReplayHook, ReplayHooks};
+use crate::matching::MatchingStrategy;
+use crate::player::{ExhaustedPolicy, LatencyMode, Player};
+use crate::recorder::{Recorder, DEFAULT_MAX_BODY_SIZE};
+use crate::rerecord::ReRecordPolicy;
+use crate::sensitive::SensitiveData;
+use crate::tls::{CertificateAuthority, UpstreamTls};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex as StdMutex};
 use tokio::runtime::Runtime;
 use tokio::sync::Mutex;
 
 // Import from submodules
-use self::server::ProxyServer;
+use self::server::{ProxyServer, ServerHandle};
 pub use client::HttpForwarder;
 pub use http_handler::HttpHandler;
+pub use ignore::{IgnoreConfig, IgnoreRules};
+pub use reverse::{ReverseProxy, ReverseProxyConfig};
 pub use server::MatgtoHttpHandler;
+pub use upstream::{UpstreamProxy, UpstreamProxyConfig};
+pub use websocket_handler::MatgtoWebSocketHandler;
 
 /// Proxy operation mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Written in lowercase in magneto.toml (`ReplayStrict` is `strict`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ProxyMode {
     /// Auto mode: Record if cassette doesn't exist, otherwise replay
     Auto,
@@ -34,6 +55,7 @@ pub enum ProxyMode {
 
     /// Strict replay mode: Errors on missing cassette AND missing interactions
     /// Use this mode in CI/CD to ensure all network calls are captured
+    #[serde(rename = "strict")]
     ReplayStrict,
 
     /// Hybrid mode: Replay from cassette if interaction exists, otherwise record new
@@ -48,14 +70,35 @@ pub enum ProxyMode {
     Passthrough,
 }
 
+/// How long in-flight requests may take to complete when a server is stopped
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A cassette covered by another one pushed on top of it
+struct CassetteLayer {
+    /// Cassette name
+    name: String,
+
+    /// Mode the cassette was served in, restored when it is back on top
+    mode: ProxyMode,
+
+    /// Recorder (if the cassette was recording)
+    recorder: Option<Arc<Mutex<Recorder>>>,
+
+    /// Player (if the cassette was replaying)
+    player: Option<Arc<Mutex<Player>>>,
+}
+
 /// Internal mutable state for MagnetoProxy
 struct ProxyState {
     /// Directory where cassettes are stored
     cassette_dir: PathBuf,
 
-    /// Proxy listening port
+    /// Proxy listening port (0 = let the OS pick a free port)
     proxy_port: u16,
 
+    /// Port the running server is actually bound to
+    bound_port: Option<u16>,
+
     /// Current operation mode
     mode: ProxyMode,
 
@@ -67,6 +110,200 @@ struct ProxyState {
 
     /// Current player (if in Replay mode)
     player: Option<Arc<Mutex<Player>>>,
+
+    /// Mode the current cassette is served in
+    cassette_mode: Option<ProxyMode>,
+
+    /// Cassettes below the current one, bottom first (see `push_cassette`)
+    stack: Vec<CassetteLayer>,
+
+    /// Matching strategy applied to every player (None = player default)
+    matching_strategy: Option<MatchingStrategy>,
+
+    /// Latency simulation applied to every player (None = player default)
+    latency_mode: Option<LatencyMode>,
+
+    /// Exhausted sequence policy applied to every player (None = player default)
+    exhausted_policy: Option<ExhaustedPolicy>,
+
+    /// When interactions are old enough to be re-recorded
+    re_record: ReRecordPolicy,
+
+    /// Hybrid mode writes new interactions to a pending sidecar for review
+    pending_review: bool,
+
+    /// Hooks run on every interaction before it is recorded
+    record_hooks: RecordHooks,
+
+    /// Hooks run on every interaction before it is replayed
+    replay_hooks: ReplayHooks,
+
+    /// Sensitive values replaced with placeholders when recording
+    sensitive_data: SensitiveData,
+
+    /// Which cookies are recorded, and replayed through the player's jar
+    cookie_policy: CookiePolicy,
+
+    /// Interactions left out of recordings (None = record everything)
+    recording_filters: Option<RecordingFilters>,
+
+    /// Format cassettes are saved in (and preferred when loading)
+    format: CassetteFormat,
+
+    /// Largest response body recorded, in bytes
+    max_body_size: u64,
+
+    /// Proxy that upstream traffic is chained through (None = direct)
+    upstream_proxy: Option<UpstreamProxy>,
+
+    /// Trust roots and client certificates for upstream TLS (None = native roots)
+    upstream_tls: Option<UpstreamTls>,
+
+    /// Routes of the reverse-proxy listener (None = MITM proxy)
+    reverse_proxy: Option<ReverseProxy>,
+
+    /// Descriptors used to decode gRPC messages (None = bytes only)
+    grpc_descriptors: Option<GrpcDescriptors>,
+
+    /// Hosts forwarded live in every mode (None = nothing ignored)
+    ignore_rules: Option<IgnoreRules>,
+
+    /// WebSocket sessions are recorded and replayed (forwarded live otherwise)
+    websocket_enabled: bool,
+
+    /// Handle to the running proxy server (if any)
+    server: Option<ServerHandle>,
+}
+
+impl ProxyState {
+    /// Take the running server (if any) out of the state
+    ///
+    /// Shut it down with `stop_server` once the state lock is released:
+    /// draining in-flight requests can take up to `SHUTDOWN_GRACE`.
+    fn take_server(&mut self) -> Option<ServerHandle> {
+        self.bound_port = None;
+        self.server.take()
+    }
+
+    /// Create a recorder with the configured format, body size cap and filters
+    fn new_recorder(&self, cassette_name: String) -> Recorder {
+        let mut recorder = Recorder::new(cassette_name)
+            .with_format(self.format)
+            .with_max_body_size(self.max_body_size)
+            .with_sensitive_data(self.sensitive_data.clone())
+            .with_cookie_policy(self.cookie_policy.clone())
+            .with_hooks(self.record_hooks.clone());
+        if let Some(filters) = &self.recording_filters {
+            recorder.set_filters(filters.clone());
+        }
+        recorder
+    }
+
+    /// Apply the configured matching strategy, sequence policy, latency,
+    /// sensitive data placeholders, cookie policy and hooks to a freshly
+    /// loaded player
+    fn configure_player(&self, player: Player) -> Player {
+        let player = player
+            .with_sensitive_data(self.sensitive_data.clone())
+            .with_cookie_policy(self.cookie_policy.clone())
+            .with_hooks(self.replay_hooks.clone());
+
+        let player = match &self.matching_strategy {
+            Some(strategy) => player.with_matching_strategy(strategy.clone()),
+            None => player,
+        };
+
+        let player = match self.exhausted_policy {
+            Some(policy) => player.with_exhausted_policy(policy),
+            None => player,
+        };
+
+        match self.latency_mode {
+            Some(mode) => player.with_latency(mode),
+            None => player,
+        }
+    }
+
+    /// Apply the cassette's re-record interval to its player
+    ///
+    /// Only for players whose cassette is rewritten by an appending recorder.
+    fn track_stale(&self, cassette_name: &str, player: Player) -> Player {
+        match self.re_record.interval_for(cassette_name) {
+            Some(interval) => player.with_re_record_interval(interval),
+            None => player,
+        }
+    }
+
+    /// Player and recorder for hybrid mode with pending review
+    ///
+    /// The cassette and its pending interactions are replayed; new
+    /// interactions are appended to the pending sidecar, leaving the
+    /// cassette itself untouched. Stale interactions are not re-recorded,
+    /// since that would rewrite the cassette.
+    fn pending_hybrid(&self, cassette_name: &str) -> Result<(Player, Recorder)> {
+        let (mut cassette, format) =
+            match find_cassette(&self.cassette_dir, cassette_name, self.format) {
+                Some(path) => (
+                    storage::load_cassette(&path)?,
+                    storage::detect_format(&path),
+                ),
+                None => (Cassette::new(cassette_name.to_string()), self.format),
+            };
+
+        let mut recorder = self.new_recorder(pending::pending_name(cassette_name));
+        if let Some(path) = pending::find_pending(&self.cassette_dir, cassette_name, self.format) {
+            let pending = storage::load_cassette(&path)?;
+            cassette
+                .interactions
+                .extend(pending.interactions.iter().cloned());
+            cassette.merge_cookies(pending.cookies_rebased(cassette.recorded_at));
+
+            let recorded_at = recorder.cassette().recorded_at;
+            recorder
+                .cassette_mut()
+                .merge_cookies(pending.cookies_rebased(recorded_at));
+            recorder.cassette_mut().interactions = pending.interactions;
+            recorder.set_format(storage::detect_format(&path));
+        }
+
+        tracing::info!(
+            "📝 Hybrid mode for '{}': new interactions go to '{}' for review",
+            cassette_name,
+            pending::pending_name(cassette_name)
+        );
+
+        let player = self.configure_player(Player::from_cassette(cassette, format, false));
+        Ok((player, recorder))
+    }
+
+    /// Create a recorder appending to the player's cassette
+    ///
+    /// Stale interactions of the cassette are counted so that saving it
+    /// reports how many were re-recorded. Cookies are moved to the new
+    /// recording time, keeping their lifetime on replay.
+    fn appending_recorder(&self, cassette_name: String, player: &Player) -> Recorder {
+        let mut recorder = self.new_recorder(cassette_name.clone());
+        if let Some(cassette) = player.cassette() {
+            let recorded_at = recorder.cassette().recorded_at;
+            recorder.cassette_mut().interactions = cassette.interactions.clone();
+            recorder
+                .cassette_mut()
+                .merge_cookies(cassette.cookies_rebased(recorded_at));
+        }
+        let total = recorder.cassette().interactions.len();
+        let stale = player.stale_count();
+
+        if stale == 0 {
+            return recorder;
+        }
+        tracing::info!(
+            "♻️  {} of {} interactions in '{}' are due for re-recording",
+            stale,
+            total,
+            cassette_name
+        );
+        recorder.with_re_record(stale, total - stale)
+    }
 }
 
 /// Main proxy struct - uses interior mutability for UniFFI compatibility
@@ -101,10 +338,32 @@ impl MagnetoProxy {
         let state = ProxyState {
             cassette_dir,
             proxy_port: 8888,
+            bound_port: None,
             mode: ProxyMode::Auto,
             current_cassette: None,
             recorder: None,
             player: None,
+            cassette_mode: None,
+            stack: Vec::new(),
+            matching_strategy: None,
+            latency_mode: None,
+            exhausted_policy: None,
+            re_record: ReRecordPolicy::new(),
+            pending_review: false,
+            record_hooks: RecordHooks::new(),
+            replay_hooks: ReplayHooks::new(),
+            sensitive_data: SensitiveData::new(),
+            cookie_policy: CookiePolicy::new(),
+            recording_filters: None,
+            format: CassetteFormat::default(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            upstream_proxy: None,
+            upstream_tls: None,
+            reverse_proxy: None,
+            grpc_descriptors: None,
+            ignore_rules: None,
+            websocket_enabled: true,
+            server: None,
         };
 
         Ok(Self {
@@ -133,10 +392,119 @@ impl MagnetoProxy {
         self
     }
 
+    /// Set the request matching strategy used during replay (builder style)
+    pub fn with_matching_strategy(self, strategy: MatchingStrategy) -> Self {
+        self.set_matching_strategy(strategy);
+        self
+    }
+
+    /// Set the exhausted sequence policy (builder style)
+    pub fn with_exhausted_policy(self, policy: ExhaustedPolicy) -> Self {
+        self.set_exhausted_policy(policy);
+        self
+    }
+
+    /// Send hybrid mode's new interactions to a pending file (builder style)
+    pub fn with_pending_review(self, enabled: bool) -> Self {
+        self.set_pending_review(enabled);
+        self
+    }
+
+    /// Set when interactions are re-recorded (builder style)
+    pub fn with_re_record_policy(self, policy: ReRecordPolicy) -> Self {
+        self.set_re_record_policy(policy);
+        self
+    }
+
+    /// Run a hook on every interaction before it is recorded (builder style)
+    pub fn with_record_hook<H: RecordHook + 'static>(self, hook: H) -> Self {
+        self.add_record_hook(hook);
+        self
+    }
+
+    /// Run a hook on every interaction before it is replayed (builder style)
+    pub fn with_replay_hook<H: ReplayHook + 'static>(self, hook: H) -> Self {
+        self.add_replay_hook(hook);
+        self
+    }
+
+    /// Replace sensitive values with placeholders (builder style)
+    pub fn with_sensitive_data(self, sensitive_data: SensitiveData) -> Self {
+        self.set_sensitive_data(sensitive_data);
+        self
+    }
+
+    /// Set which cookies are recorded and replayed (builder style)
+    pub fn with_cookie_policy(self, policy: CookiePolicy) -> Self {
+        self.set_cookie_policy(policy);
+        self
+    }
+
+    /// Leave interactions matching the filters out of recordings (builder style)
+    pub fn with_recording_filters(self, filters: RecordingFilters) -> Self {
+        self.set_recording_filters(Some(filters));
+        self
+    }
+
+    /// Set the latency simulated during replay (builder style)
+    pub fn with_latency(self, mode: LatencyMode) -> Self {
+        self.set_latency(mode);
+        self
+    }
+
+    /// Set the cassette format (builder style)
+    pub fn with_format(self, format: CassetteFormat) -> Self {
+        self.set_format(format);
+        self
+    }
+
+    /// Set the largest recorded response body (builder style)
+    pub fn with_max_body_size(self, bytes: u64) -> Self {
+        self.set_max_body_size(bytes);
+        self
+    }
+
+    /// Chain upstream traffic through another proxy (builder style)
+    pub fn with_upstream_proxy(self, proxy: UpstreamProxy) -> Self {
+        self.set_upstream_proxy(Some(proxy));
+        self
+    }
+
+    /// Use custom trust roots and client certificates upstream (builder style)
+    pub fn with_upstream_tls(self, tls: UpstreamTls) -> Self {
+        self.set_upstream_tls(Some(tls));
+        self
+    }
+
+    /// Serve as a reverse proxy for the given upstreams (builder style)
+    pub fn with_reverse_proxy(self, reverse: ReverseProxy) -> Self {
+        self.set_reverse_proxy(Some(reverse));
+        self
+    }
+
+    /// Decode gRPC messages with the given descriptors (builder style)
+    pub fn with_grpc_descriptors(self, descriptors: GrpcDescriptors) -> Self {
+        self.set_grpc_descriptors(Some(descriptors));
+        self
+    }
+
+    /// Forward hosts matching the given rules live (builder style)
+    pub fn with_ignore_rules(self, ignore: IgnoreRules) -> Self {
+        self.set_ignore_rules(Some(ignore));
+        self
+    }
+
+    /// Record and replay WebSocket sessions, or forward them live (builder style)
+    pub fn with_websocket_enabled(self, enabled: bool) -> Self {
+        self.set_websocket_enabled(enabled);
+        self
+    }
+
     /// Set the proxy port (setter style for UniFFI)
     pub fn set_port(&self, port: u16) {
         let mut state = self.state.lock().unwrap();
         state.proxy_port = port;
+        state.bound_port = None;
     }
 
     /// Set the proxy mode (setter style for UniFFI)
@@ -145,10 +513,328 @@ impl MagnetoProxy {
         state.mode = mode;
     }
 
+    /// Set the request matching strategy used during replay
+    ///
+    /// Applies to every mode that replays from a cassette (Replay, ReplayStrict,
+    /// Auto, Hybrid, Once). Takes effect on the next start call.
+    pub fn set_matching_strategy(&self, strategy: MatchingStrategy) {
+        let mut state = self.state.lock().unwrap();
+        state.matching_strategy = Some(strategy);
+    }
+
+    /// Get the configured matching strategy (None if players use their default)
+    pub fn matching_strategy(&self) -> Option<MatchingStrategy> {
+        let state = self.state.lock().unwrap();
+        state.matching_strategy.clone()
+    }
+
+    /// Set what repeated requests get once their recorded responses are used up
+    ///
+    /// A request recorded several times replays its responses in recording
+    /// order; afterwards the last one repeats by default (strict replay fails).
+    /// Takes effect on the next start call.
+    pub fn set_exhausted_policy(&self, policy: ExhaustedPolicy) {
+        let mut state = self.state.lock().unwrap();
+        state.exhausted_policy = Some(policy);
+    }
+
+    /// Get the configured exhausted sequence policy (None if players use their default)
+    pub fn exhausted_policy(&self) -> Option<ExhaustedPolicy> {
+        let state = self.state.lock().unwrap();
+        state.exhausted_policy
+    }
+
+    /// Set when interactions are re-recorded
+    ///
+    /// In Auto, Hybrid and Once modes, a request matching an interaction older
+    /// than the cassette's interval is forwarded upstream again, and the new
+    /// exchange replaces the stale one when the cassette is saved. Fresh
+    /// interactions keep replaying. Takes effect on the next start call.
+    pub fn set_re_record_policy(&self, policy: ReRecordPolicy) {
+        let mut state = self.state.lock().unwrap();
+        state.re_record = policy;
+    }
+
+    /// Get the re-record policy
+    pub fn re_record_policy(&self) -> ReRecordPolicy {
+        let state = self.state.lock().unwrap();
+        state.re_record.clone()
+    }
+
+    /// Run a hook on every interaction before it is recorded
+    ///
+    /// Hooks run in the order they were added, after sensitive data is
+    /// replaced with placeholders. Takes effect on the next start call.
+    pub fn add_record_hook<H: RecordHook + 'static>(&self, hook: H) {
+        let mut state = self.state.lock().unwrap();
+        state.record_hooks.add(hook);
+    }
+
+    /// Run a hook on every interaction before it is replayed
+    ///
+    /// Hooks run in the order they were added, before templates are rendered
+    /// and placeholders restored. Takes effect on the next start call.
+    pub fn add_replay_hook<H: ReplayHook + 'static>(&self, hook: H) {
+        let mut state = self.state.lock().unwrap();
+        state.replay_hooks.add(hook);
+    }
+
+    /// Replace sensitive values with placeholders
+    ///
+    /// Recorded interactions hold placeholders such as `<API_KEY>` instead
+    /// of the configured headers, query parameters, JSON fields, patterns
+    /// and secrets. Replay redacts live requests the same way before
+    /// matching and fills placeholders in responses back with the live
+    /// values. Takes effect on the next start call.
+    pub fn set_sensitive_data(&self, sensitive_data: SensitiveData) {
+        let mut state = self.state.lock().unwrap();
+        state.sensitive_data = sensitive_data;
+    }
+
+    /// Get the sensitive data filter
+    pub fn sensitive_data(&self) -> SensitiveData {
+        let state = self.state.lock().unwrap();
+        state.sensitive_data.clone()
+    }
+
+    /// Set which cookies are recorded and replayed
+    ///
+    /// Cookies set by recorded responses are stored in the cassette, minus
+    /// sensitive ones (dropped or hashed); replay keeps them in the player's
+    /// cookie jar for requests sent without cookies. Takes effect on the next
+    /// start call.
+    pub fn set_cookie_policy(&self, policy: CookiePolicy) {
+        let mut state = self.state.lock().unwrap();
+        state.cookie_policy = policy;
+    }
+
+    /// Get the cookie policy
+    pub fn cookie_policy(&self) -> CookiePolicy {
+        let state = self.state.lock().unwrap();
+        state.cookie_policy.clone()
+    }
+
+    /// Leave interactions matching the filters out of recordings (None = record everything)
+    ///
+    /// Filtered requests are still proxied. Takes effect on the next start call.
+    pub fn set_recording_filters(&self, filters: Option<RecordingFilters>) {
+        let mut state = self.state.lock().unwrap();
+        state.recording_filters = filters;
+    }
+
+    /// Get the recording filters
+    pub fn recording_filters(&self) -> Option<RecordingFilters> {
+        let state = self.state.lock().unwrap();
+        state.recording_filters.clone()
+    }
+
+    /// Send hybrid mode's new interactions to a pending file for review
+    ///
+    /// Instead of rewriting the cassette, hybrid mode appends new
+    /// interactions to `name.pending.json` (in the cassette's format) and
+    /// replays them along with the cassette. `magneto pending` accepts or
+    /// rejects them into the cassette. Takes effect on the next start call.
+    pub fn set_pending_review(&self, enabled: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.pending_review = enabled;
+    }
+
+    /// Whether hybrid mode sends new interactions to a pending file
+    pub fn pending_review(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.pending_review
+    }
+
+    /// Set the latency simulated during replay
+    ///
+    /// Applies to HTTP responses, event streams and WebSocket frames served
+    /// from a cassette.
+    /// Takes effect on the next start call.
+    pub fn set_latency(&self, mode: LatencyMode) {
+        let mut state = self.state.lock().unwrap();
+        state.latency_mode = Some(mode);
+    }
+
+    /// Get the configured latency mode (None if players use their default)
+    pub fn latency(&self) -> Option<LatencyMode> {
+        let state = self.state.lock().unwrap();
+        state.latency_mode
+    }
+
+    /// Set the format new cassettes are saved in
+    ///
+    /// Existing cassettes are loaded whatever their format (detected from the
+    /// file extension); a file in this format wins if several exist.
+    /// Takes effect on the next start call.
+    pub fn set_format(&self, format: CassetteFormat) {
+        let mut state = self.state.lock().unwrap();
+        state.format = format;
+    }
+
+    /// Get the configured cassette format
+    pub fn format(&self) -> CassetteFormat {
+        let state = self.state.lock().unwrap();
+        state.format
+    }
+
+    /// Set the largest response body that is recorded, in bytes
+    ///
+    /// Responses are streamed to the client whatever their size; a response
+    /// whose body exceeds the cap is not written to the cassette.
+    /// Takes effect on the next start call.
+    pub fn set_max_body_size(&self, bytes: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.max_body_size = bytes;
+    }
+
+    /// Get the largest response body that is recorded, in bytes
+    pub fn max_body_size(&self) -> u64 {
+        let state = self.state.lock().unwrap();
+        state.max_body_size
+    }
+
+    /// Chain upstream traffic through another proxy (None = connect directly)
+    ///
+    /// Use `UpstreamProxy::from_env()` to honour `HTTPS_PROXY`/`NO_PROXY`.
+    /// Takes effect on the next start call.
+    pub fn set_upstream_proxy(&self, proxy: Option<UpstreamProxy>) {
+        let mut state = self.state.lock().unwrap();
+        state.upstream_proxy = proxy;
+    }
+
+    /// Get the upstream proxy traffic is chained through (if any)
+    pub fn upstream_proxy(&self) -> Option<UpstreamProxy> {
+        let state = self.state.lock().unwrap();
+        state.upstream_proxy.clone()
+    }
+
+    /// Set the trust roots and client certificates used towards upstream servers
+    ///
+    /// Build the settings with `UpstreamTlsConfig::load()`, which reports
+    /// unreadable certificate files. None trusts the native roots only.
+    /// Takes effect on the next start call.
+    pub fn set_upstream_tls(&self, tls: Option<UpstreamTls>) {
+        let mut state = self.state.lock().unwrap();
+        state.upstream_tls = tls;
+    }
+
+    /// Get the upstream TLS settings (if any besides the defaults)
+    pub fn upstream_tls(&self) -> Option<UpstreamTls> {
+        let state = self.state.lock().unwrap();
+        state.upstream_tls.clone()
+    }
+
+    /// Serve as a reverse proxy instead of a MITM proxy (None = MITM proxy)
+    ///
+    /// Clients then use `http://localhost:PORT/` as their base URL instead of
+    /// a proxy setting, and need not trust the magneto CA. Every mode works
+    /// the same way; cassettes store the real upstream URLs.
+    /// Takes effect on the next start call.
+    pub fn set_reverse_proxy(&self, reverse: Option<ReverseProxy>) {
+        let mut state = self.state.lock().unwrap();
+        state.reverse_proxy = reverse;
+    }
+
+    /// Get the reverse-proxy routes (if serving as a reverse proxy)
+    pub fn reverse_proxy(&self) -> Option<ReverseProxy> {
+        let state = self.state.lock().unwrap();
+        state.reverse_proxy.clone()
+    }
+
+    /// Set the descriptors used to decode gRPC messages (None = bytes only)
+    ///
+    /// Build them with `GrpcConfig::load()` or `GrpcDescriptors::from_files`
+    /// (requires the `grpc` feature). Decoded messages are stored next to
+    /// their bytes and matched on their content.
+    /// Takes effect on the next start call.
+    pub fn set_grpc_descriptors(&self, descriptors: Option<GrpcDescriptors>) {
+        let mut state = self.state.lock().unwrap();
+        state.grpc_descriptors = descriptors;
+    }
+
+    /// Get the descriptors used to decode gRPC messages (if any)
+    pub fn grpc_descriptors(&self) -> Option<GrpcDescriptors> {
+        let state = self.state.lock().unwrap();
+        state.grpc_descriptors.clone()
+    }
+
+    /// Set the hosts forwarded live in every mode (None = nothing ignored)
+    ///
+    /// Requests to them are neither recorded nor replayed, see `IgnoreRules`.
+    /// Takes effect on the next start call.
+    pub fn set_ignore_rules(&self, ignore: Option<IgnoreRules>) {
+        let mut state = self.state.lock().unwrap();
+        state.ignore_rules = ignore.filter(|ignore| !ignore.is_empty());
+    }
+
+    /// Get the hosts forwarded live (if any)
+    pub fn ignore_rules(&self) -> Option<IgnoreRules> {
+        let state = self.state.lock().unwrap();
+        state.ignore_rules.clone()
+    }
+
+    /// Record and replay WebSocket sessions (the default), or forward them live
+    ///
+    /// When disabled, WebSocket upgrades are forwarded upstream in every mode
+    /// and their frames are not recorded. Takes effect on the next start call.
+    pub fn set_websocket_enabled(&self, enabled: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.websocket_enabled = enabled;
+    }
+
+    /// Whether WebSocket sessions are recorded and replayed
+    pub fn websocket_enabled(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.websocket_enabled
+    }
+
+    /// Forward `localhost`, `127.0.0.0/8` and `::1` live (setter style for UniFFI)
+    ///
+    /// Takes effect on the next start call.
+    pub fn set_ignore_localhost(&self, ignore: bool) {
+        let mut state = self.state.lock().unwrap();
+        let rules = state
+            .ignore_rules
+            .take()
+            .unwrap_or_default()
+            .with_localhost(ignore);
+        state.ignore_rules = Some(rules).filter(|rules| !rules.is_empty());
+    }
+
+    /// Replace the ignored hosts, `*.domain` wildcards, CIDR ranges and URL
+    /// prefixes (setter style for UniFFI)
+    ///
+    /// Returns false, leaving the rules unchanged, if a rule is invalid.
+    /// Takes effect on the next start call.
+    pub fn set_ignore_hosts(&self, hosts: Vec<String>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let localhost = state
+            .ignore_rules
+            .as_ref()
+            .is_some_and(IgnoreRules::ignores_localhost);
+
+        match IgnoreRules::new()
+            .with_localhost(localhost)
+            .with_hosts(&hosts)
+        {
+            Ok(rules) => {
+                state.ignore_rules = Some(rules).filter(|rules| !rules.is_empty());
+                true
+            }
+            Err(e) => {
+                tracing::error!("Failed to set ignored hosts: {}", e);
+                false
+            }
+        }
+    }
+
     /// Get the current proxy port
+    ///
+    /// Once a mode has been started this is the port the listener is bound to,
+    /// which is how callers learn the OS-assigned port after `set_port(0)`.
     pub fn port(&self) -> u16 {
         let state = self.state.lock().unwrap();
-        state.proxy_port
+        state.bound_port.unwrap_or(state.proxy_port)
     }
 
     /// Get the current proxy mode
@@ -164,13 +850,17 @@ impl MagnetoProxy {
     }
 
     /// Start recording a new cassette (internal version with Result)
+    ///
+    /// Like every start call, this first ends the current cassette, saving
+    /// what it recorded.
     pub fn start_recording_internal(&self, cassette_name: String) -> Result<()> {
+        self.end_cassette()?;
         let mut state = self.state.lock().unwrap();
 
         state.current_cassette = Some(cassette_name.clone());
 
         // Create recorder
-        let recorder = Arc::new(Mutex::new(Recorder::new(cassette_name.clone())));
+        let recorder = Arc::new(Mutex::new(state.new_recorder(cassette_name.clone())));
         state.recorder = Some(recorder.clone());
 
         // Create and start proxy server
@@ -180,27 +870,7 @@ impl MagnetoProxy {
         eprintln!("🎬 Starting recording for cassette: {}", cassette_name);
         tracing::info!("🎬 Starting recording for cassette: {}", cassette_name);
 
-        // Start server in dedicated thread with its own Tokio runtime
-        // This ensures the async server task actually runs
-        std::thread::spawn(move || {
-            eprintln!("🧵 Proxy server thread started");
-            // Create a new runtime for this thread
-            let rt = tokio::runtime::Runtime::new().expect("Failed to create runtime");
-            eprintln!("✅ Tokio runtime created in thread");
-
-            // Block on the server start - this will run until shutdown
-            if let Err(e) = rt.block_on(server.start()) {
-                eprintln!("❌ Proxy server error: {}", e);
-                tracing::error!("Proxy server error: {}", e);
-            }
-            eprintln!("🧵 Proxy server thread ending");
-        });
-
-        // Give the proxy server a moment to start listening
-        // The server starts in a background thread, so we need to wait briefly
-        eprintln!("⏳ Waiting for proxy to start listening...");
-        std::thread::sleep(std::time::Duration::from_millis(1000));
-        eprintln!("✅ Wait complete, proxy should be ready");
+        self.spawn_server(&mut state, server)?;
 
         Ok(())
     }
@@ -217,41 +887,18 @@ impl MagnetoProxy {
         if let Some(cassette_name) = state.current_cassette.take() {
             tracing::info!("💾 Stopping recording for cassette: {}", cassette_name);
 
+            let server = state.take_server();
+            let recorder = state.recorder.take();
+            let cassette_dir = state.cassette_dir.clone();
+            // Drop the lock before draining and saving
+            drop(state);
+
+            // Drain in-flight requests first so they make it into the cassette
+            stop_server(server);
+
             // Save the cassette
-            if let Some(recorder) = state.recorder.take() {
-                let cassette_dir = state.cassette_dir.clone();
-                // Drop the lock before saving
-                drop(state);
-
-                // Try to lock the recorder (should succeed immediately since we own the Arc)
-                // Use try_lock to avoid async context issues
-                let can_lock_immediately = recorder.try_lock().is_ok();
-
-                if can_lock_immediately {
-                    // Lock again (we know it will succeed)
-                    let recorder_guard = recorder.try_lock().unwrap();
-                    recorder_guard.save(&cassette_dir)?;
-                    tracing::info!("✅ Cassette saved");
-                } else {
-                    // If try_lock fails, fall back to spawning a thread
-                    let save_result = std::thread::spawn(move || {
-                        let rt =
-                            tokio::runtime::Runtime::new().expect("Failed to create save runtime");
-                        let result = rt.block_on(async move {
-                            let recorder_lock = recorder.lock().await;
-                            recorder_lock.save(&cassette_dir)
-                        });
-                        // Forget the runtime to avoid "drop in async context" error
-                        std::mem::forget(rt);
-                        result
-                    })
-                    .join()
-                    .map_err(|e| MatgtoError::RecordingFailed {
-                        reason: format!("Save thread panicked: {:?}", e),
-                    })?;
-                    save_result?;
-                    tracing::info!("✅ Cassette saved (via thread)");
-                }
+            if let Some(recorder) = recorder {
+                save_recorder(recorder, cassette_dir)?;
             }
 
             Ok(())
@@ -269,13 +916,19 @@ impl MagnetoProxy {
 
     /// Replay an existing cassette (internal version with Result)
     pub fn replay_internal(&self, cassette_name: String) -> Result<()> {
+        self.end_cassette()?;
         let mut state = self.state.lock().unwrap();
 
         state.current_cassette = Some(cassette_name.clone());
 
         // Load cassette
         let cassette_dir = state.cassette_dir.clone();
-        let player = Player::load(&cassette_dir, &cassette_name)?;
+        let player = state.configure_player(Player::load_with_format(
+            &cassette_dir,
+            &cassette_name,
+            state.format,
+            false,
+        )?);
 
         let player_arc = Arc::new(Mutex::new(player));
         state.player = Some(player_arc.clone());
@@ -286,13 +939,7 @@ impl MagnetoProxy {
 
         tracing::info!("▶️  Starting replay for cassette: {}", cassette_name);
 
-        // Start server in background
-        let runtime_handle = self.runtime.handle().clone();
-        runtime_handle.spawn(async move {
-            if let Err(e) = server.start().await {
-                tracing::error!("Proxy server error: {}", e);
-            }
-        });
+        self.spawn_server(&mut state, server)?;
 
         Ok(())
     }
@@ -305,13 +952,19 @@ impl MagnetoProxy {
     /// Replay an existing cassette in STRICT mode (internal version with Result)
     /// In strict mode, any request not found in the cassette will cause an error
     pub fn replay_strict_internal(&self, cassette_name: String) -> Result<()> {
+        self.end_cassette()?;
         let mut state = self.state.lock().unwrap();
 
         state.current_cassette = Some(cassette_name.clone());
 
         // Load cassette in strict mode
         let cassette_dir = state.cassette_dir.clone();
-        let player = Player::load_strict(&cassette_dir, &cassette_name)?;
+        let player = state.configure_player(Player::load_with_format(
+            &cassette_dir,
+            &cassette_name,
+            state.format,
+            true,
+        )?);
 
         let player_arc = Arc::new(Mutex::new(player));
         state.player = Some(player_arc.clone());
@@ -323,13 +976,7 @@ impl MagnetoProxy {
         tracing::info!("🔒 Starting STRICT replay for cassette: {}", cassette_name);
         tracing::info!("⚠️  Any missing interaction will cause an error");
 
-        // Start server in background
-        let runtime_handle = self.runtime.handle().clone();
-        runtime_handle.spawn(async move {
-            if let Err(e) = server.start().await {
-                tracing::error!("Proxy server error: {}", e);
-            }
-        });
+        self.spawn_server(&mut state, server)?;
 
         Ok(())
     }
@@ -345,6 +992,7 @@ impl MagnetoProxy {
     /// - Incremental testing: Gradually build up cassettes
     /// - API exploration: Capture only new interactions during development
     pub fn hybrid_internal(&self, cassette_name: String) -> Result<()> {
+        self.end_cassette()?;
         let mut state = self.state.lock().unwrap();
 
         state.current_cassette = Some(cassette_name.clone());
@@ -352,8 +1000,11 @@ impl MagnetoProxy {
         let cassette_dir = state.cassette_dir.clone();
 
         // Try to load existing cassette, or create new one
-        let (player, recorder) =
-            match Player::load(&cassette_dir, &cassette_name) {
+        let (player, recorder) = if state.pending_review {
+            let (player, recorder) = state.pending_hybrid(&cassette_name)?;
+            (Some(player), recorder)
+        } else {
+            match Player::load_with_format(&cassette_dir, &cassette_name, state.format, false) {
                 Ok(player) => {
                     tracing::info!(
                         "📼 Loaded existing cassette '{}' for hybrid mode",
@@ -363,16 +1014,15 @@ impl MagnetoProxy {
                         "   Existing interactions will be replayed, new ones will be recorded"
                     );
 
-                    // Load existing cassette into recorder to append to it
-                    let cassette = player.cassette().cloned().ok_or_else(|| {
-                        MatgtoError::CassetteNotFound {
+                    if !player.has_cassette() {
+                        return Err(MatgtoError::CassetteNotFound {
                             name: cassette_name.clone(),
-                        }
-                    })?;
+                        });
+                    }
 
-                    let mut recorder = Recorder::new(cassette_name.clone());
-                    // Copy existing interactions
-                    recorder.cassette_mut().interactions = cassette.interactions.clone();
+                    // Append to the existing cassette
+                    let player = state.track_stale(&cassette_name, state.configure_player(player));
+                    let recorder = state.appending_recorder(cassette_name.clone(), &player);
 
                     (Some(player), recorder)
                 }
@@ -383,9 +1033,10 @@ impl MagnetoProxy {
                         cassette_name
                     );
 
-                    (None, Recorder::new(cassette_name.clone()))
+                    (None, state.new_recorder(cassette_name.clone()))
                 }
-            };
+            }
+        };
 
         let recorder_arc = Arc::new(Mutex::new(recorder));
         state.recorder = Some(recorder_arc.clone());
@@ -403,13 +1054,7 @@ impl MagnetoProxy {
 
         tracing::info!("🔀 Starting HYBRID mode for cassette: {}", cassette_name);
 
-        // Start server in background
-        let runtime_handle = self.runtime.handle().clone();
-        runtime_handle.spawn(async move {
-            if let Err(e) = server.start().await {
-                tracing::error!("Proxy server error: {}", e);
-            }
-        });
+        self.spawn_server(&mut state, server)?;
 
         Ok(())
     }
@@ -437,6 +1082,7 @@ impl MagnetoProxy {
     /// - Safety: Prevent accidental re-recording
     /// - CI/CD: Ensure cassettes are preserved
     pub fn once_internal(&self, cassette_name: String) -> Result<()> {
+        self.end_cassette()?;
         let mut state = self.state.lock().unwrap();
 
         state.current_cassette = Some(cassette_name.clone());
@@ -444,18 +1090,7 @@ impl MagnetoProxy {
         let cassette_dir = state.cassette_dir.clone();
 
         // Try to load existing cassette
-        let cassette_exists = cassette_dir
-            .join(format!("{}.json", cassette_name))
-            .exists()
-            || cassette_dir
-                .join(format!("{}.json.gz", cassette_name))
-                .exists()
-            || cassette_dir
-                .join(format!("{}.msgpack", cassette_name))
-                .exists()
-            || cassette_dir
-                .join(format!("{}.msgpack.gz", cassette_name))
-                .exists();
+        let cassette_exists = find_cassette(&cassette_dir, &cassette_name, state.format).is_some();
 
         if cassette_exists {
             // Cassette exists, switch to replay mode (read-only)
@@ -464,21 +1099,35 @@ impl MagnetoProxy {
                 cassette_name
             );
 
-            let player = Player::load(&cassette_dir, &cassette_name)?;
+            let player = state.track_stale(
+                &cassette_name,
+                state.configure_player(Player::load_with_format(
+                    &cassette_dir,
+                    &cassette_name,
+                    state.format,
+                    false,
+                )?),
+            );
+
+            // Stale interactions are the only ones ever recorded again
+            let recorder = (player.stale_count() > 0).then(|| {
+                Arc::new(Mutex::new(
+                    state.appending_recorder(cassette_name.clone(), &player),
+                ))
+            });
+            state.recorder = recorder.clone();
+
             let player_arc = Arc::new(Mutex::new(player));
             state.player = Some(player_arc.clone());
 
             // Create and start proxy server in once mode (will replay)
-            let server = ProxyServer::new(state.proxy_port, self.ca.clone(), ProxyMode::Once)?
+            let mut server = ProxyServer::new(state.proxy_port, self.ca.clone(), ProxyMode::Once)?
                 .with_player(player_arc);
+            if let Some(recorder) = recorder {
+                server = server.with_recorder(recorder);
+            }
 
-            // Start server in background
-            let runtime_handle = self.runtime.handle().clone();
-            runtime_handle.spawn(async move {
-                if let Err(e) = server.start().await {
-                    tracing::error!("Proxy server error: {}", e);
-                }
-            });
+            self.spawn_server(&mut state, server)?;
         } else {
             // Cassette doesn't exist, record it
             tracing::info!(
@@ -486,20 +1135,14 @@ impl MagnetoProxy {
                 cassette_name
             );
 
-            let recorder = Arc::new(Mutex::new(Recorder::new(cassette_name.clone())));
+            let recorder = Arc::new(Mutex::new(state.new_recorder(cassette_name.clone())));
             state.recorder = Some(recorder.clone());
 
             // Create and start proxy server in once mode (will record)
             let server = ProxyServer::new(state.proxy_port, self.ca.clone(), ProxyMode::Once)?
                 .with_recorder(recorder);
 
-            // Start server in background
-            let runtime_handle = self.runtime.handle().clone();
-            runtime_handle.spawn(async move {
-                if let Err(e) = server.start().await {
-                    tracing::error!("Proxy server error: {}", e);
-                }
-            });
+            self.spawn_server(&mut state, server)?;
         }
 
         Ok(())
@@ -514,7 +1157,7 @@ impl MagnetoProxy {
     pub fn stop_once_internal(&self) -> Result<()> {
         // If recording, save the cassette
         // If replaying, just clean up
-        let state = self.state.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
 
         if state.recorder.is_some() {
             // Was recording, save the cassette
@@ -522,6 +1165,11 @@ impl MagnetoProxy {
             self.stop_recording_internal()
         } else {
             // Was replaying, just clean up
+            let server = state.take_server();
+            state.current_cassette = None;
+            state.player = None;
+            drop(state);
+            stop_server(server);
             Ok(())
         }
     }
@@ -543,50 +1191,299 @@ impl MagnetoProxy {
 
     /// Start in auto mode: replay if cassette exists, record if not
     pub fn auto(&self, cassette_name: &str) {
-        let cassette_dir = {
+        let _ = self.auto_internal(cassette_name);
+    }
+
+    /// Start in auto mode (internal version with Result)
+    fn auto_internal(&self, cassette_name: &str) -> Result<()> {
+        let (cassette_dir, format) = {
             let state = self.state.lock().unwrap();
-            state.cassette_dir.clone()
+            (state.cassette_dir.clone(), state.format)
         };
 
         // Check if cassette exists
-        let cassette_exists = cassette_dir
-            .join(format!("{}.json", cassette_name))
-            .exists()
-            || cassette_dir
-                .join(format!("{}.json.gz", cassette_name))
-                .exists()
-            || cassette_dir
-                .join(format!("{}.msgpack", cassette_name))
-                .exists()
-            || cassette_dir
-                .join(format!("{}.msgpack.gz", cassette_name))
-                .exists();
+        let cassette_exists = find_cassette(&cassette_dir, cassette_name, format).is_some();
 
         if cassette_exists {
             tracing::info!("🔄 Auto mode: Cassette exists, replaying");
-            let _ = self.replay_internal(cassette_name.to_string());
+            self.auto_replay(cassette_name)
         } else {
             tracing::info!("🔄 Auto mode: Cassette doesn't exist, recording");
-            let _ = self.start_recording_internal(cassette_name.to_string());
+            self.start_recording_internal(cassette_name.to_string())
         }
     }
 
-    /// Start in passthrough mode (no recording/replaying)
-    pub fn passthrough(&self) {
+    /// Replay an existing cassette in auto mode
+    ///
+    /// Without stale interactions this is plain replay; otherwise the server
+    /// runs in Auto mode with a recorder, re-recording stale interactions
+    /// (and recording misses) until the cassette is stopped.
+    fn auto_replay(&self, cassette_name: &str) -> Result<()> {
+        self.end_cassette()?;
+        let mut state = self.state.lock().unwrap();
+        if state.re_record.interval_for(cassette_name).is_none() {
+            drop(state);
+            return self.replay_internal(cassette_name.to_string());
+        }
+
+        let cassette_dir = state.cassette_dir.clone();
+        let player = state.track_stale(
+            cassette_name,
+            state.configure_player(Player::load_with_format(
+                &cassette_dir,
+                cassette_name,
+                state.format,
+                false,
+            )?),
+        );
+        if player.stale_count() == 0 {
+            drop(state);
+            return self.replay_internal(cassette_name.to_string());
+        }
+
+        let recorder = Arc::new(Mutex::new(
+            state.appending_recorder(cassette_name.to_string(), &player),
+        ));
+        let player = Arc::new(Mutex::new(player));
+        state.current_cassette = Some(cassette_name.to_string());
+        state.recorder = Some(recorder.clone());
+        state.player = Some(player.clone());
+
+        let server = ProxyServer::new(state.proxy_port, self.ca.clone(), ProxyMode::Auto)?
+            .with_recorder(recorder)
+            .with_player(player);
+
+        self.spawn_server(&mut state, server)
+    }
+
+    /// Push a cassette on top of the current one (internal version with Result)
+    ///
+    /// The new cassette starts in `mode` and receives new recordings; the
+    /// current one stays below it in its own mode. Requests the top cassette
+    /// has no match for are replayed from the cassettes below, top-down,
+    /// before the top's mode records them or fails. Cassettes below are never
+    /// recorded to, and replay what they held when they were covered.
+    /// `pop_cassette` ends the top cassette and serves the one below again,
+    /// like VCR's nested `insert_cassette`/`eject_cassette`.
+    pub fn push_cassette_internal(&self, cassette_name: String, mode: ProxyMode) -> Result<()> {
+        if mode == ProxyMode::Passthrough {
+            return Err(MatgtoError::Config(
+                "Passthrough mode has no cassette to push".to_string(),
+            ));
+        }
+
+        let pushed = {
+            let mut state = self.state.lock().unwrap();
+            match (state.current_cassette.take(), state.cassette_mode) {
+                (Some(name), Some(current_mode)) => {
+                    tracing::info!(
+                        "📚 Pushing cassette '{}' on top of '{}'",
+                        cassette_name,
+                        name
+                    );
+                    let layer = CassetteLayer {
+                        name,
+                        mode: current_mode,
+                        recorder: state.recorder.take(),
+                        player: state.player.take(),
+                    };
+                    state.stack.push(layer);
+                    true
+                }
+                (name, _) => {
+                    state.current_cassette = name;
+                    false
+                }
+            }
+        };
+
+        let started = match mode {
+            ProxyMode::Auto => self.auto_internal(&cassette_name),
+            ProxyMode::Record => self.start_recording_internal(cassette_name),
+            ProxyMode::Replay => self.replay_internal(cassette_name),
+            ProxyMode::ReplayStrict => self.replay_strict_internal(cassette_name),
+            ProxyMode::Hybrid => self.hybrid_internal(cassette_name),
+            ProxyMode::Once => self.once_internal(cassette_name),
+            ProxyMode::Passthrough => unreachable!("rejected above"),
+        };
+
+        if let Err(e) = started {
+            // Keep serving the cassette that was on top
+            if pushed {
+                let mut state = self.state.lock().unwrap();
+                self.resume_layer(&mut state)?;
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Push a cassette on top of the current one (UniFFI compatible - returns bool)
+    pub fn push_cassette(&self, cassette_name: String, mode: ProxyMode) -> bool {
+        self.push_cassette_internal(cassette_name, mode).is_ok()
+    }
+
+    /// End the top cassette and serve the one below it again (internal version with Result)
+    ///
+    /// The top cassette is stopped like its stop call would (saving what it
+    /// recorded); the cassette below resumes in the mode it was pushed over in.
+    pub fn pop_cassette_internal(&self) -> Result<()> {
+        if self.state.lock().unwrap().stack.is_empty() {
+            return Err(MatgtoError::Config(
+                "No cassette below the current one".to_string(),
+            ));
+        }
+
+        self.end_cassette()?;
+
+        let mut state = self.state.lock().unwrap();
+        self.resume_layer(&mut state)
+    }
+
+    /// End the top cassette and serve the one below it again (UniFFI compatible - returns bool)
+    pub fn pop_cassette(&self) -> bool {
+        self.pop_cassette_internal().is_ok()
+    }
+
+    /// Names of the cassettes below the current one, bottom first
+    pub fn stacked_cassettes(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.stack.iter().map(|layer| layer.name.clone()).collect()
+    }
+
+    /// Serve the cassette on top of the stack again
+    fn resume_layer(&self, state: &mut ProxyState) -> Result<()> {
+        let layer = state
+            .stack
+            .pop()
+            .ok_or_else(|| MatgtoError::Config("No cassette below the current one".to_string()))?;
+
+        state.current_cassette = Some(layer.name.clone());
+        state.recorder = layer.recorder.clone();
+        state.player = layer.player.clone();
+
+        let mut server = ProxyServer::new(state.proxy_port, self.ca.clone(), layer.mode)?;
+        if let Some(recorder) = layer.recorder {
+            server = server.with_recorder(recorder);
+        }
+        if let Some(player) = layer.player {
+            server = server.with_player(player);
+        }
+
+        tracing::info!("📚 Back to cassette '{}' ({:?})", layer.name, layer.mode);
+
+        self.spawn_server(state, server)
+    }
+
+    /// Stop the current cassette, saving it if it was recording
+    fn end_cassette(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.recorder.is_some() && state.current_cassette.is_some() {
+            // Flush the pending recording (this also stops the server)
+            drop(state);
+            return self.stop_recording_internal();
+        }
+
+        let server = state.take_server();
+        state.current_cassette = None;
+        state.recorder = None;
+        state.player = None;
+        drop(state);
+        stop_server(server);
+        Ok(())
+    }
+
+    /// Start in passthrough mode (internal version with Result)
+    pub fn passthrough_internal(&self) -> Result<()> {
+        self.end_cassette()?;
         let mut state = self.state.lock().unwrap();
         state.mode = ProxyMode::Passthrough;
 
-        let server =
-            ProxyServer::new(state.proxy_port, self.ca.clone(), ProxyMode::Passthrough).unwrap();
+        let server = ProxyServer::new(state.proxy_port, self.ca.clone(), ProxyMode::Passthrough)?;
 
         tracing::info!("🔀 Starting passthrough mode");
 
-        let runtime_handle = self.runtime.handle().clone();
-        runtime_handle.spawn(async move {
-            if let Err(e) = server.start().await {
-                tracing::error!("Proxy server error: {}", e);
+        self.spawn_server(&mut state, server)
+    }
+
+    /// Start in passthrough mode (no recording/replaying)
+    pub fn passthrough(&self) {
+        let _ = self.passthrough_internal();
+    }
+
+    /// Bind the server's listener and run it in the background
+    ///
+    /// Start calls end the current cassette first, so one instance can switch
+    /// cassettes on the same port. The listener is bound before this returns,
+    /// so bind errors reach the caller and the proxy accepts connections as
+    /// soon as the start call succeeds - no need to sleep before the first request.
+    fn spawn_server(&self, state: &mut ProxyState, server: ProxyServer) -> Result<()> {
+        // Only reached with a server still running if a start call skipped `end_cassette`
+        stop_server(state.take_server());
+
+        let server = if state.upstream_proxy.is_some() || state.upstream_tls.is_some() {
+            let mut forwarder = HttpForwarder::new();
+            if let Some(proxy) = &state.upstream_proxy {
+                forwarder = forwarder.with_upstream_proxy(proxy.clone());
+            }
+            if let Some(tls) = &state.upstream_tls {
+                forwarder = forwarder.with_tls(tls.clone());
             }
-        });
+            server.with_forwarder(forwarder)
+        } else {
+            server
+        };
+
+        let server = match &state.reverse_proxy {
+            Some(reverse) => server.with_reverse_proxy(reverse.clone()),
+            None => server,
+        };
+
+        let server = match &state.grpc_descriptors {
+            Some(descriptors) => server.with_grpc_descriptors(descriptors.clone()),
+            None => server,
+        };
+
+        let server = match &state.ignore_rules {
+            Some(ignore) => server.with_ignore_rules(ignore.clone()),
+            None => server,
+        };
+
+        let server = if state.websocket_enabled {
+            server
+        } else {
+            server.without_websocket()
+        };
+
+        // Cassettes below the current one, top-down
+        let server = server.with_base_players(
+            state
+                .stack
+                .iter()
+                .rev()
+                .filter_map(|layer| layer.player.clone())
+                .collect(),
+        );
+        let mode = server.mode();
+
+        let handle = match server.spawn(self.runtime.handle()) {
+            Ok(handle) => handle,
+            Err(e) => {
+                state.current_cassette = None;
+                state.recorder = None;
+                state.player = None;
+                return Err(e);
+            }
+        };
+
+        tracing::info!("📡 Proxy listening on {}", handle.local_addr());
+        state.bound_port = Some(handle.local_addr().port());
+        state.cassette_mode = Some(mode);
+        state.server = Some(handle);
+
+        Ok(())
     }
 
     // ========== Aliases for test framework integration ==========
@@ -597,35 +1494,44 @@ impl MagnetoProxy {
     }
 
     /// Stop replay mode - for #[magneto_test] macro compatibility
-    /// Note: Currently a no-op since replay mode doesn't maintain state
+    ///
+    /// Interactions re-recorded in auto mode are saved.
     pub fn stop_replay(&self) -> Result<()> {
-        // Replay doesn't need explicit stopping as it doesn't modify state
-        // But we provide this for API consistency
-        Ok(())
+        self.end_cassette()
     }
 
     /// Alias for passthrough() - for #[magneto_test] macro compatibility
     pub fn start_passthrough(&self) -> Result<()> {
-        self.passthrough();
-        Ok(())
+        self.passthrough_internal()
     }
 
     /// Stop passthrough mode - for #[magneto_test] macro compatibility
     pub fn stop_passthrough(&self) -> Result<()> {
-        // Passthrough doesn't need explicit stopping
-        // But we provide this for API consistency
+        let server = self.state.lock().unwrap().take_server();
+        stop_server(server);
         Ok(())
     }
 
     /// Shutdown the proxy (internal version with Result)
+    ///
+    /// Pending recordings are saved, including those of stacked cassettes.
     pub fn shutdown_internal(&self) -> Result<()> {
-        let mut state = self.state.lock().unwrap();
-
-        // TODO: Stop the proxy server
         tracing::info!("Shutting down proxy");
 
-        state.current_cassette = None;
-        Ok(())
+        let (stack, cassette_dir) = {
+            let mut state = self.state.lock().unwrap();
+            (std::mem::take(&mut state.stack), state.cassette_dir.clone())
+        };
+
+        let result = self.end_cassette();
+
+        for layer in stack.into_iter().rev() {
+            if let Some(recorder) = layer.recorder {
+                save_recorder(recorder, cassette_dir.clone())?;
+            }
+        }
+
+        result
     }
 
     /// Shutdown the proxy (UniFFI compatible)
@@ -634,6 +1540,47 @@ impl MagnetoProxy {
     }
 }
 
+/// Shut down a server taken out of the state, draining in-flight requests and freeing its port
+fn stop_server(server: Option<ServerHandle>) {
+    if let Some(server) = server {
+        server.shutdown(SHUTDOWN_GRACE);
+    }
+}
+
+/// Save a recorder's cassette to `cassette_dir`
+fn save_recorder(recorder: Arc<Mutex<Recorder>>, cassette_dir: PathBuf) -> Result<()> {
+    // Try to lock the recorder (should succeed immediately since we own the Arc)
+    // Use try_lock to avoid async context issues
+    let can_lock_immediately = recorder.try_lock().is_ok();
+
+    if can_lock_immediately {
+        // Lock again (we know it will succeed)
+        let recorder_guard = recorder.try_lock().unwrap();
+        recorder_guard.save(&cassette_dir)?;
+        tracing::info!("✅ Cassette saved");
+    } else {
+        // If try_lock fails, fall back to spawning a thread
+        let save_result = std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create save runtime");
+            let result = rt.block_on(async move {
+                let recorder_lock = recorder.lock().await;
+                recorder_lock.save(&cassette_dir)
+            });
+            // Forget the runtime to avoid "drop in async context" error
+            std::mem::forget(rt);
+            result
+        })
+        .join()
+        .map_err(|e| MatgtoError::RecordingFailed {
+            reason: format!("Save thread panicked: {:?}", e),
+        })?;
+        save_result?;
+        tracing::info!("✅ Cassette saved (via thread)");
+    }
+
+    Ok(())
+}
+
 impl Drop for MagnetoProxy {
     fn drop(&mut self) {
         self.shutdown();
@@ -658,6 +1605,117 @@ mod tests {
         assert_eq!(proxy.port(), 9999);
     }
 
+    #[test]
+    fn test_proxy_ephemeral_port_is_ready_on_return() {
+        let dir = tempfile::tempdir().unwrap();
+        let proxy = MagnetoProxy::new_internal(dir.path().join("cassettes")).unwrap();
+        proxy.set_port(0);
+
+        proxy.start_passthrough().unwrap();
+
+        let port = proxy.port();
+        assert_ne!(port, 0);
+        std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+    }
+
+    #[test]
+    fn test_proxy_bind_error_is_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = MagnetoProxy::new_internal(dir.path().join("cassettes")).unwrap();
+        proxy.set_port(taken.local_addr().unwrap().port());
+
+        let result = proxy.start_recording_internal("busy".to_string());
+
+        assert!(matches!(result, Err(MatgtoError::ProxyStartFailed { .. })));
+        assert!(proxy.current_cassette_name().is_none());
+    }
+
+    #[test]
+    fn test_proxy_with_matching_strategy() {
+        use crate::matching::UrlMatchMode;
+
+        let proxy = MagnetoProxy::new("./cassettes".to_string());
+        assert!(proxy.matching_strategy().is_none());
+
+        proxy.set_matching_strategy(
+            MatchingStrategy::new().with_url_mode(UrlMatchMode::IgnoreQuery),
+        );
+        assert_eq!(
+            proxy.matching_strategy().unwrap().url_mode,
+            UrlMatchMode::IgnoreQuery
+        );
+    }
+
+    #[test]
+    fn test_proxy_with_latency() {
+        let proxy = MagnetoProxy::new("./cassettes".to_string());
+        assert!(proxy.latency().is_none());
+
+        proxy.set_latency(LatencyMode::Fixed(25));
+        assert_eq!(proxy.latency(), Some(LatencyMode::Fixed(25)));
+    }
+
+    #[test]
+    fn test_proxy_with_exhausted_policy() {
+        let proxy = MagnetoProxy::new("./cassettes".to_string());
+        assert!(proxy.exhausted_policy().is_none());
+
+        let proxy = proxy.with_exhausted_policy(ExhaustedPolicy::Cycle);
+        assert_eq!(proxy.exhausted_policy(), Some(ExhaustedPolicy::Cycle));
+    }
+
+    #[test]
+    fn test_proxy_with_re_record_policy() {
+        let proxy = MagnetoProxy::new("./cassettes".to_string());
+        assert_eq!(proxy.re_record_policy().interval_for("api"), None);
+
+        let interval = std::time::Duration::from_secs(3600);
+        let proxy = proxy.with_re_record_policy(ReRecordPolicy::new().with_interval(interval));
+        assert_eq!(proxy.re_record_policy().interval_for("api"), Some(interval));
+    }
+
+    #[test]
+    fn test_proxy_ignore_rules() {
+        let proxy = MagnetoProxy::new("./cassettes".to_string());
+        assert!(proxy.ignore_rules().is_none());
+
+        proxy.set_ignore_localhost(true);
+        assert!(proxy.set_ignore_hosts(vec!["*.sentry.io".to_string()]));
+        let ignore = proxy.ignore_rules().unwrap();
+        assert!(ignore.ignores_localhost());
+        assert!(ignore.matches_url("https://o1.ingest.sentry.io/api"));
+
+        // Invalid rules leave the current ones in place
+        assert!(!proxy.set_ignore_hosts(vec!["10.0.0.0/40".to_string()]));
+        assert_eq!(proxy.ignore_rules(), Some(ignore));
+
+        proxy.set_ignore_localhost(false);
+        assert!(proxy.set_ignore_hosts(Vec::new()));
+        assert!(proxy.ignore_rules().is_none());
+    }
+
+    #[test]
+    fn test_proxy_with_format() {
+        let proxy = MagnetoProxy::new("./cassettes".to_string());
+        assert_eq!(proxy.format(), CassetteFormat::Json);
+
+        #[cfg(feature = "msgpack")]
+        {
+            proxy.set_format(CassetteFormat::MessagePack);
+            assert_eq!(proxy.format(), CassetteFormat::MessagePack);
+        }
+    }
+
+    #[test]
+    fn test_proxy_with_max_body_size() {
+        let proxy = MagnetoProxy::new("./cassettes".to_string());
+        assert_eq!(proxy.max_body_size(), DEFAULT_MAX_BODY_SIZE);
+
+        let proxy = proxy.with_max_body_size(1024);
+        assert_eq!(proxy.max_body_size(), 1024);
+    }
+
     #[test]
     fn test_proxy_with_mode() {
         let proxy = MagnetoProxy::new("./cassettes".to_string());
@@ -0,0 +1,232 @@
+//! Streaming bodies
+//!
+//! Responses are forwarded chunk by chunk as they arrive from the upstream
+//! server. When an interaction is being recorded the chunks are also copied
+//! into a buffer (up to a size cap) that is handed to the recorder once the
+//! body is complete.
+
+use hyper::body::{Bytes, HttpBody};
+use hyper::{Body, HeaderMap};
+use std::future::Future;
+
+/// Outcome of copying a streamed body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Capture {
+    /// The whole body was received
+    Complete(Vec<u8>),
+
+    /// The body was received but grew past the cap (total size in bytes)
+    TooLarge(u64),
+
+    /// The upstream body failed or the client went away before the end
+    Interrupted(String),
+}
+
+/// Stream `body` to the client while copying it
+///
+/// At most `limit` bytes are buffered. `on_end` runs once the upstream body is
+/// finished and before the end of the stream reaches the client, so whatever
+/// it records is in place by the time the client sees a complete response.
+/// Trailers are forwarded after the body.
+pub fn tee<F, Fut>(body: Body, limit: u64, on_end: F) -> Body
+where
+    F: FnOnce(Capture) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (mut sender, client_body) = Body::channel();
+
+    tokio::spawn(async move {
+        let mut body = body;
+        let mut buffer = Some(Vec::new());
+        let mut total: u64 = 0;
+
+        while let Some(chunk) = body.data().await {
+            let chunk: Bytes = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    sender.abort();
+                    on_end(Capture::Interrupted(format!("Upstream body failed: {}", e))).await;
+                    return;
+                }
+            };
+
+            total += chunk.len() as u64;
+            if total > limit {
+                buffer = None;
+            } else if let Some(buffer) = buffer.as_mut() {
+                buffer.extend_from_slice(&chunk);
+            }
+
+            if sender.send_data(chunk).await.is_err() {
+                on_end(Capture::Interrupted(
+                    "Client closed the connection".to_string(),
+                ))
+                .await;
+                return;
+            }
+        }
+
+        let trailers = body.trailers().await.ok().flatten();
+
+        let capture = match buffer {
+            Some(buffer) => Capture::Complete(buffer),
+            None => Capture::TooLarge(total),
+        };
+        on_end(capture).await;
+
+        if let Some(trailers) = trailers {
+            let _ = sender.send_trailers(trailers).await;
+        }
+    });
+
+    client_body
+}
+
+/// Stream `body` through unchanged, showing each chunk to `on_chunk`
+///
+/// Used for bodies that are recorded piece by piece (gRPC messages) rather
+/// than as a whole. `on_end` gets the trailers once the body is complete, or
+/// the reason it failed, before the end of the stream and the trailers are
+/// passed on.
+pub fn tap<C, F, Fut>(body: Body, mut on_chunk: C, on_end: F) -> Body
+where
+    C: FnMut(&Bytes) + Send + 'static,
+    F: FnOnce(std::result::Result<Option<HeaderMap>, String>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (mut sender, tapped) = Body::channel();
+
+    tokio::spawn(async move {
+        let mut body = body;
+
+        while let Some(chunk) = body.data().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    sender.abort();
+                    on_end(Err(format!("Body failed: {}", e))).await;
+                    return;
+                }
+            };
+
+            on_chunk(&chunk);
+            if sender.send_data(chunk).await.is_err() {
+                on_end(Err("Peer closed the stream".to_string())).await;
+                return;
+            }
+        }
+
+        let trailers = match body.trailers().await {
+            Ok(trailers) => trailers,
+            Err(e) => {
+                sender.abort();
+                on_end(Err(format!("Trailers failed: {}", e))).await;
+                return;
+            }
+        };
+        on_end(Ok(trailers.clone())).await;
+
+        if let Some(trailers) = trailers {
+            let _ = sender.send_trailers(trailers).await;
+        }
+    });
+
+    tapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    fn chunked(chunks: &[&'static str]) -> Body {
+        let chunks: Vec<Result<Bytes, std::io::Error>> =
+            chunks.iter().map(|c| Ok(Bytes::from(*c))).collect();
+        Body::wrap_stream(futures::stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn test_tee_forwards_and_captures_body() {
+        let (tx, rx) = oneshot::channel();
+        let body = tee(chunked(&["hello ", "world"]), 1024, |capture| async move {
+            let _ = tx.send(capture);
+        });
+
+        let received = hyper::body::to_bytes(body).await.unwrap();
+
+        assert_eq!(&received[..], b"hello world");
+        assert_eq!(
+            rx.await.unwrap(),
+            Capture::Complete(b"hello world".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tee_stops_copying_past_limit() {
+        let (tx, rx) = oneshot::channel();
+        let body = tee(chunked(&["hello ", "world"]), 8, |capture| async move {
+            let _ = tx.send(capture);
+        });
+
+        let received = hyper::body::to_bytes(body).await.unwrap();
+
+        // The client still gets everything
+        assert_eq!(&received[..], b"hello world");
+        assert_eq!(rx.await.unwrap(), Capture::TooLarge(11));
+    }
+
+    #[tokio::test]
+    async fn test_tee_reports_upstream_failure() {
+        let failing: Vec<Result<Bytes, std::io::Error>> = vec![
+            Ok(Bytes::from("partial")),
+            Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "reset",
+            )),
+        ];
+        let (tx, rx) = oneshot::channel();
+        let body = tee(
+            Body::wrap_stream(futures::stream::iter(failing)),
+            1024,
+            |capture| async move {
+                let _ = tx.send(capture);
+            },
+        );
+
+        assert!(hyper::body::to_bytes(body).await.is_err());
+        assert!(matches!(rx.await.unwrap(), Capture::Interrupted(_)));
+    }
+
+    #[tokio::test]
+    async fn test_tap_sees_chunks_and_trailers() {
+        let (mut sender, body) = Body::channel();
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+        let sent = trailers.clone();
+        tokio::spawn(async move {
+            sender.send_data(Bytes::from("ab")).await.unwrap();
+            sender.send_data(Bytes::from("c")).await.unwrap();
+            sender.send_trailers(sent).await.unwrap();
+        });
+
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let chunks = seen.clone();
+        let (tx, rx) = oneshot::channel();
+        let mut body = tap(
+            body,
+            move |chunk| chunks.lock().unwrap().push(chunk.clone()),
+            |end| async move {
+                let _ = tx.send(end);
+            },
+        );
+
+        let mut received = Vec::new();
+        while let Some(chunk) = body.data().await {
+            received.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(received, b"abc");
+        assert_eq!(body.trailers().await.unwrap(), Some(trailers.clone()));
+        assert_eq!(seen.lock().unwrap().len(), 2);
+        assert_eq!(rx.await.unwrap(), Ok(Some(trailers)));
+    }
+}
@@ -1,21 +1,109 @@
 //! HTTP client for forwarding requests to real servers
 
-use crate::cassette::{HttpRequest, HttpResponse};
+use crate::cassette::{Headers, HttpRequest, HttpResponse, HttpVersion, NetworkError};
 use crate::error::{MatgtoError, Result};
-
-use hyper::{Body, Client, Request, Uri};
-use hyper_rustls::HttpsConnectorBuilder;
-use std::collections::HashMap;
+use crate::grpc::is_grpc;
+use crate::proxy::upstream::{UpstreamConnector, UpstreamProxy};
+use crate::tls::upstream::{host_matches, UpstreamTls};
+
+use hyper::header::HeaderValue;
+use hyper::service::Service;
+use hyper::{Body, Client, HeaderMap, Request, Response, Uri, Version};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use std::error::Error as StdError;
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 /// HTTP client for forwarding proxied requests
 pub struct HttpForwarder {
-    client: Client<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>>,
+    client: Client<TlsConnector>,
+    /// Prior-knowledge HTTP/2 client for gRPC to plain `http://` upstreams
+    h2c: Client<TlsConnector>,
+    /// HTTP/1.1-only client for protocol upgrades (WebSocket)
+    http1: Client<TlsConnector>,
+    timeout: Option<Duration>,
+    upstream: Option<UpstreamProxy>,
+    tls: Option<UpstreamTls>,
+}
+
+/// HTTPS connector picking the TLS configuration for each upstream host
+///
+/// rustls cannot choose a client certificate by server name, so every
+/// client certificate gets its own connector.
+#[derive(Clone)]
+struct TlsConnector {
+    default: HttpsConnector<UpstreamConnector>,
+    per_host: Arc<Vec<(Vec<String>, HttpsConnector<UpstreamConnector>)>>,
+}
+
+impl TlsConnector {
+    fn new(upstream: Option<UpstreamProxy>, tls: Option<&UpstreamTls>, http2: bool) -> Self {
+        let connector = |config: Option<&rustls::ClientConfig>| {
+            let builder = match config {
+                Some(config) => HttpsConnectorBuilder::new().with_tls_config(config.clone()),
+                None => HttpsConnectorBuilder::new().with_native_roots(),
+            };
+            // ALPN offers h2 (unless disabled) and http/1.1; plain HTTP
+            // upstreams stay on HTTP/1.1
+            let builder = builder.https_or_http().enable_http1();
+            if http2 {
+                builder
+                    .enable_http2()
+                    .wrap_connector(UpstreamConnector::new(upstream.clone()))
+            } else {
+                builder.wrap_connector(UpstreamConnector::new(upstream.clone()))
+            }
+        };
+
+        match tls {
+            Some(tls) => Self {
+                default: connector(Some(&tls.default_config())),
+                per_host: Arc::new(
+                    tls.host_configs()
+                        .iter()
+                        .map(|(hosts, config)| (hosts.clone(), connector(Some(config))))
+                        .collect(),
+                ),
+            },
+            None => Self {
+                default: connector(None),
+                per_host: Arc::new(Vec::new()),
+            },
+        }
+    }
+}
+
+impl Service<Uri> for TlsConnector {
+    type Response = <HttpsConnector<UpstreamConnector> as Service<Uri>>::Response;
+    type Error = <HttpsConnector<UpstreamConnector> as Service<Uri>>::Error;
+    type Future = <HttpsConnector<UpstreamConnector> as Service<Uri>>::Future;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let host = dst.host().unwrap_or_default();
+        let mut connector = self
+            .per_host
+            .iter()
+            .find(|(hosts, _)| hosts.iter().any(|pattern| host_matches(pattern, host)))
+            .map(|(_, connector)| connector.clone())
+            .unwrap_or_else(|| self.default.clone());
+        connector.call(dst)
+    }
 }
 
 impl std::fmt::Debug for HttpForwarder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("HttpForwarder")
             .field("client", &"<HttpsClient>")
+            .field("timeout", &self.timeout)
+            .field("upstream", &self.upstream.as_ref().map(|p| p.to_string()))
+            .field("tls", &self.tls.as_ref().map(|t| t.config()))
             .finish()
     }
 }
@@ -24,6 +112,11 @@ impl Clone for HttpForwarder {
     fn clone(&self) -> Self {
         Self {
             client: self.client.clone(),
+            h2c: self.h2c.clone(),
+            http1: self.http1.clone(),
+            timeout: self.timeout,
+            upstream: self.upstream.clone(),
+            tls: self.tls.clone(),
         }
     }
 }
@@ -31,16 +124,70 @@ impl Clone for HttpForwarder {
 impl HttpForwarder {
     /// Create a new HTTP forwarder
     pub fn new() -> Self {
-        // Build HTTPS connector with rustls
-        let https = HttpsConnectorBuilder::new()
-            .with_native_roots()
-            .https_or_http()
-            .enable_http1()
-            .build();
+        let (client, h2c, http1) = Self::build_clients(None, None);
+        Self {
+            client,
+            h2c,
+            http1,
+            timeout: None,
+            upstream: None,
+            tls: None,
+        }
+    }
+
+    /// Build the HTTPS, h2c and HTTP/1.1-only clients, connecting directly or
+    /// through `upstream`
+    fn build_clients(
+        upstream: Option<UpstreamProxy>,
+        tls: Option<&UpstreamTls>,
+    ) -> (
+        Client<TlsConnector>,
+        Client<TlsConnector>,
+        Client<TlsConnector>,
+    ) {
+        let connector = TlsConnector::new(upstream.clone(), tls, true);
+        (
+            Client::builder().build(connector.clone()),
+            Client::builder().http2_only(true).build(connector),
+            Client::builder().build(TlsConnector::new(upstream, tls, false)),
+        )
+    }
+
+    /// Reach upstream servers through another proxy
+    ///
+    /// Hosts on the proxy's NO_PROXY list are still reached directly.
+    pub fn with_upstream_proxy(mut self, proxy: UpstreamProxy) -> Self {
+        (self.client, self.h2c, self.http1) =
+            Self::build_clients(Some(proxy.clone()), self.tls.as_ref());
+        self.upstream = Some(proxy);
+        self
+    }
+
+    /// Upstream proxy requests are chained through (if any)
+    pub fn upstream_proxy(&self) -> Option<&UpstreamProxy> {
+        self.upstream.as_ref()
+    }
 
-        let client = Client::builder().build(https);
+    /// Use custom trust roots and client certificates for upstream TLS
+    pub fn with_tls(mut self, tls: UpstreamTls) -> Self {
+        (self.client, self.h2c, self.http1) =
+            Self::build_clients(self.upstream.clone(), Some(&tls));
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Upstream TLS settings (if any besides the defaults)
+    pub fn tls(&self) -> Option<&UpstreamTls> {
+        self.tls.as_ref()
+    }
 
-        Self { client }
+    /// Fail upstream exchanges that take longer than `timeout`
+    ///
+    /// The failure is reported as `NetworkError::Timeout`. Without a timeout the
+    /// forwarder waits as long as the upstream server keeps the connection open.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 
     /// Forward an HTTP request to the real server
@@ -49,10 +196,57 @@ impl HttpForwarder {
     /// * `http_req` - The HTTP request to forward
     ///
     /// # Returns
-    /// The HTTP response from the server
+    /// The HTTP response from the server, or `MatgtoError::Network` when the
+    /// upstream exchange failed at the transport level
     pub async fn forward(&self, http_req: &HttpRequest) -> Result<HttpResponse> {
-        tracing::debug!("Forwarding request: {} {}", http_req.method, http_req.url);
+        let request = Self::build_request(http_req)?;
+
+        self.within_timeout(async {
+            let started = Instant::now();
+            let response = self.send(request).await?;
+            let (parts, body) = response.into_parts();
+
+            // Read body
+            let body_bytes = hyper::body::to_bytes(body)
+                .await
+                .map_err(|e| MatgtoError::Network(classify_error(&e, started.elapsed())))?;
+
+            let body = if !body_bytes.is_empty() {
+                Some(body_bytes.to_vec())
+            } else {
+                None
+            };
+
+            Ok(HttpResponse {
+                status: parts.status.as_u16(),
+                headers: headers_from_hyper(&parts.headers),
+                body,
+            })
+        })
+        .await
+    }
+
+    /// Forward an HTTP request and return the response as soon as its head arrives
+    ///
+    /// The response body streams from the upstream server; the timeout only
+    /// covers waiting for the status line and headers.
+    pub async fn forward_streaming(&self, http_req: &HttpRequest) -> Result<Response<Body>> {
+        let request = Self::build_request(http_req)?;
+        self.within_timeout(self.send(request)).await
+    }
+
+    /// Forward a proxied request as is, streaming both bodies
+    ///
+    /// Nothing is buffered: the request body is sent upstream while the client
+    /// uploads it, and the response body is returned still streaming. Upgrade
+    /// requests (WebSocket) go over HTTP/1.1, so a `101` response can be
+    /// upgraded with `hyper::upgrade::on`.
+    pub async fn forward_request(&self, request: Request<Body>) -> Result<Response<Body>> {
+        self.within_timeout(self.send(request)).await
+    }
 
+    /// Build a hyper request from a recorded request
+    fn build_request(http_req: &HttpRequest) -> Result<Request<Body>> {
         // Parse URI
         let uri = http_req
             .url
@@ -64,9 +258,9 @@ impl HttpForwarder {
         // Build hyper request
         let mut builder = Request::builder().method(http_req.method.as_str()).uri(uri);
 
-        // Add headers
-        for (name, value) in &http_req.headers {
-            builder = builder.header(name, value);
+        // Add headers (every value, raw bytes included)
+        for header in http_req.headers.entries() {
+            builder = builder.header(header.name.as_str(), header.value.as_slice());
         }
 
         // Add body if present
@@ -76,52 +270,144 @@ impl HttpForwarder {
             Body::empty()
         };
 
-        let request = builder
+        builder
             .body(body)
             .map_err(|e| MatgtoError::ProxyStartFailed {
                 reason: format!("Failed to build request: {}", e),
-            })?;
+            })
+    }
 
-        // Send request
-        let response =
-            self.client
-                .request(request)
-                .await
-                .map_err(|e| MatgtoError::ProxyStartFailed {
-                    reason: format!("Request failed: {}", e),
-                })?;
-
-        // Extract status
-        let status = response.status().as_u16();
-
-        // Extract headers
-        let mut headers = HashMap::new();
-        for (name, value) in response.headers().iter() {
-            if let Ok(value_str) = value.to_str() {
-                headers.insert(name.to_string(), value_str.to_string());
+    /// Send a request and wait for the response head
+    async fn send(&self, mut request: Request<Body>) -> Result<Response<Body>> {
+        // Plain HTTP requests relayed by an HTTP proxy authenticate per request
+        if let Some(authorization) = self
+            .upstream
+            .as_ref()
+            .and_then(|proxy| proxy.authorization_for(request.uri()))
+        {
+            if let Ok(value) = HeaderValue::from_str(&authorization) {
+                request
+                    .headers_mut()
+                    .insert(hyper::header::PROXY_AUTHORIZATION, value);
             }
         }
 
-        // Read body
-        let body_bytes = hyper::body::to_bytes(response.into_body())
-            .await
-            .map_err(|e| MatgtoError::ProxyStartFailed {
-                reason: format!("Failed to read response body: {}", e),
-            })?;
-
-        let body = if !body_bytes.is_empty() {
-            Some(body_bytes.to_vec())
+        // Clients may have spoken HTTP/2 to the proxy; the upstream version is
+        // whatever ALPN negotiates on the upstream connection. gRPC needs
+        // HTTP/2, so plain-HTTP gRPC upstreams get prior-knowledge HTTP/2 (h2c)
+        let h2c = request.uri().scheme_str() == Some("http") && is_grpc(request.headers());
+        *request.version_mut() = if h2c {
+            Version::HTTP_2
+        } else {
+            Version::HTTP_11
+        };
+        let client = if h2c {
+            &self.h2c
+        } else if request.headers().contains_key(hyper::header::UPGRADE) {
+            &self.http1
         } else {
-            None
+            &self.client
         };
 
-        tracing::debug!("Response received: status={}", status);
+        tracing::debug!("Forwarding request: {} {}", request.method(), request.uri());
+        let started = Instant::now();
 
-        Ok(HttpResponse {
-            status,
-            headers,
-            body,
-        })
+        let response = client
+            .request(request)
+            .await
+            .map_err(|e| MatgtoError::Network(classify_error(&e, started.elapsed())))?;
+
+        tracing::debug!("Response received: status={}", response.status());
+        Ok(response)
+    }
+
+    /// Run an upstream exchange, failing with `NetworkError::Timeout` past the timeout
+    async fn within_timeout<T>(&self, exchange: impl Future<Output = Result<T>>) -> Result<T> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, exchange)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(MatgtoError::Network(NetworkError::timeout(
+                        format!("No response within {}ms", timeout.as_millis()),
+                        timeout.as_millis() as u64,
+                    )))
+                }),
+            None => exchange.await,
+        }
+    }
+}
+
+/// Cassette representation of a hyper HTTP version (None for HTTP/0.9 and HTTP/3)
+pub fn http_version_from_hyper(version: Version) -> Option<HttpVersion> {
+    match version {
+        Version::HTTP_10 => Some(HttpVersion::Http10),
+        Version::HTTP_11 => Some(HttpVersion::Http11),
+        Version::HTTP_2 => Some(HttpVersion::Http2),
+        _ => None,
+    }
+}
+
+/// Copy hyper headers, keeping repeated values and non-UTF-8 bytes
+///
+/// hyper groups values by name, so every value of a name keeps its order but
+/// lines of different names come out grouped.
+pub fn headers_from_hyper(map: &HeaderMap) -> Headers {
+    let mut headers = Headers::new();
+    for (name, value) in map.iter() {
+        headers.append_raw(name.as_str(), value.as_bytes().to_vec());
+    }
+    headers
+}
+
+/// Classify a transport failure from the upstream client
+///
+/// Walks the error's source chain: the underlying `io::Error` kind decides between
+/// refused, reset and timeout; DNS failures are only recognisable by hyper's message,
+/// and rustls reports handshake failures as `InvalidData`.
+pub fn classify_error(err: &hyper::Error, elapsed: Duration) -> NetworkError {
+    let mut message = err.to_string();
+    let mut io_kind = None;
+    let mut timed_out = err.is_timeout();
+    let mut closed = err.is_incomplete_message() || err.is_closed();
+
+    // hyper may wrap the actual failure (e.g. a canceled request caused by a closed connection)
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        if let Some(io_err) = cause.downcast_ref::<io::Error>() {
+            io_kind.get_or_insert(io_err.kind());
+        }
+        if let Some(hyper_err) = cause.downcast_ref::<hyper::Error>() {
+            timed_out |= hyper_err.is_timeout();
+            closed |= hyper_err.is_incomplete_message() || hyper_err.is_closed();
+        }
+        source = cause.source();
+    }
+
+    let lowercase = message.to_lowercase();
+    if lowercase.contains("dns error") || lowercase.contains("failed to lookup address") {
+        return NetworkError::dns_failed(message);
+    }
+
+    if timed_out {
+        return NetworkError::timeout(message, elapsed.as_millis() as u64);
+    }
+
+    match io_kind {
+        Some(io::ErrorKind::ConnectionRefused) => NetworkError::connection_refused(message),
+        Some(io::ErrorKind::TimedOut) => NetworkError::timeout(message, elapsed.as_millis() as u64),
+        Some(io::ErrorKind::InvalidData) => NetworkError::tls_error(message),
+        Some(
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof,
+        ) => NetworkError::connection_reset(message),
+        _ if lowercase.contains("tls") || lowercase.contains("certificate") => {
+            NetworkError::tls_error(message)
+        }
+        _ if closed => NetworkError::connection_reset(message),
+        _ => NetworkError::other(message),
     }
 }
 
@@ -142,6 +428,94 @@ mod tests {
         drop(forwarder);
     }
 
+    fn get(url: String) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            url,
+            headers: Headers::new(),
+            body: None,
+        }
+    }
+
+    fn network_error(result: Result<HttpResponse>) -> NetworkError {
+        match result {
+            Err(MatgtoError::Network(error)) => error,
+            other => panic!("Expected a network error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_classifies_connection_refused() {
+        // Bind then drop to get a port nobody listens on
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let forwarder = HttpForwarder::new();
+        let error = network_error(
+            forwarder
+                .forward(&get(format!("http://127.0.0.1:{}/", port)))
+                .await,
+        );
+        assert!(matches!(error, NetworkError::ConnectionRefused { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_forward_classifies_connection_reset() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            // Accept and close without answering
+            while let Ok((socket, _)) = listener.accept().await {
+                drop(socket);
+            }
+        });
+
+        let forwarder = HttpForwarder::new();
+        let error = network_error(
+            forwarder
+                .forward(&get(format!("http://127.0.0.1:{}/", port)))
+                .await,
+        );
+        assert!(matches!(error, NetworkError::ConnectionReset { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_forward_classifies_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            // Accept and never answer
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let forwarder = HttpForwarder::new().with_timeout(Duration::from_millis(100));
+        let error = network_error(
+            forwarder
+                .forward(&get(format!("http://127.0.0.1:{}/", port)))
+                .await,
+        );
+        assert_eq!(
+            error,
+            NetworkError::timeout("No response within 100ms", 100)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward_classifies_dns_failure() {
+        let forwarder = HttpForwarder::new();
+        let error = network_error(
+            forwarder
+                .forward(&get("http://magneto-serge.invalid/".to_string()))
+                .await,
+        );
+        assert!(matches!(error, NetworkError::DnsResolutionFailed { .. }));
+    }
+
     #[tokio::test]
     #[ignore] // Ignore by default as it requires network access
     async fn test_forward_real_request() {
@@ -150,7 +524,7 @@ mod tests {
         let req = HttpRequest {
             method: "GET".to_string(),
             url: "https://httpbin.org/get".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
@@ -166,7 +540,7 @@ mod tests {
     async fn test_forward_post_request() {
         let forwarder = HttpForwarder::new();
 
-        let mut headers = HashMap::new();
+        let mut headers = Headers::new();
         headers.insert("Content-Type".to_string(), "application/json".to_string());
 
         let req = HttpRequest {
@@ -1,12 +1,11 @@
 //! HTTP/HTTPS proxy handler using Hudsucker
 
-use crate::cassette::{HttpRequest, HttpResponse};
+use crate::cassette::{Headers, HttpRequest, HttpResponse};
 use crate::error::{MatgtoError, Result};
 use crate::player::Player;
 use crate::proxy::ProxyMode;
 use crate::recorder::Recorder;
 
-use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -49,7 +48,7 @@ impl HttpHandler {
         &'a mut self,
         method: String,
         url: String,
-        headers: HashMap<String, String>,
+        headers: Headers,
         body: Option<Vec<u8>>,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<HttpResponse>> + Send + 'a>>
     {
@@ -63,7 +62,7 @@ impl HttpHandler {
                     // For now, return a mock response
                     let response = HttpResponse {
                         status: 200,
-                        headers: HashMap::new(),
+                        headers: Headers::new(),
                         body: Some(b"{}".to_vec()),
                     };
 
@@ -97,7 +96,7 @@ impl HttpHandler {
                         // For now, return a mock response
                         Ok(HttpResponse {
                             status: 200,
-                            headers: HashMap::new(),
+                            headers: Headers::new(),
                             body: Some(b"{}".to_vec()),
                         })
                     } else {
@@ -146,7 +145,7 @@ impl HttpHandler {
                     // Record new interaction
                     let response = HttpResponse {
                         status: 200,
-                        headers: HashMap::new(),
+                        headers: Headers::new(),
                         body: Some(b"{}".to_vec()),
                     };
 
@@ -201,7 +200,7 @@ impl HttpHandler {
                     // TODO: Forward to actual server
                     Ok(HttpResponse {
                         status: 200,
-                        headers: HashMap::new(),
+                        headers: Headers::new(),
                         body: Some(b"{}".to_vec()),
                     })
                 }
@@ -225,7 +224,7 @@ mod tests {
             .handle_request(
                 "GET".to_string(),
                 "https://api.example.com/users".to_string(),
-                HashMap::new(),
+                Headers::new(),
                 None,
             )
             .await
@@ -247,7 +246,7 @@ mod tests {
             .handle_request(
                 "GET".to_string(),
                 "https://api.example.com/users".to_string(),
-                HashMap::new(),
+                Headers::new(),
                 None,
             )
             .await
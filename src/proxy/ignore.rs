@@ -0,0 +1,326 @@
+//! Hosts kept out of cassettes
+//!
+//! Requests to ignored hosts (the application's own test server, telemetry,
+//! auth providers...) are forwarded live in every mode: they are never
+//! recorded and never answered from a cassette, so replay does not turn them
+//! into "No matching interaction" errors.
+//!
+//! Rules are matched against the request URL:
+//! - exact host (`auth.example.com`, `127.0.0.1`), optionally with a port
+//!   (`localhost:3000`)
+//! - `*.domain` for every subdomain of `domain`
+//! - CIDR ranges for IP addresses (`10.0.0.0/8`, `fd00::/8`)
+//! - URL prefixes (`https://api.example.com/telemetry`)
+//!
+//! `localhost = true` ignores `localhost`, `127.0.0.0/8` and `::1`, like
+//! VCR's `ignore_localhost`.
+//!
+//! ```toml
+//! [ignore]
+//! localhost = true
+//! hosts = ["*.sentry.io", "auth.example.com", "10.0.0.0/8"]
+//! ```
+
+use crate::error::{MatgtoError, Result};
+use crate::tls::upstream::host_matches;
+
+use hyper::header::HOST;
+use hyper::{Body, Request, Uri};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Ignore rules, as written in magneto.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IgnoreConfig {
+    /// Ignore `localhost`, `127.0.0.0/8` and `::1`
+    pub localhost: bool,
+
+    /// Hosts, `*.domain` wildcards, CIDR ranges and URL prefixes to ignore
+    pub hosts: Vec<String>,
+}
+
+impl IgnoreConfig {
+    /// Build the configured rules (None when nothing is ignored)
+    pub fn resolve(&self) -> Result<Option<IgnoreRules>> {
+        if !self.localhost && self.hosts.is_empty() {
+            return Ok(None);
+        }
+
+        IgnoreRules::new()
+            .with_localhost(self.localhost)
+            .with_hosts(&self.hosts)
+            .map(Some)
+    }
+}
+
+/// Hosts and URLs forwarded live instead of being recorded or replayed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreRules {
+    localhost: bool,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    /// Exact host or `*.domain`, on any port unless one is given
+    Host { pattern: String, port: Option<u16> },
+    /// IP addresses within `network/prefix_len`
+    Cidr { network: IpAddr, prefix_len: u8 },
+    /// URLs starting with the prefix
+    UrlPrefix(String),
+}
+
+impl Rule {
+    fn parse(rule: &str) -> Result<Self> {
+        let rule = rule.trim();
+        let invalid = |reason: &str| {
+            MatgtoError::Config(format!("Invalid ignore rule '{}': {}", rule, reason))
+        };
+
+        if rule.is_empty() {
+            return Err(invalid("empty rule"));
+        }
+
+        if rule.starts_with("http://") || rule.starts_with("https://") {
+            url::Url::parse(rule).map_err(|e| invalid(&e.to_string()))?;
+            return Ok(Rule::UrlPrefix(rule.to_string()));
+        }
+
+        if let Some((network, prefix_len)) = rule.split_once('/') {
+            let network: IpAddr = network
+                .parse()
+                .map_err(|_| invalid("expected an IP network such as 10.0.0.0/8"))?;
+            let max = if network.is_ipv4() { 32 } else { 128 };
+            let prefix_len = prefix_len
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max)
+                .ok_or_else(|| invalid("invalid prefix length"))?;
+            return Ok(Rule::Cidr {
+                network,
+                prefix_len,
+            });
+        }
+
+        // `host:port`, except for bare IPv6 addresses (`::1`, `[::1]:8080`)
+        let (host, port) = match rule.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+                let port = port.parse().map_err(|_| invalid("invalid port"))?;
+                (host, Some(port))
+            }
+            _ => (rule, None),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        Ok(Rule::Host {
+            pattern: host.to_ascii_lowercase(),
+            port,
+        })
+    }
+
+    fn matches(&self, url: &str, host: &str, ip: Option<IpAddr>, port: Option<u16>) -> bool {
+        match self {
+            Rule::Host {
+                pattern,
+                port: rule_port,
+            } => host_matches(pattern, host) && (rule_port.is_none() || *rule_port == port),
+            Rule::Cidr {
+                network,
+                prefix_len,
+            } => ip.is_some_and(|ip| in_network(ip, *network, *prefix_len)),
+            Rule::UrlPrefix(prefix) => url.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// Whether `ip` is within `network/prefix_len` (IPv4-mapped IPv6 addresses included)
+fn in_network(ip: IpAddr, network: IpAddr, prefix_len: u8) -> bool {
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        ip => ip,
+    };
+
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+impl IgnoreRules {
+    /// Create rules ignoring nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignore `localhost`, `127.0.0.0/8` and `::1`
+    pub fn with_localhost(mut self, ignore: bool) -> Self {
+        self.localhost = ignore;
+        self
+    }
+
+    /// Ignore a host, `*.domain`, CIDR range or URL prefix
+    pub fn with_host(mut self, rule: &str) -> Result<Self> {
+        self.rules.push(Rule::parse(rule)?);
+        Ok(self)
+    }
+
+    /// Ignore several hosts, see `with_host`
+    pub fn with_hosts<I, S>(self, rules: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        rules
+            .into_iter()
+            .try_fold(self, |ignore, rule| ignore.with_host(rule.as_ref()))
+    }
+
+    /// Whether localhost is ignored
+    pub fn ignores_localhost(&self) -> bool {
+        self.localhost
+    }
+
+    /// Whether no rule is set
+    pub fn is_empty(&self) -> bool {
+        !self.localhost && self.rules.is_empty()
+    }
+
+    /// Whether a proxied request targets an ignored host
+    ///
+    /// The host comes from the request URI, or from the `Host` header for
+    /// origin-form requests.
+    pub fn matches_request(&self, req: &Request<Body>) -> bool {
+        if req.uri().host().is_some() {
+            return self.matches(req.uri());
+        }
+
+        req.headers()
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .and_then(|host| format!("http://{}{}", host, req.uri()).parse().ok())
+            .is_some_and(|uri| self.matches(&uri))
+    }
+
+    /// Whether a URL targets an ignored host
+    pub fn matches_url(&self, url: &str) -> bool {
+        url.parse().is_ok_and(|uri| self.matches(&uri))
+    }
+
+    /// Whether a URI targets an ignored host
+    pub fn matches(&self, uri: &Uri) -> bool {
+        let Some(host) = uri.host() else {
+            return false;
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let ip: Option<IpAddr> = host.parse().ok();
+        let port = uri.port_u16().or(match uri.scheme_str() {
+            Some("https") | Some("wss") => Some(443),
+            Some("http") | Some("ws") => Some(80),
+            _ => None,
+        });
+
+        if self.localhost {
+            let loopback = match ip {
+                Some(ip) => in_network(ip, IpAddr::from([127, 0, 0, 0]), 8) || ip.is_loopback(),
+                None => host.eq_ignore_ascii_case("localhost"),
+            };
+            if loopback {
+                return true;
+            }
+        }
+
+        let url = uri.to_string();
+        self.rules
+            .iter()
+            .any(|rule| rule.matches(&url, host, ip, port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(url: &str) -> Uri {
+        url.parse().unwrap()
+    }
+
+    #[test]
+    fn test_host_rules() {
+        let ignore = IgnoreRules::new()
+            .with_hosts(["Auth.Example.com", "*.sentry.io", "localhost:3000"])
+            .unwrap();
+
+        assert!(ignore.matches(&uri("https://auth.example.com/token")));
+        assert!(ignore.matches(&uri("https://o1.ingest.sentry.io/api")));
+        assert!(!ignore.matches(&uri("https://sentry.io/")));
+        assert!(!ignore.matches(&uri("https://api.example.com/users")));
+
+        assert!(ignore.matches(&uri("http://localhost:3000/health")));
+        assert!(!ignore.matches(&uri("http://localhost:4000/health")));
+    }
+
+    #[test]
+    fn test_cidr_and_url_prefix_rules() {
+        let ignore = IgnoreRules::new()
+            .with_hosts([
+                "10.0.0.0/8",
+                "fd00::/8",
+                "https://api.example.com/telemetry",
+            ])
+            .unwrap();
+
+        assert!(ignore.matches(&uri("http://10.1.2.3:8080/")));
+        assert!(!ignore.matches(&uri("http://11.1.2.3/")));
+        assert!(ignore.matches(&uri("http://[fd12::1]/")));
+        assert!(ignore.matches_url("https://api.example.com/telemetry/events"));
+        assert!(!ignore.matches_url("https://api.example.com/users"));
+    }
+
+    #[test]
+    fn test_localhost() {
+        let ignore = IgnoreRules::new().with_localhost(true);
+
+        assert!(ignore.matches(&uri("http://localhost:8080/")));
+        assert!(ignore.matches(&uri("http://127.0.0.2/")));
+        assert!(ignore.matches(&uri("http://[::1]:3000/")));
+        assert!(!ignore.matches(&uri("https://example.com/")));
+        assert!(!IgnoreRules::new().matches(&uri("http://localhost/")));
+
+        let req = Request::get("/users")
+            .header(HOST, "localhost:3000")
+            .body(Body::empty())
+            .unwrap();
+        assert!(ignore.matches_request(&req));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        for rule in ["", "10.0.0.0/33", "not-an-ip/8", "host:port"] {
+            assert!(IgnoreRules::new().with_host(rule).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn test_config_resolve() {
+        assert_eq!(IgnoreConfig::default().resolve().unwrap(), None);
+
+        let config: IgnoreConfig = toml::from_str(
+            r#"
+            localhost = true
+            hosts = ["*.example.com"]
+            "#,
+        )
+        .unwrap();
+        let ignore = config.resolve().unwrap().unwrap();
+        assert!(ignore.ignores_localhost());
+        assert!(ignore.matches(&uri("https://cdn.example.com/")));
+    }
+}
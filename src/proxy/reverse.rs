@@ -0,0 +1,390 @@
+//! Reverse-proxy (base URL) routing
+//!
+//! Instead of configuring clients with `HTTP(S)_PROXY` and the magneto CA, the
+//! listener can act as a reverse proxy: the application points its base URL at
+//! `http://localhost:PORT/` and each request is routed to a real upstream:
+//!
+//! - by path prefix: `/github/users` with prefix `/github` and upstream
+//!   `https://api.github.com` goes to `https://api.github.com/users`
+//! - by `Host` header (exact or `*.domain`), for clients that can set it
+//!
+//! Requests are rewritten to the upstream URL (and `Host`) before they reach
+//! `MatgtoHttpHandler`, so cassettes hold the real URLs and stay
+//! interchangeable with cassettes recorded through the MITM proxy. URLs take
+//! the MITM proxy's form: `https` ones always carry the port
+//! (`https://api.github.com:443/users`), `http` ones omit the default port.
+//!
+//! ```toml
+//! [[reverse_proxy.routes]]
+//! prefix = "/github"
+//! upstream = "https://api.github.com"
+//!
+//! [[reverse_proxy.routes]]
+//! host = "payments.localhost"
+//! upstream = "https://api.stripe.com/v1"
+//! ```
+
+use crate::error::{MatgtoError, Result};
+use crate::tls::upstream::host_matches;
+
+use hyper::header::{HeaderValue, HOST};
+use hyper::{Body, Request, Uri};
+use serde::{Deserialize, Serialize};
+
+/// A route, as written in magneto.toml
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReverseRouteConfig {
+    /// Upstream base URL (`http://` or `https://`, optionally with a base path)
+    pub upstream: String,
+
+    /// Path prefix routed to the upstream, stripped before forwarding
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+
+    /// Only route requests whose `Host` header matches (exact or `*.domain`)
+    #[serde(default)]
+    pub host: Option<String>,
+}
+
+fn default_prefix() -> String {
+    "/".to_string()
+}
+
+/// Reverse-proxy settings, as written in magneto.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReverseProxyConfig {
+    /// Routes, the most specific one wins
+    pub routes: Vec<ReverseRouteConfig>,
+}
+
+impl ReverseProxyConfig {
+    /// Build the configured routes (None when no route is configured)
+    pub fn resolve(&self) -> Result<Option<ReverseProxy>> {
+        if self.routes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut reverse = ReverseProxy::default();
+        for route in &self.routes {
+            reverse.routes.push(Route::new(
+                route.host.clone(),
+                &route.prefix,
+                &route.upstream,
+            )?);
+        }
+        Ok(Some(reverse))
+    }
+}
+
+/// Routes from the reverse-proxy listener to upstream base URLs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReverseProxy {
+    routes: Vec<Route>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Route {
+    host: Option<String>,
+    /// Path prefix without its trailing slash ("" for the root)
+    prefix: String,
+    scheme: String,
+    /// Upstream host and port, in the form the MITM proxy records: always
+    /// with the port for `https` (the CONNECT authority), without the
+    /// default port for `http` (the client's absolute-form URL)
+    authority: String,
+    /// Upstream base path without its trailing slash
+    base_path: String,
+}
+
+impl Route {
+    fn new(host: Option<String>, prefix: &str, upstream: &str) -> Result<Self> {
+        let url = url::Url::parse(upstream)?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(MatgtoError::Config(format!(
+                "Unsupported reverse-proxy upstream '{}' (expected http or https)",
+                upstream
+            )));
+        }
+        let upstream_host = url.host_str().ok_or_else(|| {
+            MatgtoError::Config(format!("Reverse-proxy upstream '{}' has no host", upstream))
+        })?;
+
+        let port = match url.scheme() {
+            "https" => url.port_or_known_default(),
+            _ => url.port(),
+        };
+        let authority = match port {
+            Some(port) => format!("{}:{}", upstream_host, port),
+            None => upstream_host.to_string(),
+        };
+        let prefix = format!("/{}", prefix.trim_matches('/'));
+
+        Ok(Self {
+            host: host.map(|host| host.to_ascii_lowercase()),
+            prefix: prefix.trim_end_matches('/').to_string(),
+            scheme: url.scheme().to_string(),
+            authority,
+            base_path: url.path().trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Path left once the prefix is removed, if the prefix matches at a segment boundary
+    fn strip<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let rest = path.strip_prefix(self.prefix.as_str())?;
+        (rest.is_empty() || rest.starts_with('/')).then_some(rest)
+    }
+}
+
+impl ReverseProxy {
+    /// Send every request to `upstream` (e.g. `https://api.example.com`)
+    pub fn new(upstream: &str) -> Result<Self> {
+        Self::default().with_route("/", upstream)
+    }
+
+    /// Send requests under `prefix` to `upstream`, stripping the prefix
+    pub fn with_route(mut self, prefix: &str, upstream: &str) -> Result<Self> {
+        self.routes.push(Route::new(None, prefix, upstream)?);
+        Ok(self)
+    }
+
+    /// Send requests whose `Host` header matches `host` to `upstream`
+    ///
+    /// `host` is an exact name or a `*.domain` wildcard; the port is ignored.
+    pub fn with_host_route(mut self, host: &str, upstream: &str) -> Result<Self> {
+        self.routes
+            .push(Route::new(Some(host.to_string()), "/", upstream)?);
+        Ok(self)
+    }
+
+    /// Upstream URL for a request received by the listener
+    ///
+    /// Routes bound to a host win over the others, then the longest prefix.
+    pub fn upstream_url(&self, host: Option<&str>, path_and_query: &str) -> Option<String> {
+        let host = host.map(strip_port);
+        let (path, query) = match path_and_query.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path_and_query, None),
+        };
+
+        let (route, rest) = self
+            .routes
+            .iter()
+            .filter(|route| match (&route.host, host) {
+                (Some(pattern), Some(host)) => host_matches(pattern, host),
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .filter_map(|route| route.strip(path).map(|rest| (route, rest)))
+            .max_by_key(|(route, _)| (route.host.is_some(), route.prefix.len()))?;
+
+        let mut url = format!("{}://{}{}", route.scheme, route.authority, route.base_path);
+        if rest.is_empty() && route.base_path.is_empty() {
+            url.push('/');
+        } else {
+            url.push_str(rest);
+        }
+        if let Some(query) = query {
+            url.push('?');
+            url.push_str(query);
+        }
+        Some(url)
+    }
+
+    /// Rewrite a request received by the listener to target its upstream
+    ///
+    /// The URI becomes the absolute upstream URL and `Host` names the
+    /// upstream (without the default port, as clients send it), as in
+    /// requests seen by the MITM proxy. Returns false, leaving the request
+    /// unchanged, when no route matches.
+    pub fn route(&self, req: &mut Request<Body>) -> bool {
+        let host = req
+            .headers()
+            .get(HOST)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| req.uri().host());
+        let path_and_query = req
+            .uri()
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/");
+
+        let uri = match self
+            .upstream_url(host, path_and_query)
+            .and_then(|url| url.parse::<Uri>().ok())
+        {
+            Some(uri) => uri,
+            None => return false,
+        };
+
+        let host = match (uri.host(), uri.port_u16()) {
+            (Some(host), Some(443)) if uri.scheme_str() == Some("https") => Some(host.to_string()),
+            _ => uri.authority().map(|authority| authority.to_string()),
+        };
+        if let Some(host) = host.and_then(|host| HeaderValue::from_str(&host).ok()) {
+            req.headers_mut().insert(HOST, host);
+        }
+        *req.uri_mut() = uri;
+        true
+    }
+}
+
+impl std::fmt::Display for ReverseProxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let routes: Vec<String> = self
+            .routes
+            .iter()
+            .map(|route| {
+                format!(
+                    "{}{}/ -> {}://{}{}/",
+                    route.host.as_deref().unwrap_or(""),
+                    route.prefix,
+                    route.scheme,
+                    route.authority,
+                    route.base_path
+                )
+            })
+            .collect();
+        write!(f, "{}", routes.join(", "))
+    }
+}
+
+/// Host name of a `Host` header value, without its port
+fn strip_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        // IPv6 literal: [::1]:8080
+        return host.split_once(']').map_or(host, |(ip, _)| &ip[1..]);
+    }
+    host.rsplit_once(':').map_or(host, |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_base_url() {
+        let reverse = ReverseProxy::new("https://api.example.com").unwrap();
+
+        assert_eq!(
+            reverse.upstream_url(Some("localhost:8888"), "/users/1?full=true"),
+            Some("https://api.example.com:443/users/1?full=true".to_string())
+        );
+        assert_eq!(
+            reverse.upstream_url(None, "/"),
+            Some("https://api.example.com:443/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_prefix_routes_and_base_path() {
+        let reverse = ReverseProxy::new("http://127.0.0.1:9000")
+            .unwrap()
+            .with_route("/github/", "https://api.github.com")
+            .unwrap()
+            .with_route("/stripe", "https://api.stripe.com/v1/")
+            .unwrap();
+
+        assert_eq!(
+            reverse.upstream_url(None, "/github/users/octocat"),
+            Some("https://api.github.com:443/users/octocat".to_string())
+        );
+        assert_eq!(
+            reverse.upstream_url(None, "/stripe"),
+            Some("https://api.stripe.com:443/v1".to_string())
+        );
+        assert_eq!(
+            reverse.upstream_url(None, "/stripe/charges?limit=3"),
+            Some("https://api.stripe.com:443/v1/charges?limit=3".to_string())
+        );
+        // Prefixes only match whole path segments
+        assert_eq!(
+            reverse.upstream_url(None, "/githubusercontent"),
+            Some("http://127.0.0.1:9000/githubusercontent".to_string())
+        );
+    }
+
+    #[test]
+    fn test_host_routes() {
+        let reverse = ReverseProxy::default()
+            .with_route("/api", "https://api.example.com")
+            .unwrap()
+            .with_host_route("*.payments.localhost", "https://api.stripe.com")
+            .unwrap();
+
+        assert_eq!(
+            reverse.upstream_url(Some("eu.payments.localhost:8888"), "/api/charges"),
+            Some("https://api.stripe.com:443/api/charges".to_string())
+        );
+        assert_eq!(
+            reverse.upstream_url(Some("localhost:8888"), "/api/charges"),
+            Some("https://api.example.com:443/charges".to_string())
+        );
+        assert_eq!(reverse.upstream_url(Some("localhost"), "/other"), None);
+    }
+
+    #[test]
+    fn test_route_rewrites_uri_and_host() {
+        let reverse = ReverseProxy::new("https://api.example.com:8443/v2").unwrap();
+        let mut req = Request::builder()
+            .uri("/users?page=2")
+            .header(HOST, "localhost:8888")
+            .body(Body::empty())
+            .unwrap();
+
+        assert!(reverse.route(&mut req));
+
+        assert_eq!(
+            req.uri().to_string(),
+            "https://api.example.com:8443/v2/users?page=2"
+        );
+        assert_eq!(req.headers()[HOST], "api.example.com:8443");
+    }
+
+    #[test]
+    fn test_default_ports_match_mitm_urls() {
+        // HTTPS through the MITM proxy: CONNECT authorities carry the port
+        let reverse = ReverseProxy::new("https://api.example.com").unwrap();
+        let mut req = Request::builder().uri("/v1/x").body(Body::empty()).unwrap();
+        assert!(reverse.route(&mut req));
+        assert_eq!(req.uri().to_string(), "https://api.example.com:443/v1/x");
+        assert_eq!(req.headers()[HOST], "api.example.com");
+
+        // Plain HTTP: clients send absolute-form URLs without the default port
+        let reverse = ReverseProxy::new("http://api.example.com:80").unwrap();
+        assert_eq!(
+            reverse.upstream_url(None, "/v1/x"),
+            Some("http://api.example.com/v1/x".to_string())
+        );
+    }
+
+    #[test]
+    fn test_config_resolve() {
+        let config: ReverseProxyConfig = toml::from_str(
+            r#"
+            [[routes]]
+            prefix = "/github"
+            upstream = "https://api.github.com"
+
+            [[routes]]
+            host = "payments.localhost"
+            upstream = "https://api.stripe.com"
+            "#,
+        )
+        .unwrap();
+
+        let reverse = config.resolve().unwrap().unwrap();
+        assert_eq!(
+            reverse,
+            ReverseProxy::default()
+                .with_route("/github", "https://api.github.com")
+                .unwrap()
+                .with_host_route("payments.localhost", "https://api.stripe.com")
+                .unwrap()
+        );
+
+        assert_eq!(ReverseProxyConfig::default().resolve().unwrap(), None);
+        assert!(ReverseProxy::new("ftp://files.example.com").is_err());
+    }
+}
@@ -3,22 +3,55 @@
 //! This module implements the actual MITM proxy server that intercepts
 //! HTTP/HTTPS and WebSocket traffic.
 
-use crate::cassette::{HttpRequest, HttpResponse};
+use crate::cassette::{
+    Direction, GrpcMessage, GrpcRequest, GrpcResponse, Headers, HttpRequest, HttpResponse,
+    HttpVersion, Interaction, InteractionKind, NetworkError, SseEvent,
+};
+use crate::content_encoding;
 use crate::error::{MatgtoError, Result};
-use crate::player::{Player, RequestSignature};
-use crate::proxy::client::HttpForwarder;
+use crate::grpc::{self, CallCapture, GrpcDescriptors};
+use crate::player::{LatencyMode, Player};
+use crate::proxy::body::{tap, tee, Capture};
+use crate::proxy::client::{headers_from_hyper, http_version_from_hyper, HttpForwarder};
+use crate::proxy::ignore::IgnoreRules;
+use crate::proxy::reverse::ReverseProxy;
+use crate::proxy::websocket_handler::{
+    is_websocket_upgrade, websocket_url, MatgtoWebSocketHandler, ReplaySession,
+};
 use crate::proxy::ProxyMode;
 use crate::recorder::Recorder;
+use crate::sensitive::SensitiveData;
+use crate::sse::{self, EventParser};
 use crate::tls::CertificateAuthority;
+use crate::websocket::WebSocketPlayer;
 
+use futures::StreamExt;
 use hudsucker::{
-    hyper::{Body, Request, Response, StatusCode},
+    hyper::{
+        body::{Bytes, HttpBody},
+        Body, HeaderMap, Method, Request, Response, StatusCode,
+    },
     HttpContext, HttpHandler as HudsuckerHttpHandler, RequestOrResponse,
 };
-use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
 use std::net::SocketAddr;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+
+/// Outcome of looking up the recorded interaction for a request
+enum Recorded<'a> {
+    /// Prepared interaction, with the player it came from, its delay and latency mode
+    Found(
+        &'a Arc<Mutex<Player>>,
+        Box<Interaction>,
+        Option<u64>,
+        LatencyMode,
+    ),
+    /// Index of a top cassette interaction older than the re-record interval
+    Stale(usize),
+}
 
 /// Matgto HTTP handler that implements Hudsucker's HttpHandler trait
 #[derive(Debug, Clone)]
@@ -26,7 +59,12 @@ pub struct MatgtoHttpHandler {
     mode: ProxyMode,
     recorder: Option<Arc<Mutex<Recorder>>>,
     player: Option<Arc<Mutex<Player>>>,
+    base_players: Vec<Arc<Mutex<Player>>>,
+    ws_player: Option<Arc<Mutex<WebSocketPlayer>>>,
     forwarder: HttpForwarder,
+    grpc_descriptors: Option<Arc<GrpcDescriptors>>,
+    ignore: Option<Arc<IgnoreRules>>,
+    websocket: bool,
 }
 
 impl MatgtoHttpHandler {
@@ -36,7 +74,12 @@ impl MatgtoHttpHandler {
             mode,
             recorder: None,
             player: None,
+            base_players: Vec::new(),
+            ws_player: None,
             forwarder: HttpForwarder::new(),
+            grpc_descriptors: None,
+            ignore: None,
+            websocket: true,
         }
     }
 
@@ -52,6 +95,45 @@ impl MatgtoHttpHandler {
         self
     }
 
+    /// Set the players of the cassettes below the top one, top-down
+    ///
+    /// HTTP requests the top player has no match for are looked up in them,
+    /// in order, before the mode's fallback applies. They are never recorded to.
+    pub fn with_base_players(mut self, players: Vec<Arc<Mutex<Player>>>) -> Self {
+        self.base_players = players;
+        self
+    }
+
+    /// Set the player serving recorded WebSocket sessions
+    pub fn with_websocket_player(mut self, ws_player: Arc<Mutex<WebSocketPlayer>>) -> Self {
+        self.ws_player = Some(ws_player);
+        self
+    }
+
+    /// Forward requests to hosts matching `ignore` live, whatever the mode
+    pub fn with_ignore_rules(mut self, ignore: Arc<IgnoreRules>) -> Self {
+        self.ignore = Some(ignore);
+        self
+    }
+
+    /// Set the forwarder used to reach upstream servers
+    pub fn with_forwarder(mut self, forwarder: HttpForwarder) -> Self {
+        self.forwarder = forwarder;
+        self
+    }
+
+    /// Forward WebSocket upgrades live instead of replaying them
+    pub fn without_websocket(mut self) -> Self {
+        self.websocket = false;
+        self
+    }
+
+    /// Set the descriptors used to decode gRPC messages
+    pub fn with_grpc_descriptors(mut self, descriptors: Arc<GrpcDescriptors>) -> Self {
+        self.grpc_descriptors = Some(descriptors);
+        self
+    }
+
     /// Convert hyper Request to our HttpRequest format
     /// This consumes the request body
     async fn convert_request(req: Request<Body>) -> Result<(HttpRequest, Vec<u8>)> {
@@ -59,12 +141,7 @@ impl MatgtoHttpHandler {
         let url = req.uri().to_string();
 
         // Extract headers
-        let mut headers = HashMap::new();
-        for (name, value) in req.headers().iter() {
-            if let Ok(value_str) = value.to_str() {
-                headers.insert(name.to_string(), value_str.to_string());
-            }
-        }
+        let headers = headers_from_hyper(req.headers());
 
         // Read and buffer the body
         let body_bytes = hyper::body::to_bytes(req.into_body()).await.map_err(|e| {
@@ -98,8 +175,8 @@ impl MatgtoHttpHandler {
             .uri(&http_req.url);
 
         // Add headers
-        for (name, value) in &http_req.headers {
-            builder = builder.header(name, value);
+        for header in http_req.headers.entries() {
+            builder = builder.header(header.name.as_str(), header.value.as_slice());
         }
 
         // Build request with body
@@ -117,12 +194,21 @@ impl MatgtoHttpHandler {
     }
 
     /// Convert our HttpResponse to hyper Response
+    ///
+    /// The version is left to the client connection, so a response recorded
+    /// over HTTP/2 replays over HTTP/1.1 and vice versa (hyper drops headers
+    /// that are not allowed in HTTP/2). When there is a body, its length is
+    /// sent instead of the recorded `Content-Length`, since hooks and templates
+    /// may have changed it.
     fn convert_response(resp: &HttpResponse) -> Result<Response<Body>> {
         let mut builder = Response::builder().status(resp.status);
 
-        // Add headers
-        for (name, value) in &resp.headers {
-            builder = builder.header(name, value);
+        // Add headers (repeated values such as Set-Cookie are all sent)
+        for header in resp.headers.entries() {
+            if resp.body.is_some() && header.name.eq_ignore_ascii_case("content-length") {
+                continue;
+            }
+            builder = builder.header(header.name.as_str(), header.value.as_slice());
         }
 
         // Build response with body
@@ -139,23 +225,895 @@ impl MatgtoHttpHandler {
             })
     }
 
-    /// Convert HttpRequest to RequestSignature for player lookups
-    fn to_signature(http_req: &HttpRequest) -> RequestSignature {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
+    /// Replay the recorded response for a request
+    ///
+    /// The single replay pipeline shared by every mode:
+    /// 1. match with `Player::find_interaction_advanced` (the player's `MatchingStrategy`),
+    ///    on the top cassette first, then on the cassettes below it
+    /// 2. clone the interaction, run `before_replay` hooks and render templates
+    ///    with the live request (`Player::prepare_replay`)
+    /// 3. wait according to the player's `LatencyMode`
+    /// 4. build the response (bodies recorded decoded are re-encoded when the
+    ///    client accepts their `Content-Encoding`, recorded network failures drop
+    ///    the connection, event streams re-emit their events with the recorded pacing),
+    ///    then run `after_replay` hooks (`Player::mark_replayed`)
+    ///
+    /// With a recorder set, a match older than the re-record interval is
+    /// fetched from upstream again instead (`Player::is_stale`). If upstream
+    /// cannot be reached, the recorded response is kept and replayed.
+    async fn replay_recorded(&self, http_req: &HttpRequest) -> Option<Response<Body>> {
+        let recorded = match self.find_recorded(http_req).await? {
+            Recorded::Stale(idx) => match self.re_record(idx, http_req).await {
+                Some(response) => return Some(response),
+                None => {
+                    self.prepare_recorded(self.player.as_ref()?, idx, http_req)
+                        .await?
+                }
+            },
+            found => found,
+        };
+        let Recorded::Found(player, interaction, delay, latency) = recorded else {
+            return None;
+        };
+        let interaction = *interaction;
+
+        // Simulate latency without holding the player lock
+        if let Some(delay_ms) = delay {
+            tracing::debug!("⏱️  Simulating {}ms latency", delay_ms);
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+        }
+
+        let response = match &interaction.kind {
+            InteractionKind::Http { response, .. } => {
+                // Bodies recorded decoded are encoded again if the client accepts it
+                let response = match &interaction.content_encoding {
+                    Some(encoding) => {
+                        let mut response = response.clone();
+                        content_encoding::encode_response(
+                            &mut response,
+                            encoding,
+                            http_req.headers.get("Accept-Encoding"),
+                        );
+                        std::borrow::Cow::Owned(response)
+                    }
+                    None => std::borrow::Cow::Borrowed(response),
+                };
+
+                match Self::convert_response(&response) {
+                    Ok(response) => response,
+                    Err(e) => {
+                        tracing::error!("Failed to convert response: {}", e);
+                        return None;
+                    }
+                }
+            }
+            InteractionKind::HttpError { error, .. } => {
+                tracing::info!("💥 Replaying network failure: {}", error);
 
-        let body_hash = http_req.body.as_ref().map(|b| {
-            let mut hasher = DefaultHasher::new();
-            b.hash(&mut hasher);
-            hasher.finish()
+                // A timed out request stalls as long as the original did before the
+                // connection goes away; clients with a shorter timeout give up first
+                if let NetworkError::Timeout { timeout_ms, .. } = error {
+                    tokio::time::sleep(std::time::Duration::from_millis(*timeout_ms)).await;
+                }
+
+                Self::network_failure_response(error)
+            }
+            InteractionKind::ServerSentEvents {
+                response, events, ..
+            } => match Self::event_stream_response(response, events.clone(), latency) {
+                Ok(response) => response,
+                Err(e) => {
+                    tracing::error!("Failed to convert response: {}", e);
+                    return None;
+                }
+            },
+            _ => return None,
+        };
+
+        if let Err(e) = player.lock().await.mark_replayed(&interaction) {
+            tracing::warn!("Hook after_replay failed: {}", e);
+        }
+
+        Some(response)
+    }
+
+    /// Find and prepare the recorded interaction for a request
+    ///
+    /// Players are tried top-down: the top cassette's, then those of the
+    /// cassettes it was pushed on (`with_base_players`). Returns the player
+    /// that matched with the prepared interaction, its delay and latency mode,
+    /// or the index of a stale top cassette interaction to re-record.
+    async fn find_recorded(&self, http_req: &HttpRequest) -> Option<Recorded<'_>> {
+        for player in self.player.iter().chain(&self.base_players) {
+            let mut player_lock = player.lock().await;
+
+            let idx = match player_lock.find_interaction_advanced(http_req) {
+                Ok(idx) => idx,
+                Err(e) => {
+                    tracing::warn!("No match: {}", e);
+                    continue;
+                }
+            };
+
+            // Only the top cassette is recorded to
+            let is_top = self
+                .player
+                .as_ref()
+                .is_some_and(|top| Arc::ptr_eq(top, player));
+            if is_top && self.recorder.is_some() && player_lock.is_stale(idx) {
+                return Some(Recorded::Stale(idx));
+            }
+
+            drop(player_lock);
+            return self.prepare_recorded(player, idx, http_req).await;
+        }
+
+        None
+    }
+
+    /// Prepare interaction `idx` of `player` for replay against a request
+    async fn prepare_recorded<'a>(
+        &self,
+        player: &'a Arc<Mutex<Player>>,
+        idx: usize,
+        http_req: &HttpRequest,
+    ) -> Option<Recorded<'a>> {
+        let player_lock = player.lock().await;
+
+        let interaction = match player_lock.prepare_replay(idx, http_req) {
+            Ok(interaction) => interaction,
+            Err(e) => {
+                tracing::error!("Failed to prepare interaction #{}: {}", idx, e);
+                return None;
+            }
+        };
+
+        let delay = player_lock.calculate_delay(&interaction);
+        Some(Recorded::Found(
+            player,
+            Box::new(interaction),
+            delay,
+            player_lock.latency_mode(),
+        ))
+    }
+
+    /// Fetch a stale interaction from upstream again, replacing it in the cassette
+    ///
+    /// The stale interaction is only dropped once the new exchange is
+    /// recorded. Returns None, leaving it in place, if upstream fails.
+    async fn re_record(&self, idx: usize, http_req: &HttpRequest) -> Option<Response<Body>> {
+        tracing::info!(
+            "♻️  Re-recording stale interaction #{}: {} {}",
+            idx,
+            http_req.method,
+            http_req.url
+        );
+
+        let started = std::time::Instant::now();
+        match self.forwarder.forward_streaming(http_req).await {
+            Ok(response) => {
+                let response_time_ms = started.elapsed().as_millis() as u64;
+                Some(
+                    self.record_response(http_req.clone(), response, response_time_ms, Some(idx))
+                        .await,
+                )
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Re-recording {} {} failed, replaying the recorded response: {}",
+                    http_req.method,
+                    http_req.url,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Forward a request upstream and stream the response back to the client
+    ///
+    /// With a recorder set, the response body is copied as it streams and the
+    /// interaction is recorded once the body is complete, unless it grew past
+    /// the recorder's `max_body_size`. The recorded response time is the time
+    /// until the response head arrived.
+    async fn forward_and_record(&self, http_req: HttpRequest) -> RequestOrResponse {
+        let started = std::time::Instant::now();
+        let response = match self.forwarder.forward_streaming(&http_req).await {
+            Ok(response) => response,
+            Err(e) => return self.upstream_failure(&http_req, e).await,
+        };
+        let response_time_ms = started.elapsed().as_millis() as u64;
+
+        RequestOrResponse::Response(
+            self.record_response(http_req, response, response_time_ms, None)
+                .await,
+        )
+    }
+
+    /// Stream an upstream response back to the client, recording it
+    ///
+    /// `replaces` is the index of a stale interaction that is expired once
+    /// the new exchange is recorded.
+    async fn record_response(
+        &self,
+        http_req: HttpRequest,
+        response: Response<Body>,
+        response_time_ms: u64,
+        replaces: Option<usize>,
+    ) -> Response<Body> {
+        let recorder = match &self.recorder {
+            Some(recorder) => recorder.clone(),
+            None => return response,
+        };
+
+        let (parts, body) = response.into_parts();
+        let status = parts.status.as_u16();
+        let headers = headers_from_hyper(&parts.headers);
+        let version = http_version_from_hyper(parts.version);
+
+        if sse::is_event_stream(&parts.headers) {
+            let response = HttpResponse {
+                status,
+                headers,
+                body: None,
+            };
+            if let Some(idx) = replaces {
+                recorder.lock().await.expire(idx);
+            }
+            let body = Self::record_event_stream(
+                recorder,
+                http_req,
+                response,
+                body,
+                response_time_ms,
+                version,
+            )
+            .await;
+            return Response::from_parts(parts, body);
+        }
+
+        let limit = recorder.lock().await.max_body_size();
+
+        let body = tee(body, limit, move |capture| async move {
+            match capture {
+                Capture::Complete(body) => {
+                    let response = HttpResponse {
+                        status,
+                        headers,
+                        body: if body.is_empty() { None } else { Some(body) },
+                    };
+                    let mut recorder = recorder.lock().await;
+                    if let Some(idx) = replaces {
+                        recorder.expire(idx);
+                    }
+                    match version {
+                        Some(version) => recorder.record_http_exchange(
+                            http_req,
+                            response,
+                            response_time_ms,
+                            version,
+                        ),
+                        None => {
+                            recorder.record_http_with_timing(http_req, response, response_time_ms)
+                        }
+                    }
+                    tracing::debug!("Recorded interaction");
+                }
+                Capture::TooLarge(size) => tracing::warn!(
+                    "Not recording {} {}: response body of {} bytes exceeds the {} byte limit",
+                    http_req.method,
+                    http_req.url,
+                    size,
+                    limit
+                ),
+                Capture::Interrupted(reason) => tracing::warn!(
+                    "Not recording {} {}: {}",
+                    http_req.method,
+                    http_req.url,
+                    reason
+                ),
+            }
+        });
+
+        Response::from_parts(parts, body)
+    }
+
+    /// Stream a Server-Sent Events body to the client, recording events as they arrive
+    ///
+    /// The stream is added to the cassette as soon as its head arrives and
+    /// each event is appended with its time since then, so streams that never
+    /// close are recorded up to the moment the client or the recording stops.
+    async fn record_event_stream(
+        recorder: Arc<Mutex<Recorder>>,
+        http_req: HttpRequest,
+        response: HttpResponse,
+        body: Body,
+        response_time_ms: u64,
+        version: Option<HttpVersion>,
+    ) -> Body {
+        let started = std::time::Instant::now();
+        let url = http_req.url.clone();
+        let stream =
+            match recorder
+                .lock()
+                .await
+                .start_sse(http_req, response, response_time_ms, version)
+            {
+                Some(stream) => stream,
+                None => return body,
+            };
+
+        let (mut sender, client_body) = Body::channel();
+        tokio::spawn(async move {
+            let mut body = body;
+            let mut parser = EventParser::new();
+
+            while let Some(chunk) = body.data().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        tracing::warn!("Event stream {} failed: {}", url, e);
+                        sender.abort();
+                        return;
+                    }
+                };
+
+                let events = parser.push(&chunk, started.elapsed().as_millis() as u64);
+                if !events.is_empty() {
+                    let mut recorder = recorder.lock().await;
+                    for event in events {
+                        recorder.record_sse_event(stream, event);
+                    }
+                }
+
+                if sender.send_data(chunk).await.is_err() {
+                    tracing::debug!("Client closed event stream {}", url);
+                    return;
+                }
+            }
+        });
+
+        client_body
+    }
+
+    /// Build a response re-emitting recorded Server-Sent Events
+    ///
+    /// Each event is sent after the time recorded since the previous one (or
+    /// since the response start), scaled by `latency`; the stream closes after
+    /// the last event, and clients reconnect as they would to the real server.
+    fn event_stream_response(
+        resp: &HttpResponse,
+        events: Vec<SseEvent>,
+        latency: LatencyMode,
+    ) -> Result<Response<Body>> {
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            let mut previous = 0;
+            for event in events {
+                let elapsed = event.timestamp_ms.saturating_sub(previous);
+                if let Some(delay_ms) = latency.message_delay(elapsed) {
+                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                }
+                previous = event.timestamp_ms;
+
+                if sender
+                    .send_data(Bytes::from(sse::encode_event(&event)))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
         });
 
-        RequestSignature {
-            method: http_req.method.clone(),
-            url: http_req.url.clone(),
-            body_hash,
+        let mut builder = Response::builder().status(resp.status);
+        for header in resp.headers.entries() {
+            builder = builder.header(header.name.as_str(), header.value.as_slice());
+        }
+
+        builder
+            .body(body)
+            .map_err(|e| MatgtoError::ProxyStartFailed {
+                reason: format!("Failed to build response: {}", e),
+            })
+    }
+
+    /// Forward a request to an ignored host live, without recording or replaying it
+    async fn forward_ignored(&self, req: Request<Body>) -> RequestOrResponse {
+        tracing::info!(
+            "🙈 Ignored host, forwarding live: {} {}",
+            req.method(),
+            req.uri()
+        );
+
+        // Hudsucker forwards the upgrade; the WebSocket handler ignores the host too
+        if is_websocket_upgrade(&req) {
+            return RequestOrResponse::Request(req);
+        }
+
+        match self.forwarder.forward_request(req).await {
+            Ok(response) => RequestOrResponse::Response(response),
+            Err(MatgtoError::Network(error)) => {
+                tracing::warn!("Upstream failure for ignored host: {}", error);
+                RequestOrResponse::Response(Self::network_failure_response(&error))
+            }
+            Err(e) => {
+                tracing::error!("Failed to forward request: {}", e);
+                let err_response = Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(Body::from(format!("Proxy error: {}", e)))
+                    .unwrap();
+                RequestOrResponse::Response(err_response)
+            }
+        }
+    }
+
+    /// Answer a request whose upstream exchange failed
+    ///
+    /// Network failures are recorded (when a recorder is set) and reproduced to the
+    /// client like a replayed failure; any other error becomes a 502.
+    async fn upstream_failure(
+        &self,
+        http_req: &HttpRequest,
+        error: MatgtoError,
+    ) -> RequestOrResponse {
+        match error {
+            MatgtoError::Network(network_error) => {
+                tracing::warn!(
+                    "Upstream failure for {} {}: {}",
+                    http_req.method,
+                    http_req.url,
+                    network_error
+                );
+
+                if let Some(recorder) = &self.recorder {
+                    let mut recorder_lock = recorder.lock().await;
+                    recorder_lock.record_http_error(http_req.clone(), network_error.clone());
+                }
+
+                RequestOrResponse::Response(Self::network_failure_response(&network_error))
+            }
+            e => {
+                tracing::error!("Failed to forward request: {}", e);
+                let err_response = Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(Body::from(format!("Proxy error: {}", e)))
+                    .unwrap();
+                RequestOrResponse::Response(err_response)
+            }
+        }
+    }
+
+    /// Build a response that makes hyper drop the client connection
+    ///
+    /// The body fails before any byte is written, so hyper closes the socket without
+    /// sending a status line: the client sees the connection go away, as it would
+    /// with the real upstream failure, instead of an HTTP 502.
+    fn network_failure_response(error: &NetworkError) -> Response<Body> {
+        let reason = error.to_string();
+        let failing_body = futures::stream::once(async move {
+            Err::<hyper::body::Bytes, _>(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                reason,
+            ))
+        });
+
+        Response::new(Body::wrap_stream(failing_body))
+    }
+
+    /// Route a WebSocket upgrade request
+    ///
+    /// Modes that replay serve the next recorded session for the URL, unless WebSocket
+    /// support is off. Otherwise the request goes back to Hudsucker, which opens the
+    /// upstream connection and runs its frames through `MatgtoWebSocketHandler`
+    /// (recording them when a recorder is set).
+    async fn handle_websocket_upgrade(&self, req: Request<Body>) -> RequestOrResponse {
+        let url = websocket_url(req.uri());
+
+        let replays =
+            self.websocket && !matches!(self.mode, ProxyMode::Record | ProxyMode::Passthrough);
+        if !replays {
+            return RequestOrResponse::Request(req);
+        }
+
+        if let Some(ws_player) = &self.ws_player {
+            match ReplaySession::next(&mut *ws_player.lock().await, &url) {
+                Ok(session) => {
+                    tracing::info!("✅ Replaying WebSocket session for {}", url);
+                    return RequestOrResponse::Response(session.serve(req));
+                }
+                Err(e) => tracing::warn!("No WebSocket match: {}", e),
+            }
+        }
+
+        // Same fallback rules as HTTP: only modes that can record go upstream
+        let no_fallback = match self.mode {
+            ProxyMode::Replay | ProxyMode::ReplayStrict => true,
+            ProxyMode::Once => self.ws_player.is_some(),
+            _ => false,
+        };
+
+        if no_fallback {
+            tracing::warn!("❌ No recorded WebSocket session for {}", url);
+            let response = Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from("No matching WebSocket session in cassette"))
+                .unwrap();
+            return RequestOrResponse::Response(response);
+        }
+
+        RequestOrResponse::Request(req)
+    }
+
+    /// Record, replay or forward a gRPC call according to the mode
+    ///
+    /// Calls are never buffered: replay matches request messages as they
+    /// arrive, and recording captures messages while they stream through.
+    /// The fallback rules are those of HTTP requests.
+    async fn handle_grpc(
+        &self,
+        req: Request<Body>,
+        service: String,
+        method: String,
+    ) -> RequestOrResponse {
+        tracing::info!("gRPC call {}/{} ({:?} mode)", service, method, self.mode);
+
+        let has_cassette = match &self.player {
+            Some(player) => player.lock().await.has_cassette(),
+            None => false,
+        };
+        let replays = match self.mode {
+            ProxyMode::Record | ProxyMode::Passthrough => false,
+            ProxyMode::Replay | ProxyMode::ReplayStrict | ProxyMode::Hybrid => true,
+            ProxyMode::Auto | ProxyMode::Once => has_cassette,
+        };
+
+        let req = if replays {
+            match self.replay_grpc(req, &service, &method).await {
+                RequestOrResponse::Response(response) => {
+                    tracing::info!("✅ Replaying gRPC call {}/{}", service, method);
+                    return RequestOrResponse::Response(response);
+                }
+                RequestOrResponse::Request(req) => req,
+            }
+        } else {
+            req
+        };
+
+        let no_fallback = match self.mode {
+            ProxyMode::Replay | ProxyMode::ReplayStrict => true,
+            ProxyMode::Once => has_cassette,
+            _ => false,
+        };
+        if no_fallback {
+            tracing::warn!("❌ No recorded gRPC call for {}/{}", service, method);
+            return RequestOrResponse::Response(Self::grpc_status_response(
+                grpc::STATUS_NOT_FOUND,
+                "No matching gRPC call in cassette",
+            ));
+        }
+
+        self.forward_grpc(req, service, method).await
+    }
+
+    /// Replay the recorded gRPC call matching the live request messages
+    ///
+    /// Request messages are read until the earliest recorded call still
+    /// matching them would have answered, then that call is replayed: its
+    /// responses are sent in the recorded order, paced with the player's
+    /// `LatencyMode`, interleaved with the client's later messages, and its
+    /// trailers end the stream. The request is handed back, with the
+    /// messages already read, when no recorded call matches.
+    async fn replay_grpc(
+        &self,
+        req: Request<Body>,
+        service: &str,
+        method: &str,
+    ) -> RequestOrResponse {
+        let player = match &self.player {
+            Some(player) => player.clone(),
+            None => return RequestOrResponse::Request(req),
+        };
+        let (mut candidates, latency, sensitive) = {
+            let player = player.lock().await;
+            (
+                player.grpc_calls(service, method),
+                player.latency_mode(),
+                player.sensitive_data().clone(),
+            )
+        };
+        if candidates.is_empty() {
+            return RequestOrResponse::Request(req);
+        }
+
+        let (parts, mut body) = req.into_parts();
+        let mut capture = CallCapture::new(
+            service,
+            method,
+            self.grpc_descriptors.clone(),
+            std::time::Instant::now(),
+        );
+        let mut consumed: Vec<Bytes> = Vec::new();
+        let mut ended = false;
+
+        loop {
+            // Recorded messages hold placeholders instead of sensitive values
+            let live = sensitive.redacted_grpc_messages(capture.messages());
+            candidates.retain(|(_, interaction)| match &interaction.kind {
+                InteractionKind::Grpc { messages, .. } => {
+                    grpc::call_matches(messages, &live, ended)
+                }
+                _ => false,
+            });
+            let needed = match candidates.first().map(|(_, i)| &i.kind) {
+                Some(InteractionKind::Grpc { messages, .. }) => grpc::sent_before_reply(messages),
+                _ => break,
+            };
+            if ended || capture.messages().len() >= needed {
+                break;
+            }
+
+            match body.data().await {
+                Some(Ok(chunk)) => {
+                    capture.push(Direction::Sent, &chunk);
+                    consumed.push(chunk);
+                }
+                Some(Err(e)) => {
+                    tracing::warn!("gRPC request body failed: {}", e);
+                    return RequestOrResponse::Response(Self::grpc_status_response(
+                        1,
+                        "Request stream failed",
+                    ));
+                }
+                None => ended = true,
+            }
+        }
+
+        let (idx, interaction) = match candidates.into_iter().next() {
+            Some(candidate) => candidate,
+            None => {
+                tracing::warn!(
+                    "No recorded {}/{} call matches the request messages",
+                    service,
+                    method
+                );
+                // Put the messages already read back in front of the rest of the body
+                let replayed = futures::stream::iter(consumed.into_iter().map(Ok));
+                let body = Body::wrap_stream(replayed.chain(body));
+                return RequestOrResponse::Request(Request::from_parts(parts, body));
+            }
+        };
+
+        if let Err(e) = player.lock().await.mark_grpc_replayed(idx, &interaction) {
+            tracing::warn!("Hook after_replay failed: {}", e);
+        }
+
+        let (response, messages) = match interaction.kind {
+            InteractionKind::Grpc {
+                response, messages, ..
+            } => (response, messages),
+            _ => unreachable!("candidates only hold gRPC calls"),
+        };
+
+        let mut builder = Response::builder().status(StatusCode::OK);
+        for header in response.metadata.entries() {
+            builder = builder.header(header.name.as_str(), header.value.as_slice());
+        }
+        let (sender, reply) = Body::channel();
+        tokio::spawn(Self::play_grpc(
+            sender,
+            body,
+            capture,
+            messages,
+            response.trailers,
+            latency,
+            sensitive,
+        ));
+
+        match builder.body(reply) {
+            Ok(response) => RequestOrResponse::Response(response),
+            Err(e) => {
+                tracing::error!("Failed to build gRPC response: {}", e);
+                RequestOrResponse::Response(Self::grpc_status_response(
+                    13,
+                    "Invalid recorded metadata",
+                ))
+            }
+        }
+    }
+
+    /// Send the recorded messages and trailers of a gRPC call
+    ///
+    /// Before each recorded request message the client's next message is
+    /// awaited (a mismatch is only logged: the call was already chosen).
+    async fn play_grpc(
+        mut sender: hyper::body::Sender,
+        mut body: Body,
+        mut capture: CallCapture,
+        script: Vec<GrpcMessage>,
+        trailers: Headers,
+        latency: LatencyMode,
+        sensitive: SensitiveData,
+    ) {
+        let mut sent = 0;
+        let mut previous = 0;
+        let mut client_done = false;
+
+        for message in script {
+            match message.direction {
+                Direction::Sent => {
+                    while !client_done && capture.messages().len() <= sent {
+                        match body.data().await {
+                            Some(Ok(chunk)) => {
+                                capture.push(Direction::Sent, &chunk);
+                            }
+                            _ => client_done = true,
+                        }
+                    }
+                    if let Some(live) = capture.messages().get(sent) {
+                        let mut live = live.clone();
+                        sensitive.redact_grpc_message(&mut live);
+                        if !grpc::messages_match(&message, &live) {
+                            tracing::warn!(
+                                "gRPC request message #{} differs from the recording",
+                                sent
+                            );
+                        }
+                    }
+                    sent += 1;
+                }
+                Direction::Received => {
+                    let elapsed = message.timestamp_ms.saturating_sub(previous);
+                    if let Some(delay_ms) = latency.message_delay(elapsed) {
+                        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                    }
+                    let frame = grpc::encode_frame(message.compressed, &message.data);
+                    if sender.send_data(frame).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            previous = message.timestamp_ms;
+        }
+
+        let mut map = HeaderMap::new();
+        for header in trailers.entries() {
+            if let (Ok(name), Ok(value)) = (
+                hyper::header::HeaderName::from_bytes(header.name.as_bytes()),
+                hyper::header::HeaderValue::from_bytes(&header.value),
+            ) {
+                map.append(name, value);
+            }
+        }
+        if !map.is_empty() {
+            let _ = sender.send_trailers(map).await;
         }
     }
+
+    /// Forward a gRPC call upstream, recording its messages when a recorder is set
+    ///
+    /// The call is recorded once the response trailers arrived. Calls that
+    /// fail before that are not recorded, and transport failures are answered
+    /// with the UNAVAILABLE status.
+    async fn forward_grpc(
+        &self,
+        req: Request<Body>,
+        service: String,
+        method: String,
+    ) -> RequestOrResponse {
+        let started = std::time::Instant::now();
+        let request = GrpcRequest {
+            url: req.uri().to_string(),
+            service,
+            method,
+            metadata: headers_from_hyper(req.headers()),
+        };
+
+        let recorder = self.recorder.clone();
+        let capture = Arc::new(std::sync::Mutex::new(CallCapture::new(
+            &request.service,
+            &request.method,
+            self.grpc_descriptors.clone(),
+            started,
+        )));
+
+        let req = match &recorder {
+            Some(_) => {
+                let (parts, body) = req.into_parts();
+                let sent = capture.clone();
+                let body = tap(
+                    body,
+                    move |chunk| {
+                        sent.lock().unwrap().push(Direction::Sent, chunk);
+                    },
+                    |_| async {},
+                );
+                Request::from_parts(parts, body)
+            }
+            None => req,
+        };
+
+        let response = match self.forwarder.forward_request(req).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!(
+                    "Upstream failure for gRPC call {}/{}: {}",
+                    request.service,
+                    request.method,
+                    e
+                );
+                return RequestOrResponse::Response(Self::grpc_status_response(
+                    14,
+                    &format!("Upstream unavailable: {}", e),
+                ));
+            }
+        };
+        let response_time_ms = started.elapsed().as_millis() as u64;
+
+        let recorder = match recorder {
+            Some(recorder) => recorder,
+            None => return RequestOrResponse::Response(response),
+        };
+
+        let (parts, body) = response.into_parts();
+        let metadata = headers_from_hyper(&parts.headers);
+        let received = capture.clone();
+        let body = tap(
+            body,
+            move |chunk| {
+                received.lock().unwrap().push(Direction::Received, chunk);
+            },
+            move |end| async move {
+                let trailers = match end {
+                    Ok(trailers) => trailers
+                        .map(|trailers| headers_from_hyper(&trailers))
+                        .unwrap_or_default(),
+                    Err(reason) => {
+                        tracing::warn!(
+                            "Not recording gRPC call {}/{}: {}",
+                            request.service,
+                            request.method,
+                            reason
+                        );
+                        return;
+                    }
+                };
+                let (status, message) = grpc::call_status(&metadata, &trailers);
+                let messages = capture.lock().unwrap().messages().to_vec();
+                let response = GrpcResponse {
+                    status,
+                    message,
+                    metadata,
+                    trailers,
+                };
+                recorder
+                    .lock()
+                    .await
+                    .record_grpc(request, response, messages, response_time_ms);
+            },
+        );
+
+        RequestOrResponse::Response(Response::from_parts(parts, body))
+    }
+
+    /// Build a trailers-only gRPC response carrying `status` and `message`
+    fn grpc_status_response(status: u32, message: &str) -> Response<Body> {
+        let message: String = message
+            .bytes()
+            .map(|byte| match byte {
+                b' '..=b'~' if byte != b'%' => (byte as char).to_string(),
+                _ => format!("%{:02X}", byte),
+            })
+            .collect();
+
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(hyper::header::CONTENT_TYPE, "application/grpc")
+            .header("grpc-status", status.to_string())
+            .header("grpc-message", message)
+            .body(Body::empty())
+            .unwrap()
+    }
 }
 
 #[async_trait::async_trait]
@@ -165,8 +1123,53 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
         _ctx: &HttpContext,
         req: Request<Body>,
     ) -> RequestOrResponse {
+        self.handle(req).await
+    }
+
+    async fn handle_response(&mut self, _ctx: &HttpContext, res: Response<Body>) -> Response<Body> {
+        // We handle everything in handle_request now (streaming the upstream response)
+        // This method just passes through responses unchanged
+        tracing::debug!("Response passthrough: {}", res.status());
+        res
+    }
+}
+
+impl MatgtoHttpHandler {
+    /// Record, replay or forward a request according to the mode
+    ///
+    /// Shared by the MITM proxy and the reverse-proxy listener. Requests
+    /// handed back must be forwarded by the caller (CONNECT tunnels and
+    /// WebSocket upgrades that are not replayed).
+    async fn handle(&mut self, req: Request<Body>) -> RequestOrResponse {
         tracing::debug!("Intercepting request: {} {}", req.method(), req.uri());
 
+        // CONNECT tunnels must reach Hudsucker so it can perform TLS interception;
+        // the decrypted requests come back through this handler afterwards
+        if req.method() == Method::CONNECT {
+            return RequestOrResponse::Request(req);
+        }
+
+        // Ignored hosts never reach the cassette, whatever the mode
+        if self
+            .ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.matches_request(&req))
+        {
+            return self.forward_ignored(req).await;
+        }
+
+        // WebSocket upgrades are replayed here or handed to Hudsucker's WebSocket handler
+        if is_websocket_upgrade(&req) {
+            return self.handle_websocket_upgrade(req).await;
+        }
+
+        // gRPC calls stream in both directions and are handled message by message
+        if req.method() == Method::POST && grpc::is_grpc(req.headers()) {
+            if let Some((service, method)) = grpc::parse_path(req.uri().path()) {
+                return self.handle_grpc(req, service, method).await;
+            }
+        }
+
         match self.mode {
             ProxyMode::Record => {
                 // In record mode, we buffer the request, forward it, and record
@@ -175,38 +1178,16 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
                 // Buffer the request (this consumes it)
                 match Self::convert_request(req).await {
                     Ok((http_req, _body_bytes)) => {
-                        // Forward via our HttpForwarder
-                        match self.forwarder.forward(&http_req).await {
-                            Ok(http_resp) => {
-                                // Record the interaction
-                                if let Some(recorder) = &self.recorder {
-                                    let mut recorder_lock = recorder.lock().await;
-                                    recorder_lock.record_http(http_req, http_resp.clone());
-                                    tracing::debug!("Recorded interaction");
-                                }
-
-                                // Convert response back to hyper Response
-                                match Self::convert_response(&http_resp) {
-                                    Ok(response) => RequestOrResponse::Response(response),
-                                    Err(e) => {
-                                        tracing::error!("Failed to convert response: {}", e);
-                                        let err_response = Response::builder()
-                                            .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                            .body(Body::from("Failed to convert response"))
-                                            .unwrap();
-                                        RequestOrResponse::Response(err_response)
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                tracing::error!("Failed to forward request: {}", e);
-                                let err_response = Response::builder()
-                                    .status(StatusCode::BAD_GATEWAY)
-                                    .body(Body::from(format!("Proxy error: {}", e)))
-                                    .unwrap();
-                                RequestOrResponse::Response(err_response)
+                        // Cassettes below the one being recorded answer what they hold
+                        if !self.base_players.is_empty() {
+                            if let Some(response) = self.replay_recorded(&http_req).await {
+                                tracing::info!("  📼 Found in a lower cassette, replaying");
+                                return RequestOrResponse::Response(response);
                             }
                         }
+
+                        // Forward via our HttpForwarder
+                        self.forward_and_record(http_req).await
                     }
                     Err(e) => {
                         tracing::error!("Failed to buffer request: {}", e);
@@ -236,43 +1217,14 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
                 // Buffer the request to match against cassette
                 match Self::convert_request(req).await {
                     Ok((http_req, _body_bytes)) => {
-                        if let Some(player) = &self.player {
-                            let mut player_lock = player.lock().await;
-                            let signature = Self::to_signature(&http_req);
-
-                            // Try to find matching interaction
-                            match player_lock.find_interaction(&signature) {
-                                Ok(idx) => {
-                                    if let Some(interaction) = player_lock.get_interaction(idx) {
-                                        if let crate::cassette::InteractionKind::Http {
-                                            response,
-                                            ..
-                                        } = &interaction.kind
-                                        {
-                                            tracing::info!(
-                                                "✅ Replay match found for {} {}",
-                                                http_req.method,
-                                                http_req.url
-                                            );
-
-                                            match Self::convert_response(response) {
-                                                Ok(response) => {
-                                                    return RequestOrResponse::Response(response);
-                                                }
-                                                Err(e) => {
-                                                    tracing::error!(
-                                                        "Failed to convert response: {}",
-                                                        e
-                                                    );
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::warn!("No match: {}", e);
-                                }
-                            }
+                        // Try to find matching interaction
+                        if let Some(response) = self.replay_recorded(&http_req).await {
+                            tracing::info!(
+                                "✅ Replay match found for {} {}",
+                                http_req.method,
+                                http_req.url
+                            );
+                            return RequestOrResponse::Response(response);
                         }
 
                         // If replay fails, return 404
@@ -309,30 +1261,15 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
                         let should_replay = if let Some(player) = &self.player {
                             player.lock().await.has_cassette()
                         } else {
-                            false
+                            !self.base_players.is_empty()
                         };
 
                         if should_replay {
                             // Try replay
                             tracing::info!("Auto mode: Cassette exists, attempting replay");
-                            if let Some(player) = &self.player {
-                                let mut player_lock = player.lock().await;
-                                let signature = Self::to_signature(&http_req);
-
-                                if let Ok(idx) = player_lock.find_interaction(&signature) {
-                                    if let Some(interaction) = player_lock.get_interaction(idx) {
-                                        if let crate::cassette::InteractionKind::Http {
-                                            response,
-                                            ..
-                                        } = &interaction.kind
-                                        {
-                                            tracing::info!("✅ Auto replay: Match found");
-                                            if let Ok(response) = Self::convert_response(response) {
-                                                return RequestOrResponse::Response(response);
-                                            }
-                                        }
-                                    }
-                                }
+                            if let Some(response) = self.replay_recorded(&http_req).await {
+                                tracing::info!("✅ Auto replay: Match found");
+                                return RequestOrResponse::Response(response);
                             }
                             tracing::warn!("Auto mode: Replay failed, falling back to record");
                         } else {
@@ -340,36 +1277,7 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
                         }
 
                         // Fall back to record mode
-                        match self.forwarder.forward(&http_req).await {
-                            Ok(http_resp) => {
-                                // Record if recorder is available
-                                if let Some(recorder) = &self.recorder {
-                                    let mut recorder_lock = recorder.lock().await;
-                                    recorder_lock.record_http(http_req.clone(), http_resp.clone());
-                                    tracing::debug!("Auto mode: Recorded interaction");
-                                }
-
-                                match Self::convert_response(&http_resp) {
-                                    Ok(response) => RequestOrResponse::Response(response),
-                                    Err(e) => {
-                                        tracing::error!("Failed to convert response: {}", e);
-                                        let err_response = Response::builder()
-                                            .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                            .body(Body::from("Failed to convert response"))
-                                            .unwrap();
-                                        RequestOrResponse::Response(err_response)
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                tracing::error!("Failed to forward request: {}", e);
-                                let err_response = Response::builder()
-                                    .status(StatusCode::BAD_GATEWAY)
-                                    .body(Body::from(format!("Proxy error: {}", e)))
-                                    .unwrap();
-                                RequestOrResponse::Response(err_response)
-                            }
-                        }
+                        self.forward_and_record(http_req).await
                     }
                     Err(e) => {
                         tracing::error!("Failed to buffer request: {}", e);
@@ -389,71 +1297,15 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
                 match Self::convert_request(req).await {
                     Ok((http_req, _body_bytes)) => {
                         // First, try to find in cassette
-                        let mut found_in_cassette = false;
-                        if let Some(player) = &self.player {
-                            let mut player_lock = player.lock().await;
-                            let signature = Self::to_signature(&http_req);
-
-                            if let Ok(idx) = player_lock.find_interaction(&signature) {
-                                if let Some(interaction) = player_lock.get_interaction(idx) {
-                                    if let crate::cassette::InteractionKind::Http {
-                                        response, ..
-                                    } = &interaction.kind
-                                    {
-                                        tracing::info!("  📼 Found in cassette, replaying");
-                                        found_in_cassette = true;
-
-                                        if let Ok(resp) = Self::convert_response(response) {
-                                            return RequestOrResponse::Response(resp);
-                                        }
-                                    }
-                                }
-                            }
+                        if let Some(response) = self.replay_recorded(&http_req).await {
+                            tracing::info!("  📼 Found in cassette, replaying");
+                            return RequestOrResponse::Response(response);
                         }
 
-                        // If not found, record new interaction
-                        if !found_in_cassette {
-                            tracing::info!("  📹 Not in cassette, forwarding and recording");
-
-                            match self.forwarder.forward(&http_req).await {
-                                Ok(http_resp) => {
-                                    // Record the new interaction
-                                    if let Some(recorder) = &self.recorder {
-                                        let mut recorder_lock = recorder.lock().await;
-                                        recorder_lock
-                                            .record_http(http_req.clone(), http_resp.clone());
-                                        tracing::debug!("  ✅ New interaction recorded");
-                                    }
-
-                                    match Self::convert_response(&http_resp) {
-                                        Ok(response) => RequestOrResponse::Response(response),
-                                        Err(e) => {
-                                            tracing::error!("Failed to convert response: {}", e);
-                                            let err_response = Response::builder()
-                                                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                                .body(Body::from("Failed to convert response"))
-                                                .unwrap();
-                                            RequestOrResponse::Response(err_response)
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::error!("Failed to forward request: {}", e);
-                                    let err_response = Response::builder()
-                                        .status(StatusCode::BAD_GATEWAY)
-                                        .body(Body::from(format!("Proxy error: {}", e)))
-                                        .unwrap();
-                                    RequestOrResponse::Response(err_response)
-                                }
-                            }
-                        } else {
-                            // Should never reach here but handle it anyway
-                            let err_response = Response::builder()
-                                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                .body(Body::from("Hybrid mode logic error"))
-                                .unwrap();
-                            RequestOrResponse::Response(err_response)
-                        }
+                        // Not found, record new interaction
+                        tracing::info!("  📹 Not in cassette, forwarding and recording");
+
+                        self.forward_and_record(http_req).await
                     }
                     Err(e) => {
                         tracing::error!("Failed to buffer request: {}", e);
@@ -484,24 +1336,9 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
                             // Cassette exists, replay from it
                             tracing::info!("  📼 Cassette exists, replaying (read-only)");
 
-                            if let Some(player) = &self.player {
-                                let mut player_lock = player.lock().await;
-                                let signature = Self::to_signature(&http_req);
-
-                                if let Ok(idx) = player_lock.find_interaction(&signature) {
-                                    if let Some(interaction) = player_lock.get_interaction(idx) {
-                                        if let crate::cassette::InteractionKind::Http {
-                                            response,
-                                            ..
-                                        } = &interaction.kind
-                                        {
-                                            tracing::info!("  ✅ Replayed from cassette");
-                                            if let Ok(resp) = Self::convert_response(response) {
-                                                return RequestOrResponse::Response(resp);
-                                            }
-                                        }
-                                    }
-                                }
+                            if let Some(response) = self.replay_recorded(&http_req).await {
+                                tracing::info!("  ✅ Replayed from cassette");
+                                return RequestOrResponse::Response(response);
                             }
 
                             // No match found in cassette
@@ -515,39 +1352,16 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
                             RequestOrResponse::Response(response)
                         } else {
                             // Cassette doesn't exist, record new one
-                            tracing::info!("  📹 Cassette doesn't exist, recording (first time)");
-
-                            match self.forwarder.forward(&http_req).await {
-                                Ok(http_resp) => {
-                                    // Record the interaction
-                                    if let Some(recorder) = &self.recorder {
-                                        let mut recorder_lock = recorder.lock().await;
-                                        recorder_lock
-                                            .record_http(http_req.clone(), http_resp.clone());
-                                        tracing::debug!("  ✅ Interaction recorded");
-                                    }
-
-                                    match Self::convert_response(&http_resp) {
-                                        Ok(response) => RequestOrResponse::Response(response),
-                                        Err(e) => {
-                                            tracing::error!("Failed to convert response: {}", e);
-                                            let err_response = Response::builder()
-                                                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                                .body(Body::from("Failed to convert response"))
-                                                .unwrap();
-                                            RequestOrResponse::Response(err_response)
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::error!("Failed to forward request: {}", e);
-                                    let err_response = Response::builder()
-                                        .status(StatusCode::BAD_GATEWAY)
-                                        .body(Body::from(format!("Proxy error: {}", e)))
-                                        .unwrap();
-                                    RequestOrResponse::Response(err_response)
+                            if !self.base_players.is_empty() {
+                                if let Some(response) = self.replay_recorded(&http_req).await {
+                                    tracing::info!("  📼 Found in a lower cassette, replaying");
+                                    return RequestOrResponse::Response(response);
                                 }
                             }
+
+                            tracing::info!("  📹 Cassette doesn't exist, recording (first time)");
+
+                            self.forward_and_record(http_req).await
                         }
                     }
                     Err(e) => {
@@ -569,46 +1383,59 @@ impl HudsuckerHttpHandler for MatgtoHttpHandler {
                     req.uri()
                 );
 
-                match Self::convert_request(req).await {
-                    Ok((http_req, _body_bytes)) => match self.forwarder.forward(&http_req).await {
-                        Ok(http_resp) => match Self::convert_response(&http_resp) {
-                            Ok(response) => RequestOrResponse::Response(response),
-                            Err(e) => {
-                                tracing::error!("Failed to convert response: {}", e);
-                                let err_response = Response::builder()
-                                    .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                    .body(Body::from("Failed to convert response"))
-                                    .unwrap();
-                                RequestOrResponse::Response(err_response)
-                            }
-                        },
-                        Err(e) => {
-                            tracing::error!("Failed to forward request: {}", e);
-                            let err_response = Response::builder()
-                                .status(StatusCode::BAD_GATEWAY)
-                                .body(Body::from(format!("Proxy error: {}", e)))
-                                .unwrap();
-                            RequestOrResponse::Response(err_response)
-                        }
-                    },
-                    Err(e) => {
-                        tracing::error!("Failed to buffer request: {}", e);
-                        let err_response = Response::builder()
-                            .status(StatusCode::BAD_REQUEST)
-                            .body(Body::from("Failed to read request"))
-                            .unwrap();
-                        RequestOrResponse::Response(err_response)
-                    }
+                // Nothing is buffered: both bodies stream straight through
+                let http_req = HttpRequest {
+                    method: req.method().to_string(),
+                    url: req.uri().to_string(),
+                    headers: Headers::new(),
+                    body: None,
+                };
+
+                match self.forwarder.forward_request(req).await {
+                    Ok(response) => RequestOrResponse::Response(response),
+                    Err(e) => self.upstream_failure(&http_req, e).await,
                 }
             }
         }
     }
 
-    async fn handle_response(&mut self, _ctx: &HttpContext, res: Response<Body>) -> Response<Body> {
-        // We handle everything in handle_request now (with buffering)
-        // This method just passes through responses unchanged
-        tracing::debug!("Response passthrough: {}", res.status());
-        res
+    /// Answer a request received by the reverse-proxy listener
+    ///
+    /// The request is rewritten to its upstream URL, then handled like a
+    /// request intercepted by the MITM proxy. WebSocket upgrades that are not
+    /// replayed are tunneled upstream through `ws_handler`.
+    async fn handle_reverse(
+        mut self,
+        reverse: Arc<ReverseProxy>,
+        ws_handler: MatgtoWebSocketHandler,
+        client: SocketAddr,
+        mut req: Request<Body>,
+    ) -> Response<Body> {
+        if req.method() == Method::CONNECT {
+            return Response::builder()
+                .status(StatusCode::METHOD_NOT_ALLOWED)
+                .body(Body::from("CONNECT is not supported in reverse-proxy mode"))
+                .unwrap();
+        }
+
+        if !reverse.route(&mut req) {
+            tracing::warn!("❌ No reverse-proxy route for {}", req.uri());
+            return Response::builder()
+                .status(StatusCode::BAD_GATEWAY)
+                .body(Body::from(format!(
+                    "No reverse-proxy route for {}",
+                    req.uri()
+                )))
+                .unwrap();
+        }
+
+        // CONNECT was refused above: only WebSocket upgrades come back as requests
+        match self.handle(req).await {
+            RequestOrResponse::Response(response) => response,
+            RequestOrResponse::Request(req) => {
+                ws_handler.tunnel(client, req, &self.forwarder).await
+            }
+        }
     }
 }
 
@@ -622,12 +1449,58 @@ async fn shutdown_signal() {
     tracing::info!("🛑 Shutdown signal received");
 }
 
+/// Handle to a proxy server running in the background
+///
+/// Dropping the handle also stops the server, without waiting for it.
+#[derive(Debug)]
+pub struct ServerHandle {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: tokio::task::JoinHandle<()>,
+    /// Disconnected once the server task has finished (or was aborted)
+    stopped: mpsc::Receiver<()>,
+}
+
+impl ServerHandle {
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop the server and wait until its port is released
+    ///
+    /// The listener stops accepting immediately and in-flight requests get up
+    /// to `grace` to complete; connections still open after that are dropped.
+    /// This blocks the calling thread, so the server must not run on a
+    /// current-thread runtime driven by the caller.
+    pub fn shutdown(mut self, grace: Duration) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+
+        if let Err(mpsc::RecvTimeoutError::Timeout) = self.stopped.recv_timeout(grace) {
+            tracing::warn!(
+                "Proxy on {} still busy after {:?}, dropping open connections",
+                self.addr,
+                grace
+            );
+            self.task.abort();
+            let _ = self.stopped.recv();
+        }
+
+        tracing::info!("🛑 Proxy on {} shut down", self.addr);
+    }
+}
+
 /// Proxy server configuration
 pub struct ProxyServer {
     addr: SocketAddr,
     #[allow(dead_code)]
     ca: Arc<CertificateAuthority>,
     handler: MatgtoHttpHandler,
+    ws_handler: MatgtoWebSocketHandler,
+    reverse: Option<ReverseProxy>,
+    listener: Option<std::net::TcpListener>,
 }
 
 impl ProxyServer {
@@ -635,13 +1508,54 @@ impl ProxyServer {
     pub fn new(port: u16, ca: Arc<CertificateAuthority>, mode: ProxyMode) -> Result<Self> {
         let addr = SocketAddr::from(([127, 0, 0, 1], port));
         let handler = MatgtoHttpHandler::new(mode);
+        let ws_handler = MatgtoWebSocketHandler::new();
+
+        Ok(Self {
+            addr,
+            ca,
+            handler,
+            ws_handler,
+            reverse: None,
+            listener: None,
+        })
+    }
+
+    /// Bind the listening socket
+    ///
+    /// Returns the bound address, which carries the OS-assigned port when the
+    /// server was created with port 0. Once this returns, connections are
+    /// accepted by the kernel and queue until `start` begins serving them.
+    pub fn bind(&mut self) -> Result<SocketAddr> {
+        if self.listener.is_none() {
+            let listener = std::net::TcpListener::bind(self.addr).map_err(|e| {
+                MatgtoError::ProxyStartFailed {
+                    reason: format!("Failed to bind {}: {}", self.addr, e),
+                }
+            })?;
+            self.addr = listener.local_addr()?;
+            self.listener = Some(listener);
+        }
+
+        Ok(self.addr)
+    }
+
+    /// Address the server listens on (the bound address once `bind` succeeded)
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
 
-        Ok(Self { addr, ca, handler })
+    /// Forward requests to hosts matching `ignore` live, never recording or replaying them
+    pub fn with_ignore_rules(mut self, ignore: IgnoreRules) -> Self {
+        let ignore = Arc::new(ignore);
+        self.handler = self.handler.with_ignore_rules(ignore.clone());
+        self.ws_handler = self.ws_handler.with_ignore_rules(ignore);
+        self
     }
 
     /// Set recorder for Record mode
     pub fn with_recorder(mut self, recorder: Arc<Mutex<Recorder>>) -> Self {
-        self.handler = self.handler.with_recorder(recorder);
+        self.handler = self.handler.with_recorder(recorder.clone());
+        self.ws_handler = self.ws_handler.with_recorder(recorder);
         self
     }
 
@@ -651,19 +1565,126 @@ impl ProxyServer {
         self
     }
 
+    /// Also replay from the cassettes below the top one, top-down
+    pub fn with_base_players(mut self, players: Vec<Arc<Mutex<Player>>>) -> Self {
+        self.handler = self.handler.with_base_players(players);
+        self
+    }
+
+    /// Mode the server handles requests in
+    pub fn mode(&self) -> ProxyMode {
+        self.handler.mode
+    }
+
+    /// Set the forwarder used to reach upstream servers (e.g. through an upstream proxy)
+    pub fn with_forwarder(mut self, forwarder: HttpForwarder) -> Self {
+        self.handler = self.handler.with_forwarder(forwarder);
+        self
+    }
+
+    /// Forward WebSocket sessions live, neither recording nor replaying them
+    pub fn without_websocket(mut self) -> Self {
+        self.handler = self.handler.without_websocket();
+        self
+    }
+
+    /// Decode gRPC messages with `descriptors` when recording and matching
+    pub fn with_grpc_descriptors(mut self, descriptors: GrpcDescriptors) -> Self {
+        self.handler = self.handler.with_grpc_descriptors(Arc::new(descriptors));
+        self
+    }
+
+    /// Serve as a reverse proxy instead of a MITM proxy
+    ///
+    /// Clients use the listener as their base URL; requests are routed to the
+    /// upstreams of `reverse` and recorded or replayed under their real URLs.
+    pub fn with_reverse_proxy(mut self, reverse: ReverseProxy) -> Self {
+        self.reverse = Some(reverse);
+        self
+    }
+
+    /// Bind the listener and run the server on `runtime`
+    ///
+    /// Bind errors are returned here; the server then runs until the returned
+    /// handle is shut down or dropped.
+    pub fn spawn(mut self, runtime: &tokio::runtime::Handle) -> Result<ServerHandle> {
+        let addr = self.bind()?;
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let (stopped_tx, stopped) = mpsc::channel::<()>();
+
+        let task = runtime.spawn(async move {
+            // Dropped when the task completes or is aborted, which wakes `ServerHandle::shutdown`
+            let _stopped = stopped_tx;
+            let signal = async {
+                let _ = signal.await;
+            };
+            if let Err(e) = self.start_with_shutdown(signal).await {
+                tracing::error!("Proxy server error: {}", e);
+            }
+        });
+
+        Ok(ServerHandle {
+            addr,
+            shutdown: Some(shutdown),
+            task,
+            stopped,
+        })
+    }
+
     /// Start the proxy server
     ///
     /// This will run the proxy until a shutdown signal is received
-    /// For use in a spawned task - doesn't block the calling thread
+    /// For use in a spawned task - doesn't block the calling thread.
+    /// Binds the listener first unless `bind` was already called.
     pub async fn start(self) -> Result<()> {
+        self.start_with_shutdown(shutdown_signal()).await
+    }
+
+    /// Start the proxy server, stopping gracefully once `signal` completes
+    ///
+    /// In-flight requests are allowed to finish before this returns.
+    pub async fn start_with_shutdown<F>(mut self, signal: F) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
         use hudsucker::{certificate_authority::RcgenAuthority, Proxy};
         use rustls::{Certificate, PrivateKey};
 
+        self.bind()?;
+        let listener = self.listener.take().expect("listener bound above");
+
+        // Frames of live sessions are only recorded when WebSocket support is on
+        if !self.handler.websocket {
+            self.ws_handler = MatgtoWebSocketHandler::new();
+        }
+
+        // WebSocket sessions replay from the same cassette as HTTP interactions
+        if let Some(player) = self
+            .handler
+            .player
+            .clone()
+            .filter(|_| self.handler.websocket)
+        {
+            let player = player.lock().await;
+            if let Some(cassette) = player.cassette() {
+                let mut ws_player = WebSocketPlayer::new().with_latency(player.latency_mode());
+                ws_player.load_cassette(cassette.clone());
+                self.handler = self
+                    .handler
+                    .with_websocket_player(Arc::new(Mutex::new(ws_player)));
+            }
+        }
+
         eprintln!("🚀 Starting proxy server on {}", self.addr);
         eprintln!("🔧 Mode: {:?}", self.handler.mode);
         tracing::info!("🚀 Starting proxy server on {}", self.addr);
         tracing::info!("🔧 Mode: {:?}", self.handler.mode);
 
+        if let Some(reverse) = self.reverse.take() {
+            return Self::serve_reverse(listener, self.handler, self.ws_handler, reverse, signal)
+                .await;
+        }
+
         // 1. Create RcgenAuthority from our CA certificate
         let ca_cert = self.ca.inner_certificate();
 
@@ -696,21 +1717,21 @@ impl ProxyServer {
         // 2. Build and start the Hudsucker proxy
         // with_rustls_client() creates a default HTTPS client automatically
         let proxy = Proxy::builder()
-            .with_addr(self.addr)
+            .with_listener(listener)
             .with_rustls_client()
             .with_ca(authority)
             .with_http_handler(self.handler)
+            .with_websocket_handler(self.ws_handler)
             .build();
 
         eprintln!("✅ Proxy built, about to start listening...");
         tracing::info!("✅ Proxy server ready on {}", self.addr);
         tracing::info!("📝 Listening for incoming connections...");
 
-        // Start the proxy with a never-ending future instead of waiting for Ctrl+C
-        // This allows the proxy to run in the background without blocking
+        // Serve until the shutdown signal completes, then drain in-flight requests
         eprintln!("📡 Calling proxy.start()...");
         proxy
-            .start(shutdown_signal())
+            .start(signal)
             .await
             .map_err(|e| MatgtoError::ProxyStartFailed {
                 reason: format!("Proxy server failed: {}", e),
@@ -721,11 +1742,65 @@ impl ProxyServer {
 
         Ok(())
     }
+
+    /// Serve plain HTTP requests as a reverse proxy until `signal` completes
+    async fn serve_reverse<F>(
+        listener: std::net::TcpListener,
+        handler: MatgtoHttpHandler,
+        ws_handler: MatgtoWebSocketHandler,
+        reverse: ReverseProxy,
+        signal: F,
+    ) -> Result<()>
+    where
+        F: Future<Output = ()>,
+    {
+        use hyper::server::conn::AddrStream;
+        use hyper::service::{make_service_fn, service_fn};
+
+        tracing::info!("🔁 Reverse proxy routes: {}", reverse);
+        let reverse = Arc::new(reverse);
+
+        let make_service = make_service_fn(move |conn: &AddrStream| {
+            let client = conn.remote_addr();
+            let handler = handler.clone();
+            let ws_handler = ws_handler.clone();
+            let reverse = reverse.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let handler = handler.clone();
+                    let ws_handler = ws_handler.clone();
+                    let reverse = reverse.clone();
+                    async move {
+                        let response = handler
+                            .handle_reverse(reverse, ws_handler, client, req)
+                            .await;
+                        Ok::<_, Infallible>(response)
+                    }
+                }))
+            }
+        });
+
+        hyper::Server::from_tcp(listener)
+            .map_err(|e| MatgtoError::ProxyStartFailed {
+                reason: format!("Failed to use listener: {}", e),
+            })?
+            .serve(make_service)
+            .with_graceful_shutdown(signal)
+            .await
+            .map_err(|e| MatgtoError::ProxyStartFailed {
+                reason: format!("Reverse proxy failed: {}", e),
+            })?;
+
+        tracing::info!("🛑 Proxy server stopped");
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::Headers;
     use tempfile::TempDir;
 
     #[test]
@@ -788,7 +1863,7 @@ mod tests {
             method: "POST".to_string(),
             url: "https://example.com/test".to_string(),
             headers: {
-                let mut h = HashMap::new();
+                let mut h = Headers::new();
                 h.insert("Content-Type".to_string(), "application/json".to_string());
                 h
             },
@@ -804,12 +1879,160 @@ mod tests {
         assert_eq!(reconstructed.uri(), "https://example.com/test");
     }
 
+    #[tokio::test]
+    async fn test_replay_recorded_uses_matching_strategy() {
+        use crate::matching::{MatchingStrategy, UrlMatchMode};
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut recorder = Recorder::new("strategy".to_string());
+        recorder.record_http(
+            HttpRequest {
+                method: "GET".to_string(),
+                url: "https://api.example.com/users?_t=1".to_string(),
+                headers: Headers::new(),
+                body: None,
+            },
+            HttpResponse {
+                status: 200,
+                headers: Headers::new(),
+                body: Some(b"[]".to_vec()),
+            },
+        );
+        recorder.save(temp_dir.path()).unwrap();
+
+        let live_request = HttpRequest {
+            method: "GET".to_string(),
+            url: "https://api.example.com/users?_t=2".to_string(),
+            headers: Headers::new(),
+            body: None,
+        };
+
+        // Default (exact) strategy: cache-busting param prevents the match
+        let player = Player::load(temp_dir.path(), "strategy").unwrap();
+        let handler =
+            MatgtoHttpHandler::new(ProxyMode::Replay).with_player(Arc::new(Mutex::new(player)));
+        assert!(handler.replay_recorded(&live_request).await.is_none());
+
+        // Ignoring the param makes the recorded response available
+        let player = Player::load(temp_dir.path(), "strategy")
+            .unwrap()
+            .with_matching_strategy(MatchingStrategy::new().with_url_mode(
+                UrlMatchMode::IgnoreQueryParams {
+                    params: vec!["_t".to_string()],
+                },
+            ));
+        let handler =
+            MatgtoHttpHandler::new(ProxyMode::Replay).with_player(Arc::new(Mutex::new(player)));
+        let response = handler.replay_recorded(&live_request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"[]");
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_runs_hooks_and_latency() {
+        use crate::cassette::Interaction;
+        use crate::hooks::ReplayHook;
+        use crate::player::LatencyMode;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Debug)]
+        struct RewriteBody(Arc<AtomicUsize>);
+
+        impl ReplayHook for RewriteBody {
+            fn before_replay(&self, interaction: &mut Interaction) -> Result<()> {
+                if let InteractionKind::Http { response, .. } = &mut interaction.kind {
+                    response.body = Some(b"rewritten".to_vec());
+                }
+                Ok(())
+            }
+
+            fn after_replay(&self, _interaction: &Interaction) -> Result<()> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let request = HttpRequest {
+            method: "GET".to_string(),
+            url: "https://api.example.com/slow".to_string(),
+            headers: Headers::new(),
+            body: None,
+        };
+        let mut recorder = Recorder::new("pipeline".to_string());
+        recorder.record_http_with_timing(
+            request.clone(),
+            HttpResponse {
+                status: 200,
+                headers: Headers::new(),
+                body: Some(b"original".to_vec()),
+            },
+            50,
+        );
+        recorder.save(temp_dir.path()).unwrap();
+
+        let replayed = Arc::new(AtomicUsize::new(0));
+        let mut player = Player::load(temp_dir.path(), "pipeline")
+            .unwrap()
+            .with_latency(LatencyMode::Recorded);
+        player.add_hook(RewriteBody(replayed.clone()));
+        let handler =
+            MatgtoHttpHandler::new(ProxyMode::Replay).with_player(Arc::new(Mutex::new(player)));
+
+        let started = std::time::Instant::now();
+        let response = handler.replay_recorded(&request).await.unwrap();
+        assert!(started.elapsed() >= std::time::Duration::from_millis(50));
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"rewritten");
+        assert_eq!(replayed.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "templates")]
+    #[tokio::test]
+    async fn test_replay_recorded_renders_templates() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut recorder = Recorder::new("templated".to_string());
+        recorder.record_http(
+            HttpRequest {
+                method: "GET".to_string(),
+                url: "https://api.example.com/me".to_string(),
+                headers: Headers::new(),
+                body: None,
+            },
+            HttpResponse {
+                status: 200,
+                headers: Headers::new(),
+                body: Some(b"User: {{ request.headers.x-user-id }}".to_vec()),
+            },
+        );
+        recorder.save(temp_dir.path()).unwrap();
+
+        let player = Player::load(temp_dir.path(), "templated").unwrap();
+        let handler =
+            MatgtoHttpHandler::new(ProxyMode::Replay).with_player(Arc::new(Mutex::new(player)));
+
+        let mut headers = Headers::new();
+        headers.insert("x-user-id".to_string(), "user123".to_string());
+        let live_request = HttpRequest {
+            method: "GET".to_string(),
+            url: "https://api.example.com/me".to_string(),
+            headers,
+            body: None,
+        };
+
+        let response = handler.replay_recorded(&live_request).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"User: user123");
+    }
+
     #[test]
     fn test_convert_response() {
         let http_resp = HttpResponse {
             status: 200,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = Headers::new();
                 h.insert("Content-Type".to_string(), "application/json".to_string());
                 h
             },
@@ -822,4 +2045,43 @@ mod tests {
         let response = result.unwrap();
         assert_eq!(response.status(), 200);
     }
+
+    #[tokio::test]
+    async fn test_convert_request_keeps_repeated_and_raw_headers() {
+        let request = Request::builder()
+            .method("GET")
+            .uri("https://example.com/")
+            .header("Via", "1.1 first")
+            .header("Via", "1.1 second")
+            .header("X-Raw", &[0xff, 0xfe][..])
+            .body(Body::empty())
+            .unwrap();
+
+        let (http_req, _) = MatgtoHttpHandler::convert_request(request).await.unwrap();
+
+        assert_eq!(
+            http_req.headers.get_all("via").collect::<Vec<_>>(),
+            vec!["1.1 first", "1.1 second"]
+        );
+        assert_eq!(http_req.headers.get_raw("x-raw"), Some(&[0xff, 0xfe][..]));
+    }
+
+    #[test]
+    fn test_convert_response_sends_every_set_cookie() {
+        let http_resp = HttpResponse {
+            status: 200,
+            headers: Headers::from([("set-cookie", "session=abc"), ("set-cookie", "theme=dark")]),
+            body: None,
+        };
+
+        let response = MatgtoHttpHandler::convert_response(&http_resp).unwrap();
+
+        let cookies: Vec<_> = response
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .map(|v| v.to_str().unwrap())
+            .collect();
+        assert_eq!(cookies, vec!["session=abc", "theme=dark"]);
+    }
 }
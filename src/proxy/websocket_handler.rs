@@ -0,0 +1,622 @@
+//! WebSocket handling for the MITM proxy
+//!
+//! Hudsucker upgrades WebSocket connections itself and forwards frames in both
+//! directions through a [`WebSocketHandler`]. `MatgtoWebSocketHandler` records
+//! those frames into the active cassette (and tunnels the upgrades received by
+//! the reverse-proxy listener), while `ReplaySession` answers an upgrade from a
+//! recorded session without reaching the upstream server.
+
+use crate::cassette::{CloseFrame, Direction, MessagePayload, WebSocketMessage};
+use crate::error::Result;
+use crate::proxy::client::HttpForwarder;
+use crate::proxy::ignore::IgnoreRules;
+use crate::recorder::Recorder;
+use crate::websocket::WebSocketPlayer;
+
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use hudsucker::{
+    hyper::{
+        self, header, http::uri::Scheme, upgrade::Upgraded, Body, Request, Response, StatusCode,
+        Uri,
+    },
+    tokio_tungstenite::{
+        tungstenite::{
+            self,
+            handshake::derive_accept_key,
+            protocol::{frame::coding::CloseCode, CloseFrame as WsCloseFrame, Role},
+            Message,
+        },
+        WebSocketStream,
+    },
+    WebSocketContext, WebSocketHandler,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Identifies one proxied connection; both of its forwarders share the key
+type SessionKey = (SocketAddr, String);
+
+/// A WebSocket connection currently being recorded
+#[derive(Debug)]
+struct LiveSession {
+    /// Index of the WebSocket interaction in the recorder's cassette
+    interaction: usize,
+
+    /// Connection start, message timestamps are relative to it
+    started: Instant,
+
+    /// Number of forwarders (directions) still running
+    open_directions: u8,
+}
+
+/// Hudsucker WebSocket handler that records both directions of every connection
+#[derive(Debug, Clone, Default)]
+pub struct MatgtoWebSocketHandler {
+    recorder: Option<Arc<Mutex<Recorder>>>,
+    ignore: Option<Arc<IgnoreRules>>,
+    sessions: Arc<Mutex<HashMap<SessionKey, LiveSession>>>,
+}
+
+impl MatgtoWebSocketHandler {
+    /// Create a handler that forwards frames without recording them
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record frames into the recorder's cassette
+    pub fn with_recorder(mut self, recorder: Arc<Mutex<Recorder>>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Forward connections to hosts matching `ignore` without recording them
+    pub fn with_ignore_rules(mut self, ignore: Arc<IgnoreRules>) -> Self {
+        self.ignore = Some(ignore);
+        self
+    }
+
+    /// Map a forwarder context to its connection key and message direction
+    ///
+    /// Hudsucker names contexts after its own sockets: `ServerToClient` forwards
+    /// from the socket it serves (the real client) to the upstream connection, so
+    /// it carries the frames the client sent.
+    fn session_key(ctx: &WebSocketContext) -> (SessionKey, Direction) {
+        match ctx {
+            WebSocketContext::ServerToClient { src, dst, .. } => {
+                ((*dst, src.to_string()), Direction::Sent)
+            }
+            WebSocketContext::ClientToServer { src, dst, .. } => {
+                ((*src, dst.to_string()), Direction::Received)
+            }
+        }
+    }
+
+    /// Register a forwarder, opening the session in the cassette on first use
+    async fn open_direction(&self, key: &SessionKey) {
+        let Some(recorder) = &self.recorder else {
+            return;
+        };
+        if let Some(ignore) = &self.ignore {
+            if ignore.matches_url(&key.1) {
+                return;
+            }
+        }
+
+        let mut sessions = self.sessions.lock().await;
+        match sessions.get_mut(key) {
+            Some(session) => session.open_directions += 1,
+            None => {
+                let interaction = recorder.lock().await.start_websocket(key.1.clone());
+                sessions.insert(
+                    key.clone(),
+                    LiveSession {
+                        interaction,
+                        started: Instant::now(),
+                        open_directions: 1,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Unregister a forwarder, forgetting the session once both directions ended
+    async fn close_direction(&self, key: &SessionKey) {
+        let mut sessions = self.sessions.lock().await;
+        if let Some(session) = sessions.get_mut(key) {
+            session.open_directions = session.open_directions.saturating_sub(1);
+            if session.open_directions == 0 {
+                sessions.remove(key);
+                tracing::info!("✅ WebSocket session recorded: {}", key.1);
+            }
+        }
+    }
+
+    /// Record a frame in its session
+    async fn record_frame(&self, key: &SessionKey, direction: Direction, message: &Message) {
+        let Some(recorder) = &self.recorder else {
+            return;
+        };
+
+        let sessions = self.sessions.lock().await;
+        let Some(session) = sessions.get(key) else {
+            return;
+        };
+
+        let mut recorder = recorder.lock().await;
+        match message {
+            Message::Close(frame) => {
+                if let Some(frame) = frame {
+                    recorder.record_websocket_close(
+                        session.interaction,
+                        CloseFrame {
+                            code: u16::from(frame.code),
+                            reason: frame.reason.to_string(),
+                        },
+                    );
+                }
+            }
+            message => {
+                if let Some(payload) = to_payload(message) {
+                    recorder.record_websocket_message(
+                        session.interaction,
+                        WebSocketMessage {
+                            direction,
+                            timestamp_ms: session.started.elapsed().as_millis() as u64,
+                            payload,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Tunnel a WebSocket upgrade received by the reverse-proxy listener
+    ///
+    /// The upgrade request is sent upstream through `forwarder`. Once both
+    /// sides switched protocols, frames flow through this handler as on
+    /// connections Hudsucker upgrades, so they are recorded the same way.
+    /// Upstream answers other than `101 Switching Protocols` are returned as is.
+    pub async fn tunnel(
+        self,
+        client: SocketAddr,
+        mut req: Request<Body>,
+        forwarder: &HttpForwarder,
+    ) -> Response<Body> {
+        let url = websocket_url(req.uri());
+        let client_upgrade = hyper::upgrade::on(&mut req);
+
+        let mut response = match forwarder.forward_request(req).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("❌ Cannot open WebSocket upstream for {}: {}", url, e);
+                return Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(Body::from(format!("Proxy error: {}", e)))
+                    .unwrap();
+            }
+        };
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            return response;
+        }
+
+        let upstream_upgrade = hyper::upgrade::on(&mut response);
+        tokio::spawn(async move {
+            let (client_io, upstream_io) = match tokio::try_join!(client_upgrade, upstream_upgrade)
+            {
+                Ok(upgraded) => upgraded,
+                Err(e) => {
+                    tracing::error!("Failed to upgrade WebSocket tunnel for {}: {}", url, e);
+                    return;
+                }
+            };
+
+            let client_socket =
+                WebSocketStream::from_raw_socket(client_io, Role::Server, None).await;
+            let upstream_socket =
+                WebSocketStream::from_raw_socket(upstream_io, Role::Client, None).await;
+            let (client_sink, client_stream) = client_socket.split();
+            let (upstream_sink, upstream_stream) = upstream_socket.split();
+
+            let key = (client, url);
+            tokio::spawn(self.clone().forward(
+                key.clone(),
+                Direction::Sent,
+                client_stream,
+                upstream_sink,
+            ));
+            self.forward(key, Direction::Received, upstream_stream, client_sink)
+                .await;
+        });
+
+        let (parts, _) = response.into_parts();
+        Response::from_parts(parts, Body::empty())
+    }
+
+    /// Forward the frames of one direction of a connection, recording them
+    ///
+    /// Same forwarding loop as Hudsucker's default, bracketed by session bookkeeping.
+    async fn forward(
+        self,
+        key: SessionKey,
+        direction: Direction,
+        mut stream: impl Stream<Item = std::result::Result<Message, tungstenite::Error>>
+            + Unpin
+            + Send
+            + 'static,
+        mut sink: impl Sink<Message, Error = tungstenite::Error> + Unpin + Send + 'static,
+    ) {
+        self.open_direction(&key).await;
+
+        while let Some(message) = stream.next().await {
+            match message {
+                Ok(message) => {
+                    self.record_frame(&key, direction, &message).await;
+
+                    match sink.send(message).await {
+                        Err(tungstenite::Error::ConnectionClosed) => (),
+                        Err(e) => tracing::error!("WebSocket send error: {}", e),
+                        _ => (),
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("WebSocket message error: {}", e);
+
+                    match sink.send(Message::Close(None)).await {
+                        Err(tungstenite::Error::ConnectionClosed) => (),
+                        Err(e) => tracing::error!("WebSocket close error: {}", e),
+                        _ => (),
+                    }
+
+                    break;
+                }
+            }
+        }
+
+        self.close_direction(&key).await;
+    }
+}
+
+#[async_trait::async_trait]
+impl WebSocketHandler for MatgtoWebSocketHandler {
+    async fn handle_websocket(
+        self,
+        ctx: WebSocketContext,
+        stream: impl Stream<Item = std::result::Result<Message, tungstenite::Error>>
+            + Unpin
+            + Send
+            + 'static,
+        sink: impl Sink<Message, Error = tungstenite::Error> + Unpin + Send + 'static,
+    ) {
+        let (key, direction) = Self::session_key(&ctx);
+        self.forward(key, direction, stream, sink).await;
+    }
+}
+
+/// A recorded WebSocket session ready to be served to a client
+#[derive(Debug, Clone)]
+pub struct ReplaySession {
+    /// Recorded frames with the delay to wait before each one
+    frames: Vec<(WebSocketMessage, Option<u64>)>,
+
+    /// Close frame sent once all frames were replayed
+    close_frame: Option<CloseFrame>,
+}
+
+impl ReplaySession {
+    /// Take the next recorded session for `url` from the player
+    ///
+    /// Delays are computed between consecutive frames using the player's latency mode.
+    pub fn next(player: &mut WebSocketPlayer, url: &str) -> Result<Self> {
+        let (messages, close_frame) = player.replay_session(url)?;
+
+        let mut previous = messages.first().map(|m| m.timestamp_ms).unwrap_or(0);
+        let frames = messages
+            .into_iter()
+            .map(|message| {
+                let delay = player.calculate_message_delay(message.timestamp_ms, previous);
+                previous = message.timestamp_ms;
+                (message, delay)
+            })
+            .collect();
+
+        Ok(Self {
+            frames,
+            close_frame,
+        })
+    }
+
+    /// Accept a WebSocket upgrade request and serve this session on it
+    ///
+    /// Returns the `101 Switching Protocols` response; the session itself runs in a
+    /// spawned task once the connection is upgraded.
+    pub fn serve(self, mut req: Request<Body>) -> Response<Body> {
+        let accept_key = match req.headers().get(header::SEC_WEBSOCKET_KEY) {
+            Some(key) => derive_accept_key(key.as_bytes()),
+            None => {
+                return Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(Body::from("Missing Sec-WebSocket-Key header"))
+                    .unwrap();
+            }
+        };
+
+        let on_upgrade = hyper::upgrade::on(&mut req);
+        tokio::spawn(async move {
+            match on_upgrade.await {
+                Ok(upgraded) => {
+                    let socket =
+                        WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                    self.play(socket).await;
+                }
+                Err(e) => tracing::error!("Failed to upgrade replayed WebSocket: {}", e),
+            }
+        });
+
+        Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::CONNECTION, "Upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_ACCEPT, accept_key)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    /// Replay the recorded frames on an upgraded connection
+    ///
+    /// Received frames are sent to the client; for each recorded Sent data frame we wait
+    /// for the client to send one, so replies never overtake the requests they answer.
+    async fn play(self, socket: WebSocketStream<Upgraded>) {
+        let (mut sink, mut stream) = socket.split();
+
+        for (message, delay) in self.frames {
+            match message.direction {
+                Direction::Sent => {
+                    if matches!(
+                        message.payload,
+                        MessagePayload::Ping { .. } | MessagePayload::Pong { .. }
+                    ) {
+                        continue;
+                    }
+
+                    loop {
+                        match stream.next().await {
+                            Some(Ok(Message::Text(_) | Message::Binary(_))) => break,
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                            Some(Ok(_)) => continue,
+                        }
+                    }
+                }
+                Direction::Received => {
+                    if let Some(delay_ms) = delay {
+                        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                    }
+
+                    if sink.send(to_message(&message.payload)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+
+        match self.close_frame {
+            Some(frame) => {
+                let _ = sink
+                    .send(Message::Close(Some(WsCloseFrame {
+                        code: CloseCode::from(frame.code),
+                        reason: frame.reason.into(),
+                    })))
+                    .await;
+            }
+            None => {
+                // The recording ended with the connection still open: keep it open
+                // until the client closes it
+                while let Some(Ok(message)) = stream.next().await {
+                    if message.is_close() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Check whether a request asks for a WebSocket upgrade
+pub fn is_websocket_upgrade(req: &Request<Body>) -> bool {
+    req.headers()
+        .get(header::UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.eq_ignore_ascii_case("websocket"))
+        .unwrap_or(false)
+}
+
+/// URL a WebSocket session is recorded under
+///
+/// Mirrors Hudsucker's upgrade: `http` becomes `ws`, any other scheme becomes `wss`.
+pub fn websocket_url(uri: &Uri) -> String {
+    let mut parts = uri.clone().into_parts();
+    let secure = parts.scheme.as_ref().unwrap_or(&Scheme::HTTP) != &Scheme::HTTP;
+    parts.scheme = Some(
+        if secure { "wss" } else { "ws" }
+            .parse()
+            .expect("valid scheme"),
+    );
+
+    Uri::from_parts(parts)
+        .map(|uri| uri.to_string())
+        .unwrap_or_else(|_| uri.to_string())
+}
+
+/// Convert a tungstenite message to a cassette payload (None for control frames)
+fn to_payload(message: &Message) -> Option<MessagePayload> {
+    match message {
+        Message::Text(data) => Some(MessagePayload::Text { data: data.clone() }),
+        Message::Binary(data) => Some(MessagePayload::Binary { data: data.clone() }),
+        Message::Ping(data) => Some(MessagePayload::Ping { data: data.clone() }),
+        Message::Pong(data) => Some(MessagePayload::Pong { data: data.clone() }),
+        _ => None,
+    }
+}
+
+/// Convert a cassette payload back to a tungstenite message
+fn to_message(payload: &MessagePayload) -> Message {
+    match payload {
+        MessagePayload::Text { data } => Message::Text(data.clone()),
+        MessagePayload::Binary { data } => Message::Binary(data.clone()),
+        MessagePayload::Ping { data } => Message::Ping(data.clone()),
+        MessagePayload::Pong { data } => Message::Pong(data.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cassette::{Cassette, Interaction, InteractionKind};
+    use crate::player::LatencyMode;
+
+    #[test]
+    fn test_websocket_url() {
+        let uri: Uri = "http://example.com/socket?x=1".parse().unwrap();
+        assert_eq!(websocket_url(&uri), "ws://example.com/socket?x=1");
+
+        let uri: Uri = "https://example.com:443/feed".parse().unwrap();
+        assert_eq!(websocket_url(&uri), "wss://example.com:443/feed");
+    }
+
+    #[test]
+    fn test_is_websocket_upgrade() {
+        let req = Request::builder()
+            .uri("http://example.com/socket")
+            .header("Upgrade", "WebSocket")
+            .body(Body::empty())
+            .unwrap();
+        assert!(is_websocket_upgrade(&req));
+
+        let req = Request::builder()
+            .uri("http://example.com/")
+            .body(Body::empty())
+            .unwrap();
+        assert!(!is_websocket_upgrade(&req));
+    }
+
+    #[test]
+    fn test_payload_roundtrip() {
+        for message in [
+            Message::Text("hello".to_string()),
+            Message::Binary(vec![1, 2, 3]),
+            Message::Ping(vec![9]),
+            Message::Pong(vec![9]),
+        ] {
+            let payload = to_payload(&message).unwrap();
+            assert_eq!(to_message(&payload), message);
+        }
+
+        assert!(to_payload(&Message::Close(None)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_record_both_directions() {
+        let recorder = Arc::new(Mutex::new(Recorder::new("ws".to_string())));
+        let handler = MatgtoWebSocketHandler::new().with_recorder(recorder.clone());
+        let key: SessionKey = (
+            "127.0.0.1:50000".parse().unwrap(),
+            "ws://example.com/feed".to_string(),
+        );
+
+        // Both forwarders register before frames flow
+        handler.open_direction(&key).await;
+        handler.open_direction(&key).await;
+
+        handler
+            .record_frame(&key, Direction::Sent, &Message::Text("subscribe".into()))
+            .await;
+        handler
+            .record_frame(&key, Direction::Received, &Message::Text("tick".into()))
+            .await;
+        handler
+            .record_frame(
+                &key,
+                Direction::Sent,
+                &Message::Close(Some(WsCloseFrame {
+                    code: CloseCode::Normal,
+                    reason: "done".into(),
+                })),
+            )
+            .await;
+
+        handler.close_direction(&key).await;
+        assert_eq!(handler.sessions.lock().await.len(), 1);
+        handler.close_direction(&key).await;
+        assert!(handler.sessions.lock().await.is_empty());
+
+        let recorder = recorder.lock().await;
+        let interactions = &recorder.cassette().interactions;
+        assert_eq!(interactions.len(), 1);
+        match &interactions[0].kind {
+            InteractionKind::WebSocket {
+                url,
+                messages,
+                close_frame,
+            } => {
+                assert_eq!(url, "ws://example.com/feed");
+                assert_eq!(messages.len(), 2);
+                assert_eq!(messages[0].direction, Direction::Sent);
+                assert_eq!(messages[1].direction, Direction::Received);
+                assert_eq!(close_frame.as_ref().map(|f| f.code), Some(1000));
+            }
+            _ => panic!("Expected WebSocket interaction"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_recorder_records_nothing() {
+        let handler = MatgtoWebSocketHandler::new();
+        let key: SessionKey = (
+            "127.0.0.1:50001".parse().unwrap(),
+            "ws://example.com/feed".to_string(),
+        );
+
+        handler.open_direction(&key).await;
+        handler
+            .record_frame(&key, Direction::Sent, &Message::Text("hi".into()))
+            .await;
+
+        assert!(handler.sessions.lock().await.is_empty());
+    }
+
+    #[test]
+    fn test_replay_session_delays() {
+        let message = |timestamp_ms, data: &str| WebSocketMessage {
+            direction: Direction::Received,
+            timestamp_ms,
+            payload: MessagePayload::Text {
+                data: data.to_string(),
+            },
+        };
+
+        let mut cassette = Cassette::new("ws".to_string());
+        cassette.interactions.push(Interaction {
+            kind: InteractionKind::WebSocket {
+                url: "ws://example.com/feed".to_string(),
+                messages: vec![message(100, "a"), message(150, "b"), message(400, "c")],
+                close_frame: None,
+            },
+            recorded_at: chrono::Utc::now(),
+            response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
+        });
+
+        let mut player = WebSocketPlayer::new().with_latency(LatencyMode::Recorded);
+        player.load_cassette(cassette);
+
+        let session = ReplaySession::next(&mut player, "ws://example.com/feed").unwrap();
+        let delays: Vec<_> = session.frames.iter().map(|(_, d)| *d).collect();
+        assert_eq!(delays, vec![Some(0), Some(50), Some(250)]);
+
+        // The only recorded session has been consumed
+        assert!(ReplaySession::next(&mut player, "ws://example.com/feed").is_err());
+    }
+}
@@ -1,14 +1,24 @@
-//! Recording HTTP/WebSocket interactions to cassettes
+//! Recording HTTP/SSE/WebSocket/gRPC interactions to cassettes
 
 use crate::cassette::{
-    Cassette, HttpRequest, HttpResponse, Interaction, InteractionKind, NetworkError,
+    storage, Cassette, CassetteFormat, CloseFrame, GrpcMessage, GrpcRequest, GrpcResponse,
+    HttpRequest, HttpResponse, HttpVersion, Interaction, InteractionKind, NetworkError, SseEvent,
+    WebSocketMessage,
 };
+use crate::content_encoding;
+use crate::cookies::{Cookie, CookiePolicy};
 use crate::error::Result;
 use crate::filters::RecordingFilters;
 use crate::hooks::RecordHooks;
-use std::fs::File;
+use crate::rerecord::ReRecordSummary;
+use crate::sensitive::SensitiveData;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::path::Path;
 
+/// Largest body recorded by default (10 MiB)
+pub const DEFAULT_MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+
 /// Records HTTP/WebSocket interactions
 #[derive(Debug)]
 pub struct Recorder {
@@ -23,6 +33,24 @@ pub struct Recorder {
 
     /// Record hooks
     hooks: RecordHooks,
+
+    /// Sensitive values replaced with placeholders before hooks run
+    sensitive_data: SensitiveData,
+
+    /// Which cookies are kept in the cassette, and how
+    cookie_policy: CookiePolicy,
+
+    /// Format used when saving the cassette
+    format: CassetteFormat,
+
+    /// Largest response body kept in the cassette, in bytes
+    max_body_size: u64,
+
+    /// Stale and fresh interaction counts, when re-recording an existing cassette
+    re_record: Option<ReRecordSummary>,
+
+    /// Stale interactions replaced by a new recording, dropped on save
+    expired: BTreeSet<usize>,
 }
 
 impl Recorder {
@@ -35,6 +63,12 @@ impl Recorder {
             cassette,
             filters: None,
             hooks: RecordHooks::new(),
+            sensitive_data: SensitiveData::new(),
+            cookie_policy: CookiePolicy::new(),
+            format: CassetteFormat::default(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            re_record: None,
+            expired: BTreeSet::new(),
         }
     }
 
@@ -47,9 +81,73 @@ impl Recorder {
             cassette,
             filters: Some(filters),
             hooks: RecordHooks::new(),
+            sensitive_data: SensitiveData::new(),
+            cookie_policy: CookiePolicy::new(),
+            format: CassetteFormat::default(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            re_record: None,
+            expired: BTreeSet::new(),
         }
     }
 
+    /// Set the format used when saving the cassette
+    pub fn with_format(mut self, format: CassetteFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the format used when saving the cassette
+    pub fn set_format(&mut self, format: CassetteFormat) {
+        self.format = format;
+    }
+
+    /// Get the format used when saving the cassette
+    pub fn format(&self) -> CassetteFormat {
+        self.format
+    }
+
+    /// Set the largest response body that is recorded
+    pub fn with_max_body_size(mut self, bytes: u64) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Set the largest response body that is recorded
+    ///
+    /// The proxy streams responses to the client while copying them for the
+    /// cassette. Once a body grows past this size the copy is dropped and the
+    /// interaction is not recorded; the client still receives the whole body.
+    pub fn set_max_body_size(&mut self, bytes: u64) {
+        self.max_body_size = bytes;
+    }
+
+    /// Get the largest response body that is recorded
+    pub fn max_body_size(&self) -> u64 {
+        self.max_body_size
+    }
+
+    /// Replace sensitive values with placeholders in recorded interactions
+    pub fn with_sensitive_data(mut self, sensitive_data: SensitiveData) -> Self {
+        self.sensitive_data = sensitive_data;
+        self
+    }
+
+    /// Get the sensitive data filter
+    pub fn sensitive_data(&self) -> &SensitiveData {
+        &self.sensitive_data
+    }
+
+    /// Set which cookies are stored in the cassette, and how
+    pub fn with_cookie_policy(mut self, policy: CookiePolicy) -> Self {
+        self.cookie_policy = policy;
+        self
+    }
+
+    /// Get the cookie policy
+    pub fn cookie_policy(&self) -> &CookiePolicy {
+        &self.cookie_policy
+    }
+
     /// Set recording filters
     pub fn set_filters(&mut self, filters: RecordingFilters) {
         self.filters = Some(filters);
@@ -60,6 +158,12 @@ impl Recorder {
         self.filters.as_ref()
     }
 
+    /// Replace the record hooks
+    pub fn with_hooks(mut self, hooks: RecordHooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
     /// Add a record hook
     pub fn add_hook<H: crate::hooks::RecordHook + 'static>(&mut self, hook: H) {
         self.hooks.add(hook);
@@ -72,6 +176,47 @@ impl Recorder {
 
     /// Record an HTTP interaction
     pub fn record_http(&mut self, request: HttpRequest, response: HttpResponse) {
+        self.record_http_timed(request, response, None, None);
+    }
+
+    /// Record an HTTP interaction with its upstream response time
+    ///
+    /// The timing is what `LatencyMode::Recorded` and `LatencyMode::Scaled` replay.
+    pub fn record_http_with_timing(
+        &mut self,
+        request: HttpRequest,
+        response: HttpResponse,
+        response_time_ms: u64,
+    ) {
+        self.record_http_timed(request, response, Some(response_time_ms), None);
+    }
+
+    /// Record an HTTP interaction with its response time and negotiated HTTP version
+    pub fn record_http_exchange(
+        &mut self,
+        request: HttpRequest,
+        response: HttpResponse,
+        response_time_ms: u64,
+        http_version: HttpVersion,
+    ) {
+        self.record_http_timed(
+            request,
+            response,
+            Some(response_time_ms),
+            Some(http_version),
+        );
+    }
+
+    fn record_http_timed(
+        &mut self,
+        mut request: HttpRequest,
+        mut response: HttpResponse,
+        response_time_ms: Option<u64>,
+        http_version: Option<HttpVersion>,
+    ) {
+        // Store compressed bodies decoded, remembering their encoding
+        let content_encoding = content_encoding::decode_response(&mut response);
+
         // Apply filters if configured
         if let Some(filters) = &self.filters {
             // Check if interaction should be recorded
@@ -85,13 +230,21 @@ impl Recorder {
             }
         }
 
+        // Drop or hash sensitive cookies, keeping the ones the response sets
+        let cookies = self.cookie_policy.apply(&mut request, &mut response);
+
         // Create interaction
         let mut interaction = Interaction {
             kind: InteractionKind::Http { request, response },
             recorded_at: chrono::Utc::now(),
-            response_time_ms: None,
+            response_time_ms,
+            http_version,
+            content_encoding,
         };
 
+        // Replace sensitive values with placeholders
+        self.sensitive_data.redact(&mut interaction);
+
         // Call before_record hooks
         if let Err(e) = self.hooks.before_record(&mut interaction) {
             tracing::error!("Hook before_record failed: {}", e);
@@ -100,6 +253,7 @@ impl Recorder {
 
         // Add to cassette
         self.cassette.interactions.push(interaction.clone());
+        self.store_cookies(cookies);
 
         // Call after_record hooks
         if let Err(e) = self.hooks.after_record(&interaction) {
@@ -122,8 +276,13 @@ impl Recorder {
             kind: InteractionKind::HttpError { request, error },
             recorded_at: chrono::Utc::now(),
             response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
         };
 
+        // Replace sensitive values with placeholders
+        self.sensitive_data.redact(&mut interaction);
+
         // Call before_record hooks
         if let Err(e) = self.hooks.before_record(&mut interaction) {
             tracing::error!("Hook before_record failed for error: {}", e);
@@ -139,21 +298,231 @@ impl Recorder {
         }
     }
 
-    /// Save the cassette to disk
-    pub fn save(&self, cassette_dir: &Path) -> Result<()> {
-        // Ensure directory exists
-        std::fs::create_dir_all(cassette_dir)?;
+    /// Open a Server-Sent Events stream in the cassette
+    ///
+    /// `response` holds the status and headers. Returns the interaction index
+    /// that events are appended to as they arrive, so a stream is saved even
+    /// if it never closes, or None when filters or hooks exclude it.
+    pub fn start_sse(
+        &mut self,
+        mut request: HttpRequest,
+        mut response: HttpResponse,
+        response_time_ms: u64,
+        http_version: Option<HttpVersion>,
+    ) -> Option<usize> {
+        if let Some(filters) = &self.filters {
+            if !filters.should_record(&request, &response) {
+                tracing::debug!(
+                    "Skipping recording for {} {} (filtered)",
+                    request.method,
+                    request.url
+                );
+                return None;
+            }
+        }
+
+        tracing::info!("Recording event stream: {} {}", request.method, request.url);
+        let cookies = self.cookie_policy.apply(&mut request, &mut response);
+
+        let mut interaction = Interaction {
+            kind: InteractionKind::ServerSentEvents {
+                request,
+                response,
+                events: Vec::new(),
+            },
+            recorded_at: chrono::Utc::now(),
+            response_time_ms: Some(response_time_ms),
+            http_version,
+            content_encoding: None,
+        };
+
+        // Replace sensitive values with placeholders
+        self.sensitive_data.redact(&mut interaction);
+
+        // Call before_record hooks
+        if let Err(e) = self.hooks.before_record(&mut interaction) {
+            tracing::error!("Hook before_record failed for event stream: {}", e);
+            return None;
+        }
+
+        // Add to cassette
+        self.cassette.interactions.push(interaction.clone());
+        self.store_cookies(cookies);
+
+        // Call after_record hooks
+        if let Err(e) = self.hooks.after_record(&interaction) {
+            tracing::warn!("Hook after_record failed for event stream: {}", e);
+        }
+
+        Some(self.cassette.interactions.len() - 1)
+    }
+
+    /// Append an event to a stream opened with `start_sse`
+    pub fn record_sse_event(&mut self, stream: usize, mut event: SseEvent) {
+        if let Some(InteractionKind::ServerSentEvents { events, .. }) = self
+            .cassette
+            .interactions
+            .get_mut(stream)
+            .map(|i| &mut i.kind)
+        {
+            self.sensitive_data.redact_event(&mut event);
+            events.push(event);
+        }
+    }
+
+    /// Record a completed gRPC call
+    ///
+    /// `response_time_ms` is the time until the response headers arrived;
+    /// message timings are kept in each message.
+    pub fn record_grpc(
+        &mut self,
+        request: GrpcRequest,
+        response: GrpcResponse,
+        messages: Vec<GrpcMessage>,
+        response_time_ms: u64,
+    ) {
+        tracing::info!(
+            "Recording gRPC call {}/{} ({} messages, status {})",
+            request.service,
+            request.method,
+            messages.len(),
+            response.status
+        );
+
+        let mut interaction = Interaction {
+            kind: InteractionKind::Grpc {
+                request,
+                response,
+                messages,
+            },
+            recorded_at: chrono::Utc::now(),
+            response_time_ms: Some(response_time_ms),
+            http_version: Some(HttpVersion::Http2),
+            content_encoding: None,
+        };
 
-        let path = cassette_dir.join(format!("{}.json", self.cassette_name));
-        let file = File::create(path)?;
+        // Replace sensitive values with placeholders
+        self.sensitive_data.redact(&mut interaction);
 
-        serde_json::to_writer_pretty(file, &self.cassette)?;
+        // Call before_record hooks
+        if let Err(e) = self.hooks.before_record(&mut interaction) {
+            tracing::error!("Hook before_record failed for gRPC call: {}", e);
+            return;
+        }
+
+        // Add to cassette
+        self.cassette.interactions.push(interaction.clone());
+
+        // Call after_record hooks
+        if let Err(e) = self.hooks.after_record(&interaction) {
+            tracing::warn!("Hook after_record failed for gRPC call: {}", e);
+        }
+    }
+
+    /// Open a WebSocket session in the cassette
+    ///
+    /// Returns the interaction index that frames are appended to while the
+    /// connection is alive, so a session is saved even if it never closes.
+    pub fn start_websocket(&mut self, url: String) -> usize {
+        tracing::info!("Recording WebSocket session: {}", url);
+
+        let mut interaction = Interaction {
+            kind: InteractionKind::WebSocket {
+                url,
+                messages: Vec::new(),
+                close_frame: None,
+            },
+            recorded_at: chrono::Utc::now(),
+            response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
+        };
+        self.sensitive_data.redact(&mut interaction);
+        self.cassette.interactions.push(interaction);
+
+        self.cassette.interactions.len() - 1
+    }
+
+    /// Append a frame to a WebSocket session opened with `start_websocket`
+    pub fn record_websocket_message(&mut self, session: usize, mut message: WebSocketMessage) {
+        if let Some(InteractionKind::WebSocket { messages, .. }) = self
+            .cassette
+            .interactions
+            .get_mut(session)
+            .map(|i| &mut i.kind)
+        {
+            self.sensitive_data.redact_message(&mut message);
+            messages.push(message);
+        }
+    }
+
+    /// Store the close frame of a WebSocket session opened with `start_websocket`
+    pub fn record_websocket_close(&mut self, session: usize, frame: CloseFrame) {
+        if let Some(InteractionKind::WebSocket { close_frame, .. }) = self
+            .cassette
+            .interactions
+            .get_mut(session)
+            .map(|i| &mut i.kind)
+        {
+            *close_frame = Some(frame);
+        }
+    }
+
+    /// Keep cookies set by a recorded response in the cassette
+    ///
+    /// A cookie replaces the one with the same name, domain and path.
+    fn store_cookies(&mut self, cookies: Vec<Cookie>) {
+        self.cassette.merge_cookies(cookies);
+    }
+
+    /// Re-record stale interactions of the copied cassette
+    pub fn with_re_record(mut self, stale: usize, fresh: usize) -> Self {
+        self.re_record = Some(ReRecordSummary {
+            stale,
+            re_recorded: 0,
+            fresh,
+        });
+        self
+    }
+
+    /// Replace a stale interaction: it is dropped when the cassette is saved
+    pub fn expire(&mut self, idx: usize) {
+        self.expired.insert(idx);
+    }
+
+    /// Progress of re-recording stale interactions
+    pub fn re_record_summary(&self) -> Option<ReRecordSummary> {
+        self.re_record.map(|summary| ReRecordSummary {
+            re_recorded: self.expired.len(),
+            ..summary
+        })
+    }
+
+    /// Save the cassette to disk in the recorder's format
+    pub fn save(&self, cassette_dir: &Path) -> Result<()> {
+        let cassette = if self.expired.is_empty() {
+            Cow::Borrowed(&self.cassette)
+        } else {
+            let mut cassette = self.cassette.clone();
+            let mut idx = 0;
+            cassette.interactions.retain(|_| {
+                idx += 1;
+                !self.expired.contains(&(idx - 1))
+            });
+            Cow::Owned(cassette)
+        };
+
+        let path = storage::cassette_path(cassette_dir, &self.cassette_name, self.format);
+        storage::save_cassette(&cassette, &path, self.format)?;
 
         tracing::info!(
             "Saved cassette '{}' with {} interactions",
             self.cassette_name,
-            self.cassette.interactions.len()
+            cassette.interactions.len()
         );
+        if let Some(summary) = self.re_record_summary() {
+            tracing::info!("♻️  Cassette '{}': {}", self.cassette_name, summary);
+        }
 
         Ok(())
     }
@@ -172,7 +541,7 @@ impl Recorder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::cassette::Headers;
     use tempfile::tempdir;
 
     #[test]
@@ -189,13 +558,13 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"users\":[]}".to_vec()),
         };
 
@@ -211,13 +580,13 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"users\":[]}".to_vec()),
         };
 
@@ -231,6 +600,38 @@ mod tests {
         assert!(cassette_path.exists());
     }
 
+    #[cfg(all(feature = "msgpack", feature = "compression"))]
+    #[test]
+    fn test_save_cassette_in_configured_format() {
+        use crate::player::Player;
+
+        let mut recorder =
+            Recorder::new("test-format".to_string()).with_format(CassetteFormat::MessagePackGzip);
+        recorder.record_http(
+            HttpRequest {
+                method: "GET".to_string(),
+                url: "https://api.example.com/users".to_string(),
+                headers: Headers::new(),
+                body: None,
+            },
+            HttpResponse {
+                status: 200,
+                headers: Headers::new(),
+                body: None,
+            },
+        );
+
+        let dir = tempdir().unwrap();
+        recorder.save(dir.path()).unwrap();
+        assert!(dir.path().join("test-format.msgpack.gz").exists());
+        assert!(!dir.path().join("test-format.json").exists());
+
+        // The player detects the format on load
+        let player = Player::load(dir.path(), "test-format").unwrap();
+        assert_eq!(player.format(), CassetteFormat::MessagePackGzip);
+        assert_eq!(player.cassette().unwrap().interactions.len(), 1);
+    }
+
     #[test]
     fn test_record_http_error_timeout() {
         let mut recorder = Recorder::new("test-error".to_string());
@@ -238,7 +639,7 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://slow-api.example.com/timeout".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
@@ -271,7 +672,7 @@ mod tests {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://nonexistent.invalid/api".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
@@ -297,7 +698,7 @@ mod tests {
         let request = HttpRequest {
             method: "POST".to_string(),
             url: "http://localhost:9999/api".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"test\":true}".to_vec()),
         };
 
@@ -326,12 +727,12 @@ mod tests {
         let request1 = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/success".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
         let response1 = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"OK".to_vec()),
         };
         recorder.record_http(request1, response1);
@@ -340,7 +741,7 @@ mod tests {
         let request2 = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/timeout".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
         let error = NetworkError::timeout("Request timed out", 3000);
@@ -350,12 +751,12 @@ mod tests {
         let request3 = HttpRequest {
             method: "POST".to_string(),
             url: "https://api.example.com/data".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"data\":1}".to_vec()),
         };
         let response3 = HttpResponse {
             status: 201,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"id\":123}".to_vec()),
         };
         recorder.record_http(request3, response3);
@@ -377,4 +778,55 @@ mod tests {
             InteractionKind::Http { .. }
         ));
     }
+
+    #[test]
+    fn test_record_websocket_session() {
+        use crate::cassette::{CloseFrame, Direction, MessagePayload, WebSocketMessage};
+
+        let mut recorder = Recorder::new("test-ws".to_string());
+
+        let session = recorder.start_websocket("ws://example.com/feed".to_string());
+        recorder.record_websocket_message(
+            session,
+            WebSocketMessage {
+                direction: Direction::Sent,
+                timestamp_ms: 0,
+                payload: MessagePayload::Text {
+                    data: "subscribe".to_string(),
+                },
+            },
+        );
+        recorder.record_websocket_message(
+            session,
+            WebSocketMessage {
+                direction: Direction::Received,
+                timestamp_ms: 15,
+                payload: MessagePayload::Text {
+                    data: "tick".to_string(),
+                },
+            },
+        );
+        recorder.record_websocket_close(
+            session,
+            CloseFrame {
+                code: 1000,
+                reason: "bye".to_string(),
+            },
+        );
+
+        assert_eq!(recorder.cassette().interactions.len(), 1);
+        match &recorder.cassette().interactions[session].kind {
+            InteractionKind::WebSocket {
+                url,
+                messages,
+                close_frame,
+            } => {
+                assert_eq!(url, "ws://example.com/feed");
+                assert_eq!(messages.len(), 2);
+                assert_eq!(messages[1].direction, Direction::Received);
+                assert_eq!(close_frame.as_ref().map(|f| f.code), Some(1000));
+            }
+            _ => panic!("Expected WebSocket interaction"),
+        }
+    }
 }
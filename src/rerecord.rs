@@ -0,0 +1,146 @@
+//! Automatic re-recording of expired interactions
+//!
+//! Cassettes drift away from the APIs they were recorded from. With a
+//! re-record interval, interactions older than the interval (or all of them,
+//! when the cassette itself is older) are stale: in Auto, Hybrid and Once
+//! modes a request matching a stale interaction is fetched from upstream
+//! again and the new exchange replaces the stale one when the cassette is
+//! saved. Fresh interactions keep replaying, and stale ones that were not
+//! requested stay as they are.
+//!
+//! ```toml
+//! [re_record]
+//! interval_days = 30
+//!
+//! [re_record.cassettes]
+//! auth = 7
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Re-record settings, as written in magneto.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReRecordConfig {
+    /// Interval for every cassette, in days (None = never re-record)
+    pub interval_days: Option<u64>,
+
+    /// Per-cassette intervals in days, overriding `interval_days`
+    pub cassettes: HashMap<String, u64>,
+}
+
+impl ReRecordConfig {
+    /// Build the configured policy
+    pub fn resolve(&self) -> ReRecordPolicy {
+        let days = |days: u64| Duration::from_secs(days * SECONDS_PER_DAY);
+
+        let mut policy = ReRecordPolicy::new();
+        policy.interval = self.interval_days.map(days);
+        for (cassette, interval) in &self.cassettes {
+            policy = policy.with_cassette_interval(cassette, days(*interval));
+        }
+        policy
+    }
+}
+
+/// When interactions are old enough to be re-recorded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReRecordPolicy {
+    interval: Option<Duration>,
+    per_cassette: HashMap<String, Duration>,
+}
+
+impl ReRecordPolicy {
+    /// Create a policy that never re-records
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Re-record interactions older than `interval` in every cassette
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Re-record interactions of `cassette` older than `interval`
+    pub fn with_cassette_interval(mut self, cassette: &str, interval: Duration) -> Self {
+        self.per_cassette.insert(cassette.to_string(), interval);
+        self
+    }
+
+    /// Interval applying to `cassette` (None = never re-record)
+    pub fn interval_for(&self, cassette: &str) -> Option<Duration> {
+        self.per_cassette.get(cassette).copied().or(self.interval)
+    }
+}
+
+/// Outcome of re-recording a cassette, logged when it is saved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReRecordSummary {
+    /// Interactions older than the interval when the cassette was loaded
+    pub stale: usize,
+
+    /// Stale interactions fetched again and replaced
+    pub re_recorded: usize,
+
+    /// Interactions within the interval, kept as they were
+    pub fresh: usize,
+}
+
+impl std::fmt::Display for ReRecordSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "re-recorded {} of {} stale interactions, {} left stale, {} fresh kept",
+            self.re_recorded,
+            self.stale,
+            self.stale - self.re_recorded,
+            self.fresh
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_intervals() {
+        let policy = ReRecordPolicy::new();
+        assert_eq!(policy.interval_for("api"), None);
+
+        let policy = policy
+            .with_interval(Duration::from_secs(60))
+            .with_cassette_interval("auth", Duration::from_secs(10));
+        assert_eq!(policy.interval_for("api"), Some(Duration::from_secs(60)));
+        assert_eq!(policy.interval_for("auth"), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_config_resolve() {
+        let config: ReRecordConfig = toml::from_str(
+            r#"
+            interval_days = 30
+
+            [cassettes]
+            auth = 7
+            "#,
+        )
+        .unwrap();
+
+        let policy = config.resolve();
+        assert_eq!(
+            policy.interval_for("api"),
+            Some(Duration::from_secs(30 * SECONDS_PER_DAY))
+        );
+        assert_eq!(
+            policy.interval_for("auth"),
+            Some(Duration::from_secs(7 * SECONDS_PER_DAY))
+        );
+        assert_eq!(ReRecordConfig::default().resolve(), ReRecordPolicy::new());
+    }
+}
@@ -0,0 +1,958 @@
+//! Built-in filtering of sensitive data
+//!
+//! Secrets never reach the cassette: at record time header values, query
+//! parameters, JSON body fields, regex matches and the values of selected
+//! environment variables are replaced with placeholders such as `<API_KEY>`.
+//!
+//! On replay the live request is redacted the same way before matching, so
+//! a request carrying the real token matches the interaction recorded with
+//! its placeholder. Placeholders in the replayed response are substituted
+//! back with the real values seen in the live request (or read from the
+//! environment).
+//!
+//! ```toml
+//! [sensitive_data]
+//! headers = ["authorization", "x-api-key"]
+//! query_params = ["api_key"]
+//! json_paths = ["password", "auth.token", "users.*.ssn"]
+//! env = ["STRIPE_SECRET_KEY"]
+//!
+//! [[sensitive_data.patterns]]
+//! regex = "sk_live_[A-Za-z0-9]+"
+//! placeholder = "<STRIPE_KEY>"
+//! ```
+//!
+//! Headers, query parameters and JSON paths get a placeholder derived from
+//! their name (`x-api-key` → `<X_API_KEY>`, `auth.token` → `<TOKEN>`);
+//! environment secrets are named after their variable. JSON paths use the
+//! matcher's `user.name` syntax, with array indices and `*` for every
+//! element or field.
+//!
+//! gRPC messages are redacted in their decoded form (JSON paths and text)
+//! and in their protobuf encoding, where the text fields holding a redacted
+//! value are rewritten. Compressed messages are stored as received.
+
+use crate::cassette::{
+    GrpcMessage, Headers, HttpRequest, HttpResponse, Interaction, InteractionKind, MessagePayload,
+    SseEvent, WebSocketMessage,
+};
+use crate::error::{MatgtoError, Result};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Real values replaced during a redaction, as (placeholder, value) pairs
+type Captured = Vec<(String, String)>;
+
+/// Deepest protobuf message nesting rewritten when redacting gRPC payloads
+const MAX_PROTOBUF_DEPTH: usize = 32;
+
+/// Sensitive data settings, as written in magneto.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SensitiveDataConfig {
+    /// Headers whose values are replaced (case-insensitive)
+    pub headers: Vec<String>,
+
+    /// Query parameters whose values are replaced
+    pub query_params: Vec<String>,
+
+    /// JSON body fields whose values are replaced
+    pub json_paths: Vec<String>,
+
+    /// Environment variables whose values are replaced wherever they appear
+    pub env: Vec<String>,
+
+    /// Regexes whose matches are replaced
+    pub patterns: Vec<SensitivePattern>,
+}
+
+/// A regex and the placeholder its matches are replaced with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensitivePattern {
+    pub regex: String,
+    pub placeholder: String,
+}
+
+impl SensitiveDataConfig {
+    /// Build the configured filter
+    ///
+    /// Fails on an invalid regex.
+    pub fn resolve(&self) -> Result<SensitiveData> {
+        let mut data = SensitiveData::new();
+        for header in &self.headers {
+            data = data.with_header(header);
+        }
+        for param in &self.query_params {
+            data = data.with_query_param(param);
+        }
+        for path in &self.json_paths {
+            data = data.with_json_path(path);
+        }
+        for var in &self.env {
+            data = data.with_env(var);
+        }
+        for pattern in &self.patterns {
+            data = data.with_pattern(&pattern.regex, &pattern.placeholder)?;
+        }
+        Ok(data)
+    }
+}
+
+/// Replaces sensitive values with placeholders, and restores them on replay
+#[derive(Debug, Clone, Default)]
+pub struct SensitiveData {
+    /// (header name, placeholder)
+    headers: Vec<(String, String)>,
+
+    /// (query parameter, placeholder)
+    query_params: Vec<(String, String)>,
+
+    /// (JSON path, placeholder)
+    json_paths: Vec<(String, String)>,
+
+    /// (regex, placeholder)
+    patterns: Vec<(Regex, String)>,
+
+    /// (literal value, placeholder)
+    secrets: Vec<(String, String)>,
+}
+
+impl SensitiveData {
+    /// Create a filter that redacts nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether nothing is redacted
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+            && self.query_params.is_empty()
+            && self.json_paths.is_empty()
+            && self.patterns.is_empty()
+            && self.secrets.is_empty()
+    }
+
+    /// Redact the values of a header, in requests and responses
+    pub fn with_header(mut self, name: &str) -> Self {
+        self.headers.push((name.to_string(), placeholder(name)));
+        self
+    }
+
+    /// Redact the value of a query parameter
+    pub fn with_query_param(mut self, name: &str) -> Self {
+        self.query_params
+            .push((name.to_string(), placeholder(name)));
+        self
+    }
+
+    /// Redact a JSON body field (`user.password`, `items.0.token`, `users.*.ssn`)
+    pub fn with_json_path(mut self, path: &str) -> Self {
+        let path = path.strip_prefix("$.").unwrap_or(path);
+        let name = path.rsplit('.').find(|s| *s != "*").unwrap_or(path);
+        self.json_paths.push((path.to_string(), placeholder(name)));
+        self
+    }
+
+    /// Redact every match of `regex`
+    pub fn with_pattern(mut self, regex: &str, placeholder: &str) -> Result<Self> {
+        let regex = Regex::new(regex).map_err(|e| {
+            MatgtoError::Config(format!("Invalid sensitive data regex '{}': {}", regex, e))
+        })?;
+        self.patterns.push((regex, placeholder.to_string()));
+        Ok(self)
+    }
+
+    /// Redact a secret value wherever it appears
+    pub fn with_secret(mut self, value: &str, placeholder: &str) -> Self {
+        if !value.is_empty() {
+            self.secrets
+                .push((value.to_string(), placeholder.to_string()));
+        }
+        self
+    }
+
+    /// Redact the value of an environment variable wherever it appears
+    ///
+    /// The placeholder is named after the variable. Unset variables are
+    /// skipped.
+    pub fn with_env(self, var: &str) -> Self {
+        match std::env::var(var) {
+            Ok(value) => self.with_secret(&value, &placeholder(var)),
+            Err(_) => {
+                tracing::debug!("Sensitive data: ${} is not set, skipped", var);
+                self
+            }
+        }
+    }
+
+    /// Replace sensitive values in an interaction before it is recorded
+    pub fn redact(&self, interaction: &mut Interaction) {
+        if self.is_empty() {
+            return;
+        }
+
+        let captured = &mut Captured::new();
+        match &mut interaction.kind {
+            InteractionKind::Http { request, response } => {
+                self.redact_request_into(request, captured);
+                self.redact_response_into(response, captured);
+            }
+            InteractionKind::HttpError { request, .. } => {
+                self.redact_request_into(request, captured);
+            }
+            InteractionKind::ServerSentEvents {
+                request,
+                response,
+                events,
+            } => {
+                self.redact_request_into(request, captured);
+                self.redact_response_into(response, captured);
+                for event in events {
+                    self.redact_event(event);
+                }
+            }
+            InteractionKind::WebSocket { url, messages, .. } => {
+                self.redact_url(url, captured);
+                for message in messages {
+                    self.redact_message(message);
+                }
+            }
+            InteractionKind::Grpc {
+                request,
+                response,
+                messages,
+            } => {
+                self.redact_url(&mut request.url, captured);
+                self.redact_headers(&mut request.metadata, captured);
+                self.redact_headers(&mut response.metadata, captured);
+                self.redact_headers(&mut response.trailers, captured);
+                if let Some(message) = response
+                    .message
+                    .as_deref()
+                    .and_then(|message| self.replace_text(message, captured))
+                {
+                    response.message = Some(message);
+                }
+                for message in messages {
+                    self.redact_grpc_message(message);
+                }
+            }
+        }
+    }
+
+    /// Replace sensitive values in a request
+    pub fn redact_request(&self, request: &mut HttpRequest) {
+        self.redact_request_into(request, &mut Captured::new());
+    }
+
+    /// A request with its sensitive values replaced, as recorded
+    ///
+    /// Used to match live requests against recorded interactions.
+    pub fn redacted<'a>(&self, request: &'a HttpRequest) -> Cow<'a, HttpRequest> {
+        if self.is_empty() {
+            return Cow::Borrowed(request);
+        }
+
+        let mut request = request.clone();
+        self.redact_request(&mut request);
+        Cow::Owned(request)
+    }
+
+    /// Replace sensitive values in the data of a Server-Sent Event
+    pub fn redact_event(&self, event: &mut SseEvent) {
+        if let Some(data) = self.replace_text(&event.data, &mut Captured::new()) {
+            event.data = data;
+        }
+    }
+
+    /// Replace sensitive values in a WebSocket text message
+    pub fn redact_message(&self, message: &mut WebSocketMessage) {
+        if let MessagePayload::Text { data } = &mut message.payload {
+            if let Some(redacted) = self.replace_text(data, &mut Captured::new()) {
+                *data = redacted;
+            }
+        }
+    }
+
+    /// Replace sensitive values in a gRPC message
+    ///
+    /// The decoded form gets the JSON paths and text replacements; in the
+    /// payload, text fields holding a replaced value are rewritten.
+    pub fn redact_grpc_message(&self, message: &mut GrpcMessage) {
+        if self.is_empty() {
+            return;
+        }
+
+        // Values redacted in the decoded form are looked for in the payload too
+        let mut known = Captured::new();
+        if let Some(decoded) = &mut message.decoded {
+            for (path, placeholder) in &self.json_paths {
+                let segments: Vec<&str> = path.split('.').collect();
+                redact_json(decoded, &segments, placeholder, &mut known);
+            }
+            self.replace_json_text(decoded, &mut known);
+        }
+
+        if message.compressed {
+            return;
+        }
+        let mut replace = |text: &str| {
+            let mut result = Cow::Borrowed(text);
+            for (placeholder, value) in &known {
+                if !value.is_empty() && result.contains(value.as_str()) {
+                    result = Cow::Owned(result.replace(value.as_str(), placeholder));
+                }
+            }
+            match self.replace_text(&result, &mut Captured::new()) {
+                Some(replaced) => Some(replaced),
+                None => match result {
+                    Cow::Owned(result) => Some(result),
+                    Cow::Borrowed(_) => None,
+                },
+            }
+        };
+        if let Some(data) = redact_protobuf(&message.data, &mut replace, 0) {
+            message.data = data;
+        }
+    }
+
+    /// gRPC messages with their sensitive values replaced, as recorded
+    ///
+    /// Used to match live calls against recorded ones.
+    pub fn redacted_grpc_messages<'a>(
+        &self,
+        messages: &'a [GrpcMessage],
+    ) -> Cow<'a, [GrpcMessage]> {
+        if self.is_empty() {
+            return Cow::Borrowed(messages);
+        }
+
+        let mut messages = messages.to_vec();
+        for message in &mut messages {
+            self.redact_grpc_message(message);
+        }
+        Cow::Owned(messages)
+    }
+
+    /// Substitute placeholders in a replayed response with real values
+    ///
+    /// Values come from the live request (the ones its redaction replaces)
+    /// and from secrets. Placeholders without a known value are left as is.
+    pub fn restore(&self, interaction: &mut Interaction, request: &HttpRequest) {
+        if self.is_empty() {
+            return;
+        }
+
+        let mut known = Captured::new();
+        self.redact_request_into(&mut request.clone(), &mut known);
+        known.extend(
+            self.secrets
+                .iter()
+                .map(|(value, placeholder)| (placeholder.clone(), value.clone())),
+        );
+
+        match &mut interaction.kind {
+            InteractionKind::Http { response, .. } => {
+                restore_headers(&mut response.headers, &known);
+                restore_body(&mut response.headers, &mut response.body, &known);
+            }
+            InteractionKind::ServerSentEvents {
+                response, events, ..
+            } => {
+                restore_headers(&mut response.headers, &known);
+                for event in events {
+                    if let Some(data) = restore_text(&event.data, &known) {
+                        event.data = data;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn redact_request_into(&self, request: &mut HttpRequest, captured: &mut Captured) {
+        self.redact_url(&mut request.url, captured);
+        self.redact_headers(&mut request.headers, captured);
+        self.redact_body(&mut request.headers, &mut request.body, captured);
+    }
+
+    fn redact_response_into(&self, response: &mut HttpResponse, captured: &mut Captured) {
+        self.redact_headers(&mut response.headers, captured);
+        self.redact_body(&mut response.headers, &mut response.body, captured);
+    }
+
+    fn redact_url(&self, url: &mut String, captured: &mut Captured) {
+        if let Some(query_start) = url.find('?') {
+            let query_end = url[query_start..]
+                .find('#')
+                .map_or(url.len(), |end| query_start + end);
+
+            let mut changed = false;
+            let query: Vec<String> = url[query_start + 1..query_end]
+                .split('&')
+                .map(|pair| {
+                    let redacted = pair.split_once('=').and_then(|(key, value)| {
+                        let (_, placeholder) =
+                            self.query_params.iter().find(|(name, _)| name == key)?;
+                        if value == placeholder {
+                            return None;
+                        }
+                        captured.push((placeholder.clone(), value.to_string()));
+                        Some(format!("{}={}", key, placeholder))
+                    });
+                    changed |= redacted.is_some();
+                    redacted.unwrap_or_else(|| pair.to_string())
+                })
+                .collect();
+
+            if changed {
+                url.replace_range(query_start + 1..query_end, &query.join("&"));
+            }
+        }
+
+        if let Some(redacted) = self.replace_text(url, captured) {
+            *url = redacted;
+        }
+    }
+
+    /// Replace sensitive values in every header line, keeping their order
+    fn redact_headers(&self, headers: &mut Headers, captured: &mut Captured) {
+        let mut changed = false;
+        let mut redacted = Headers::new();
+        for header in headers.entries() {
+            let sensitive = self
+                .headers
+                .iter()
+                .find(|(name, _)| header.name.eq_ignore_ascii_case(name));
+
+            let value = match sensitive {
+                Some((_, placeholder)) if header.value == placeholder.as_bytes() => None,
+                Some((_, placeholder)) => {
+                    let value = String::from_utf8_lossy(&header.value).into_owned();
+                    captured.push((placeholder.clone(), value));
+                    Some(placeholder.clone())
+                }
+                None => header
+                    .value_str()
+                    .and_then(|value| self.replace_text(value, captured)),
+            };
+
+            match value {
+                Some(value) => {
+                    changed = true;
+                    redacted.append(header.name.as_str(), value);
+                }
+                None => redacted.append_raw(header.name.as_str(), header.value.clone()),
+            }
+        }
+        if changed {
+            *headers = redacted;
+        }
+    }
+
+    fn redact_body(
+        &self,
+        headers: &mut Headers,
+        body: &mut Option<Vec<u8>>,
+        captured: &mut Captured,
+    ) {
+        let Some(bytes) = body.as_mut() else {
+            return;
+        };
+        let mut changed = false;
+
+        if !self.json_paths.is_empty() {
+            if let Ok(mut value) = serde_json::from_slice::<Value>(bytes) {
+                let mut redacted = false;
+                for (path, placeholder) in &self.json_paths {
+                    let segments: Vec<&str> = path.split('.').collect();
+                    redacted |= redact_json(&mut value, &segments, placeholder, captured);
+                }
+                if redacted {
+                    if let Ok(json) = serde_json::to_vec(&value) {
+                        *bytes = json;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        if let Some(text) = std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|text| self.replace_text(text, captured))
+        {
+            *bytes = text.into_bytes();
+            changed = true;
+        }
+
+        if changed && headers.contains_key("Content-Length") {
+            headers.insert("Content-Length", bytes.len().to_string());
+        }
+    }
+
+    /// Replace secrets and regex matches in every string of a JSON value
+    fn replace_json_text(&self, value: &mut Value, captured: &mut Captured) {
+        match value {
+            Value::String(text) => {
+                if let Some(replaced) = self.replace_text(text, captured) {
+                    *text = replaced;
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.replace_json_text(item, captured);
+                }
+            }
+            Value::Object(map) => {
+                for item in map.values_mut() {
+                    self.replace_json_text(item, captured);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace secrets and regex matches in `text` (None if nothing matched)
+    fn replace_text(&self, text: &str, captured: &mut Captured) -> Option<String> {
+        let mut result = Cow::Borrowed(text);
+
+        for (value, placeholder) in &self.secrets {
+            if result.contains(value.as_str()) {
+                captured.push((placeholder.clone(), value.clone()));
+                result = Cow::Owned(result.replace(value.as_str(), placeholder));
+            }
+        }
+
+        for (regex, placeholder) in &self.patterns {
+            let replaced = match regex.replace_all(&result, |caps: &Captures| {
+                captured.push((placeholder.clone(), caps[0].to_string()));
+                placeholder.clone()
+            }) {
+                Cow::Owned(replaced) => Some(replaced),
+                Cow::Borrowed(_) => None,
+            };
+            if let Some(replaced) = replaced {
+                result = Cow::Owned(replaced);
+            }
+        }
+
+        match result {
+            Cow::Owned(result) => Some(result),
+            Cow::Borrowed(_) => None,
+        }
+    }
+}
+
+/// Placeholder for a header, parameter, field or variable name
+fn placeholder(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("<{}>", name.trim_matches('_'))
+}
+
+/// Replace the values at `path`, returning whether anything changed
+fn redact_json(
+    value: &mut Value,
+    path: &[&str],
+    placeholder: &str,
+    captured: &mut Captured,
+) -> bool {
+    let Some((segment, rest)) = path.split_first() else {
+        let original = match &*value {
+            Value::Null => return false,
+            Value::String(s) if s == placeholder => return false,
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        captured.push((placeholder.to_string(), original));
+        *value = Value::String(placeholder.to_string());
+        return true;
+    };
+
+    let mut redact = |value: &mut Value| redact_json(value, rest, placeholder, captured);
+    match value {
+        Value::Object(map) if *segment == "*" => map
+            .values_mut()
+            .fold(false, |changed, v| redact(v) | changed),
+        Value::Object(map) => map.get_mut(*segment).is_some_and(redact),
+        Value::Array(items) if *segment == "*" => items
+            .iter_mut()
+            .fold(false, |changed, v| redact(v) | changed),
+        Value::Array(items) => segment
+            .parse::<usize>()
+            .ok()
+            .and_then(|idx| items.get_mut(idx))
+            .is_some_and(redact),
+        _ => false,
+    }
+}
+
+/// Rewrite the text fields of an encoded protobuf message
+///
+/// Length-delimited fields that are themselves messages are rewritten
+/// recursively, the others are passed to `replace` when they are UTF-8.
+/// Returns None if nothing changed or `data` is not a valid message.
+fn redact_protobuf(
+    data: &[u8],
+    replace: &mut impl FnMut(&str) -> Option<String>,
+    depth: usize,
+) -> Option<Vec<u8>> {
+    if data.is_empty() || depth > MAX_PROTOBUF_DEPTH {
+        return None;
+    }
+
+    let mut redacted = Vec::with_capacity(data.len());
+    let mut changed = false;
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (key, key_len) = read_varint(rest)?;
+        if key >> 3 == 0 {
+            return None;
+        }
+        let value_len = match key & 7 {
+            0 => read_varint(&rest[key_len..])?.1,
+            1 => 8,
+            5 => 4,
+            2 => {
+                let (len, len_len) = read_varint(&rest[key_len..])?;
+                let start = key_len + len_len;
+                let end = start.checked_add(usize::try_from(len).ok()?)?;
+                let field = rest.get(start..end)?;
+
+                let rewritten = redact_protobuf(field, replace, depth + 1).or_else(|| {
+                    std::str::from_utf8(field)
+                        .ok()
+                        .and_then(&mut *replace)
+                        .map(String::into_bytes)
+                });
+                if let Some(field) = rewritten {
+                    redacted.extend_from_slice(&rest[..key_len]);
+                    write_varint(&mut redacted, field.len() as u64);
+                    redacted.extend_from_slice(&field);
+                    rest = &rest[end..];
+                    changed = true;
+                    continue;
+                }
+                end - key_len
+            }
+            _ => return None,
+        };
+
+        let end = key_len.checked_add(value_len)?;
+        redacted.extend_from_slice(rest.get(..end)?);
+        rest = &rest[end..];
+    }
+
+    changed.then_some(redacted)
+}
+
+/// Decode a protobuf varint, returning it with its length in bytes
+fn read_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().take(10).enumerate() {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn restore_text(text: &str, known: &Captured) -> Option<String> {
+    let mut result = Cow::Borrowed(text);
+    for (placeholder, value) in known {
+        if result.contains(placeholder.as_str()) {
+            result = Cow::Owned(result.replace(placeholder.as_str(), value));
+        }
+    }
+
+    match result {
+        Cow::Owned(result) => Some(result),
+        Cow::Borrowed(_) => None,
+    }
+}
+
+fn restore_headers(headers: &mut Headers, known: &Captured) {
+    let mut changed = false;
+    let mut restored = Headers::new();
+    for header in headers.entries() {
+        match header
+            .value_str()
+            .and_then(|value| restore_text(value, known))
+        {
+            Some(value) => {
+                changed = true;
+                restored.append(header.name.as_str(), value);
+            }
+            None => restored.append_raw(header.name.as_str(), header.value.clone()),
+        }
+    }
+    if changed {
+        *headers = restored;
+    }
+}
+
+fn restore_body(headers: &mut Headers, body: &mut Option<Vec<u8>>, known: &Captured) {
+    let Some(restored) = body
+        .as_deref()
+        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+        .and_then(|text| restore_text(text, known))
+    else {
+        return;
+    };
+
+    if headers.contains_key("Content-Length") {
+        headers.insert("Content-Length", restored.len().to_string());
+    }
+    *body = Some(restored.into_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str, headers: &[(&str, &str)], body: &str) -> HttpRequest {
+        HttpRequest {
+            method: "POST".to_string(),
+            url: url.to_string(),
+            headers: headers.iter().copied().collect(),
+            body: Some(body.as_bytes().to_vec()),
+        }
+    }
+
+    fn http(request: HttpRequest, body: &str) -> Interaction {
+        Interaction {
+            kind: InteractionKind::Http {
+                request,
+                response: HttpResponse {
+                    status: 200,
+                    headers: [("Content-Length", body.len().to_string())].into(),
+                    body: Some(body.as_bytes().to_vec()),
+                },
+            },
+            recorded_at: chrono::Utc::now(),
+            response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
+        }
+    }
+
+    fn parts(interaction: &Interaction) -> (&HttpRequest, &HttpResponse) {
+        match &interaction.kind {
+            InteractionKind::Http { request, response } => (request, response),
+            _ => unreachable!(),
+        }
+    }
+
+    fn text(body: &Option<Vec<u8>>) -> &str {
+        std::str::from_utf8(body.as_deref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_redact_and_restore() {
+        let data = SensitiveData::new()
+            .with_header("Authorization")
+            .with_query_param("api_key")
+            .with_json_path("$.auth.token")
+            .with_json_path("users.*.ssn")
+            .with_secret("s3cr3t", "<SECRET>")
+            .with_pattern(r"sk_live_\w+", "<STRIPE_KEY>")
+            .unwrap();
+
+        let live = request(
+            "https://api.example.com/users?api_key=abc123&page=2",
+            &[("authorization", "Bearer xyz"), ("x-note", "uses s3cr3t")],
+            r#"{"auth":{"token":"t0k"},"users":[{"ssn":"111"},{"ssn":"222"}],"key":"sk_live_42"}"#,
+        );
+        let mut interaction = http(live.clone(), r#"{"echo":"abc123 sk_live_42"}"#);
+        data.redact(&mut interaction);
+
+        let (recorded, response) = parts(&interaction);
+        assert_eq!(
+            recorded.url,
+            "https://api.example.com/users?api_key=<API_KEY>&page=2"
+        );
+        assert_eq!(
+            recorded.headers.get("authorization"),
+            Some("<AUTHORIZATION>")
+        );
+        assert_eq!(recorded.headers.get("x-note"), Some("uses <SECRET>"));
+        assert_eq!(
+            text(&recorded.body),
+            r#"{"auth":{"token":"<TOKEN>"},"users":[{"ssn":"<SSN>"},{"ssn":"<SSN>"}],"key":"<STRIPE_KEY>"}"#
+        );
+        assert_eq!(text(&response.body), r#"{"echo":"abc123 <STRIPE_KEY>"}"#);
+        assert_eq!(
+            response.headers.get("content-length"),
+            Some(response.body.as_ref().unwrap().len().to_string().as_str())
+        );
+
+        // The live request matches the recorded one once redacted
+        assert_eq!(data.redacted(&live).url, recorded.url);
+        assert_eq!(data.redacted(&live).body, recorded.body);
+
+        // Placeholders are filled back from the live request
+        data.restore(&mut interaction, &live);
+        let (_, response) = parts(&interaction);
+        assert_eq!(text(&response.body), r#"{"echo":"abc123 sk_live_42"}"#);
+    }
+
+    #[test]
+    fn test_redact_multi_valued_headers() {
+        let data = SensitiveData::new().with_header("x-api-key");
+
+        let mut headers = Headers::new();
+        headers.append("X-Api-Key", "first");
+        headers.append("Accept", "*/*");
+        headers.append("x-api-key", "second");
+        let mut live = HttpRequest {
+            method: "GET".to_string(),
+            url: "/".to_string(),
+            headers,
+            body: None,
+        };
+        data.redact_request(&mut live);
+
+        let lines: Vec<(&str, &str)> = live.headers.iter().collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("X-Api-Key", "<X_API_KEY>"),
+                ("Accept", "*/*"),
+                ("x-api-key", "<X_API_KEY>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redact_grpc() {
+        use crate::cassette::{Direction, GrpcRequest, GrpcResponse};
+
+        let data = SensitiveData::new()
+            .with_header("authorization")
+            .with_json_path("auth.token")
+            .with_pattern(r"sk_live_\w+", "<STRIPE_KEY>")
+            .unwrap();
+
+        // { id: 150, auth: { token: "t0k" }, key: "sk_live_42" }
+        let mut payload = vec![0x08, 0x96, 0x01, 0x12, 0x05, 0x0a, 0x03];
+        payload.extend_from_slice(b"t0k");
+        payload.extend_from_slice(&[0x1a, 0x0a]);
+        payload.extend_from_slice(b"sk_live_42");
+
+        let mut interaction = Interaction {
+            kind: InteractionKind::Grpc {
+                request: GrpcRequest {
+                    url: "https://api.example.com/pkg.Svc/Get".to_string(),
+                    service: "pkg.Svc".to_string(),
+                    method: "Get".to_string(),
+                    metadata: [("authorization", "Bearer xyz")].into(),
+                },
+                response: GrpcResponse {
+                    status: 0,
+                    message: None,
+                    metadata: [("authorization", "Bearer abc")].into(),
+                    trailers: [("x-debug", "key sk_live_42")].into(),
+                },
+                messages: vec![GrpcMessage {
+                    direction: Direction::Received,
+                    timestamp_ms: 0,
+                    compressed: false,
+                    data: payload,
+                    decoded: Some(serde_json::json!({
+                        "id": 150,
+                        "auth": { "token": "t0k" },
+                        "key": "sk_live_42"
+                    })),
+                }],
+            },
+            recorded_at: chrono::Utc::now(),
+            response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
+        };
+        data.redact(&mut interaction);
+
+        let InteractionKind::Grpc {
+            request,
+            response,
+            messages,
+        } = &interaction.kind
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            request.metadata.get("authorization"),
+            Some("<AUTHORIZATION>")
+        );
+        assert_eq!(
+            response.metadata.get("authorization"),
+            Some("<AUTHORIZATION>")
+        );
+        assert_eq!(response.trailers.get("x-debug"), Some("key <STRIPE_KEY>"));
+        assert_eq!(
+            messages[0].decoded,
+            Some(serde_json::json!({
+                "id": 150,
+                "auth": { "token": "<TOKEN>" },
+                "key": "<STRIPE_KEY>"
+            }))
+        );
+
+        let mut expected = vec![0x08, 0x96, 0x01, 0x12, 0x09, 0x0a, 0x07];
+        expected.extend_from_slice(b"<TOKEN>");
+        expected.extend_from_slice(&[0x1a, 0x0c]);
+        expected.extend_from_slice(b"<STRIPE_KEY>");
+        assert_eq!(messages[0].data, expected);
+    }
+
+    #[test]
+    fn test_config_resolve() {
+        let config: SensitiveDataConfig = toml::from_str(
+            r#"
+            headers = ["x-api-key"]
+            env = ["MAGNETO_TEST_UNSET_SECRET"]
+
+            [[patterns]]
+            regex = "[0-9]{16}"
+            placeholder = "<CARD>"
+            "#,
+        )
+        .unwrap();
+
+        let data = config.resolve().unwrap();
+        let mut live = request("/pay", &[("X-Api-Key", "k")], "card 4111111111111111");
+        data.redact_request(&mut live);
+        assert_eq!(live.headers.get("x-api-key"), Some("<X_API_KEY>"));
+        assert_eq!(text(&live.body), "card <CARD>");
+
+        assert!(SensitiveDataConfig::default().resolve().unwrap().is_empty());
+        let invalid = SensitiveDataConfig {
+            patterns: vec![SensitivePattern {
+                regex: "(".to_string(),
+                placeholder: "<X>".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert!(invalid.resolve().is_err());
+    }
+}
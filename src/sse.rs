@@ -0,0 +1,208 @@
+//! Server-Sent Events support
+//!
+//! `text/event-stream` responses stay open and deliver events as they happen,
+//! so they cannot be recorded as one body once the stream closes (it may never
+//! close). Instead events are parsed while they stream to the client and each
+//! one is appended to an `InteractionKind::ServerSentEvents` interaction with
+//! its time relative to the response start. Replay re-emits the events with
+//! the recorded pacing, scaled by the player's `LatencyMode`.
+//!
+//! Events are stored by field (`id`, `event`, `data`, `retry`); comment lines
+//! such as keep-alive pings are not kept.
+
+use crate::cassette::SseEvent;
+
+use hyper::header::CONTENT_TYPE;
+use hyper::HeaderMap;
+
+/// Whether a response is a Server-Sent Events stream (`text/event-stream`)
+pub fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().eq_ignore_ascii_case("text/event-stream"))
+        .unwrap_or(false)
+}
+
+/// Incremental parser of an event stream
+///
+/// Body chunks do not line up with events: lines and events may be split
+/// across chunks, which is handled by keeping incomplete data until the next
+/// chunk. Lines end with CRLF, LF or CR, as in the HTML specification.
+#[derive(Debug, Default)]
+pub struct EventParser {
+    line: Vec<u8>,
+    /// The previous chunk ended with CR, so a leading LF belongs to it
+    after_cr: bool,
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<u64>,
+}
+
+impl EventParser {
+    /// Create an empty parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a body chunk, returning the events it completes stamped with `timestamp_ms`
+    pub fn push(&mut self, chunk: &[u8], timestamp_ms: u64) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+
+        for &byte in chunk {
+            match byte {
+                b'\n' if self.after_cr => self.after_cr = false,
+                b'\n' | b'\r' => {
+                    self.after_cr = byte == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.process_line(&line, timestamp_ms) {
+                        events.push(event);
+                    }
+                }
+                _ => {
+                    self.after_cr = false;
+                    self.line.push(byte);
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Apply a complete line, returning the event a blank line dispatches
+    fn process_line(&mut self, line: &[u8], timestamp_ms: u64) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch(timestamp_ms);
+        }
+        if line[0] == b':' {
+            // Comment (keep-alive)
+            return None;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+
+        match field {
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(retry) = value.parse() {
+                    self.retry = Some(retry);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self, timestamp_ms: u64) -> Option<SseEvent> {
+        if self.id.is_none() && self.event.is_none() && self.data.is_none() && self.retry.is_none()
+        {
+            return None;
+        }
+
+        Some(SseEvent {
+            timestamp_ms,
+            id: self.id.take(),
+            event: self.event.take(),
+            data: self.data.take().unwrap_or_default(),
+            retry: self.retry.take(),
+        })
+    }
+}
+
+/// Encode an event as it is sent on the stream
+pub fn encode_event(event: &SseEvent) -> Vec<u8> {
+    let mut encoded = String::new();
+
+    if let Some(id) = &event.id {
+        encoded.push_str(&format!("id: {}\n", id));
+    }
+    if let Some(name) = &event.event {
+        encoded.push_str(&format!("event: {}\n", name));
+    }
+    if let Some(retry) = event.retry {
+        encoded.push_str(&format!("retry: {}\n", retry));
+    }
+    if !event.data.is_empty() {
+        for line in event.data.split('\n') {
+            encoded.push_str(&format!("data: {}\n", line));
+        }
+    }
+    encoded.push('\n');
+
+    encoded.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn test_is_event_stream() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/event-stream; charset=utf-8"),
+        );
+        assert!(is_event_stream(&headers));
+
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        assert!(!is_event_stream(&headers));
+    }
+
+    #[test]
+    fn test_parse_events_split_across_chunks() {
+        let mut parser = EventParser::new();
+
+        assert!(parser
+            .push(b": keep-alive\n\nid: 1\nevent: st", 10)
+            .is_empty());
+        let events = parser.push(b"atus\ndata: pending\r\n\r", 20);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].timestamp_ms, 20);
+        assert_eq!(events[0].id.as_deref(), Some("1"));
+        assert_eq!(events[0].event.as_deref(), Some("status"));
+        assert_eq!(events[0].data, "pending");
+
+        // The LF completing the CRLF above does not end another line
+        let events = parser.push(b"\ndata: line 1\ndata:line 2\nretry: 3000\n\n", 30);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "line 1\nline 2");
+        assert_eq!(events[0].retry, Some(3000));
+        assert_eq!(events[0].id, None);
+    }
+
+    #[test]
+    fn test_encode_event_round_trip() {
+        let event = SseEvent {
+            timestamp_ms: 5,
+            id: Some("42".to_string()),
+            event: Some("update".to_string()),
+            data: "{\"a\":1}\n{\"b\":2}".to_string(),
+            retry: None,
+        };
+
+        let encoded = encode_event(&event);
+        assert_eq!(
+            String::from_utf8(encoded.clone()).unwrap(),
+            "id: 42\nevent: update\ndata: {\"a\":1}\ndata: {\"b\":2}\n\n"
+        );
+
+        let parsed = EventParser::new().push(&encoded, 5);
+        assert_eq!(parsed, vec![event]);
+    }
+}
@@ -196,11 +196,21 @@ impl TemplateEngine {
 
     /// Build template context from HTTP request
     fn build_context(&self, request: &HttpRequest) -> serde_json::Value {
+        // Templates address headers by name; repeated headers expose their first value
+        let headers: serde_json::Map<String, serde_json::Value> = request
+            .headers
+            .keys()
+            .filter_map(|name| {
+                let value = request.headers.get(name)?;
+                Some((name.to_string(), json!(value)))
+            })
+            .collect();
+
         json!({
             "request": {
                 "method": request.method,
                 "url": request.url,
-                "headers": request.headers,
+                "headers": headers,
                 "body": request.body.as_ref().and_then(|b| std::str::from_utf8(b).ok()),
             }
         })
@@ -254,10 +264,10 @@ impl Default for TemplateEngine {
 #[cfg(all(test, feature = "templates"))]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::cassette::Headers;
 
     fn create_test_request() -> HttpRequest {
-        let mut headers = HashMap::new();
+        let mut headers = Headers::new();
         headers.insert("x-user-id".to_string(), "user123".to_string());
         headers.insert("authorization".to_string(), "Bearer token".to_string());
 
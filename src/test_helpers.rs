@@ -11,14 +11,16 @@
 //! async fn test_user_login() {
 //!     let cassette = load_cassette("user-login").unwrap();
 //!
-//!     assert_cassette_version(&cassette, "1.0");
+//!     assert_cassette_version(&cassette, "2.0");
 //!     assert_interaction_count(&cassette, 3);
 //!     assert_has_cookies(&cassette);
 //!     assert_has_cookie(&cassette, "JSESSIONID");
 //! }
 //! ```
 
-use crate::cassette::Cassette;
+use crate::cassette::{
+    find_cassette, load_cassette as load_cassette_file, Cassette, CassetteFormat,
+};
 use crate::error::{MatgtoError, Result};
 use std::path::Path;
 
@@ -49,34 +51,13 @@ pub fn load_cassette(name: &str) -> Result<Cassette> {
 /// let cassette = load_cassette_from("user-login", "./test-cassettes").unwrap();
 /// ```
 pub fn load_cassette_from(name: &str, dir: impl AsRef<Path>) -> Result<Cassette> {
-    let dir = dir.as_ref();
-
-    // Try .json first
-    let json_path = dir.join(format!("{}.json", name));
-    if json_path.exists() {
-        let file = std::fs::File::open(&json_path).map_err(MatgtoError::Io)?;
-        let cassette: Cassette =
-            serde_json::from_reader(file).map_err(MatgtoError::Serialization)?;
-        return Ok(cassette);
-    }
-
-    // Try .msgpack
-    #[cfg(feature = "msgpack")]
-    {
-        let msgpack_path = dir.join(format!("{}.msgpack", name));
-        if msgpack_path.exists() {
-            let file = std::fs::File::open(&msgpack_path).map_err(MatgtoError::Io)?;
-            let cassette: Cassette =
-                rmp_serde::from_read(file).map_err(|e| MatgtoError::CassetteLoadFailed {
-                    reason: format!("Failed to deserialize MessagePack: {}", e),
-                })?;
-            return Ok(cassette);
+    let path = find_cassette(dir.as_ref(), name, CassetteFormat::default()).ok_or_else(|| {
+        MatgtoError::CassetteNotFound {
+            name: name.to_string(),
         }
-    }
+    })?;
 
-    Err(MatgtoError::CassetteNotFound {
-        name: name.to_string(),
-    })
+    load_cassette_file(&path)
 }
 
 /// Assert that a cassette has a specific version
@@ -91,7 +72,7 @@ pub fn load_cassette_from(name: &str, dir: impl AsRef<Path>) -> Result<Cassette>
 /// use magneto_serge::test_helpers::{load_cassette, assert_cassette_version};
 ///
 /// let cassette = load_cassette("user-login").unwrap();
-/// assert_cassette_version(&cassette, "1.0");
+/// assert_cassette_version(&cassette, "2.0");
 /// ```
 pub fn assert_cassette_version(cassette: &Cassette, expected: &str) {
     assert_eq!(
@@ -371,10 +352,10 @@ macro_rules! assert_cassette {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::Headers;
     use crate::cassette::{Cassette, HttpRequest, HttpResponse, Interaction, InteractionKind};
     use crate::cookies::Cookie;
     use chrono::Utc;
-    use std::collections::HashMap;
 
     fn create_test_cassette() -> Cassette {
         let mut cassette = Cassette::new("test".to_string());
@@ -399,17 +380,19 @@ mod tests {
                 request: HttpRequest {
                     method: "GET".to_string(),
                     url: "https://api.example.com/users".to_string(),
-                    headers: HashMap::new(),
+                    headers: Headers::new(),
                     body: None,
                 },
                 response: HttpResponse {
                     status: 200,
-                    headers: HashMap::new(),
+                    headers: Headers::new(),
                     body: Some(vec![]),
                 },
             },
             recorded_at: Utc::now(),
             response_time_ms: None,
+            http_version: None,
+            content_encoding: None,
         });
 
         cassette
@@ -418,7 +401,7 @@ mod tests {
     #[test]
     fn test_assert_cassette_version() {
         let cassette = create_test_cassette();
-        assert_cassette_version(&cassette, "1.0");
+        assert_cassette_version(&cassette, "2.0");
     }
 
     #[test]
@@ -458,10 +441,10 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Expected cassette version '2.0' but found '1.0'")]
+    #[should_panic(expected = "Expected cassette version '1.0' but found '2.0'")]
     fn test_assert_cassette_version_fails() {
         let cassette = create_test_cassette();
-        assert_cassette_version(&cassette, "2.0");
+        assert_cassette_version(&cassette, "1.0");
     }
 
     #[test]
@@ -1,5 +1,7 @@
 //! TLS/Certificate management module
 
 pub mod certificate;
+pub mod upstream;
 
 pub use certificate::CertificateAuthority;
+pub use upstream::{ClientCertConfig, UpstreamTls, UpstreamTlsConfig};
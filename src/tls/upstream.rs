@@ -0,0 +1,336 @@
+//! TLS settings for connections to upstream servers
+//!
+//! By default the forwarder trusts the operating system's root certificates
+//! and presents no client certificate. `UpstreamTlsConfig` adds:
+//!
+//! - extra CA bundles (PEM) trusted on top of the native roots
+//! - client certificate/key pairs (PEM), each presented to a set of hosts
+//! - `insecure_skip_verify`, which accepts any server certificate
+//!
+//! ```toml
+//! [upstream_tls]
+//! ca_bundles = ["certs/internal-ca.pem"]
+//! insecure_skip_verify = false
+//!
+//! [[upstream_tls.client_certs]]
+//! hosts = ["staging.internal.corp", "*.staging.internal.corp"]
+//! cert = "certs/client.pem"
+//! key = "certs/client-key.pem"
+//! ```
+
+use crate::error::{MatgtoError, Result};
+
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerName};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Upstream TLS settings, as written in magneto.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpstreamTlsConfig {
+    /// PEM files with extra CA certificates to trust
+    pub ca_bundles: Vec<PathBuf>,
+
+    /// Client certificates presented to specific hosts
+    pub client_certs: Vec<ClientCertConfig>,
+
+    /// Accept any server certificate (throwaway environments only)
+    pub insecure_skip_verify: bool,
+}
+
+/// A client certificate and the hosts it is presented to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientCertConfig {
+    /// Host names, exact or `*.domain` wildcards
+    pub hosts: Vec<String>,
+
+    /// PEM file with the certificate chain (leaf first)
+    pub cert: PathBuf,
+
+    /// PEM file with the private key (PKCS#8, RSA or EC)
+    pub key: PathBuf,
+}
+
+impl UpstreamTlsConfig {
+    /// Create settings equivalent to the default (native roots, no client certificate)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust the CA certificates in a PEM file
+    pub fn with_ca_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_bundles.push(path.into());
+        self
+    }
+
+    /// Present a client certificate to the given hosts
+    pub fn with_client_cert<I, S>(
+        mut self,
+        hosts: I,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.client_certs.push(ClientCertConfig {
+            hosts: hosts.into_iter().map(Into::into).collect(),
+            cert: cert.into(),
+            key: key.into(),
+        });
+        self
+    }
+
+    /// Accept any server certificate, without verifying it
+    ///
+    /// Only meant for throwaway environments: connections are still
+    /// encrypted but can be intercepted by anyone.
+    pub fn with_insecure_skip_verify(mut self, skip: bool) -> Self {
+        self.insecure_skip_verify = skip;
+        self
+    }
+
+    /// Read the certificate files and build the TLS client configurations
+    pub fn load(&self) -> Result<UpstreamTls> {
+        let mut roots = RootCertStore::empty();
+
+        match rustls_native_certs::load_native_certs() {
+            Ok(certs) => {
+                let certs: Vec<Vec<u8>> = certs.into_iter().map(|c| c.0).collect();
+                let (_, ignored) = roots.add_parsable_certificates(&certs);
+                if ignored > 0 {
+                    tracing::debug!("Ignored {} unparsable native root certificates", ignored);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to load native root certificates: {}", e),
+        }
+
+        for path in &self.ca_bundles {
+            let certs = read_certs(path)?;
+            if certs.is_empty() {
+                return Err(MatgtoError::Tls(format!(
+                    "No certificate found in CA bundle {}",
+                    path.display()
+                )));
+            }
+            for cert in &certs {
+                roots.add(cert).map_err(|e| {
+                    MatgtoError::Tls(format!(
+                        "Invalid CA certificate in {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+            }
+        }
+
+        if self.insecure_skip_verify {
+            tracing::warn!("⚠️  Upstream TLS certificates are NOT verified (insecure_skip_verify)");
+        }
+
+        let default = Arc::new(self.client_config(&roots, None)?);
+        let mut per_host = Vec::new();
+        for client_cert in &self.client_certs {
+            let chain = read_certs(&client_cert.cert)?;
+            if chain.is_empty() {
+                return Err(MatgtoError::Tls(format!(
+                    "No certificate found in {}",
+                    client_cert.cert.display()
+                )));
+            }
+            let key = read_key(&client_cert.key)?;
+            let config = self.client_config(&roots, Some((chain, key)))?;
+            per_host.push((client_cert.hosts.clone(), Arc::new(config)));
+        }
+
+        Ok(UpstreamTls {
+            config: self.clone(),
+            default,
+            per_host,
+        })
+    }
+
+    fn client_config(
+        &self,
+        roots: &RootCertStore,
+        client_cert: Option<(Vec<Certificate>, PrivateKey)>,
+    ) -> Result<ClientConfig> {
+        let builder = ClientConfig::builder().with_safe_defaults();
+
+        let config = if self.insecure_skip_verify {
+            let builder = builder.with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate));
+            match client_cert {
+                Some((chain, key)) => builder.with_client_auth_cert(chain, key),
+                None => Ok(builder.with_no_client_auth()),
+            }
+        } else {
+            let builder = builder.with_root_certificates(roots.clone());
+            match client_cert {
+                Some((chain, key)) => builder.with_client_auth_cert(chain, key),
+                None => Ok(builder.with_no_client_auth()),
+            }
+        };
+
+        config.map_err(|e| MatgtoError::Tls(format!("Invalid client certificate: {}", e)))
+    }
+}
+
+/// Loaded upstream TLS settings, ready to build connectors from
+#[derive(Clone)]
+pub struct UpstreamTls {
+    config: UpstreamTlsConfig,
+    default: Arc<ClientConfig>,
+    per_host: Vec<(Vec<String>, Arc<ClientConfig>)>,
+}
+
+impl std::fmt::Debug for UpstreamTls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UpstreamTls")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl UpstreamTls {
+    /// Settings these configurations were loaded from
+    pub fn config(&self) -> &UpstreamTlsConfig {
+        &self.config
+    }
+
+    /// Configuration for hosts without a client certificate
+    pub(crate) fn default_config(&self) -> Arc<ClientConfig> {
+        self.default.clone()
+    }
+
+    /// Per-host configurations, in declaration order
+    pub(crate) fn host_configs(&self) -> &[(Vec<String>, Arc<ClientConfig>)] {
+        &self.per_host
+    }
+}
+
+/// Whether `host` matches a client certificate host entry (exact or `*.domain`)
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    let host = host.trim_end_matches('.').as_bytes();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => {
+            let domain = domain.as_bytes();
+            host.len() > domain.len() + 1
+                && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+                && host[host.len() - domain.len() - 1] == b'.'
+        }
+        None => pattern.as_bytes().eq_ignore_ascii_case(host),
+    }
+}
+
+fn read_pem(path: &Path) -> Result<Vec<rustls_pemfile::Item>> {
+    let data = std::fs::read(path)
+        .map_err(|e| MatgtoError::Tls(format!("Failed to read {}: {}", path.display(), e)))?;
+    rustls_pemfile::read_all(&mut data.as_slice())
+        .map_err(|e| MatgtoError::Tls(format!("Failed to parse {}: {}", path.display(), e)))
+}
+
+fn read_certs(path: &Path) -> Result<Vec<Certificate>> {
+    Ok(read_pem(path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            rustls_pemfile::Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None,
+        })
+        .collect())
+}
+
+fn read_key(path: &Path) -> Result<PrivateKey> {
+    read_pem(path)?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(der)
+            | rustls_pemfile::Item::RSAKey(der)
+            | rustls_pemfile::Item::ECKey(der) => Some(PrivateKey(der)),
+            _ => None,
+        })
+        .ok_or_else(|| MatgtoError::Tls(format!("No private key found in {}", path.display())))
+}
+
+/// Certificate verifier for `insecure_skip_verify`
+struct AcceptAnyCertificate;
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_matches() {
+        assert!(host_matches("staging.corp", "staging.corp"));
+        assert!(host_matches("staging.corp", "STAGING.corp"));
+        assert!(!host_matches("staging.corp", "api.staging.corp"));
+
+        assert!(host_matches("*.staging.corp", "api.staging.corp"));
+        assert!(host_matches("*.staging.corp", "a.b.staging.corp"));
+        assert!(!host_matches("*.staging.corp", "staging.corp"));
+        assert!(!host_matches("*.staging.corp", "apistaging.corp"));
+    }
+
+    #[test]
+    fn test_config_from_toml() {
+        let config: UpstreamTlsConfig = toml::from_str(
+            r#"
+            ca_bundles = ["certs/ca.pem"]
+
+            [[client_certs]]
+            hosts = ["*.staging.corp"]
+            cert = "certs/client.pem"
+            key = "certs/client-key.pem"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            UpstreamTlsConfig::new()
+                .with_ca_bundle("certs/ca.pem")
+                .with_client_cert(
+                    ["*.staging.corp"],
+                    "certs/client.pem",
+                    "certs/client-key.pem"
+                )
+        );
+        assert!(!config.insecure_skip_verify);
+    }
+
+    #[test]
+    fn test_load_reports_missing_files() {
+        let error = UpstreamTlsConfig::new()
+            .with_ca_bundle("/nonexistent/ca.pem")
+            .load()
+            .unwrap_err();
+
+        assert!(
+            matches!(error, MatgtoError::Tls(message) if message.contains("/nonexistent/ca.pem"))
+        );
+    }
+
+    #[test]
+    fn test_load_default() {
+        let tls = UpstreamTlsConfig::new().load().unwrap();
+        assert!(tls.host_configs().is_empty());
+    }
+}
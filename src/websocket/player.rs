@@ -2,7 +2,10 @@
 //!
 //! Replays WebSocket interactions from cassettes.
 
-use crate::cassette::{Cassette, CloseFrame, InteractionKind, WebSocketMessage};
+use crate::cassette::{
+    find_cassette, load_cassette, Cassette, CassetteFormat, CloseFrame, InteractionKind,
+    WebSocketMessage,
+};
 use crate::error::{MatgtoError, Result};
 use crate::player::LatencyMode;
 use std::collections::HashMap;
@@ -10,6 +13,7 @@ use std::path::Path;
 use tracing::{debug, info};
 
 /// Replays WebSocket interactions from cassettes
+#[derive(Debug)]
 pub struct WebSocketPlayer {
     /// Loaded cassette
     cassette: Option<Cassette>,
@@ -54,42 +58,22 @@ impl WebSocketPlayer {
     /// - LatencyMode::Fixed(ms): Fixed delay for all messages
     /// - LatencyMode::Scaled(percentage): Scale recorded timestamps
     pub fn calculate_message_delay(&self, timestamp_ms: u64, base_timestamp: u64) -> Option<u64> {
-        match self.latency_mode {
-            LatencyMode::None => None,
-            LatencyMode::Recorded => {
-                // Use relative timestamp (current - base)
-                Some(timestamp_ms.saturating_sub(base_timestamp))
-            }
-            LatencyMode::Fixed(ms) => Some(ms),
-            LatencyMode::Scaled(percentage) => {
-                // Scale the relative timestamp
-                let relative = timestamp_ms.saturating_sub(base_timestamp);
-                Some((relative * percentage) / 100)
-            }
-        }
+        self.latency_mode
+            .message_delay(timestamp_ms.saturating_sub(base_timestamp))
     }
 
     /// Load a cassette from disk
     pub fn load(&mut self, cassette_dir: &Path, cassette_name: &str) -> Result<()> {
         info!("📼 Loading WebSocket cassette: {}", cassette_name);
 
-        let cassette_path = cassette_dir.join(format!("{}.json", cassette_name));
-
-        if !cassette_path.exists() {
-            return Err(MatgtoError::CassetteNotFound {
-                name: cassette_name.to_string(),
-            });
-        }
-
-        let json = std::fs::read_to_string(&cassette_path).map_err(|e| {
-            MatgtoError::CassetteLoadFailed {
-                reason: format!("Failed to read cassette file: {}", e),
-            }
+        let cassette_path = find_cassette(cassette_dir, cassette_name, CassetteFormat::default())
+            .ok_or_else(|| MatgtoError::CassetteNotFound {
+            name: cassette_name.to_string(),
         })?;
 
-        let cassette: Cassette =
-            serde_json::from_str(&json).map_err(|e| MatgtoError::CassetteLoadFailed {
-                reason: format!("Failed to parse cassette JSON: {}", e),
+        let cassette =
+            load_cassette(&cassette_path).map_err(|e| MatgtoError::CassetteLoadFailed {
+                reason: format!("Failed to read cassette file: {}", e),
             })?;
 
         info!(
@@ -98,11 +82,16 @@ impl WebSocketPlayer {
             cassette.interactions.len()
         );
 
+        self.load_cassette(cassette);
+        Ok(())
+    }
+
+    /// Use an already loaded cassette (e.g. the one shared with the HTTP player)
+    pub fn load_cassette(&mut self, cassette: Cassette) {
         // Build WebSocket index
         self.build_ws_index(&cassette);
 
         self.cassette = Some(cassette);
-        Ok(())
     }
 
     /// Build index of WebSocket interactions by URL
@@ -265,6 +254,8 @@ mod tests {
                 Interaction {
                     recorded_at: Utc::now(),
                     response_time_ms: None,
+                    http_version: None,
+                    content_encoding: None,
                     kind: InteractionKind::WebSocket {
                         url: "ws://example.com/socket".to_string(),
                         messages: vec![
@@ -289,6 +280,8 @@ mod tests {
                 Interaction {
                     recorded_at: Utc::now(),
                     response_time_ms: None,
+                    http_version: None,
+                    content_encoding: None,
                     kind: InteractionKind::WebSocket {
                         url: "ws://example.com/socket".to_string(),
                         messages: vec![WebSocketMessage {
@@ -2,7 +2,9 @@
 //!
 //! Records WebSocket messages into cassettes for later replay.
 
-use crate::cassette::{Cassette, CloseFrame, Interaction, InteractionKind, WebSocketMessage};
+use crate::cassette::{
+    Cassette, CloseFrame, Interaction, InteractionKind, WebSocketMessage, CASSETTE_VERSION,
+};
 use crate::error::{MatgtoError, Result};
 use chrono::Utc;
 use std::path::Path;
@@ -26,7 +28,7 @@ impl WebSocketRecorder {
         Self {
             cassette_name: cassette_name.clone(),
             cassette: Cassette {
-                version: "1.0".to_string(),
+                version: CASSETTE_VERSION.to_string(),
                 name: cassette_name,
                 recorded_at: Utc::now(),
                 cookies: None,
@@ -63,6 +65,8 @@ impl WebSocketRecorder {
             let interaction = Interaction {
                 recorded_at: Utc::now(),
                 response_time_ms: None, // WebSocket sessions don't have a single response time
+                http_version: None,
+                content_encoding: None,
                 kind: InteractionKind::WebSocket {
                     url,
                     messages: self.current_messages.drain(..).collect(),
@@ -3,13 +3,13 @@
 //! These tests verify the complete record/replay cycle using real HTTP requests
 //! to httpbin.org (a public HTTP testing service).
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::{
     cassette::Cassette,
     player::{Player, RequestSignature},
     recorder::Recorder,
     CertificateAuthority, MagnetoProxy, ProxyMode,
 };
-use std::collections::HashMap;
 use tempfile::TempDir;
 
 /// Helper to create a test proxy with temporary directories
@@ -110,7 +110,7 @@ async fn test_http_forwarder_direct() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://httpbin.org/get".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -133,7 +133,7 @@ async fn test_http_forwarder_post() {
 
     let forwarder = HttpForwarder::new();
 
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
 
     let request = HttpRequest {
@@ -209,7 +209,7 @@ async fn test_full_record_replay_cycle() {
             method: "GET".to_string(),
             url: "https://httpbin.org/get".to_string(),
             headers: {
-                let mut h = HashMap::new();
+                let mut h = Headers::new();
                 h.insert("User-Agent".to_string(), "matgto-test/1.0".to_string());
                 h.insert("Accept".to_string(), "application/json".to_string());
                 h
@@ -220,7 +220,7 @@ async fn test_full_record_replay_cycle() {
         let response = magneto_serge::cassette::HttpResponse {
             status: 200,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = Headers::new();
                 h.insert("Content-Type".to_string(), "application/json".to_string());
                 h.insert("Server".to_string(), "httpbin".to_string());
                 h
@@ -317,7 +317,7 @@ async fn test_full_record_replay_cycle() {
 
         // Verify cassette structure
         assert_eq!(cassette.name, cassette_name);
-        assert_eq!(cassette.version, "1.0");
+        assert_eq!(cassette.version, "2.0");
         assert_eq!(cassette.interactions.len(), 1);
 
         tracing::info!("✅ Cassette structure validated");
@@ -347,7 +347,7 @@ async fn test_record_with_post_body() {
         method: "POST".to_string(),
         url: "https://httpbin.org/post".to_string(),
         headers: {
-            let mut h = HashMap::new();
+            let mut h = Headers::new();
             h.insert("Content-Type".to_string(), "application/json".to_string());
             h
         },
@@ -357,7 +357,7 @@ async fn test_record_with_post_body() {
     let response = magneto_serge::cassette::HttpResponse {
         status: 200,
         headers: {
-            let mut h = HashMap::new();
+            let mut h = Headers::new();
             h.insert("Content-Type".to_string(), "application/json".to_string());
             h
         },
@@ -376,7 +376,7 @@ async fn test_record_with_post_body() {
         method: "POST".to_string(),
         url: "https://httpbin.org/post".to_string(),
         headers: {
-            let mut h = HashMap::new();
+            let mut h = Headers::new();
             h.insert("Content-Type".to_string(), "application/json".to_string());
             h
         },
@@ -417,12 +417,12 @@ async fn test_multiple_interactions() {
         magneto_serge::cassette::HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         },
         magneto_serge::cassette::HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"[{\"id\":1,\"name\":\"Alice\"}]".to_vec()),
         },
     );
@@ -432,12 +432,12 @@ async fn test_multiple_interactions() {
         magneto_serge::cassette::HttpRequest {
             method: "POST".to_string(),
             url: "https://api.example.com/users".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"name\":\"Bob\"}".to_vec()),
         },
         magneto_serge::cassette::HttpResponse {
             status: 201,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"id\":2,\"name\":\"Bob\"}".to_vec()),
         },
     );
@@ -447,12 +447,12 @@ async fn test_multiple_interactions() {
         magneto_serge::cassette::HttpRequest {
             method: "DELETE".to_string(),
             url: "https://api.example.com/users/1".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         },
         magneto_serge::cassette::HttpResponse {
             status: 204,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         },
     );
@@ -466,7 +466,7 @@ async fn test_multiple_interactions() {
     let get_sig = RequestSignature::from(magneto_serge::cassette::HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     });
     assert!(player.find_interaction(&get_sig).is_ok());
@@ -475,7 +475,7 @@ async fn test_multiple_interactions() {
     let post_sig = RequestSignature::from(magneto_serge::cassette::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"name\":\"Bob\"}".to_vec()),
     });
     assert!(player.find_interaction(&post_sig).is_ok());
@@ -484,7 +484,7 @@ async fn test_multiple_interactions() {
     let delete_sig = RequestSignature::from(magneto_serge::cassette::HttpRequest {
         method: "DELETE".to_string(),
         url: "https://api.example.com/users/1".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     });
     assert!(player.find_interaction(&delete_sig).is_ok());
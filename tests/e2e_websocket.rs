@@ -275,7 +275,7 @@ async fn test_websocket_full_cycle() {
         // Parse and verify
         let cassette: magneto_serge::Cassette = serde_json::from_str(&json).unwrap();
         assert_eq!(cassette.name, cassette_name);
-        assert_eq!(cassette.version, "1.0");
+        assert_eq!(cassette.version, "2.0");
         assert_eq!(cassette.interactions.len(), 3);
 
         tracing::info!("✅ Cassette structure valid");
@@ -418,3 +418,234 @@ async fn test_websocket_interceptor_live() {
         tracing::warn!("Network request failed (expected in offline environments)");
     }
 }
+
+/// Start a local WebSocket server answering every text frame with "echo: <text>"
+async fn start_echo_server() -> u16 {
+    use futures::{SinkExt, StreamExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                while let Some(Ok(msg)) = ws.next().await {
+                    match msg {
+                        tokio_tungstenite::tungstenite::Message::Text(text) => {
+                            ws.send(format!("echo: {}", text).into()).await.unwrap();
+                        }
+                        tokio_tungstenite::tungstenite::Message::Close(_) => break,
+                        _ => {}
+                    }
+                }
+            });
+        }
+    });
+
+    port
+}
+
+/// Open a WebSocket to `ws://127.0.0.1:<target_port>/feed` through the proxy (CONNECT tunnel)
+async fn connect_through_proxy(
+    proxy_port: u16,
+    target_port: u16,
+) -> tokio_tungstenite::WebSocketStream<tokio::net::TcpStream> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", proxy_port))
+        .await
+        .unwrap();
+    let authority = format!("127.0.0.1:{}", target_port);
+    stream
+        .write_all(format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", authority).as_bytes())
+        .await
+        .unwrap();
+
+    // Read the CONNECT response headers
+    let mut response = Vec::new();
+    let mut byte = [0u8; 1];
+    while !response.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).await.unwrap();
+        response.push(byte[0]);
+    }
+    assert!(String::from_utf8_lossy(&response).contains("200"));
+
+    let (ws, _) = tokio_tungstenite::client_async(format!("ws://{}/feed", authority), stream)
+        .await
+        .unwrap();
+    ws
+}
+
+#[tokio::test]
+async fn test_websocket_record_replay_through_proxy() {
+    use futures::{SinkExt, StreamExt};
+    use magneto_serge::proxy::server::ProxyServer;
+    use magneto_serge::{cassette::InteractionKind, CertificateAuthority, Player, ProxyMode};
+    use magneto_serge::{Recorder, Result};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let temp_dir = TempDir::new().unwrap();
+    let ca = Arc::new(CertificateAuthority::new(temp_dir.path().join("certs")).unwrap());
+    let cassette_dir = temp_dir.path().join("cassettes");
+    let upstream_port = start_echo_server().await;
+    let url = format!("ws://127.0.0.1:{}/feed", upstream_port);
+
+    // Record: frames in both directions end up in the recorder's cassette
+    let recorder = Arc::new(Mutex::new(Recorder::new("ws-proxy".to_string())));
+    let mut server = ProxyServer::new(0, ca.clone(), ProxyMode::Record)
+        .unwrap()
+        .with_recorder(recorder.clone());
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let mut ws = connect_through_proxy(proxy_port, upstream_port).await;
+    ws.send(Message::Text("hello".to_string())).await.unwrap();
+    let reply = ws.next().await.unwrap().unwrap();
+    assert_eq!(reply, Message::Text("echo: hello".to_string()));
+    ws.close(None).await.unwrap();
+
+    let mut recorded = Vec::new();
+    for _ in 0..50 {
+        let recorder = recorder.lock().await;
+        if let Some(InteractionKind::WebSocket {
+            url: recorded_url,
+            messages,
+            ..
+        }) = recorder.cassette().interactions.first().map(|i| &i.kind)
+        {
+            assert_eq!(recorded_url, &url);
+            recorded = messages.clone();
+            if recorded.len() >= 2 {
+                break;
+            }
+        }
+        drop(recorder);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(recorded.len(), 2);
+    assert_eq!(recorded[0].direction, Direction::Sent);
+    assert_eq!(recorded[1].direction, Direction::Received);
+
+    recorder.lock().await.save(&cassette_dir).unwrap();
+
+    // Replay: served from the cassette, even though the URL's port is now closed
+    let player: Result<Player> = Player::load(&cassette_dir, "ws-proxy");
+    let mut server = ProxyServer::new(0, ca, ProxyMode::Replay)
+        .unwrap()
+        .with_player(Arc::new(Mutex::new(player.unwrap())));
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let mut ws = connect_through_proxy(proxy_port, upstream_port).await;
+    ws.send(Message::Text("hello".to_string())).await.unwrap();
+    let reply = ws.next().await.unwrap().unwrap();
+    assert_eq!(reply, Message::Text("echo: hello".to_string()));
+}
+
+#[tokio::test]
+async fn test_websocket_forwarded_live_when_disabled() {
+    use futures::{SinkExt, StreamExt};
+    use magneto_serge::proxy::server::ProxyServer;
+    use magneto_serge::{CertificateAuthority, ProxyMode, Recorder};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let temp_dir = TempDir::new().unwrap();
+    let ca = Arc::new(CertificateAuthority::new(temp_dir.path().join("certs")).unwrap());
+    let upstream_port = start_echo_server().await;
+
+    let recorder = Arc::new(Mutex::new(Recorder::new("ws-disabled".to_string())));
+    let mut server = ProxyServer::new(0, ca, ProxyMode::Record)
+        .unwrap()
+        .with_recorder(recorder.clone())
+        .without_websocket();
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let mut ws = connect_through_proxy(proxy_port, upstream_port).await;
+    ws.send(Message::Text("hello".to_string())).await.unwrap();
+    let reply = ws.next().await.unwrap().unwrap();
+    assert_eq!(reply, Message::Text("echo: hello".to_string()));
+    ws.close(None).await.unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(recorder.lock().await.cassette().interactions.is_empty());
+}
+
+#[tokio::test]
+async fn test_websocket_record_replay_through_reverse_proxy() {
+    use futures::{SinkExt, StreamExt};
+    use magneto_serge::proxy::server::ProxyServer;
+    use magneto_serge::proxy::ReverseProxy;
+    use magneto_serge::{cassette::InteractionKind, CertificateAuthority, Player, ProxyMode};
+    use magneto_serge::{Recorder, Result};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let temp_dir = TempDir::new().unwrap();
+    let ca = Arc::new(CertificateAuthority::new(temp_dir.path().join("certs")).unwrap());
+    let cassette_dir = temp_dir.path().join("cassettes");
+    let upstream_port = start_echo_server().await;
+    let reverse = ReverseProxy::new(&format!("http://127.0.0.1:{}", upstream_port)).unwrap();
+
+    // Record: the upgrade is tunneled upstream and its frames recorded
+    let recorder = Arc::new(Mutex::new(Recorder::new("ws-reverse".to_string())));
+    let mut server = ProxyServer::new(0, ca.clone(), ProxyMode::Record)
+        .unwrap()
+        .with_recorder(recorder.clone())
+        .with_reverse_proxy(reverse.clone());
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let (mut ws, _) =
+        tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/feed", proxy_port))
+            .await
+            .unwrap();
+    ws.send(Message::Text("hello".to_string())).await.unwrap();
+    let reply = ws.next().await.unwrap().unwrap();
+    assert_eq!(reply, Message::Text("echo: hello".to_string()));
+    ws.close(None).await.unwrap();
+
+    let mut recorded = Vec::new();
+    for _ in 0..50 {
+        let recorder = recorder.lock().await;
+        if let Some(InteractionKind::WebSocket { url, messages, .. }) =
+            recorder.cassette().interactions.first().map(|i| &i.kind)
+        {
+            assert_eq!(url, &format!("ws://127.0.0.1:{}/feed", upstream_port));
+            recorded = messages.clone();
+            if recorded.len() >= 2 {
+                break;
+            }
+        }
+        drop(recorder);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(recorded.len(), 2);
+    assert_eq!(recorded[0].direction, Direction::Sent);
+    assert_eq!(recorded[1].direction, Direction::Received);
+
+    recorder.lock().await.save(&cassette_dir).unwrap();
+
+    // Replay through the same route, from the cassette
+    let player: Result<Player> = Player::load(&cassette_dir, "ws-reverse");
+    let mut server = ProxyServer::new(0, ca, ProxyMode::Replay)
+        .unwrap()
+        .with_player(Arc::new(Mutex::new(player.unwrap())))
+        .with_reverse_proxy(reverse);
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let (mut ws, _) =
+        tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/feed", proxy_port))
+            .await
+            .unwrap();
+    ws.send(Message::Text("hello".to_string())).await.unwrap();
+    let reply = ws.next().await.unwrap().unwrap();
+    assert_eq!(reply, Message::Text("echo: hello".to_string()));
+}
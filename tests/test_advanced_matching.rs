@@ -1,7 +1,7 @@
 //! Integration tests for advanced matching strategies
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::{BodyMatchMode, MatchingStrategy, Player, Recorder, UrlMatchMode};
-use std::collections::HashMap;
 use tempfile::tempdir;
 
 #[test]
@@ -12,13 +12,13 @@ fn test_regex_url_matching() {
     let request = magneto_serge::HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/users/123".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = magneto_serge::HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"id\":123,\"name\":\"Alice\"}".to_vec()),
     };
 
@@ -41,7 +41,7 @@ fn test_regex_url_matching() {
     let request_456 = magneto_serge::HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/users/456".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -52,7 +52,7 @@ fn test_regex_url_matching() {
     let request_posts = magneto_serge::HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/posts/123".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -67,13 +67,13 @@ fn test_ignore_query_params_matching() {
     let request = magneto_serge::HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/search?q=rust&page=1&timestamp=12345".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = magneto_serge::HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"results\":[]}".to_vec()),
     };
 
@@ -96,7 +96,7 @@ fn test_ignore_query_params_matching() {
     let request_diff = magneto_serge::HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/search?q=rust&page=2&timestamp=67890".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -107,7 +107,7 @@ fn test_ignore_query_params_matching() {
     let request_diff_q = magneto_serge::HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/search?q=python&page=1&timestamp=12345".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -131,13 +131,13 @@ fn test_json_path_body_matching() {
     let request = magneto_serge::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(serde_json::to_vec(&body1).unwrap()),
     };
 
     let response = magneto_serge::HttpResponse {
         status: 201,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"status\":\"created\"}".to_vec()),
     };
 
@@ -169,7 +169,7 @@ fn test_json_path_body_matching() {
     let request_diff = magneto_serge::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(serde_json::to_vec(&body2).unwrap()),
     };
 
@@ -189,7 +189,7 @@ fn test_json_path_body_matching() {
     let request_diff_id = magneto_serge::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(serde_json::to_vec(&body3).unwrap()),
     };
 
@@ -204,13 +204,13 @@ fn test_lenient_matching_strategy() {
     let request = magneto_serge::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/users?token=abc&timestamp=12345".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"some body content".to_vec()),
     };
 
     let response = magneto_serge::HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"status\":\"ok\"}".to_vec()),
     };
 
@@ -231,7 +231,7 @@ fn test_lenient_matching_strategy() {
     let request_diff = magneto_serge::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/users?token=xyz&timestamp=99999".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"completely different body".to_vec()),
     };
 
@@ -247,13 +247,13 @@ fn test_path_only_matching() {
     let request = magneto_serge::HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com:443/v1/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = magneto_serge::HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"[]".to_vec()),
     };
 
@@ -274,7 +274,7 @@ fn test_path_only_matching() {
     let request_diff = magneto_serge::HttpRequest {
         method: "GET".to_string(),
         url: "http://localhost:8080/v1/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -287,7 +287,7 @@ fn test_header_specific_matching() {
     // Create cassette
     let mut recorder = Recorder::new("test-header-match".to_string());
 
-    let mut headers1 = HashMap::new();
+    let mut headers1 = Headers::new();
     headers1.insert("Authorization".to_string(), "Bearer token123".to_string());
     headers1.insert("User-Agent".to_string(), "MyApp/1.0".to_string());
 
@@ -300,7 +300,7 @@ fn test_header_specific_matching() {
 
     let response = magneto_serge::HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"data\":\"secret\"}".to_vec()),
     };
 
@@ -318,7 +318,7 @@ fn test_header_specific_matching() {
         .with_matching_strategy(strategy);
 
     // Should match with same Authorization but different User-Agent
-    let mut headers2 = HashMap::new();
+    let mut headers2 = Headers::new();
     headers2.insert("Authorization".to_string(), "Bearer token123".to_string());
     headers2.insert("User-Agent".to_string(), "DifferentApp/2.0".to_string());
 
@@ -335,7 +335,7 @@ fn test_header_specific_matching() {
     assert_eq!(idx, 0);
 
     // Should not match with different Authorization
-    let mut headers3 = HashMap::new();
+    let mut headers3 = Headers::new();
     headers3.insert("Authorization".to_string(), "Bearer token456".to_string());
     headers3.insert("User-Agent".to_string(), "MyApp/1.0".to_string());
 
@@ -359,13 +359,13 @@ fn test_body_size_only_matching() {
     let request = magneto_serge::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/upload".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(vec![0u8; 1024]), // 1KB of zeros
     };
 
     let response = magneto_serge::HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"status\":\"ok\"}".to_vec()),
     };
 
@@ -386,7 +386,7 @@ fn test_body_size_only_matching() {
     let request_same_size = magneto_serge::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/upload".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(vec![1u8; 1024]), // 1KB of ones
     };
 
@@ -399,7 +399,7 @@ fn test_body_size_only_matching() {
     let request_diff_size = magneto_serge::HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/upload".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(vec![0u8; 2048]), // 2KB
     };
 
@@ -0,0 +1,138 @@
+//! Integration tests for cassette stacking (push/pop)
+
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::{MagnetoProxy, Player, ProxyMode};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Upstream server answering with the requested path, counting requests
+async fn upstream() -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            counter.fetch_add(1, Ordering::SeqCst);
+            let head = String::from_utf8_lossy(&buf[..n]).to_string();
+            let path = head.split(' ').nth(1).unwrap_or("/").to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                path.len(),
+                path
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (port, hits)
+}
+
+async fn get(port: u16, path: &str) -> (u16, String) {
+    let response = reqwest::get(format!("http://127.0.0.1:{}{}", port, path))
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+    (status, response.text().await.unwrap())
+}
+
+#[test]
+fn test_push_and_pop_cassettes() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (target, hits) = rt.block_on(upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new(&format!("http://127.0.0.1:{}", target)).unwrap());
+
+    // A shared cassette holding the login
+    proxy.start_recording_internal("auth".to_string()).unwrap();
+    assert_eq!(
+        rt.block_on(get(proxy.port(), "/login")),
+        (200, "/login".to_string())
+    );
+    proxy.stop_recording_internal().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    // Replay-only base, hybrid per-test cassette on top
+    proxy.replay_internal("auth".to_string()).unwrap();
+    proxy
+        .push_cassette_internal("per-test".to_string(), ProxyMode::Hybrid)
+        .unwrap();
+    assert_eq!(proxy.stacked_cassettes(), vec!["auth".to_string()]);
+    assert_eq!(proxy.current_cassette_name(), Some("per-test".to_string()));
+
+    assert_eq!(
+        rt.block_on(get(proxy.port(), "/login")),
+        (200, "/login".to_string())
+    );
+    assert_eq!(
+        rt.block_on(get(proxy.port(), "/items")),
+        (200, "/items".to_string())
+    );
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    // Popping saves the top cassette and replays the base again
+    proxy.pop_cassette_internal().unwrap();
+    assert!(proxy.stacked_cassettes().is_empty());
+    assert_eq!(proxy.current_cassette_name(), Some("auth".to_string()));
+    assert_eq!(
+        rt.block_on(get(proxy.port(), "/login")),
+        (200, "/login".to_string())
+    );
+    assert_eq!(rt.block_on(get(proxy.port(), "/items")).0, 404);
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+    assert!(proxy.pop_cassette_internal().is_err());
+    proxy.stop_replay().unwrap();
+
+    // New recordings only went to the top cassette
+    let urls = |name: &str| -> Vec<String> {
+        let player = Player::load(&cassette_dir, name).unwrap();
+        player
+            .cassette()
+            .unwrap()
+            .interactions
+            .iter()
+            .filter_map(|interaction| match &interaction.kind {
+                magneto_serge::cassette::InteractionKind::Http { request, .. } => {
+                    Some(request.url.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    };
+    assert_eq!(
+        urls("auth"),
+        vec![format!("http://127.0.0.1:{}/login", target)]
+    );
+    assert_eq!(
+        urls("per-test"),
+        vec![format!("http://127.0.0.1:{}/items", target)]
+    );
+}
+
+#[test]
+fn test_failed_push_keeps_current_cassette() {
+    let dir = tempfile::tempdir().unwrap();
+    let proxy = MagnetoProxy::new_internal(dir.path()).unwrap().with_port(0);
+
+    proxy.start_recording_internal("base".to_string()).unwrap();
+    assert!(proxy
+        .push_cassette_internal("missing".to_string(), ProxyMode::Replay)
+        .is_err());
+    assert!(proxy
+        .push_cassette_internal("other".to_string(), ProxyMode::Passthrough)
+        .is_err());
+
+    assert_eq!(proxy.current_cassette_name(), Some("base".to_string()));
+    assert!(proxy.stacked_cassettes().is_empty());
+    proxy.stop_recording_internal().unwrap();
+}
@@ -0,0 +1,99 @@
+//! Integration tests for recording compressed bodies decoded
+
+use hyper::{Body, Client, Request};
+use magneto_serge::cassette::InteractionKind;
+use magneto_serge::content_encoding::ContentCoding;
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::{MagnetoProxy, Player};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const JSON: &str = r#"{"users":["alice","bob"]}"#;
+
+/// Upstream server answering a gzip-encoded JSON body
+async fn gzip_upstream() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let body = ContentCoding::Gzip.encode(JSON.as_bytes()).unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = socket.write_all(head.as_bytes()).await;
+            let _ = socket.write_all(&body).await;
+        }
+    });
+
+    port
+}
+
+/// GET /users, returning the Content-Encoding and raw body
+async fn get(port: u16, accept_encoding: Option<&str>) -> (Option<String>, Vec<u8>) {
+    let mut request = Request::get(format!("http://127.0.0.1:{}/users", port));
+    if let Some(accept_encoding) = accept_encoding {
+        request = request.header("Accept-Encoding", accept_encoding);
+    }
+    let response = Client::new()
+        .request(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    let encoding = response
+        .headers()
+        .get("content-encoding")
+        .map(|v| v.to_str().unwrap().to_string());
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (encoding, body.to_vec())
+}
+
+#[test]
+fn test_compressed_bodies_are_recorded_decoded() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let target = rt.block_on(gzip_upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new(&format!("http://127.0.0.1:{}", target)).unwrap());
+
+    // Record: the client still receives the body as sent upstream
+    proxy.start_recording_internal("gzip".to_string()).unwrap();
+    let (encoding, body) = rt.block_on(get(proxy.port(), Some("gzip")));
+    assert_eq!(encoding.as_deref(), Some("gzip"));
+    assert_eq!(ContentCoding::Gzip.decode(&body).unwrap(), JSON.as_bytes());
+    proxy.stop_recording_internal().unwrap();
+
+    // The cassette holds the decoded body and remembers the encoding
+    let player = Player::load(&cassette_dir, "gzip").unwrap();
+    let interaction = &player.cassette().unwrap().interactions[0];
+    assert_eq!(interaction.content_encoding.as_deref(), Some("gzip"));
+    match &interaction.kind {
+        InteractionKind::Http { response, .. } => {
+            assert_eq!(response.body.as_deref(), Some(JSON.as_bytes()));
+            assert!(response.headers.get("Content-Encoding").is_none());
+            assert_eq!(
+                response.headers.get("Content-Length"),
+                Some(JSON.len().to_string().as_str())
+            );
+        }
+        other => panic!("unexpected interaction {:?}", other),
+    }
+
+    // Replay: re-encoded for clients accepting gzip, identity otherwise
+    proxy.replay_internal("gzip".to_string()).unwrap();
+    let (encoding, body) = rt.block_on(get(proxy.port(), Some("br, gzip")));
+    assert_eq!(encoding.as_deref(), Some("gzip"));
+    assert_eq!(ContentCoding::Gzip.decode(&body).unwrap(), JSON.as_bytes());
+
+    let (encoding, body) = rt.block_on(get(proxy.port(), None));
+    assert_eq!(encoding, None);
+    assert_eq!(body, JSON.as_bytes());
+    proxy.stop_replay().unwrap();
+}
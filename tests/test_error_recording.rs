@@ -1,7 +1,7 @@
 //! Integration tests for error recording and replay
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::cassette::{Cassette, HttpRequest, InteractionKind, NetworkError};
-use std::collections::HashMap;
 use tempfile::tempdir;
 
 #[test]
@@ -11,7 +11,7 @@ fn test_cassette_with_timeout_error() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://slow-api.example.com/endpoint".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -58,7 +58,7 @@ fn test_cassette_with_dns_error() {
     let request = HttpRequest {
         method: "POST".to_string(),
         url: "https://nonexistent.invalid/api/endpoint".to_string(),
-        headers: HashMap::from([("Content-Type".to_string(), "application/json".to_string())]),
+        headers: Headers::from([("Content-Type".to_string(), "application/json".to_string())]),
         body: Some(b"{\"data\":\"test\"}".to_vec()),
     };
 
@@ -93,7 +93,7 @@ fn test_cassette_with_connection_refused() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "http://localhost:9999/health".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -128,7 +128,7 @@ fn test_cassette_with_tls_error() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://expired-cert.badssl.com/".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -161,7 +161,7 @@ fn test_cassette_with_connection_reset() {
     let request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/upload".to_string(),
-        headers: HashMap::from([(
+        headers: Headers::from([(
             "Content-Type".to_string(),
             "multipart/form-data".to_string(),
         )]),
@@ -197,7 +197,7 @@ fn test_cassette_with_too_many_redirects() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/redirect-loop".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -233,7 +233,7 @@ fn test_cassette_with_other_error() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/unknown".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
@@ -272,3 +272,110 @@ fn test_network_error_equality() {
     let dns2 = NetworkError::dns_failed("dns error");
     assert_eq!(dns1, dns2);
 }
+
+fn client_through(proxy_port: u16, timeout_ms: u64) -> reqwest::Client {
+    reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://127.0.0.1:{}", proxy_port)).unwrap())
+        .timeout(std::time::Duration::from_millis(timeout_ms))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_proxy_records_and_replays_connection_reset() {
+    use magneto_serge::proxy::server::ProxyServer;
+    use magneto_serge::{CertificateAuthority, Player, ProxyMode, Recorder};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    // Upstream that accepts connections and closes them without answering
+    let upstream = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let upstream_port = upstream.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = upstream.accept().await {
+            drop(socket);
+        }
+    });
+    let url = format!("http://127.0.0.1:{}/flaky", upstream_port);
+
+    let dir = tempdir().unwrap();
+    let ca = Arc::new(CertificateAuthority::new(dir.path().join("certs")).unwrap());
+
+    // Record: the failure is stored and the client sees the connection drop, not a 502
+    let recorder = Arc::new(Mutex::new(Recorder::new("reset".to_string())));
+    let mut server = ProxyServer::new(0, ca.clone(), ProxyMode::Record)
+        .unwrap()
+        .with_recorder(recorder.clone());
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let result = client_through(proxy_port, 5_000).get(&url).send().await;
+    assert!(
+        result.is_err(),
+        "expected a transport error, got {:?}",
+        result
+    );
+
+    {
+        let recorder = recorder.lock().await;
+        let interactions = &recorder.cassette().interactions;
+        assert_eq!(interactions.len(), 1);
+        match &interactions[0].kind {
+            InteractionKind::HttpError { request, error } => {
+                assert_eq!(request.url, url);
+                assert!(matches!(error, NetworkError::ConnectionReset { .. }));
+            }
+            _ => panic!("Expected HttpError interaction"),
+        }
+        recorder.save(dir.path()).unwrap();
+    }
+
+    // Replay: the same failure, without any upstream involved
+    let player = Player::load(dir.path(), "reset").unwrap();
+    let mut server = ProxyServer::new(0, ca, ProxyMode::Replay)
+        .unwrap()
+        .with_player(Arc::new(Mutex::new(player)));
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let result = client_through(proxy_port, 5_000).get(&url).send().await;
+    let error = result.expect_err("replayed reset must not produce a response");
+    assert!(!error.is_timeout());
+}
+
+#[tokio::test]
+async fn test_proxy_replays_timeout_by_stalling() {
+    use magneto_serge::proxy::server::ProxyServer;
+    use magneto_serge::{CertificateAuthority, Player, ProxyMode};
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    let dir = tempdir().unwrap();
+    let mut cassette = Cassette::new("stall".to_string());
+    cassette.add_error(
+        HttpRequest {
+            method: "GET".to_string(),
+            url: "http://slow.example.com/report".to_string(),
+            headers: Headers::new(),
+            body: None,
+        },
+        NetworkError::timeout("No response within 5000ms", 5000),
+    );
+    let file = std::fs::File::create(dir.path().join("stall.json")).unwrap();
+    serde_json::to_writer_pretty(&file, &cassette).unwrap();
+
+    let ca = Arc::new(CertificateAuthority::new(dir.path().join("certs")).unwrap());
+    let player = Player::load(dir.path(), "stall").unwrap();
+    let mut server = ProxyServer::new(0, ca, ProxyMode::Replay)
+        .unwrap()
+        .with_player(Arc::new(Mutex::new(player)));
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let error = client_through(proxy_port, 300)
+        .get("http://slow.example.com/report")
+        .send()
+        .await
+        .expect_err("replayed timeout must not produce a response");
+    assert!(error.is_timeout());
+}
@@ -0,0 +1,206 @@
+//! Integration tests for gRPC recording and replay
+
+use hyper::body::HttpBody;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Client, HeaderMap, Request, Response};
+use magneto_serge::cassette::{Direction, HttpVersion, InteractionKind};
+use magneto_serge::grpc::{encode_frame, FrameDecoder};
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::{MagnetoProxy, Player};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+/// h2c server for `echo.Echo`, counting calls
+///
+/// `Chat` answers every request message with `echo:<message>` then OK;
+/// `Fail` answers with a trailers-only INVALID_ARGUMENT response.
+async fn echo_upstream() -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |req: Request<Body>| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    async move { Ok::<_, Infallible>(echo(req)) }
+                });
+                let _ = Http::new()
+                    .http2_only(true)
+                    .serve_connection(socket, service)
+                    .await;
+            });
+        }
+    });
+
+    (port, calls)
+}
+
+fn echo(req: Request<Body>) -> Response<Body> {
+    if req.uri().path() == "/echo.Echo/Fail" {
+        return Response::builder()
+            .header("content-type", "application/grpc")
+            .header("grpc-status", "3")
+            .header("grpc-message", "bad%20input")
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let mut request = req.into_body();
+        let mut decoder = FrameDecoder::new();
+        while let Some(Ok(chunk)) = request.data().await {
+            for (_, message) in decoder.push(&chunk) {
+                let reply = [b"echo:".as_slice(), &message].concat();
+                let _ = sender.send_data(encode_frame(false, &reply)).await;
+            }
+        }
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+        let _ = sender.send_trailers(trailers).await;
+    });
+
+    Response::builder()
+        .header("content-type", "application/grpc")
+        .body(body)
+        .unwrap()
+}
+
+/// Call `method` over h2c, returning the response messages and `grpc-status`
+async fn call(port: u16, method: &str, messages: &[&str]) -> (Vec<String>, String) {
+    let client = Client::builder().http2_only(true).build_http::<Body>();
+    let body: Vec<u8> = messages
+        .iter()
+        .flat_map(|message| encode_frame(false, message.as_bytes()).to_vec())
+        .collect();
+    let request = Request::post(format!("http://127.0.0.1:{}/echo.Echo/{}", port, method))
+        .header("content-type", "application/grpc")
+        .header("te", "trailers")
+        .body(Body::from(body))
+        .unwrap();
+
+    let response = client.request(request).await.unwrap();
+    let headers = response.headers().clone();
+    let mut body = response.into_body();
+    let mut decoder = FrameDecoder::new();
+    let mut replies = Vec::new();
+    while let Some(chunk) = body.data().await {
+        for (_, message) in decoder.push(&chunk.unwrap()) {
+            replies.push(String::from_utf8(message).unwrap());
+        }
+    }
+    let trailers = body.trailers().await.unwrap().unwrap_or_default();
+    let status = trailers
+        .get("grpc-status")
+        .or_else(|| headers.get("grpc-status"))
+        .map(|status| status.to_str().unwrap().to_string())
+        .unwrap_or_default();
+
+    (replies, status)
+}
+
+#[test]
+fn test_record_and_replay_grpc_calls() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (upstream_port, calls) = rt.block_on(echo_upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(
+            ReverseProxy::new(&format!("http://127.0.0.1:{}", upstream_port)).unwrap(),
+        );
+
+    // Record: a streaming call, a unary call and a failing call
+    proxy.start_recording_internal("grpc".to_string()).unwrap();
+    let port = proxy.port();
+    assert_eq!(
+        rt.block_on(call(port, "Chat", &["a", "b"])),
+        (
+            vec!["echo:a".to_string(), "echo:b".to_string()],
+            "0".to_string()
+        )
+    );
+    assert_eq!(
+        rt.block_on(call(port, "Chat", &["c"])),
+        (vec!["echo:c".to_string()], "0".to_string())
+    );
+    assert_eq!(
+        rt.block_on(call(port, "Fail", &["x"])),
+        (vec![], "3".to_string())
+    );
+    proxy.stop_recording_internal().unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    let player = Player::load(&cassette_dir, "grpc").unwrap();
+    let interactions = &player.cassette().unwrap().interactions;
+    assert_eq!(interactions.len(), 3);
+    assert_eq!(interactions[0].http_version, Some(HttpVersion::Http2));
+    match &interactions[0].kind {
+        InteractionKind::Grpc {
+            request,
+            response,
+            messages,
+        } => {
+            assert_eq!(request.service, "echo.Echo");
+            assert_eq!(request.method, "Chat");
+            assert_eq!(response.status, 0);
+            let directions: Vec<Direction> = messages.iter().map(|m| m.direction).collect();
+            assert_eq!(
+                directions,
+                vec![
+                    Direction::Sent,
+                    Direction::Sent,
+                    Direction::Received,
+                    Direction::Received
+                ]
+            );
+            assert_eq!(messages[3].data, b"echo:b");
+        }
+        other => panic!("unexpected interaction {:?}", other),
+    }
+    match &interactions[2].kind {
+        InteractionKind::Grpc { response, .. } => {
+            assert_eq!(response.status, 3);
+            assert_eq!(response.message.as_deref(), Some("bad input"));
+            assert!(response.trailers.is_empty());
+        }
+        other => panic!("unexpected interaction {:?}", other),
+    }
+
+    // Replay: calls are told apart by their request messages
+    proxy.replay_internal("grpc".to_string()).unwrap();
+    let port = proxy.port();
+    assert_eq!(
+        rt.block_on(call(port, "Chat", &["c"])),
+        (vec!["echo:c".to_string()], "0".to_string())
+    );
+    assert_eq!(
+        rt.block_on(call(port, "Chat", &["a", "b"])),
+        (
+            vec!["echo:a".to_string(), "echo:b".to_string()],
+            "0".to_string()
+        )
+    );
+    assert_eq!(
+        rt.block_on(call(port, "Fail", &["x"])),
+        (vec![], "3".to_string())
+    );
+    // Unknown messages get NOT_FOUND instead of reaching the upstream
+    assert_eq!(
+        rt.block_on(call(port, "Chat", &["zzz"])),
+        (vec![], "5".to_string())
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    proxy.stop_replay().unwrap();
+}
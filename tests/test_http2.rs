@@ -0,0 +1,117 @@
+//! Integration tests for HTTP/2 recording and replay
+
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Client, Request, Response, Version};
+use magneto_serge::cassette::HttpVersion;
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::tls::UpstreamTlsConfig;
+use magneto_serge::{MagnetoProxy, Player};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+/// HTTPS server that only speaks HTTP/2 (ALPN `h2` only), counting requests
+async fn h2_only_upstream() -> (u16, Arc<AtomicUsize>) {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![Certificate(cert.serialize_der().unwrap())],
+            PrivateKey(cert.serialize_private_key_der()),
+        )
+        .unwrap();
+    config.alpn_protocols = vec![b"h2".to_vec()];
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                let Ok(stream) = acceptor.accept(socket).await else {
+                    return;
+                };
+                let service = service_fn(move |req: Request<Body>| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    let body = format!("{:?}", req.version());
+                    async move { Ok::<_, Infallible>(Response::new(Body::from(body))) }
+                });
+                let _ = Http::new()
+                    .http2_only(true)
+                    .serve_connection(stream, service)
+                    .await;
+            });
+        }
+    });
+
+    (port, hits)
+}
+
+/// GET a URL with prior-knowledge HTTP/2 (h2c) or HTTP/1.1
+async fn get(url: &str, http2: bool) -> (Version, String) {
+    let client = Client::builder().http2_only(http2).build_http::<Body>();
+    let response = client.get(url.parse().unwrap()).await.unwrap();
+    let version = response.version();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (version, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[test]
+fn test_record_h2_only_upstream_and_replay_over_client_protocol() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (upstream_port, hits) = rt.block_on(h2_only_upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_upstream_tls(
+            UpstreamTlsConfig::new()
+                .with_insecure_skip_verify(true)
+                .load()
+                .unwrap(),
+        )
+        .with_reverse_proxy(
+            ReverseProxy::new(&format!("https://localhost:{}", upstream_port)).unwrap(),
+        );
+
+    // Record: HTTP/2 on both legs
+    proxy.start_recording_internal("h2".to_string()).unwrap();
+    let url = format!("http://127.0.0.1:{}/version", proxy.port());
+    let (version, body) = rt.block_on(get(&url, true));
+    assert_eq!(version, Version::HTTP_2);
+    assert_eq!(body, "HTTP/2.0");
+    proxy.stop_recording_internal().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let player = Player::load(&cassette_dir, "h2").unwrap();
+    let interactions = &player.cassette().unwrap().interactions;
+    assert_eq!(interactions.len(), 1);
+    assert_eq!(interactions[0].http_version, Some(HttpVersion::Http2));
+
+    // Replay over whichever protocol the client negotiated
+    proxy.replay_internal("h2".to_string()).unwrap();
+    let url = format!("http://127.0.0.1:{}/version", proxy.port());
+    assert_eq!(
+        rt.block_on(get(&url, true)),
+        (Version::HTTP_2, "HTTP/2.0".to_string())
+    );
+    assert_eq!(
+        rt.block_on(get(&url, false)),
+        (Version::HTTP_11, "HTTP/2.0".to_string())
+    );
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    proxy.stop_replay().unwrap();
+}
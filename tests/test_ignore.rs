@@ -0,0 +1,97 @@
+//! Integration tests for hosts ignored by recording and replay
+
+use magneto_serge::proxy::{IgnoreRules, ReverseProxy};
+use magneto_serge::{MagnetoProxy, Player};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Upstream server answering `body`, counting requests
+async fn upstream(body: &'static str) -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            counter.fetch_add(1, Ordering::SeqCst);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (port, hits)
+}
+
+async fn get(url: String) -> (u16, String) {
+    let response = reqwest::get(url).await.unwrap();
+    let status = response.status().as_u16();
+    (status, response.text().await.unwrap())
+}
+
+#[test]
+fn test_ignored_hosts_are_forwarded_live_in_every_mode() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (api, api_hits) = rt.block_on(upstream("users"));
+    let (telemetry, telemetry_hits) = rt.block_on(upstream("ok"));
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let reverse = ReverseProxy::default()
+        .with_route("/api", &format!("http://127.0.0.1:{}", api))
+        .unwrap()
+        .with_route("/telemetry", &format!("http://127.0.0.1:{}", telemetry))
+        .unwrap();
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(reverse)
+        .with_ignore_rules(
+            IgnoreRules::new()
+                .with_host(&format!("127.0.0.1:{}", telemetry))
+                .unwrap(),
+        );
+
+    // Record: the ignored host is reached but kept out of the cassette
+    proxy
+        .start_recording_internal("ignore".to_string())
+        .unwrap();
+    let base = format!("http://127.0.0.1:{}", proxy.port());
+    assert_eq!(
+        rt.block_on(get(format!("{}/api/users", base))),
+        (200, "users".to_string())
+    );
+    assert_eq!(
+        rt.block_on(get(format!("{}/telemetry/events", base))),
+        (200, "ok".to_string())
+    );
+    proxy.stop_recording_internal().unwrap();
+
+    let player = Player::load(&cassette_dir, "ignore").unwrap();
+    let interactions = &player.cassette().unwrap().interactions;
+    assert_eq!(interactions.len(), 1);
+
+    // Replay: the ignored host still goes upstream instead of a 404
+    proxy.replay_internal("ignore".to_string()).unwrap();
+    let base = format!("http://127.0.0.1:{}", proxy.port());
+    assert_eq!(
+        rt.block_on(get(format!("{}/api/users", base))),
+        (200, "users".to_string())
+    );
+    assert_eq!(
+        rt.block_on(get(format!("{}/telemetry/other", base))),
+        (200, "ok".to_string())
+    );
+    proxy.stop_replay().unwrap();
+
+    assert_eq!(api_hits.load(Ordering::SeqCst), 1);
+    assert_eq!(telemetry_hits.load(Ordering::SeqCst), 2);
+}
@@ -1,8 +1,8 @@
 //! Tests for latency simulation during replay
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::cassette::{Cassette, HttpRequest, HttpResponse, InteractionKind};
 use magneto_serge::player::{LatencyMode, Player};
-use std::collections::HashMap;
 use std::time::Instant;
 use tempfile::tempdir;
 
@@ -13,13 +13,13 @@ fn create_cassette_with_timing(name: &str, response_time_ms: u64) -> Cassette {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/test".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"test response".to_vec()),
     };
 
@@ -90,12 +90,12 @@ fn test_latency_mode_scaled_without_recorded_time() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/test".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"test".to_vec()),
     };
     cassette.add_interaction(InteractionKind::Http { request, response });
@@ -121,12 +121,12 @@ fn test_interaction_without_timing() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/test".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"test".to_vec()),
     };
     cassette.add_interaction(InteractionKind::Http { request, response });
@@ -0,0 +1,110 @@
+//! Integration tests for hybrid mode with pending review
+
+use magneto_serge::cassette::{pending, CassetteFormat, InteractionKind};
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::{MagnetoProxy, Player};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Upstream server answering with the requested path, counting requests
+async fn upstream() -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            counter.fetch_add(1, Ordering::SeqCst);
+            let head = String::from_utf8_lossy(&buf[..n]).to_string();
+            let path = head.split(' ').nth(1).unwrap_or("/").to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                path.len(),
+                path
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (port, hits)
+}
+
+async fn get(port: u16, path: &str) -> String {
+    reqwest::get(format!("http://127.0.0.1:{}{}", port, path))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap()
+}
+
+/// Paths of the recorded requests, in order
+fn paths(interactions: &[magneto_serge::cassette::Interaction]) -> Vec<String> {
+    interactions
+        .iter()
+        .filter_map(|interaction| match &interaction.kind {
+            InteractionKind::Http { request, .. } => {
+                Some(request.url.rsplit('/').next().unwrap().to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn cassette_paths(cassette_dir: &Path, name: &str) -> Vec<String> {
+    let player = Player::load(cassette_dir, name).unwrap();
+    paths(&player.cassette().unwrap().interactions)
+}
+
+fn pending_paths(cassette_dir: &Path, name: &str) -> Vec<String> {
+    paths(&pending::load_pending(cassette_dir, name, CassetteFormat::default()).unwrap())
+}
+
+#[test]
+fn test_hybrid_writes_new_interactions_to_pending_file() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (target, hits) = rt.block_on(upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new(&format!("http://127.0.0.1:{}", target)).unwrap());
+
+    proxy.start_recording_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a");
+    proxy.stop_recording_internal().unwrap();
+
+    // New interactions go to the pending file, the cassette is untouched
+    proxy.set_pending_review(true);
+    proxy.hybrid_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a");
+    assert_eq!(rt.block_on(get(proxy.port(), "/b")), "/b");
+    proxy.stop_hybrid_internal().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+    assert_eq!(cassette_paths(&cassette_dir, "api"), vec!["a"]);
+    assert_eq!(pending_paths(&cassette_dir, "api"), vec!["b"]);
+
+    // Pending interactions replay, and the file accumulates
+    proxy.hybrid_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/b")), "/b");
+    assert_eq!(rt.block_on(get(proxy.port(), "/c")), "/c");
+    proxy.stop_hybrid_internal().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+    assert_eq!(pending_paths(&cassette_dir, "api"), vec!["b", "c"]);
+
+    // Accept one, reject the other
+    let format = CassetteFormat::default();
+    pending::accept_pending(&cassette_dir, "api", format, Some(&[1])).unwrap();
+    assert_eq!(cassette_paths(&cassette_dir, "api"), vec!["a", "c"]);
+    pending::reject_pending(&cassette_dir, "api", format, None).unwrap();
+    assert!(pending_paths(&cassette_dir, "api").is_empty());
+    assert!(pending::find_pending(&cassette_dir, "api", format).is_none());
+}
@@ -0,0 +1,103 @@
+//! Integration tests for record and replay hooks registered on the proxy
+
+use magneto_serge::cassette::{Interaction, InteractionKind};
+use magneto_serge::error::Result;
+use magneto_serge::hooks::{RecordHook, ReplayHook};
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::MagnetoProxy;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Upstream server answering with the request target
+async fn upstream() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let head = String::from_utf8_lossy(&buf[..n]).to_string();
+            let body = head.split(' ').nth(1).unwrap_or("/").to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    port
+}
+
+async fn get(port: u16, path: &str) -> reqwest::Response {
+    reqwest::get(format!("http://127.0.0.1:{}{}", port, path))
+        .await
+        .unwrap()
+}
+
+/// Tags recorded responses with a header
+#[derive(Debug)]
+struct TagRecorded;
+
+impl RecordHook for TagRecorded {
+    fn before_record(&self, interaction: &mut Interaction) -> Result<()> {
+        if let InteractionKind::Http { response, .. } = &mut interaction.kind {
+            response
+                .headers
+                .insert("x-recorded-by".to_string(), "hook".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Rewrites replayed response bodies
+#[derive(Debug)]
+struct RewriteBody;
+
+impl ReplayHook for RewriteBody {
+    fn before_replay(&self, interaction: &mut Interaction) -> Result<()> {
+        if let InteractionKind::Http { response, .. } = &mut interaction.kind {
+            response.body = Some(b"rewritten".to_vec());
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_proxy_hooks_mutate_recorded_and_replayed_interactions() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let target = rt.block_on(upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new(&format!("http://127.0.0.1:{}", target)).unwrap())
+        .with_record_hook(TagRecorded)
+        .with_replay_hook(RewriteBody);
+
+    proxy.start_recording_internal("hooks".to_string()).unwrap();
+    let body = rt.block_on(async { get(proxy.port(), "/users").await.text().await.unwrap() });
+    assert_eq!(body, "/users");
+    proxy.stop_recording_internal().unwrap();
+
+    let cassette = std::fs::read_to_string(cassette_dir.join("hooks.json")).unwrap();
+    assert!(cassette.contains("x-recorded-by"));
+
+    proxy.replay_internal("hooks".to_string()).unwrap();
+    let (tag, body) = rt.block_on(async {
+        let response = get(proxy.port(), "/users").await;
+        let tag = response.headers()["x-recorded-by"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        (tag, response.text().await.unwrap())
+    });
+    proxy.stop_replay().unwrap();
+
+    assert_eq!(tag, "hook");
+    assert_eq!(body, "rewritten");
+}
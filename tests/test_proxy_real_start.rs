@@ -95,3 +95,136 @@ fn test_proxy_starts_and_binds_port() {
     // Cleanup
     std::fs::remove_dir_all(cassette_dir).ok();
 }
+
+#[test]
+fn test_proxy_restarts_on_same_port() {
+    let dir = tempfile::tempdir().unwrap();
+    let proxy = MagnetoProxy::new_internal(dir.path().join("cassettes")).unwrap();
+    proxy.set_port(0);
+
+    proxy.start_recording_internal("first".to_string()).unwrap();
+    let port = proxy.port();
+    proxy.stop_recording_internal().unwrap();
+
+    // The port is released as soon as the stop call returns
+    drop(std::net::TcpListener::bind(("127.0.0.1", port)).expect("port should be free"));
+
+    proxy.set_port(port);
+    for _ in 0..3 {
+        proxy.replay_internal("first".to_string()).unwrap();
+        assert_eq!(proxy.port(), port);
+        proxy.stop_replay().unwrap();
+    }
+}
+
+#[test]
+fn test_stop_recording_drains_in_flight_requests() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+
+    // Upstream that takes a while to answer
+    let upstream = rt
+        .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+        .unwrap();
+    let upstream_port = upstream.local_addr().unwrap().port();
+    rt.spawn(async move {
+        while let Ok((mut socket, _)) = upstream.accept().await {
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nslow")
+                .await;
+        }
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir).unwrap();
+    proxy.set_port(0);
+    proxy.start_recording_internal("drain".to_string()).unwrap();
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://127.0.0.1:{}", proxy.port())).unwrap())
+        .build()
+        .unwrap();
+    let url = format!("http://127.0.0.1:{}/slow", upstream_port);
+    let request = rt.spawn(async move { client.get(&url).send().await?.text().await });
+
+    std::thread::sleep(Duration::from_millis(100));
+    proxy.stop_recording_internal().unwrap();
+
+    assert_eq!(rt.block_on(request).unwrap().unwrap(), "slow");
+    let player = magneto_serge::Player::load(&cassette_dir, "drain").unwrap();
+    assert_eq!(player.cassette().unwrap().interactions.len(), 1);
+}
+
+#[test]
+fn test_starting_a_new_cassette_saves_the_recording() {
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir).unwrap();
+    proxy.set_port(0);
+
+    proxy.start_recording_internal("first".to_string()).unwrap();
+    proxy
+        .start_recording_internal("second".to_string())
+        .unwrap();
+    assert!(cassette_dir.join("first.json").exists());
+
+    proxy.start_passthrough().unwrap();
+    assert!(cassette_dir.join("second.json").exists());
+    assert_eq!(proxy.current_cassette_name(), None);
+}
+
+#[test]
+fn test_proxy_state_is_available_while_draining() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+
+    // Upstream that takes a while to answer
+    let upstream = rt
+        .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+        .unwrap();
+    let upstream_port = upstream.local_addr().unwrap().port();
+    rt.spawn(async move {
+        while let Ok((mut socket, _)) = upstream.accept().await {
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            tokio::time::sleep(Duration::from_millis(1000)).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nslow")
+                .await;
+        }
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let proxy = MagnetoProxy::new_internal(dir.path().join("cassettes")).unwrap();
+    proxy.set_port(0);
+    proxy.start_recording_internal("busy".to_string()).unwrap();
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://127.0.0.1:{}", proxy.port())).unwrap())
+        .build()
+        .unwrap();
+    let url = format!("http://127.0.0.1:{}/slow", upstream_port);
+    let request = rt.spawn(async move { client.get(&url).send().await?.text().await });
+    std::thread::sleep(Duration::from_millis(100));
+
+    std::thread::scope(|scope| {
+        let stopping = scope.spawn(|| proxy.stop_recording_internal());
+        std::thread::sleep(Duration::from_millis(100));
+
+        // Getters answer while the stop call waits for the slow request
+        let started = std::time::Instant::now();
+        let _ = proxy.mode();
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert!(!stopping.is_finished());
+
+        stopping.join().unwrap().unwrap();
+    });
+
+    assert_eq!(rt.block_on(request).unwrap().unwrap(), "slow");
+}
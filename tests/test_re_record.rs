@@ -0,0 +1,202 @@
+//! Integration tests for re-recording stale interactions
+
+use chrono::{Duration as ChronoDuration, Utc};
+use magneto_serge::cassette::InteractionKind;
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::{MagnetoProxy, Player, ReRecordPolicy, Recorder};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Upstream server answering `<path> #<request number>`
+async fn upstream() -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let hit = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            let head = String::from_utf8_lossy(&buf[..n]).to_string();
+            let body = format!("{} #{}", head.split(' ').nth(1).unwrap_or("/"), hit);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (port, hits)
+}
+
+/// Upstream server answering a single request, then going away
+async fn one_shot_upstream() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        if let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 8\r\nConnection: close\r\n\r\nrecorded",
+                )
+                .await;
+        }
+    });
+
+    port
+}
+
+async fn get(port: u16, path: &str) -> String {
+    reqwest::get(format!("http://127.0.0.1:{}{}", port, path))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap()
+}
+
+/// Backdate the first interaction of a cassette by 60 days
+fn backdate_first(cassette_dir: &Path, name: &str) {
+    let player = Player::load(cassette_dir, name).unwrap();
+    let mut recorder = Recorder::new(name.to_string());
+    *recorder.cassette_mut() = player.cassette().unwrap().clone();
+    recorder.cassette_mut().interactions[0].recorded_at = Utc::now() - ChronoDuration::days(60);
+    recorder.save(cassette_dir).unwrap();
+}
+
+/// Response bodies of the cassette's interactions, in order
+fn bodies(cassette_dir: &Path, name: &str) -> Vec<String> {
+    let player = Player::load(cassette_dir, name).unwrap();
+    player
+        .cassette()
+        .unwrap()
+        .interactions
+        .iter()
+        .filter_map(|interaction| match &interaction.kind {
+            InteractionKind::Http { response, .. } => {
+                Some(String::from_utf8(response.body.clone().unwrap()).unwrap())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_stale_interactions_are_re_recorded() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (target, hits) = rt.block_on(upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new(&format!("http://127.0.0.1:{}", target)).unwrap())
+        .with_re_record_policy(
+            ReRecordPolicy::new().with_interval(Duration::from_secs(30 * 24 * 60 * 60)),
+        );
+
+    proxy.start_recording_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a #1");
+    assert_eq!(rt.block_on(get(proxy.port(), "/b")), "/b #2");
+    proxy.stop_recording_internal().unwrap();
+    backdate_first(&cassette_dir, "api");
+
+    // Auto: the stale interaction is fetched again, the fresh one replays
+    proxy.auto("api");
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a #3");
+    assert_eq!(rt.block_on(get(proxy.port(), "/b")), "/b #2");
+    proxy.stop_replay().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+    assert_eq!(bodies(&cassette_dir, "api"), vec!["/b #2", "/a #3"]);
+
+    // Nothing is stale anymore: plain replay
+    proxy.auto("api");
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a #3");
+    proxy.stop_replay().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    // Once: stale interactions that were not requested stay as they are
+    backdate_first(&cassette_dir, "api");
+    proxy.once_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a #3");
+    proxy.stop_once_internal().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+    assert_eq!(bodies(&cassette_dir, "api"), vec!["/b #2", "/a #3"]);
+
+    // Hybrid: the backdated interaction is re-recorded in place
+    proxy.hybrid_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/b")), "/b #4");
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a #3");
+    proxy.stop_hybrid_internal().unwrap();
+    assert_eq!(bodies(&cassette_dir, "api"), vec!["/a #3", "/b #4"]);
+}
+
+#[test]
+fn test_replay_ignores_re_record_interval() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (target, hits) = rt.block_on(upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new(&format!("http://127.0.0.1:{}", target)).unwrap())
+        .with_re_record_policy(
+            ReRecordPolicy::new().with_cassette_interval("api", Duration::from_secs(60)),
+        );
+
+    proxy.start_recording_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a #1");
+    proxy.stop_recording_internal().unwrap();
+    backdate_first(&cassette_dir, "api");
+
+    proxy.replay_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "/a #1");
+    proxy.stop_replay().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_stale_interaction_is_kept_when_upstream_fails() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let target = rt.block_on(one_shot_upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new(&format!("http://127.0.0.1:{}", target)).unwrap())
+        .with_re_record_policy(
+            ReRecordPolicy::new().with_interval(Duration::from_secs(30 * 24 * 60 * 60)),
+        );
+
+    proxy.start_recording_internal("api".to_string()).unwrap();
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "recorded");
+    proxy.stop_recording_internal().unwrap();
+    backdate_first(&cassette_dir, "api");
+
+    // Upstream is gone: the recorded response is served and kept
+    proxy.auto("api");
+    assert_eq!(rt.block_on(get(proxy.port(), "/a")), "recorded");
+    proxy.stop_replay().unwrap();
+
+    let player = Player::load(&cassette_dir, "api").unwrap();
+    let interactions = &player.cassette().unwrap().interactions;
+    assert_eq!(interactions.len(), 1);
+    assert!(matches!(interactions[0].kind, InteractionKind::Http { .. }));
+    assert_eq!(bodies(&cassette_dir, "api"), vec!["recorded"]);
+}
@@ -0,0 +1,197 @@
+//! Integration tests for the reverse-proxy (base URL) listener
+
+use magneto_serge::cassette::{
+    storage, Cassette, CassetteFormat, Headers, HttpRequest, HttpResponse, InteractionKind,
+};
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::{MagnetoProxy, Player};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Upstream server answering `hello`, keeping every request head it sees
+async fn upstream() -> (u16, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            log.lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&buf[..n]).to_string());
+            let _ = socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+                )
+                .await;
+        }
+    });
+
+    (port, seen)
+}
+
+#[test]
+fn test_reverse_proxy_records_real_urls_and_replays_them() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (target, seen) = rt.block_on(upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let reverse = ReverseProxy::default()
+        .with_route("/api", &format!("http://127.0.0.1:{}/v1", target))
+        .unwrap();
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(reverse);
+
+    // Record: the client only changes its base URL
+    proxy
+        .start_recording_internal("reverse".to_string())
+        .unwrap();
+    let base = format!("http://127.0.0.1:{}/api", proxy.port());
+    let body = rt
+        .block_on(async {
+            reqwest::get(format!("{}/users?page=2", base))
+                .await?
+                .text()
+                .await
+        })
+        .unwrap();
+    assert_eq!(body, "hello");
+    proxy.stop_recording_internal().unwrap();
+
+    {
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert!(seen[0].starts_with("GET /v1/users?page=2 HTTP/1.1\r\n"));
+        assert!(seen[0]
+            .to_lowercase()
+            .contains(&format!("host: 127.0.0.1:{}\r\n", target)));
+    }
+
+    // The cassette holds the upstream URL, as if recorded through the MITM proxy
+    let player = Player::load(&cassette_dir, "reverse").unwrap();
+    let interactions = &player.cassette().unwrap().interactions;
+    assert_eq!(interactions.len(), 1);
+    match &interactions[0].kind {
+        InteractionKind::Http { request, .. } => assert_eq!(
+            request.url,
+            format!("http://127.0.0.1:{}/v1/users?page=2", target)
+        ),
+        other => panic!("unexpected interaction {:?}", other),
+    }
+
+    // Replay without reaching the upstream
+    proxy.replay_internal("reverse".to_string()).unwrap();
+    let base = format!("http://127.0.0.1:{}/api", proxy.port());
+    let body = rt
+        .block_on(async {
+            reqwest::get(format!("{}/users?page=2", base))
+                .await?
+                .text()
+                .await
+        })
+        .unwrap();
+    assert_eq!(body, "hello");
+    assert_eq!(seen.lock().unwrap().len(), 1);
+
+    // Requests outside every route are rejected
+    let status = rt
+        .block_on(async { reqwest::get(format!("http://127.0.0.1:{}/other", proxy.port())).await })
+        .unwrap()
+        .status();
+    assert_eq!(status, reqwest::StatusCode::BAD_GATEWAY);
+
+    proxy.stop_replay().unwrap();
+}
+
+/// Save a cassette answering `GET url` with `body`
+fn save_cassette(cassette_dir: &std::path::Path, name: &str, url: &str, body: &str) {
+    let mut cassette = Cassette::new(name.to_string());
+    cassette.add_interaction(InteractionKind::Http {
+        request: HttpRequest {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            headers: Headers::new(),
+            body: None,
+        },
+        response: HttpResponse {
+            status: 200,
+            headers: Headers::new(),
+            body: Some(body.as_bytes().to_vec()),
+        },
+    });
+    let path = cassette_dir.join(format!("{}.json", name));
+    storage::save_cassette(&cassette, &path, CassetteFormat::Json).unwrap();
+}
+
+#[test]
+fn test_mitm_recorded_https_cassette_replays_through_reverse_proxy() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    std::fs::create_dir_all(&cassette_dir).unwrap();
+
+    // The MITM proxy records HTTPS URLs with the CONNECT authority's port
+    save_cassette(
+        &cassette_dir,
+        "mitm",
+        "https://api.example.com:443/v1/users",
+        "from mitm",
+    );
+
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new("https://api.example.com").unwrap());
+    proxy.replay_internal("mitm".to_string()).unwrap();
+
+    let url = format!("http://127.0.0.1:{}/v1/users", proxy.port());
+    let response = rt.block_on(reqwest::get(url)).unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(rt.block_on(response.text()).unwrap(), "from mitm");
+
+    proxy.stop_replay().unwrap();
+}
+
+#[test]
+fn test_reverse_recorded_https_cassette_replays_through_mitm_proxy() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    std::fs::create_dir_all(&cassette_dir).unwrap();
+
+    // The URL the reverse listener records for this route
+    let reverse = ReverseProxy::new("https://api.example.com").unwrap();
+    let url = reverse.upstream_url(None, "/v1/users").unwrap();
+    save_cassette(&cassette_dir, "reverse-https", &url, "from reverse");
+
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0);
+    proxy.replay_internal("reverse-https".to_string()).unwrap();
+
+    let client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::all(format!("http://127.0.0.1:{}", proxy.port())).unwrap())
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    let body = rt
+        .block_on(async {
+            let response = client
+                .get("https://api.example.com/v1/users")
+                .send()
+                .await?;
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+            response.text().await
+        })
+        .unwrap();
+    assert_eq!(body, "from reverse");
+
+    proxy.stop_replay().unwrap();
+}
@@ -0,0 +1,87 @@
+//! Integration tests for sensitive data placeholders
+
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::{MagnetoProxy, SensitiveData};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Upstream server answering with the request target
+async fn upstream() -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            counter.fetch_add(1, Ordering::SeqCst);
+            let head = String::from_utf8_lossy(&buf[..n]).to_string();
+            let body = head.split(' ').nth(1).unwrap_or("/").to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (port, hits)
+}
+
+async fn get(port: u16, path: &str) -> String {
+    reqwest::Client::new()
+        .get(format!("http://127.0.0.1:{}{}", port, path))
+        .header("Authorization", "Bearer s3cr3t")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_secrets_are_recorded_as_placeholders_and_restored() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (target, hits) = rt.block_on(upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(ReverseProxy::new(&format!("http://127.0.0.1:{}", target)).unwrap())
+        .with_sensitive_data(
+            SensitiveData::new()
+                .with_header("authorization")
+                .with_query_param("api_key")
+                .with_secret("k3y", "<KEY>"),
+        );
+
+    proxy.start_recording_internal("api".to_string()).unwrap();
+    assert_eq!(
+        rt.block_on(get(proxy.port(), "/users?api_key=k3y")),
+        "/users?api_key=k3y"
+    );
+    proxy.stop_recording_internal().unwrap();
+
+    let cassette = std::fs::read_to_string(cassette_dir.join("api.json")).unwrap();
+    assert!(!cassette.contains("s3cr3t"));
+    assert!(!cassette.contains("k3y"));
+    assert!(cassette.contains("/users?api_key=<API_KEY>"));
+    assert!(cassette.contains("<AUTHORIZATION>"));
+
+    // The live request matches its redacted recording, the response gets the value back
+    proxy.replay_internal("api".to_string()).unwrap();
+    assert_eq!(
+        rt.block_on(get(proxy.port(), "/users?api_key=k3y")),
+        "/users?api_key=k3y"
+    );
+    proxy.stop_replay().unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,123 @@
+//! Integration tests for Server-Sent Events recording and replay
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Client, Request, Response, Server};
+use magneto_serge::cassette::InteractionKind;
+use magneto_serge::player::LatencyMode;
+use magneto_serge::proxy::ReverseProxy;
+use magneto_serge::{MagnetoProxy, Player};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Server streaming three events 150ms apart on `/events`, counting requests
+async fn event_upstream() -> (u16, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    let make_svc = make_service_fn(move |_| {
+        let counter = counter.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |_req: Request<Body>| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async move { Ok::<_, Infallible>(events()) }
+            }))
+        }
+    });
+
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+    let port = server.local_addr().port();
+    tokio::spawn(server);
+
+    (port, calls)
+}
+
+fn events() -> Response<Body> {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let _ = sender.send_data("id: 1\ndata: first\n\n".into()).await;
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        // A keep-alive comment and an event split across two chunks
+        let _ = sender.send_data(": ping\n\nevent: upd".into()).await;
+        let _ = sender.send_data("ate\ndata: second\n\n".into()).await;
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let _ = sender.send_data("id: 3\ndata: a\ndata: b\n\n".into()).await;
+    });
+
+    Response::builder()
+        .header("content-type", "text/event-stream")
+        .header("cache-control", "no-cache")
+        .body(body)
+        .unwrap()
+}
+
+/// Read the whole stream, returning its body and how long it took
+async fn subscribe(port: u16) -> (String, Duration) {
+    let started = Instant::now();
+    let response = Client::new()
+        .get(format!("http://127.0.0.1:{}/events", port).parse().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+    (String::from_utf8(body.to_vec()).unwrap(), started.elapsed())
+}
+
+#[test]
+fn test_record_and_replay_event_stream() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (upstream_port, calls) = rt.block_on(event_upstream());
+
+    let dir = tempfile::tempdir().unwrap();
+    let cassette_dir = dir.path().join("cassettes");
+    let proxy = MagnetoProxy::new_internal(&cassette_dir)
+        .unwrap()
+        .with_port(0)
+        .with_reverse_proxy(
+            ReverseProxy::new(&format!("http://127.0.0.1:{}", upstream_port)).unwrap(),
+        );
+
+    // Record: the client receives the stream unchanged
+    proxy.start_recording_internal("sse".to_string()).unwrap();
+    let (body, _) = rt.block_on(subscribe(proxy.port()));
+    assert_eq!(
+        body,
+        "id: 1\ndata: first\n\n: ping\n\nevent: update\ndata: second\n\nid: 3\ndata: a\ndata: b\n\n"
+    );
+    proxy.stop_recording_internal().unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let player = Player::load(&cassette_dir, "sse").unwrap();
+    let interactions = &player.cassette().unwrap().interactions;
+    assert_eq!(interactions.len(), 1);
+    match &interactions[0].kind {
+        InteractionKind::ServerSentEvents {
+            response, events, ..
+        } => {
+            assert_eq!(response.status, 200);
+            assert!(response.body.is_none());
+            let data: Vec<&str> = events.iter().map(|e| e.data.as_str()).collect();
+            assert_eq!(data, vec!["first", "second", "a\nb"]);
+            assert_eq!(events[1].event.as_deref(), Some("update"));
+            assert_eq!(events[2].id.as_deref(), Some("3"));
+            assert!(events[1].timestamp_ms >= events[0].timestamp_ms + 100);
+            assert!(events[2].timestamp_ms >= events[1].timestamp_ms + 100);
+        }
+        other => panic!("unexpected interaction {:?}", other),
+    }
+
+    // Replay with the recorded pacing, without reaching the upstream
+    proxy.set_latency(LatencyMode::Recorded);
+    proxy.replay_internal("sse".to_string()).unwrap();
+    let (body, elapsed) = rt.block_on(subscribe(proxy.port()));
+    assert_eq!(
+        body,
+        "id: 1\ndata: first\n\nevent: update\ndata: second\n\nid: 3\ndata: a\ndata: b\n\n"
+    );
+    assert!(elapsed >= Duration::from_millis(250), "{:?}", elapsed);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    proxy.stop_replay().unwrap();
+}
@@ -0,0 +1,162 @@
+//! Integration tests for streamed response bodies
+
+use magneto_serge::cassette::InteractionKind;
+use magneto_serge::proxy::server::ProxyServer;
+use magneto_serge::{CertificateAuthority, ProxyMode, Recorder};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{oneshot, Mutex};
+
+/// Upstream that sends a chunked body in two parts
+///
+/// The second part is only sent once `release` fires, so a client that sees
+/// the first part before releasing proves the proxy did not buffer.
+async fn two_part_upstream(release: oneshot::Receiver<()>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 4096];
+        let _ = socket.read(&mut buf).await;
+
+        socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n5\r\nfirst\r\n",
+            )
+            .await
+            .unwrap();
+        socket.flush().await.unwrap();
+
+        let _ = release.await;
+        let _ = socket.write_all(b"6\r\nsecond\r\n0\r\n\r\n").await;
+    });
+
+    format!("http://127.0.0.1:{}/events", port)
+}
+
+/// Upstream that answers every request with a fixed body
+async fn fixed_upstream(body: &'static str) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+
+    format!("http://127.0.0.1:{}/download", port)
+}
+
+fn client_through(proxy_port: u16) -> reqwest::Client {
+    reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(format!("http://127.0.0.1:{}", proxy_port)).unwrap())
+        .build()
+        .unwrap()
+}
+
+/// Read the first part, release the upstream, then read the rest
+async fn read_in_two_parts(
+    client: reqwest::Client,
+    url: &str,
+    release: oneshot::Sender<()>,
+) -> String {
+    let mut response = client.get(url).send().await.unwrap();
+
+    let first = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+        .await
+        .expect("first chunk should arrive before the upstream finishes")
+        .unwrap()
+        .unwrap();
+    assert_eq!(&first[..], b"first");
+
+    release.send(()).unwrap();
+
+    let mut body = String::from_utf8(first.to_vec()).unwrap();
+    while let Some(chunk) = response.chunk().await.unwrap() {
+        body.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    body
+}
+
+#[tokio::test]
+async fn test_record_streams_response_and_records_it() {
+    let (release, released) = oneshot::channel();
+    let url = two_part_upstream(released).await;
+
+    let dir = tempdir().unwrap();
+    let ca = Arc::new(CertificateAuthority::new(dir.path().join("certs")).unwrap());
+    let recorder = Arc::new(Mutex::new(Recorder::new("stream".to_string())));
+    let mut server = ProxyServer::new(0, ca, ProxyMode::Record)
+        .unwrap()
+        .with_recorder(recorder.clone());
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let body = read_in_two_parts(client_through(proxy_port), &url, release).await;
+    assert_eq!(body, "firstsecond");
+
+    let recorder = recorder.lock().await;
+    let interactions = &recorder.cassette().interactions;
+    assert_eq!(interactions.len(), 1);
+    match &interactions[0].kind {
+        InteractionKind::Http { response, .. } => {
+            assert_eq!(response.body.as_deref(), Some(&b"firstsecond"[..]));
+        }
+        _ => panic!("Expected HTTP interaction"),
+    }
+}
+
+#[tokio::test]
+async fn test_passthrough_streams_response() {
+    let (release, released) = oneshot::channel();
+    let url = two_part_upstream(released).await;
+
+    let dir = tempdir().unwrap();
+    let ca = Arc::new(CertificateAuthority::new(dir.path().join("certs")).unwrap());
+    let mut server = ProxyServer::new(0, ca, ProxyMode::Passthrough).unwrap();
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    let body = read_in_two_parts(client_through(proxy_port), &url, release).await;
+    assert_eq!(body, "firstsecond");
+}
+
+#[tokio::test]
+async fn test_record_skips_bodies_over_the_cap() {
+    let url = fixed_upstream("a body larger than the cap").await;
+
+    let dir = tempdir().unwrap();
+    let ca = Arc::new(CertificateAuthority::new(dir.path().join("certs")).unwrap());
+    let recorder = Arc::new(Mutex::new(
+        Recorder::new("capped".to_string()).with_max_body_size(8),
+    ));
+    let mut server = ProxyServer::new(0, ca, ProxyMode::Record)
+        .unwrap()
+        .with_recorder(recorder.clone());
+    let proxy_port = server.bind().unwrap().port();
+    tokio::spawn(server.start());
+
+    // The client still receives the whole body
+    let body = client_through(proxy_port)
+        .get(&url)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "a body larger than the cap");
+
+    assert!(recorder.lock().await.cassette().interactions.is_empty());
+}
@@ -1,10 +1,10 @@
 //! Integration tests for STRICT replay mode
 
+use magneto_serge::cassette::Headers;
 use magneto_serge::cassette::{HttpRequest, HttpResponse};
 use magneto_serge::player::Player;
 use magneto_serge::proxy::{MagnetoProxy, ProxyMode};
 use magneto_serge::recorder::Recorder;
-use std::collections::HashMap;
 use tempfile::tempdir;
 
 #[test]
@@ -15,13 +15,13 @@ fn test_strict_mode_load() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"users\":[]}".to_vec()),
     };
 
@@ -55,13 +55,13 @@ fn test_strict_mode_error_on_missing_interaction() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/users".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"users\":[]}".to_vec()),
     };
 
@@ -94,13 +94,13 @@ fn test_proxy_strict_mode_creation() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/test".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{}".to_vec()),
     };
 
@@ -124,13 +124,13 @@ fn test_strict_mode_vs_normal_mode() {
     let request = HttpRequest {
         method: "GET".to_string(),
         url: "https://api.example.com/data".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: None,
     };
 
     let response = HttpResponse {
         status: 200,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{}".to_vec()),
     };
 
@@ -163,13 +163,13 @@ fn test_strict_mode_multiple_interactions() {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: format!("https://api.example.com/resource/{}", i),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(format!("{{\"id\":{}}}", i).into_bytes()),
         };
 
@@ -213,13 +213,13 @@ fn test_strict_mode_with_body_hash() {
     let request = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/create".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"name\":\"test\"}".to_vec()),
     };
 
     let response = HttpResponse {
         status: 201,
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"id\":1}".to_vec()),
     };
 
@@ -240,7 +240,7 @@ fn test_strict_mode_with_body_hash() {
     let different_body = HttpRequest {
         method: "POST".to_string(),
         url: "https://api.example.com/create".to_string(),
-        headers: HashMap::new(),
+        headers: Headers::new(),
         body: Some(b"{\"name\":\"different\"}".to_vec()),
     };
 
@@ -6,10 +6,9 @@
 #[cfg(feature = "templates")]
 mod templates_integration {
     use magneto_serge::{
-        cassette::{HttpRequest, HttpResponse, InteractionKind},
+        cassette::{Headers, HttpRequest, HttpResponse, InteractionKind},
         Player, Recorder,
     };
-    use std::collections::HashMap;
     use tempfile::tempdir;
 
     /// Test basic template rendering with environment variables
@@ -24,13 +23,13 @@ mod templates_integration {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/auth".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(r#"{"api_key":"{{ env "TEST_API_KEY" }}"}"#.as_bytes().to_vec()),
         };
 
@@ -74,13 +73,13 @@ mod templates_integration {
         let request = HttpRequest {
             method: "POST".to_string(),
             url: "https://api.example.com/events".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 201,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(
                 r#"{"event_id":"evt_123","created_at":"{{ now }}","timestamp":{{ now_timestamp }}}"#
                     .as_bytes()
@@ -127,13 +126,13 @@ mod templates_integration {
         let request = HttpRequest {
             method: "POST".to_string(),
             url: "https://api.example.com/resources".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 201,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(
                 r#"{"resource_id":"{{ uuid }}","status":"created"}"#
                     .as_bytes()
@@ -177,7 +176,7 @@ mod templates_integration {
     fn test_template_request_headers_in_cassette() {
         let mut recorder = Recorder::new("test-request-headers-template".to_string());
 
-        let mut headers = HashMap::new();
+        let mut headers = Headers::new();
         headers.insert("x-user-id".to_string(), "user-12345".to_string());
         headers.insert("x-session-id".to_string(), "sess-abcde".to_string());
 
@@ -190,7 +189,7 @@ mod templates_integration {
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(
                 r#"{"user_id":"{{ request.headers.x-user-id }}","session":"{{ request.headers.x-session-id }}"}"#
                     .as_bytes()
@@ -233,7 +232,7 @@ mod templates_integration {
 
         let mut recorder = Recorder::new("test-complex-template".to_string());
 
-        let mut headers = HashMap::new();
+        let mut headers = Headers::new();
         headers.insert("x-request-id".to_string(), "req-xyz".to_string());
 
         let request = HttpRequest {
@@ -245,7 +244,7 @@ mod templates_integration {
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(
                 r#"{
   "webhook_id": "{{ uuid }}",
@@ -306,13 +305,13 @@ mod templates_integration {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/static".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"message\":\"Hello, World!\"}".to_vec()),
         };
 
@@ -356,13 +355,13 @@ mod templates_integration {
         let request = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/custom".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(
                 r#"{"custom":"{{ my_helper }}","standard":"{{ uuid }}"}"#
                     .as_bytes()
@@ -416,13 +415,13 @@ mod templates_integration {
         let request1 = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/endpoint1".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response1 = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(r#"{"value":"{{ env "ENV_VAR_1" }}"}"#.as_bytes().to_vec()),
         };
 
@@ -432,13 +431,13 @@ mod templates_integration {
         let request2 = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/endpoint2".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response2 = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(
                 r#"{"value":"{{ env "ENV_VAR_2" }}","id":"{{ uuid }}"}"#
                     .as_bytes()
@@ -452,13 +451,13 @@ mod templates_integration {
         let request3 = HttpRequest {
             method: "GET".to_string(),
             url: "https://api.example.com/endpoint3".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
         let response3 = HttpResponse {
             status: 200,
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: Some(b"{\"value\":\"static\"}".to_vec()),
         };
 
@@ -524,8 +523,8 @@ mod templates_integration {
 /// Tests when templates feature is disabled
 #[cfg(not(feature = "templates"))]
 mod templates_disabled {
+    use magneto_serge::cassette::Headers;
     use magneto_serge::TemplateEngine;
-    use std::collections::HashMap;
 
     #[test]
     fn test_template_engine_stub() {
@@ -540,7 +539,7 @@ mod templates_disabled {
         let request = magneto_serge::cassette::HttpRequest {
             method: "GET".to_string(),
             url: "https://example.com".to_string(),
-            headers: HashMap::new(),
+            headers: Headers::new(),
             body: None,
         };
 
@@ -0,0 +1,203 @@
+//! Integration tests for upstream TLS trust roots and client certificates
+
+use magneto_serge::cassette::{Headers, HttpRequest, NetworkError};
+use magneto_serge::proxy::HttpForwarder;
+use magneto_serge::tls::UpstreamTlsConfig;
+use magneto_serge::MatgtoError;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa, SanType,
+};
+use rustls::server::AllowAnyAuthenticatedClient;
+use rustls::{PrivateKey, RootCertStore, ServerConfig};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+/// PEM files for an internal CA, a `localhost` server and a client
+struct Pki {
+    ca: String,
+    server_cert: String,
+    server_key: String,
+    client_cert: String,
+    client_key: String,
+}
+
+fn leaf(name: &str, usage: ExtendedKeyUsagePurpose, ca: &Certificate) -> (String, String) {
+    let mut params = CertificateParams::new(vec![name.to_string()]);
+    params.subject_alt_names = vec![SanType::DnsName(name.to_string())];
+    params.extended_key_usages = vec![usage];
+    let cert = Certificate::from_params(params).unwrap();
+    (
+        cert.serialize_pem_with_signer(ca).unwrap(),
+        cert.serialize_private_key_pem(),
+    )
+}
+
+fn pki() -> Pki {
+    let mut params = CertificateParams::new(Vec::new());
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = Certificate::from_params(params).unwrap();
+
+    let (server_cert, server_key) = leaf("localhost", ExtendedKeyUsagePurpose::ServerAuth, &ca);
+    let (client_cert, client_key) = leaf("client", ExtendedKeyUsagePurpose::ClientAuth, &ca);
+
+    Pki {
+        ca: ca.serialize_pem().unwrap(),
+        server_cert,
+        server_key,
+        client_cert,
+        client_key,
+    }
+}
+
+fn der(pem: &str) -> Vec<Vec<u8>> {
+    rustls_pemfile::read_all(&mut pem.as_bytes())
+        .unwrap()
+        .into_iter()
+        .map(|item| match item {
+            rustls_pemfile::Item::X509Certificate(der) | rustls_pemfile::Item::PKCS8Key(der) => der,
+            other => panic!("unexpected PEM item {:?}", other),
+        })
+        .collect()
+}
+
+/// HTTPS server requiring a client certificate signed by the CA
+async fn mtls_upstream(pki: &Pki) -> u16 {
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(&rustls::Certificate(der(&pki.ca).remove(0)))
+        .unwrap();
+
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+        .with_single_cert(
+            der(&pki.server_cert)
+                .into_iter()
+                .map(rustls::Certificate)
+                .collect(),
+            PrivateKey(der(&pki.server_key).remove(0)),
+        )
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(mut stream) = acceptor.accept(socket).await else {
+                    return;
+                };
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+                    )
+                    .await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    port
+}
+
+fn write_pki(pki: &Pki, dir: &Path) {
+    std::fs::write(dir.join("ca.pem"), &pki.ca).unwrap();
+    std::fs::write(dir.join("client.pem"), &pki.client_cert).unwrap();
+    std::fs::write(dir.join("client-key.pem"), &pki.client_key).unwrap();
+}
+
+fn get(port: u16) -> HttpRequest {
+    HttpRequest {
+        method: "GET".to_string(),
+        url: format!("https://localhost:{}/data", port),
+        headers: Headers::new(),
+        body: None,
+    }
+}
+
+#[tokio::test]
+async fn test_forward_with_ca_bundle_and_client_cert() {
+    let pki = pki();
+    let dir = tempfile::tempdir().unwrap();
+    write_pki(&pki, dir.path());
+    let port = mtls_upstream(&pki).await;
+
+    let tls = UpstreamTlsConfig::new()
+        .with_ca_bundle(dir.path().join("ca.pem"))
+        .with_client_cert(
+            ["localhost"],
+            dir.path().join("client.pem"),
+            dir.path().join("client-key.pem"),
+        )
+        .load()
+        .unwrap();
+    let forwarder = HttpForwarder::new().with_tls(tls);
+
+    let response = forwarder.forward(&get(port)).await.unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body.as_deref(), Some(&b"hello"[..]));
+}
+
+#[tokio::test]
+async fn test_client_cert_is_only_presented_to_its_hosts() {
+    let pki = pki();
+    let dir = tempfile::tempdir().unwrap();
+    write_pki(&pki, dir.path());
+    let port = mtls_upstream(&pki).await;
+
+    let tls = UpstreamTlsConfig::new()
+        .with_ca_bundle(dir.path().join("ca.pem"))
+        .with_client_cert(
+            ["*.staging.example.com"],
+            dir.path().join("client.pem"),
+            dir.path().join("client-key.pem"),
+        )
+        .load()
+        .unwrap();
+    let forwarder = HttpForwarder::new().with_tls(tls);
+
+    assert!(forwarder.forward(&get(port)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_unknown_ca_is_a_tls_error() {
+    let pki = pki();
+    let port = mtls_upstream(&pki).await;
+
+    let error = HttpForwarder::new().forward(&get(port)).await.unwrap_err();
+    assert!(
+        matches!(error, MatgtoError::Network(NetworkError::TlsError { .. })),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn test_insecure_skip_verify_accepts_unknown_ca() {
+    let pki = pki();
+    let dir = tempfile::tempdir().unwrap();
+    write_pki(&pki, dir.path());
+    let port = mtls_upstream(&pki).await;
+
+    let tls = UpstreamTlsConfig::new()
+        .with_insecure_skip_verify(true)
+        .with_client_cert(
+            ["localhost"],
+            dir.path().join("client.pem"),
+            dir.path().join("client-key.pem"),
+        )
+        .load()
+        .unwrap();
+    let forwarder = HttpForwarder::new().with_tls(tls);
+
+    let response = forwarder.forward(&get(port)).await.unwrap();
+    assert_eq!(response.body.as_deref(), Some(&b"hello"[..]));
+}